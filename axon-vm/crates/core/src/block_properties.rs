@@ -1,6 +1,8 @@
 use super::*;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct BlockProperties {
     pub default_aa_code_hash: U256,
+    // block as seen by the EVM frames started by the far calls
+    pub evm_block_env: crate::evm::EvmBlockEnv,
 }
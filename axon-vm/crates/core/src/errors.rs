@@ -11,3 +11,23 @@ impl std::fmt::Display for OpcodeDecodingError {
 }
 
 impl std::error::Error for OpcodeDecodingError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EvmError {
+    OutOfGas,
+    StackUnderflow,
+    StackOverflow,
+    InvalidJump,
+    InvalidOpcode(u8),
+    UnsupportedOpcode(u8),
+    StateChangeDuringStaticCall,
+    ReturnDataOutOfBounds,
+}
+
+impl std::fmt::Display for EvmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for EvmError {}
@@ -0,0 +1,107 @@
+//! Gas schedule of the EVM interpreter (as of the Cancun hardfork, without access lists). The
+//! accessed storage slots stay warm for the rest of the transaction, see
+//! [`EvmTransactionState`](super::EvmTransactionState).
+
+use alloy_primitives::U256;
+
+use super::opcodes::*;
+
+pub const GAS_ZERO: u64 = 0;
+pub const GAS_JUMPDEST: u64 = 1;
+pub const GAS_BASE: u64 = 2;
+pub const GAS_VERY_LOW: u64 = 3;
+pub const GAS_LOW: u64 = 5;
+pub const GAS_MID: u64 = 8;
+pub const GAS_HIGH: u64 = 10;
+pub const GAS_BLOCKHASH: u64 = 20;
+pub const GAS_EXP_BYTE: u64 = 50;
+pub const GAS_KECCAK256_WORD: u64 = 6;
+pub const GAS_COPY_WORD: u64 = 3;
+pub const GAS_MEMORY_WORD: u64 = 3;
+pub const GAS_QUADRATIC_MEMORY_DIVISOR: u64 = 512;
+pub const GAS_LOG: u64 = 375;
+pub const GAS_LOG_TOPIC: u64 = 375;
+pub const GAS_LOG_DATA_BYTE: u64 = 8;
+
+pub const GAS_WARM_ACCESS: u64 = 100;
+pub const GAS_COLD_SLOAD: u64 = 2100;
+pub const GAS_SSTORE_SET: u64 = 20000;
+pub const GAS_SSTORE_RESET: u64 = 5000 - GAS_COLD_SLOAD;
+pub const GAS_SSTORE_CLEARS_REFUND: i64 = 4800;
+// SSTORE fails if the remaining gas is not above the call stipend
+pub const GAS_SSTORE_SENTRY: u64 = 2300;
+
+/// Gas that is charged for the opcode before its execution. Dynamic parts, such as memory
+/// expansion or storage access, are charged by the interpreter.
+pub const fn static_gas(opcode: u8) -> u64 {
+    match opcode {
+        STOP | RETURN | REVERT => GAS_ZERO,
+        JUMPDEST => GAS_JUMPDEST,
+        ADDRESS | ORIGIN | CALLER | CALLVALUE | CALLDATASIZE | CODESIZE | GASPRICE | COINBASE
+        | TIMESTAMP | NUMBER | PREVRANDAO | GASLIMIT | CHAINID | RETURNDATASIZE | POP | PC
+        | MSIZE | GAS | BASEFEE | PUSH0 => GAS_BASE,
+        ADD | SUB | NOT | LT | GT | SLT | SGT | EQ | ISZERO | AND | OR | XOR | BYTE | SHL | SHR
+        | SAR | CALLDATALOAD | MLOAD | MSTORE | MSTORE8 | CALLDATACOPY | CODECOPY
+        | RETURNDATACOPY | MCOPY => GAS_VERY_LOW,
+        PUSH1..=PUSH32 | DUP1..=DUP16 | SWAP1..=SWAP16 => GAS_VERY_LOW,
+        MUL | DIV | SDIV | MOD | SMOD | SIGNEXTEND => GAS_LOW,
+        ADDMOD | MULMOD | JUMP => GAS_MID,
+        JUMPI | EXP => GAS_HIGH,
+        BLOCKHASH => GAS_BLOCKHASH,
+        KECCAK256 => 30,
+        TLOAD | TSTORE => GAS_WARM_ACCESS,
+        LOG0..=LOG4 => GAS_LOG + GAS_LOG_TOPIC * (opcode - LOG0) as u64,
+        // storage accesses and calls are fully priced dynamically
+        _ => GAS_ZERO,
+    }
+}
+
+/// Total cost of the memory of `num_words` words.
+pub const fn memory_cost(num_words: u64) -> u64 {
+    GAS_MEMORY_WORD * num_words + num_words * num_words / GAS_QUADRATIC_MEMORY_DIVISOR
+}
+
+/// Cost of copying `len` bytes (CALLDATACOPY, CODECOPY, RETURNDATACOPY and MCOPY).
+pub const fn copy_cost(len: u64) -> u64 {
+    GAS_COPY_WORD * len.div_ceil(32)
+}
+
+/// Dynamic cost of EXP that depends on the byte length of the exponent.
+pub fn exp_cost(exponent: &U256) -> u64 {
+    GAS_EXP_BYTE * exponent.bit_len().div_ceil(8) as u64
+}
+
+/// Cost and refund of SSTORE as specified by EIP-2200 with EIP-2929 and EIP-3529 amendments.
+/// The cold access surcharge is not included.
+pub fn sstore_cost(original: U256, current: U256, new: U256) -> (u64, i64) {
+    if current == new {
+        return (GAS_WARM_ACCESS, 0);
+    }
+
+    if original == current {
+        if original.is_zero() {
+            return (GAS_SSTORE_SET, 0);
+        }
+        let refund = if new.is_zero() { GAS_SSTORE_CLEARS_REFUND } else { 0 };
+        return (GAS_SSTORE_RESET, refund);
+    }
+
+    // dirty slot
+    let mut refund = 0;
+    if !original.is_zero() {
+        if current.is_zero() {
+            refund -= GAS_SSTORE_CLEARS_REFUND;
+        } else if new.is_zero() {
+            refund += GAS_SSTORE_CLEARS_REFUND;
+        }
+    }
+    if original == new {
+        if original.is_zero() {
+            refund += (GAS_SSTORE_SET - GAS_WARM_ACCESS) as i64;
+        } else {
+            refund += (GAS_SSTORE_RESET - GAS_WARM_ACCESS) as i64;
+        }
+    }
+
+    (GAS_WARM_ACCESS, refund)
+}
@@ -0,0 +1,899 @@
+use alloy_primitives::{keccak256, I256};
+use zkvm_opcodes::{
+    decoding::VmEncodingMode,
+    system_params::{EVENT_AUX_BYTE, STORAGE_AUX_BYTE},
+    FatPointer, RetOpcode, UNMAPPED_PAGE,
+};
+use zkvm_primitives::{
+    aux::{MemoryIndex, MemoryKey, MemoryLocation, MemoryPage, Timestamp},
+    queries::{LogQuery, MemoryQuery},
+    vm::MemoryType,
+};
+
+use super::{
+    gas::*,
+    opcodes::*,
+    precompiles::{evm_precompile_address_low, right_padded_words},
+    *,
+};
+use crate::{
+    address_to_u256,
+    tracing::{CycleData, EvmStepData, OnReturnData, Tracer, VmLocalStateData},
+    u256_to_address_unchecked,
+    vm_state::{CallStackEntry, PrimitiveValue, VmState},
+};
+
+/// EVM frame that runs in the callstack entry at `callstack_depth`, see
+/// [`VmState::is_evm_frame_running`].
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EvmCallFrame {
+    pub callstack_depth: usize,
+    pub context: EvmContext,
+    pub code: Vec<u8>,
+    pub frame: EvmFrame,
+}
+
+/// State of the running EVM frame.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EvmFrame {
+    pub pc: usize,
+    pub stack: Vec<U256>,
+    pub memory: Vec<u8>,
+    pub gas_remaining: u64,
+    pub gas_refunded: i64,
+    pub return_data: Vec<u8>,
+    // heap page of the frame that is used to pass data to the precompiles and the returndata
+    // to the native caller
+    pub heap_page: MemoryPage,
+    jump_destinations: Vec<bool>,
+}
+
+impl EvmFrame {
+    pub fn new(context: &EvmContext, code: &[u8], heap_page: MemoryPage) -> Self {
+        Self {
+            pc: 0,
+            stack: Vec::with_capacity(EVM_STACK_LIMIT),
+            memory: vec![],
+            gas_remaining: context.gas_limit,
+            gas_refunded: 0,
+            return_data: vec![],
+            heap_page,
+            jump_destinations: analyze_jump_destinations(code),
+        }
+    }
+
+    pub fn charge_gas(&mut self, amount: u64) -> Result<(), EvmError> {
+        self.gas_remaining = self
+            .gas_remaining
+            .checked_sub(amount)
+            .ok_or(EvmError::OutOfGas)?;
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Result<U256, EvmError> {
+        self.stack.pop().ok_or(EvmError::StackUnderflow)
+    }
+
+    pub fn push(&mut self, value: U256) -> Result<(), EvmError> {
+        if self.stack.len() == EVM_STACK_LIMIT {
+            return Err(EvmError::StackOverflow);
+        }
+        self.stack.push(value);
+        Ok(())
+    }
+
+    /// Expands memory to cover `len` bytes at `offset` charging the expansion cost, returns the
+    /// offset as `usize`. Zero length accesses do not expand memory regardless of the offset.
+    pub fn expand_memory(&mut self, offset: U256, len: U256) -> Result<usize, EvmError> {
+        if len.is_zero() {
+            return Ok(0);
+        }
+        // such memory can not be paid for anyway
+        let offset = u32::try_from(offset).map_err(|_| EvmError::OutOfGas)? as u64;
+        let len = u32::try_from(len).map_err(|_| EvmError::OutOfGas)? as u64;
+        let new_num_words = (offset + len).div_ceil(32);
+        let current_num_words = (self.memory.len() / 32) as u64;
+        if new_num_words > current_num_words {
+            self.charge_gas(memory_cost(new_num_words) - memory_cost(current_num_words))?;
+            self.memory.resize(new_num_words as usize * 32, 0);
+        }
+
+        Ok(offset as usize)
+    }
+
+    fn is_valid_jump(&self, destination: U256) -> bool {
+        usize::try_from(destination).is_ok_and(|destination| {
+            self.jump_destinations
+                .get(destination)
+                .copied()
+                .unwrap_or(false)
+        })
+    }
+}
+
+// JUMPDEST bytes that are not a part of PUSH immediates
+fn analyze_jump_destinations(code: &[u8]) -> Vec<bool> {
+    let mut jump_destinations = vec![false; code.len()];
+    let mut pc = 0;
+    while pc < code.len() {
+        match code[pc] {
+            JUMPDEST => jump_destinations[pc] = true,
+            opcode @ PUSH1..=PUSH32 => pc += (opcode - PUSH0) as usize,
+            _ => {}
+        }
+        pc += 1;
+    }
+
+    jump_destinations
+}
+
+/// Copies `src[offset..offset + dst.len()]` into `dst`, padding with zeros beyond `src`.
+fn copy_padded(dst: &mut [u8], src: &[u8], offset: U256) {
+    let offset = usize::try_from(offset).unwrap_or(usize::MAX).min(src.len());
+    let available = (src.len() - offset).min(dst.len());
+    dst[..available].copy_from_slice(&src[offset..(offset + available)]);
+    dst[available..].fill(0);
+}
+
+fn u256_as_u64_saturated(value: U256) -> u64 {
+    u64::try_from(value).unwrap_or(u64::MAX)
+}
+
+fn sign_extend(byte_index: U256, value: U256) -> U256 {
+    if byte_index >= U256::from(31) {
+        return value;
+    }
+    let sign_bit = byte_index.to::<usize>() * 8 + 7;
+    let mask = (U256::from(1) << sign_bit) - U256::from(1);
+    if value.bit(sign_bit) { value | !mask } else { value & mask }
+}
+
+impl<
+    S: zkvm_primitives::vm::Storage,
+    M: zkvm_primitives::vm::Memory,
+    EV: zkvm_primitives::vm::EventSink,
+    PP: zkvm_primitives::vm::PrecompilesProcessor,
+    DP: zkvm_primitives::vm::DecommittmentProcessor,
+    WT: crate::witness_trace::VmWitnessTracer<N, E>,
+    const N: usize,
+    E: VmEncodingMode<N>,
+> VmState<S, M, EV, PP, DP, WT, N, E>
+{
+    /// Runs the EVM `code` in a new frame until it stops, returns, reverts or halts.
+    ///
+    /// The frame gets fresh memory pages and a callstack entry for the context address, and its
+    /// storage writes, events and revertable precompile calls are rolled back unless it
+    /// successfully stops or returns. The instructions are executed in the cycles like the
+    /// ones of the EVM frames of the far calls, see [`VmState::evm_cycle`], and the gas is
+    /// charged from the ergs of the callstack entry.
+    ///
+    /// The frame starts a new transaction, so the warm slots and the transient storage left by
+    /// the previous one are discarded, see [`EvmTransactionState`]. The EVM frames of the far
+    /// calls belong to the transaction that is running.
+    pub fn run_evm_frame<DT: Tracer<N, E, SupportedMemory = M>>(
+        &mut self,
+        tracer: &mut DT,
+        context: &EvmContext,
+        code: &[u8],
+    ) -> EvmExecutionResult {
+        let current_base_page = self
+            .local_state
+            .callstack
+            .get_current_stack()
+            .base_memory_page;
+        let new_base_page = self.new_base_memory_page_on_call();
+        self.increment_memory_pages_on_call();
+
+        let initial_ergs = u32::try_from(context.gas_limit).unwrap_or(u32::MAX);
+        let new_stack = CallStackEntry {
+            this_address: context.address,
+            msg_sender: context.caller,
+            code_address: context.address,
+            base_memory_page: new_base_page,
+            code_page: MemoryPage(UNMAPPED_PAGE),
+            ergs_remaining: initial_ergs,
+            is_static: context.is_static,
+            ..CallStackEntry::empty_context()
+        };
+        self.evm_transaction.reset();
+        self.start_frame(self.local_state.monotonic_cycle_counter, new_stack);
+        self.memory.start_global_frame(
+            current_base_page,
+            new_base_page,
+            FatPointer::empty(),
+            Timestamp(self.local_state.timestamp),
+        );
+        self.push_evm_frame(EvmContext { gas_limit: initial_ergs as u64, ..context.clone() }, code);
+
+        let (evm_frame, status) = loop {
+            self.witness_tracer
+                .start_new_execution_cycle(&self.local_state);
+            let step = self.before_evm_step(tracer);
+            let finished = self.evm_step_current_frame();
+            self.finish_evm_cycle(step, tracer);
+            if let Some(finished) = finished {
+                break finished;
+            }
+        };
+        let panicked = !matches!(status, EvmExitStatus::Stopped | EvmExitStatus::Returned);
+        let finished_callstack =
+            self.finish_frame(self.local_state.monotonic_cycle_counter, panicked);
+        self.memory.finish_global_frame(
+            new_base_page,
+            FatPointer::empty(),
+            Timestamp(self.local_state.timestamp),
+        );
+
+        let EvmFrame { return_data, gas_refunded, .. } = evm_frame.frame;
+        EvmExecutionResult {
+            status,
+            return_data,
+            gas_used: (initial_ergs - finished_callstack.ergs_remaining) as u64,
+            gas_refunded: if panicked { 0 } else { gas_refunded.max(0) as u64 },
+        }
+    }
+
+    /// Whether the current callstack entry runs the EVM code, so the cycle executes the next
+    /// EVM instruction instead of decoding the native one.
+    pub fn is_evm_frame_running(&self) -> bool {
+        self.evm_frames.last().is_some_and(|evm_frame| {
+            evm_frame.callstack_depth == self.local_state.callstack.depth()
+        })
+    }
+
+    /// Starts the EVM frame in the callstack entry that the far call has just pushed, reading
+    /// the code of `code_length_in_bytes` from its code page and the calldata from `calldata`.
+    pub(crate) fn start_evm_call_frame(&mut self, code_length_in_bytes: u16, calldata: FatPointer) {
+        let entry = *self.local_state.callstack.get_current_stack();
+        let code = self.read_evm_code(entry.code_page, code_length_in_bytes as usize);
+        let block = self.block_properties.evm_block_env;
+        let context = EvmContext {
+            address: entry.this_address,
+            caller: entry.msg_sender,
+            // origin is only known to the system contracts, so the native caller is seen as the
+            // sender of the transaction
+            origin: entry.msg_sender,
+            call_value: U256::from(entry.context_u128_value),
+            gas_price: block.base_fee,
+            calldata: self.read_fat_pointer_content(calldata),
+            gas_limit: entry.ergs_remaining as u64,
+            is_static: entry.is_static,
+            block,
+        };
+        self.push_evm_frame(context, &code);
+    }
+
+    fn push_evm_frame(&mut self, context: EvmContext, code: &[u8]) {
+        let base_page = self
+            .local_state
+            .callstack
+            .get_current_stack()
+            .base_memory_page;
+        let heap_page = CallStackEntry::<N, E>::heap_page_from_base(base_page);
+        let frame = EvmFrame::new(&context, code, heap_page);
+        self.evm_frames.push(EvmCallFrame {
+            callstack_depth: self.local_state.callstack.depth(),
+            context,
+            code: code.to_vec(),
+            frame,
+        });
+    }
+
+    // code page holds the code as zero padded words
+    fn read_evm_code(&self, code_page: MemoryPage, len: usize) -> Vec<u8> {
+        let mut code = Vec::with_capacity(len.next_multiple_of(32));
+        for index in 0..len.div_ceil(32) {
+            let query = MemoryQuery {
+                timestamp: self.timestamp_for_code_or_src_read(),
+                location: MemoryLocation {
+                    memory_type: MemoryType::Code,
+                    page: code_page,
+                    index: MemoryIndex(index as u32),
+                },
+                value: U256::ZERO,
+                rw_flag: false,
+                value_is_pointer: false,
+            };
+            let query = self
+                .memory
+                .read_code_query(self.local_state.monotonic_cycle_counter, query);
+            code.extend_from_slice(&query.value.to_be_bytes::<32>());
+        }
+        code.truncate(len);
+
+        code
+    }
+
+    /// Executes the next instruction of the EVM frame on top of the callstack as a cycle. Once
+    /// the frame finishes, it returns to the native caller in the same way as `ret` from the
+    /// heap: the returndata is written to the heap of the frame, the remaining ergs are
+    /// returned, and the caller continues from its exception handler unless the frame has
+    /// successfully stopped or returned.
+    ///
+    /// The cycle calls the same tracer hooks as the native one, with the [`EvmStepData`] of
+    /// the instruction. Instructions that take more than one cycle, i.e. `SSTORE`, `LOG*` and
+    /// the precompile calls, record every cycle they take in the witness tracer, but the
+    /// tracer hooks are only called once.
+    pub(crate) fn evm_cycle<DT: Tracer<N, E, SupportedMemory = M>>(&mut self, tracer: &mut DT) {
+        self.witness_tracer
+            .start_new_execution_cycle(&self.local_state);
+        let step = self.before_evm_step(tracer);
+        if let Some((evm_frame, status)) = self.evm_step_current_frame() {
+            self.return_from_evm_frame(evm_frame, status, tracer);
+        }
+        self.finish_evm_cycle(step, tracer);
+    }
+
+    // calls the tracer hooks that precede the execution of the next instruction
+    fn before_evm_step<DT: Tracer<N, E, SupportedMemory = M>>(
+        &self,
+        tracer: &mut DT,
+    ) -> EvmStepData {
+        let evm_frame = self.evm_frames.last().expect("EVM frame must be running");
+        let pc = evm_frame.frame.pc;
+        // running past the end of the code stops the frame
+        let step = EvmStepData { opcode: evm_frame.code.get(pc).copied().unwrap_or(STOP), pc };
+
+        let local_state = VmLocalStateData { vm_local_state: &self.local_state };
+        if DT::CALL_BEFORE_DECODING {
+            tracer.before_decoding(local_state, &self.memory);
+        }
+        if DT::CALL_AFTER_DECODING {
+            tracer.after_decoding(local_state, CycleData::Evm(step), &self.memory);
+        }
+        if DT::CALL_BEFORE_EXECUTION {
+            tracer.before_execution(local_state, CycleData::Evm(step), &self.memory);
+        }
+
+        step
+    }
+
+    fn finish_evm_cycle<DT: Tracer<N, E, SupportedMemory = M>>(
+        &mut self,
+        step: EvmStepData,
+        tracer: &mut DT,
+    ) {
+        self.next_evm_cycle();
+        self.witness_tracer.end_execution_cycle(&self.local_state);
+
+        if DT::CALL_AFTER_EXECUTION {
+            let local_state = VmLocalStateData { vm_local_state: &self.local_state };
+            tracer.after_execution(local_state, CycleData::Evm(step), &self.memory);
+        }
+    }
+
+    // charges the gas of the instruction from the ergs of the callstack entry, and returns
+    // the frame with the exit status once it has finished
+    fn evm_step_current_frame(&mut self) -> Option<(EvmCallFrame, EvmExitStatus)> {
+        let mut evm_frame = self.evm_frames.pop().expect("EVM frame must be running");
+        let EvmCallFrame { context, code, frame, .. } = &mut evm_frame;
+        let entry = self.local_state.callstack.get_current_stack_mut();
+        frame.gas_remaining = entry.ergs_remaining as u64;
+
+        let status = match self.evm_step(frame, context, code) {
+            Ok(status) => status,
+            Err(error) => Some(EvmExitStatus::Halted(error)),
+        };
+        if let Some(EvmExitStatus::Halted(_)) = status {
+            // exceptional halt consumes all the gas
+            frame.gas_remaining = 0;
+            frame.return_data.clear();
+        }
+        // gas can only decrease, so it still fits
+        self.local_state
+            .callstack
+            .get_current_stack_mut()
+            .ergs_remaining = frame.gas_remaining as u32;
+
+        match status {
+            Some(status) => Some((evm_frame, status)),
+            None => {
+                self.evm_frames.push(evm_frame);
+                None
+            }
+        }
+    }
+
+    fn return_from_evm_frame<DT: Tracer<N, E, SupportedMemory = M>>(
+        &mut self,
+        evm_frame: EvmCallFrame,
+        status: EvmExitStatus,
+        tracer: &mut DT,
+    ) {
+        // ret always resets flags
+        self.local_state.flags.reset();
+
+        let return_kind = match status {
+            EvmExitStatus::Stopped | EvmExitStatus::Returned => RetOpcode::Ok,
+            EvmExitStatus::Reverted => RetOpcode::Revert,
+            EvmExitStatus::Halted(_) => RetOpcode::Panic,
+        };
+        let EvmFrame { return_data, heap_page, .. } = evm_frame.frame;
+        let timestamp = self.timestamp_for_dst_write();
+        for (index, word) in right_padded_words(&return_data).into_iter().enumerate() {
+            let location = MemoryLocation {
+                memory_type: MemoryType::Heap,
+                page: heap_page,
+                index: MemoryIndex(index as u32),
+            };
+            self.write_memory(
+                self.local_state.monotonic_cycle_counter,
+                MemoryKey { location, timestamp },
+                PrimitiveValue::from_value(word),
+            );
+        }
+        let returndata_fat_pointer = FatPointer {
+            offset: 0,
+            memory_page: heap_page.0,
+            start: 0,
+            length: return_data.len() as u32,
+        };
+
+        let ergs_left = self
+            .local_state
+            .callstack
+            .get_current_stack()
+            .ergs_remaining;
+        if DT::CALL_ON_RETURN {
+            let data = OnReturnData { return_kind, returndata_fat_pointer, ergs_left };
+            tracer.on_return(
+                VmLocalStateData { vm_local_state: &self.local_state },
+                data,
+                &self.memory,
+            );
+        }
+
+        let panicked = return_kind != RetOpcode::Ok;
+        let finished_callstack =
+            self.finish_frame(self.local_state.monotonic_cycle_counter, panicked);
+        self.pass_returndata_to_caller(finished_callstack.base_memory_page, returndata_fat_pointer);
+
+        let caller = self.local_state.callstack.get_current_stack_mut();
+        caller.ergs_remaining += ergs_left;
+        if panicked {
+            caller.pc = finished_callstack.exception_handler_location;
+        }
+        if return_kind == RetOpcode::Panic {
+            self.local_state.flags.overflow_or_less_than_flag = true;
+        }
+    }
+
+    fn next_evm_cycle(&mut self) {
+        self.increment_timestamp_after_cycle();
+        self.local_state.monotonic_cycle_counter += 1;
+    }
+
+    // the instruction takes one more cycle, that is recorded by the witness tracer like the
+    // one of any other instruction
+    pub(crate) fn extra_evm_cycle(&mut self) {
+        self.next_evm_cycle();
+        self.witness_tracer.end_execution_cycle(&self.local_state);
+        self.witness_tracer
+            .start_new_execution_cycle(&self.local_state);
+    }
+
+    fn evm_storage_query(&mut self, address: Address, key: U256, value: Option<U256>) -> LogQuery {
+        let partial_query = LogQuery {
+            timestamp: self.timestamp_for_first_decommit_or_precompile_read(),
+            tx_number_in_block: self.local_state.tx_number_in_block,
+            aux_byte: STORAGE_AUX_BYTE,
+            shard_id: 0,
+            address,
+            key,
+            read_value: U256::ZERO,
+            written_value: value.unwrap_or_default(),
+            rw_flag: value.is_some(),
+            rollback: false,
+            is_service: false,
+        };
+        self.access_storage(self.local_state.monotonic_cycle_counter, partial_query)
+    }
+
+    // every event query has a unique timestamp, so we spend a cycle for each of them
+    fn evm_emit_event(&mut self, address: Address, key: U256, value: U256, is_first: bool) {
+        if !is_first {
+            self.extra_evm_cycle();
+        }
+        let query = LogQuery {
+            timestamp: self.timestamp_for_first_decommit_or_precompile_read(),
+            tx_number_in_block: self.local_state.tx_number_in_block,
+            aux_byte: EVENT_AUX_BYTE,
+            shard_id: 0,
+            address,
+            key,
+            read_value: U256::ZERO,
+            written_value: value,
+            rw_flag: true,
+            rollback: false,
+            is_service: is_first,
+        };
+        self.emit_event(self.local_state.monotonic_cycle_counter, query);
+    }
+
+    /// Executes a single instruction, returns the exit status if the frame has finished.
+    pub fn evm_step(
+        &mut self,
+        frame: &mut EvmFrame,
+        context: &EvmContext,
+        code: &[u8],
+    ) -> Result<Option<EvmExitStatus>, EvmError> {
+        let Some(&opcode) = code.get(frame.pc) else {
+            return Ok(Some(EvmExitStatus::Stopped));
+        };
+        frame.charge_gas(static_gas(opcode))?;
+        let mut next_pc = frame.pc + 1;
+
+        match opcode {
+            STOP => return Ok(Some(EvmExitStatus::Stopped)),
+            ADD | MUL | SUB | DIV | SDIV | MOD | SMOD | LT | GT | SLT | SGT | EQ | AND | OR
+            | XOR | BYTE | SHL | SHR | SAR | SIGNEXTEND => {
+                let a = frame.pop()?;
+                let b = frame.pop()?;
+                let result = match opcode {
+                    ADD => a.wrapping_add(b),
+                    MUL => a.wrapping_mul(b),
+                    SUB => a.wrapping_sub(b),
+                    DIV => a.checked_div(b).unwrap_or_default(),
+                    MOD => a.checked_rem(b).unwrap_or_default(),
+                    SDIV | SMOD => {
+                        let (a, b) = (I256::from_raw(a), I256::from_raw(b));
+                        let result = if b.is_zero() {
+                            I256::ZERO
+                        } else if opcode == SDIV {
+                            a.wrapping_div(b)
+                        } else {
+                            a.wrapping_rem(b)
+                        };
+                        result.into_raw()
+                    }
+                    LT => U256::from(a < b),
+                    GT => U256::from(a > b),
+                    SLT => U256::from(I256::from_raw(a) < I256::from_raw(b)),
+                    SGT => U256::from(I256::from_raw(a) > I256::from_raw(b)),
+                    EQ => U256::from(a == b),
+                    AND => a & b,
+                    OR => a | b,
+                    XOR => a ^ b,
+                    BYTE => {
+                        if a < U256::from(32) {
+                            U256::from(b.byte(31 - a.to::<usize>()))
+                        } else {
+                            U256::ZERO
+                        }
+                    }
+                    SHL | SHR | SAR => {
+                        let shift = usize::try_from(a).unwrap_or(usize::MAX);
+                        match opcode {
+                            SHL if shift < 256 => b << shift,
+                            SHR if shift < 256 => b >> shift,
+                            SAR if shift < 256 => b.arithmetic_shr(shift),
+                            SAR if b.bit(255) => U256::MAX,
+                            _ => U256::ZERO,
+                        }
+                    }
+                    SIGNEXTEND => sign_extend(a, b),
+                    _ => unreachable!(),
+                };
+                frame.push(result)?;
+            }
+            ADDMOD | MULMOD => {
+                let a = frame.pop()?;
+                let b = frame.pop()?;
+                let modulus = frame.pop()?;
+                let result =
+                    if opcode == ADDMOD { a.add_mod(b, modulus) } else { a.mul_mod(b, modulus) };
+                frame.push(result)?;
+            }
+            EXP => {
+                let base = frame.pop()?;
+                let exponent = frame.pop()?;
+                frame.charge_gas(exp_cost(&exponent))?;
+                frame.push(base.wrapping_pow(exponent))?;
+            }
+            ISZERO => {
+                let a = frame.pop()?;
+                frame.push(U256::from(a.is_zero()))?;
+            }
+            NOT => {
+                let a = frame.pop()?;
+                frame.push(!a)?;
+            }
+            KECCAK256 => {
+                let offset = frame.pop()?;
+                let len = frame.pop()?;
+                let offset = frame.expand_memory(offset, len)?;
+                let len = len.to::<usize>();
+                frame.charge_gas(GAS_KECCAK256_WORD * len.div_ceil(32) as u64)?;
+                let hash = keccak256(&frame.memory[offset..(offset + len)]);
+                frame.push(U256::from_be_bytes(hash.0))?;
+            }
+            ADDRESS => frame.push(address_to_u256(&context.address))?,
+            ORIGIN => frame.push(address_to_u256(&context.origin))?,
+            CALLER => frame.push(address_to_u256(&context.caller))?,
+            CALLVALUE => frame.push(context.call_value)?,
+            CALLDATALOAD => {
+                let offset = frame.pop()?;
+                let mut word = [0u8; 32];
+                copy_padded(&mut word, &context.calldata, offset);
+                frame.push(U256::from_be_bytes(word))?;
+            }
+            CALLDATASIZE => frame.push(U256::from(context.calldata.len()))?,
+            CODESIZE => frame.push(U256::from(code.len()))?,
+            CALLDATACOPY | CODECOPY | RETURNDATACOPY => {
+                let dst_offset = frame.pop()?;
+                let src_offset = frame.pop()?;
+                let len = frame.pop()?;
+                if opcode == RETURNDATACOPY {
+                    let end = src_offset.checked_add(len);
+                    if end.is_none_or(|end| end > U256::from(frame.return_data.len())) {
+                        return Err(EvmError::ReturnDataOutOfBounds);
+                    }
+                }
+                let dst_offset = frame.expand_memory(dst_offset, len)?;
+                let len = u256_as_u64_saturated(len);
+                frame.charge_gas(copy_cost(len))?;
+                let len = len as usize;
+                let source = match opcode {
+                    CALLDATACOPY => &context.calldata[..],
+                    CODECOPY => code,
+                    _ => &frame.return_data[..],
+                };
+                copy_padded(&mut frame.memory[dst_offset..(dst_offset + len)], source, src_offset);
+            }
+            GASPRICE => frame.push(context.gas_price)?,
+            RETURNDATASIZE => frame.push(U256::from(frame.return_data.len()))?,
+            BLOCKHASH => {
+                // hashes of the previous blocks are not available in the frame
+                frame.pop()?;
+                frame.push(U256::ZERO)?;
+            }
+            COINBASE => frame.push(address_to_u256(&context.block.coinbase))?,
+            TIMESTAMP => frame.push(U256::from(context.block.timestamp))?,
+            NUMBER => frame.push(U256::from(context.block.number))?,
+            PREVRANDAO => frame.push(context.block.prevrandao)?,
+            GASLIMIT => frame.push(U256::from(context.block.gas_limit))?,
+            CHAINID => frame.push(U256::from(context.block.chain_id))?,
+            BASEFEE => frame.push(context.block.base_fee)?,
+            POP => {
+                frame.pop()?;
+            }
+            MLOAD => {
+                let offset = frame.pop()?;
+                let offset = frame.expand_memory(offset, U256::from(32))?;
+                let value = U256::from_be_slice(&frame.memory[offset..(offset + 32)]);
+                frame.push(value)?;
+            }
+            MSTORE => {
+                let offset = frame.pop()?;
+                let value = frame.pop()?;
+                let offset = frame.expand_memory(offset, U256::from(32))?;
+                frame.memory[offset..(offset + 32)].copy_from_slice(&value.to_be_bytes::<32>());
+            }
+            MSTORE8 => {
+                let offset = frame.pop()?;
+                let value = frame.pop()?;
+                let offset = frame.expand_memory(offset, U256::from(1))?;
+                frame.memory[offset] = value.byte(0);
+            }
+            SLOAD => {
+                let key = frame.pop()?;
+                let is_cold = !self.evm_transaction.is_warm(context.address, key);
+                frame.charge_gas(if is_cold { GAS_COLD_SLOAD } else { GAS_WARM_ACCESS })?;
+                self.evm_transaction.warm_slot(context.address, key);
+                let value = self
+                    .evm_storage_query(context.address, key, None)
+                    .read_value;
+                self.evm_transaction
+                    .original_value(context.address, key, value);
+                frame.push(value)?;
+            }
+            SSTORE => {
+                if context.is_static {
+                    return Err(EvmError::StateChangeDuringStaticCall);
+                }
+                if frame.gas_remaining <= GAS_SSTORE_SENTRY {
+                    return Err(EvmError::OutOfGas);
+                }
+                let key = frame.pop()?;
+                let value = frame.pop()?;
+                if !self.evm_transaction.is_warm(context.address, key) {
+                    frame.charge_gas(GAS_COLD_SLOAD)?;
+                    self.evm_transaction.warm_slot(context.address, key);
+                }
+                let current = self
+                    .evm_storage_query(context.address, key, None)
+                    .read_value;
+                let original = self
+                    .evm_transaction
+                    .original_value(context.address, key, current);
+                let (cost, refund) = sstore_cost(original, current, value);
+                frame.charge_gas(cost)?;
+                frame.gas_refunded += refund;
+                // history of the slot must have monotonic time, so the write can not share
+                // the timestamp with the read above
+                self.extra_evm_cycle();
+                self.evm_storage_query(context.address, key, Some(value));
+            }
+            JUMP => {
+                let destination = frame.pop()?;
+                if !frame.is_valid_jump(destination) {
+                    return Err(EvmError::InvalidJump);
+                }
+                next_pc = destination.to::<usize>();
+            }
+            JUMPI => {
+                let destination = frame.pop()?;
+                let condition = frame.pop()?;
+                if !condition.is_zero() {
+                    if !frame.is_valid_jump(destination) {
+                        return Err(EvmError::InvalidJump);
+                    }
+                    next_pc = destination.to::<usize>();
+                }
+            }
+            PC => frame.push(U256::from(frame.pc))?,
+            MSIZE => frame.push(U256::from(frame.memory.len()))?,
+            GAS => frame.push(U256::from(frame.gas_remaining))?,
+            JUMPDEST => {}
+            TLOAD => {
+                let key = frame.pop()?;
+                let value = self.evm_transaction.transient_value(context.address, key);
+                frame.push(value)?;
+            }
+            TSTORE => {
+                if context.is_static {
+                    return Err(EvmError::StateChangeDuringStaticCall);
+                }
+                let key = frame.pop()?;
+                let value = frame.pop()?;
+                self.evm_transaction
+                    .write_transient(context.address, key, value);
+            }
+            MCOPY => {
+                let dst_offset = frame.pop()?;
+                let src_offset = frame.pop()?;
+                let len = frame.pop()?;
+                // expand memory to cover both ranges
+                let src = frame.expand_memory(src_offset, len)?;
+                let dst = frame.expand_memory(dst_offset, len)?;
+                let len = u256_as_u64_saturated(len);
+                frame.charge_gas(copy_cost(len))?;
+                frame.memory.copy_within(src..(src + len as usize), dst);
+            }
+            PUSH0..=PUSH32 => {
+                let len = (opcode - PUSH0) as usize;
+                let mut word = [0u8; 32];
+                copy_padded(&mut word[(32 - len)..], code, U256::from(frame.pc + 1));
+                frame.push(U256::from_be_bytes(word))?;
+                next_pc += len;
+            }
+            DUP1..=DUP16 => {
+                let depth = (opcode - DUP1) as usize + 1;
+                if frame.stack.len() < depth {
+                    return Err(EvmError::StackUnderflow);
+                }
+                let value = frame.stack[frame.stack.len() - depth];
+                frame.push(value)?;
+            }
+            SWAP1..=SWAP16 => {
+                let depth = (opcode - SWAP1) as usize + 1;
+                if frame.stack.len() <= depth {
+                    return Err(EvmError::StackUnderflow);
+                }
+                let top = frame.stack.len() - 1;
+                frame.stack.swap(top, top - depth);
+            }
+            LOG0..=LOG4 => {
+                if context.is_static {
+                    return Err(EvmError::StateChangeDuringStaticCall);
+                }
+                let offset = frame.pop()?;
+                let len = frame.pop()?;
+                let num_topics = (opcode - LOG0) as usize;
+                let mut topics = Vec::with_capacity(num_topics);
+                for _ in 0..num_topics {
+                    topics.push(frame.pop()?);
+                }
+                let offset = frame.expand_memory(offset, len)?;
+                let len = u256_as_u64_saturated(len);
+                frame.charge_gas(GAS_LOG_DATA_BYTE.saturating_mul(len))?;
+                let data = &frame.memory[offset..(offset + len as usize)];
+
+                // the event is encoded as a sequence of queries: the first one holds the number
+                // of topics and the data length, followed by the topics and the data words
+                self.evm_emit_event(
+                    context.address,
+                    U256::from(num_topics),
+                    U256::from(data.len()),
+                    true,
+                );
+                for (idx, topic) in topics.into_iter().enumerate() {
+                    self.evm_emit_event(context.address, U256::from(idx), topic, false);
+                }
+                for (idx, chunk) in data.chunks(32).enumerate() {
+                    let mut word = [0u8; 32];
+                    word[..chunk.len()].copy_from_slice(chunk);
+                    self.evm_emit_event(
+                        context.address,
+                        U256::from(idx),
+                        U256::from_be_bytes(word),
+                        false,
+                    );
+                }
+            }
+            CALL | CALLCODE | DELEGATECALL | STATICCALL => {
+                let gas = frame.pop()?;
+                let address = u256_to_address_unchecked(&frame.pop()?);
+                let value =
+                    if matches!(opcode, CALL | CALLCODE) { frame.pop()? } else { U256::ZERO };
+                let input_offset = frame.pop()?;
+                let input_len = frame.pop()?;
+                let output_offset = frame.pop()?;
+                let output_len = frame.pop()?;
+
+                let Some(address_low) = evm_precompile_address_low(&address) else {
+                    return Err(EvmError::UnsupportedOpcode(opcode));
+                };
+                if !value.is_zero() {
+                    if opcode == CALL && context.is_static {
+                        return Err(EvmError::StateChangeDuringStaticCall);
+                    }
+                    // there are no balances to transfer the value from
+                    return Err(EvmError::UnsupportedOpcode(opcode));
+                }
+                // precompiles are always warm
+                frame.charge_gas(GAS_WARM_ACCESS)?;
+                let input_offset = frame.expand_memory(input_offset, input_len)?;
+                let output_offset = frame.expand_memory(output_offset, output_len)?;
+                let (input_len, output_len) = (input_len.to::<usize>(), output_len.to::<usize>());
+
+                // all but one 64th of the remaining gas can be passed
+                let available_gas = frame.gas_remaining - frame.gas_remaining / 64;
+                let call_gas = u256_as_u64_saturated(gas).min(available_gas);
+
+                let input = frame.memory[input_offset..(input_offset + input_len)].to_vec();
+                let cost = precompiles::evm_precompile_gas_cost(address_low, &input);
+                let output = if cost <= call_gas {
+                    self.run_evm_precompile(address_low, &input, frame.heap_page)
+                } else {
+                    None
+                };
+                match output {
+                    Some(output) => {
+                        frame.charge_gas(cost)?;
+                        let len = output.len().min(output_len);
+                        frame.memory[output_offset..(output_offset + len)]
+                            .copy_from_slice(&output[..len]);
+                        frame.return_data = output;
+                        frame.push(U256::from(1))?;
+                    }
+                    None => {
+                        // failed call consumes all the passed gas
+                        frame.charge_gas(call_gas)?;
+                        frame.return_data.clear();
+                        frame.push(U256::ZERO)?;
+                    }
+                }
+            }
+            RETURN | REVERT => {
+                let offset = frame.pop()?;
+                let len = frame.pop()?;
+                let offset = frame.expand_memory(offset, len)?;
+                frame.return_data = frame.memory[offset..(offset + len.to::<usize>())].to_vec();
+                let status = match opcode {
+                    RETURN => EvmExitStatus::Returned,
+                    _ => EvmExitStatus::Reverted,
+                };
+                return Ok(Some(status));
+            }
+            BALANCE | EXTCODESIZE | EXTCODECOPY | EXTCODEHASH | SELFBALANCE | BLOBHASH
+            | BLOBBASEFEE | CREATE | CREATE2 | SELFDESTRUCT => {
+                return Err(EvmError::UnsupportedOpcode(opcode));
+            }
+            _ => return Err(EvmError::InvalidOpcode(opcode)),
+        }
+
+        frame.pc = next_pc;
+        Ok(None)
+    }
+}
@@ -0,0 +1,76 @@
+//! Interpreter of the native EVM bytecode.
+//!
+//! EVM code is deployed under an [`EvmCodeKeccak256`](zkvm_opcodes::EvmCodeKeccak256) versioned
+//! hash. A far call to such a contract decommits the code like the native one, and starts an EVM
+//! frame in the new callstack entry that is executed by the VM cycles, one instruction per cycle,
+//! and returns to the caller like `ret` does. [`VmState::run_evm_frame`](crate::vm_state::VmState)
+//! runs the code in a frame of its own in the same way. The frames share the storage, event sink
+//! and precompiles processor with the native frames, so their effects are rolled back on revert
+//! and recorded by the witness tracer like for any other frame, and the gas is charged from the
+//! ergs of the callstack entry. EVM code can only call the precompiles, and the opcodes that need
+//! the account model (balances, code of other accounts, contract creation and self-destruction)
+//! halt the frame with [`EvmError::UnsupportedOpcode`].
+
+use alloy_primitives::{Address, U256};
+
+use crate::errors::EvmError;
+
+pub mod gas;
+pub mod interpreter;
+pub mod opcodes;
+pub mod precompiles;
+pub mod transaction;
+
+pub use self::{interpreter::*, transaction::*};
+
+/// Maximum number of the words on the EVM stack.
+pub const EVM_STACK_LIMIT: usize = 1024;
+
+/// Properties of the block visible to the EVM code.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EvmBlockEnv {
+    pub number: u64,
+    pub timestamp: u64,
+    pub coinbase: Address,
+    pub gas_limit: u64,
+    pub chain_id: u64,
+    pub base_fee: U256,
+    pub prevrandao: U256,
+}
+
+/// Parameters of the EVM frame.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EvmContext {
+    pub address: Address,
+    pub caller: Address,
+    pub origin: Address,
+    pub call_value: U256,
+    pub gas_price: U256,
+    pub calldata: Vec<u8>,
+    pub gas_limit: u64,
+    pub is_static: bool,
+    pub block: EvmBlockEnv,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EvmExitStatus {
+    Stopped,
+    Returned,
+    Reverted,
+    Halted(EvmError),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvmExecutionResult {
+    pub status: EvmExitStatus,
+    pub return_data: Vec<u8>,
+    pub gas_used: u64,
+    // refund counter at the end of the frame, before the cap applied by the transaction
+    pub gas_refunded: u64,
+}
+
+impl EvmExecutionResult {
+    pub fn is_success(&self) -> bool {
+        matches!(self.status, EvmExitStatus::Stopped | EvmExitStatus::Returned)
+    }
+}
@@ -0,0 +1,341 @@
+//! Calls to the EVM precompiles from the interpreter.
+//!
+//! The EVM precompiles take byte strings, while the precompiles of the VM take words in memory
+//! and may expect the input to be prepared by the caller (e.g. padded for the hash functions).
+//! Here the EVM input is converted to the layout of the corresponding VM precompile, which is
+//! called through [`PrecompilesProcessor`](zkvm_primitives::vm::PrecompilesProcessor) on the
+//! heap page of the frame, and its output is converted back as specified by the EIPs.
+
+use alloy_primitives::{Address, U256};
+use zkvm_opcodes::{
+    decoding::VmEncodingMode, system_params::PRECOMPILE_AUX_BYTE, PrecompileCallABI,
+    ADDRESS_BLAKE2F, ADDRESS_ECADD, ADDRESS_ECMUL, ADDRESS_ECPAIRING, ADDRESS_ECRECOVER,
+    ADDRESS_IDENTITY, ADDRESS_MODEXP, ADDRESS_RIPEMD160, ADDRESS_SECP256R1_VERIFY,
+    ADDRESS_SHA256,
+};
+use zkvm_primitives::{
+    aux::{MemoryIndex, MemoryKey, MemoryLocation, MemoryPage},
+    precompiles::{blake2f::BLAKE2F_INPUT_BYTES, modexp::ModexpLengths},
+    queries::LogQuery,
+    vm::MemoryType,
+};
+
+use crate::vm_state::{PrimitiveValue, VmState};
+
+const ECPAIRING_PAIR_BYTES: usize = 192;
+
+/// Returns the lowest 16 bits of the address if it's one of the EVM precompiles.
+pub fn evm_precompile_address_low(address: &Address) -> Option<u16> {
+    let bytes = address.as_slice();
+    if bytes[..18].iter().any(|&byte| byte != 0) {
+        return None;
+    }
+    let address_low = u16::from_be_bytes([bytes[18], bytes[19]]);
+    match address_low {
+        ADDRESS_ECRECOVER
+        | ADDRESS_SHA256
+        | ADDRESS_RIPEMD160
+        | ADDRESS_IDENTITY
+        | ADDRESS_MODEXP
+        | ADDRESS_ECADD
+        | ADDRESS_ECMUL
+        | ADDRESS_ECPAIRING
+        | ADDRESS_BLAKE2F
+        | ADDRESS_SECP256R1_VERIFY => Some(address_low),
+        _ => None,
+    }
+}
+
+/// Gas cost of the call to the EVM precompile.
+pub fn evm_precompile_gas_cost(address_low: u16, input: &[u8]) -> u64 {
+    let num_words = input.len().div_ceil(32) as u64;
+    match address_low {
+        ADDRESS_ECRECOVER => 3000,
+        ADDRESS_SHA256 => 60 + 12 * num_words,
+        ADDRESS_RIPEMD160 => 600 + 120 * num_words,
+        ADDRESS_IDENTITY => 15 + 3 * num_words,
+        ADDRESS_MODEXP => modexp_gas_cost(input),
+        ADDRESS_ECADD => 150,
+        ADDRESS_ECMUL => 6000,
+        ADDRESS_ECPAIRING => 45000 + 34000 * (input.len() / ECPAIRING_PAIR_BYTES) as u64,
+        ADDRESS_BLAKE2F if input.len() == BLAKE2F_INPUT_BYTES => {
+            u32::from_be_bytes(input[..4].try_into().unwrap()) as u64
+        }
+        ADDRESS_BLAKE2F => 0,
+        ADDRESS_SECP256R1_VERIFY => 3450,
+        _ => unreachable!("not an EVM precompile"),
+    }
+}
+
+/// Returns `len` bytes of the input at `offset`, padded with zeros beyond the input.
+fn read_padded(input: &[u8], offset: usize, len: usize) -> Vec<u8> {
+    let mut result = vec![0u8; len];
+    if offset < input.len() {
+        let available = (input.len() - offset).min(len);
+        result[..available].copy_from_slice(&input[offset..(offset + available)]);
+    }
+    result
+}
+
+fn read_length(input: &[u8], offset: usize) -> U256 {
+    U256::from_be_slice(&read_padded(input, offset, 32))
+}
+
+/// Lengths of the modexp operands in bytes as in the EIP-198 header.
+fn modexp_byte_lengths(input: &[u8]) -> Option<(usize, usize, usize)> {
+    let length = |offset| {
+        u32::try_from(read_length(input, offset))
+            .ok()
+            .map(|len| len as usize)
+    };
+    Some((length(0)?, length(32)?, length(64)?))
+}
+
+/// Gas cost of modexp as specified by EIP-2565.
+fn modexp_gas_cost(input: &[u8]) -> u64 {
+    let Some((base_len, exponent_len, modulus_len)) = modexp_byte_lengths(input) else {
+        return u64::MAX;
+    };
+    let num_words = base_len.max(modulus_len).div_ceil(8) as u128;
+    let multiplication_complexity = num_words * num_words;
+
+    // the iteration count depends on the highest 32 bytes of the exponent
+    let exponent_head = read_padded(input, 96 + base_len, exponent_len.min(32));
+    let exponent_head = U256::from_be_slice(&exponent_head);
+    let head_bits = exponent_head.bit_len().saturating_sub(1) as u128;
+    let iteration_count =
+        if exponent_len <= 32 { head_bits } else { 8 * (exponent_len as u128 - 32) + head_bits };
+
+    let cost = multiplication_complexity * iteration_count.max(1) / 3;
+    u64::try_from(cost.max(200)).unwrap_or(u64::MAX)
+}
+
+/// Converts bytes to words, left-padding them with zeros to the whole number of words.
+fn left_padded_words(bytes: &[u8]) -> Vec<U256> {
+    let padding = bytes.len().div_ceil(32) * 32 - bytes.len();
+    let mut padded = vec![0u8; padding];
+    padded.extend_from_slice(bytes);
+    padded.chunks(32).map(U256::from_be_slice).collect()
+}
+
+/// Converts bytes to words, right-padding the last word with zeros.
+pub(super) fn right_padded_words(bytes: &[u8]) -> Vec<U256> {
+    read_padded(bytes, 0, bytes.len().div_ceil(32) * 32)
+        .chunks(32)
+        .map(U256::from_be_slice)
+        .collect()
+}
+
+/// Pads the message for SHA-256 or RIPEMD-160, which only differ in the byte order of the
+/// message length.
+fn md_padding(input: &[u8], length_is_be: bool) -> Vec<u8> {
+    let mut padded = input.to_vec();
+    padded.push(0x80);
+    padded.resize((input.len() + 8 + 1).div_ceil(64) * 64 - 8, 0);
+    let bit_length = (input.len() as u64) * 8;
+    if length_is_be {
+        padded.extend_from_slice(&bit_length.to_be_bytes());
+    } else {
+        padded.extend_from_slice(&bit_length.to_le_bytes());
+    }
+    padded
+}
+
+fn words_to_bytes(words: &[U256]) -> Vec<u8> {
+    words
+        .iter()
+        .flat_map(|word| word.to_be_bytes::<32>())
+        .collect()
+}
+
+impl<
+    S: zkvm_primitives::vm::Storage,
+    M: zkvm_primitives::vm::Memory,
+    EV: zkvm_primitives::vm::EventSink,
+    PP: zkvm_primitives::vm::PrecompilesProcessor,
+    DP: zkvm_primitives::vm::DecommittmentProcessor,
+    WT: crate::witness_trace::VmWitnessTracer<N, E>,
+    const N: usize,
+    E: VmEncodingMode<N>,
+> VmState<S, M, EV, PP, DP, WT, N, E>
+{
+    /// Calls the EVM precompile, returns its output or `None` if the call fails.
+    pub(crate) fn run_evm_precompile(
+        &mut self,
+        address_low: u16,
+        input: &[u8],
+        heap_page: MemoryPage,
+    ) -> Option<Vec<u8>> {
+        match address_low {
+            ADDRESS_ECRECOVER => {
+                let input = read_padded(input, 0, 128);
+                let v = U256::from_be_slice(&input[32..64]);
+                if v != U256::from(27) && v != U256::from(28) {
+                    return Some(vec![]);
+                }
+                let words = [
+                    U256::from_be_slice(&input[..32]),
+                    v - U256::from(27),
+                    U256::from_be_slice(&input[64..96]),
+                    U256::from_be_slice(&input[96..]),
+                ];
+                let output = self.call_precompile_on_heap(address_low, &words, 2, 0, heap_page);
+                if output[0].is_zero() { Some(vec![]) } else { Some(words_to_bytes(&output[1..])) }
+            }
+            ADDRESS_SHA256 | ADDRESS_RIPEMD160 => {
+                let padded = md_padding(input, address_low == ADDRESS_SHA256);
+                let num_blocks = (padded.len() / 64) as u64;
+                let words = right_padded_words(&padded);
+                let output =
+                    self.call_precompile_on_heap(address_low, &words, 1, num_blocks, heap_page);
+                Some(words_to_bytes(&output))
+            }
+            ADDRESS_IDENTITY => {
+                if input.is_empty() {
+                    return Some(vec![]);
+                }
+                let words = right_padded_words(input);
+                let output_len = words.len() as u32;
+                let output =
+                    self.call_precompile_on_heap(address_low, &words, output_len, 0, heap_page);
+                Some(words_to_bytes(&output)[..input.len()].to_vec())
+            }
+            ADDRESS_MODEXP => {
+                let (base_len, exponent_len, modulus_len) = modexp_byte_lengths(input)?;
+                let lengths = ModexpLengths {
+                    base: u16::try_from(base_len.div_ceil(32)).ok()? as u32,
+                    exponent: u16::try_from(exponent_len.div_ceil(32)).ok()? as u32,
+                    modulus: u16::try_from(modulus_len.div_ceil(32)).ok()? as u32,
+                };
+                // the VM precompile rejects longer operands, as does EIP-7823
+                if !lengths.is_supported() {
+                    return None;
+                }
+                if modulus_len == 0 {
+                    return Some(vec![]);
+                }
+                let base = read_padded(input, 96, base_len);
+                let exponent = read_padded(input, 96 + base_len, exponent_len);
+                let modulus = read_padded(input, 96 + base_len + exponent_len, modulus_len);
+
+                let mut words = left_padded_words(&base);
+                words.extend(left_padded_words(&exponent));
+                words.extend(left_padded_words(&modulus));
+                let output = self.call_precompile_on_heap(
+                    address_low,
+                    &words,
+                    lengths.modulus,
+                    lengths.to_interpreted_data(),
+                    heap_page,
+                );
+                let output = words_to_bytes(&output);
+                Some(output[(output.len() - modulus_len)..].to_vec())
+            }
+            ADDRESS_ECADD | ADDRESS_ECMUL => {
+                let input_len = if address_low == ADDRESS_ECADD { 128 } else { 96 };
+                let words = right_padded_words(&read_padded(input, 0, input_len));
+                let output = self.call_precompile_on_heap(address_low, &words, 3, 0, heap_page);
+                if output[0].is_zero() { None } else { Some(words_to_bytes(&output[1..])) }
+            }
+            ADDRESS_ECPAIRING => {
+                if !input.len().is_multiple_of(ECPAIRING_PAIR_BYTES) {
+                    return None;
+                }
+                // the VM precompile must be called with at least one pair
+                if input.is_empty() {
+                    return Some(U256::from(1).to_be_bytes::<32>().to_vec());
+                }
+                let num_pairs = (input.len() / ECPAIRING_PAIR_BYTES) as u64;
+                let words = right_padded_words(input);
+                let output =
+                    self.call_precompile_on_heap(address_low, &words, 2, num_pairs, heap_page);
+                if output[0].is_zero() { None } else { Some(words_to_bytes(&output[1..])) }
+            }
+            ADDRESS_BLAKE2F => {
+                if input.len() != BLAKE2F_INPUT_BYTES {
+                    return None;
+                }
+                let words = right_padded_words(input);
+                let output = self.call_precompile_on_heap(address_low, &words, 3, 0, heap_page);
+                if output[0].is_zero() { None } else { Some(words_to_bytes(&output[1..])) }
+            }
+            ADDRESS_SECP256R1_VERIFY => {
+                if input.len() != 160 {
+                    return Some(vec![]);
+                }
+                let words = right_padded_words(input);
+                let output = self.call_precompile_on_heap(address_low, &words, 2, 0, heap_page);
+                let is_valid = output[0] == U256::from(1) && output[1] == U256::from(1);
+                if is_valid { Some(words_to_bytes(&output[1..])) } else { Some(vec![]) }
+            }
+            _ => unreachable!("not an EVM precompile"),
+        }
+    }
+
+    // writes the input and zeroed output to the heap page, calls the precompile and reads
+    // the output, spending a cycle on each step
+    fn call_precompile_on_heap(
+        &mut self,
+        address_low: u16,
+        input: &[U256],
+        output_len: u32,
+        precompile_interpreted_data: u64,
+        heap_page: MemoryPage,
+    ) -> Vec<U256> {
+        let location = |index: usize| MemoryLocation {
+            memory_type: MemoryType::Heap,
+            page: heap_page,
+            index: MemoryIndex(index as u32),
+        };
+
+        let timestamp = self.timestamp_for_dst_write();
+        let output_offset = input.len();
+        let words = input
+            .iter()
+            .copied()
+            .chain(std::iter::repeat_n(U256::ZERO, output_len as usize));
+        for (index, word) in words.enumerate() {
+            let key = MemoryKey { location: location(index), timestamp };
+            self.write_memory(
+                self.local_state.monotonic_cycle_counter,
+                key,
+                PrimitiveValue::from_value(word),
+            );
+        }
+        self.extra_evm_cycle();
+
+        let precompile_abi = PrecompileCallABI {
+            input_memory_offset: 0,
+            input_memory_length: input.len() as u32,
+            output_memory_offset: output_offset as u32,
+            output_memory_length: output_len,
+            memory_page_to_read: heap_page.0,
+            memory_page_to_write: heap_page.0,
+            precompile_interpreted_data,
+        };
+        let query = LogQuery {
+            timestamp: self.timestamp_for_first_decommit_or_precompile_read(),
+            tx_number_in_block: self.local_state.tx_number_in_block,
+            aux_byte: PRECOMPILE_AUX_BYTE,
+            shard_id: 0,
+            address: Address::left_padding_from(&address_low.to_be_bytes()),
+            key: precompile_abi.to_u256(),
+            read_value: U256::ZERO,
+            written_value: U256::ZERO,
+            rw_flag: false,
+            rollback: false,
+            is_service: false,
+        };
+        self.execute_precompile_query(self.local_state.monotonic_cycle_counter, query);
+        self.extra_evm_cycle();
+
+        let timestamp = self.timestamp_for_code_or_src_read();
+        (output_offset..(output_offset + output_len as usize))
+            .map(|index| {
+                let key = MemoryKey { location: location(index), timestamp };
+                self.read_memory(self.local_state.monotonic_cycle_counter, key)
+                    .value
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use alloy_primitives::{Address, U256};
+
+/// State of the EVM code that lives for the whole transaction rather than a single frame: the
+/// original values and the warm slots of the accessed storage (EIP-2200 and EIP-2929), and the
+/// transient storage (EIP-1153).
+///
+/// The frames of the callstack, native or EVM, open a checkpoint that is rolled back if the
+/// frame panics or reverts, so the slots it has warmed become cold again and its transient
+/// writes are undone. Original values are kept, as the storage itself is rolled back to them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EvmTransactionState {
+    // maps are sorted, so equal states give equal snapshots
+    original_values: BTreeMap<Address, BTreeMap<U256, U256>>,
+    warm_slots: BTreeMap<Address, BTreeSet<U256>>,
+    transient_storage: BTreeMap<Address, BTreeMap<U256, U256>>,
+    // changes to undo on rollback, and the length of it at the start of every running frame
+    journal: Vec<EvmJournalEntry>,
+    frame_checkpoints: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum EvmJournalEntry {
+    SlotWarmed { address: Address, key: U256 },
+    TransientWritten { address: Address, key: U256, previous_value: U256 },
+}
+
+impl EvmTransactionState {
+    /// Starts a new transaction, discarding the state of the previous one.
+    pub fn reset(&mut self) {
+        // frames that are still running have nothing to roll back in the new transaction
+        let frame_checkpoints = vec![0; self.frame_checkpoints.len()];
+        *self = Self { frame_checkpoints, ..Self::default() };
+    }
+
+    pub fn start_frame(&mut self) {
+        self.frame_checkpoints.push(self.journal.len());
+    }
+
+    pub fn finish_frame(&mut self, panicked: bool) {
+        let checkpoint = self
+            .frame_checkpoints
+            .pop()
+            .expect("frame must be started before finishing");
+        if !panicked {
+            // changes are kept unless the parent frame rolls them back
+            return;
+        }
+
+        for entry in self.journal.drain(checkpoint..).rev() {
+            match entry {
+                EvmJournalEntry::SlotWarmed { address, key } => {
+                    let slots = self
+                        .warm_slots
+                        .get_mut(&address)
+                        .expect("slot must be warm");
+                    slots.remove(&key);
+                }
+                EvmJournalEntry::TransientWritten { address, key, previous_value } => {
+                    self.transient_storage
+                        .entry(address)
+                        .or_default()
+                        .insert(key, previous_value);
+                }
+            }
+        }
+    }
+
+    pub fn is_warm(&self, address: Address, key: U256) -> bool {
+        self.warm_slots
+            .get(&address)
+            .is_some_and(|slots| slots.contains(&key))
+    }
+
+    pub fn warm_slot(&mut self, address: Address, key: U256) {
+        if self.warm_slots.entry(address).or_default().insert(key) {
+            self.journal
+                .push(EvmJournalEntry::SlotWarmed { address, key });
+        }
+    }
+
+    /// Value of the slot at the start of the transaction. The `current_value` of the first
+    /// access is recorded as such.
+    pub fn original_value(&mut self, address: Address, key: U256, current_value: U256) -> U256 {
+        *self
+            .original_values
+            .entry(address)
+            .or_default()
+            .entry(key)
+            .or_insert(current_value)
+    }
+
+    pub fn transient_value(&self, address: Address, key: U256) -> U256 {
+        self.transient_storage
+            .get(&address)
+            .and_then(|slots| slots.get(&key).copied())
+            .unwrap_or_default()
+    }
+
+    pub fn write_transient(&mut self, address: Address, key: U256, value: U256) {
+        let previous_value = self
+            .transient_storage
+            .entry(address)
+            .or_default()
+            .insert(key, value)
+            .unwrap_or_default();
+        self.journal
+            .push(EvmJournalEntry::TransientWritten { address, key, previous_value });
+    }
+}
@@ -1,5 +1,6 @@
 pub mod block_properties;
 pub mod errors;
+pub mod evm;
 pub mod flags;
 pub mod opcodes;
 pub mod reference_impls;
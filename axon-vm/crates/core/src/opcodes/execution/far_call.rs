@@ -7,6 +7,7 @@ use zkvm_opcodes::{
 use zkvm_primitives::{aux::*, queries::LogQuery};
 
 use super::*;
+use crate::tracing::{OnFarCallData, VmLocalStateData};
 
 pub const FORCED_ERGS_FOR_MSG_VALUE_SIMULATOR: bool = false;
 
@@ -37,10 +38,12 @@ impl<const N: usize, E: VmEncodingMode<N>> DecodedOpcode<N, E> {
         PP: zkvm_primitives::vm::PrecompilesProcessor,
         DP: zkvm_primitives::vm::DecommittmentProcessor,
         WT: crate::witness_trace::VmWitnessTracer<N, E>,
+        DT: crate::tracing::Tracer<N, E, SupportedMemory = M>,
     >(
         &self,
         vm_state: &mut VmState<S, M, EV, PP, DP, WT, N, E>,
         prestate: PreState<N, E>,
+        tracer: &mut DT,
     ) -> anyhow::Result<()> {
         let PreState { src0, src1, new_pc, is_kernel_mode, .. } = prestate;
         let inner_variant = match self.variant.opcode {
@@ -101,7 +104,12 @@ impl<const N: usize, E: VmEncodingMode<N>> DecodedOpcode<N, E> {
 
         // NOTE: our far-call MUST take ergs to cover storage read, but we also have a contribution
         // that depends on the actual code length, so we work with it here
-        let (mapped_code_page, ergs_after_code_read_and_exceptions_resolution, stipend_for_callee) = {
+        let (
+            mapped_code_page,
+            ergs_after_code_read_and_exceptions_resolution,
+            stipend_for_callee,
+            evm_code_length_in_bytes,
+        ) = {
             let (code_hash, map_to_trivial) = if new_code_shard_id != 0 {
                 // we do NOT mask it into default AA here
                 // and for now formally jump to the page containing zeroes
@@ -145,11 +153,13 @@ impl<const N: usize, E: VmEncodingMode<N>> DecodedOpcode<N, E> {
 
             // now we handle potential exceptions
 
-            use zkvm_opcodes::{ContractCodeSha256, VersionedHashGeneric};
+            use zkvm_opcodes::{ContractCodeSha256, EvmCodeKeccak256, VersionedHashGeneric};
 
             let buffer = code_hash.to_be_bytes();
 
             let mut exceptions = FarCallExceptionFlags::empty();
+            // only set for the EVM code, that is run by the interpreter
+            let mut evm_code_length_in_bytes = None;
 
             // now let's check if code format "makes sense"
             let (code_hash, code_length_in_words) = if let Some(versioned_hash) =
@@ -220,6 +230,27 @@ impl<const N: usize, E: VmEncodingMode<N>> DecodedOpcode<N, E> {
                         }
                     }
                 }
+            } else if let Some(versioned_hash) =
+                VersionedHashGeneric::<EvmCodeKeccak256>::try_create_from_raw(buffer)
+            {
+                let layout = versioned_hash.layout_ref();
+
+                // EVM code is never constructed by the VM, so it can only be called at rest
+                let code_marker_is_at_rest =
+                    layout.extra_marker == EvmCodeKeccak256::CODE_AT_REST_MARKER;
+                if code_marker_is_at_rest && !far_call_abi.constructor_call {
+                    let code_hash_at_storage = versioned_hash
+                        .serialize_to_stored()
+                        .map(U256::from_be_bytes)
+                        .expect("Failed to serialize a valid hash");
+                    evm_code_length_in_bytes = Some(layout.code_length_in_bytes);
+
+                    (code_hash_at_storage, layout.code_length_in_words() as u32)
+                } else {
+                    exceptions.set(FarCallExceptionFlags::INVALID_CODE_HASH_FORMAT, true);
+
+                    (U256::ZERO, 0u32)
+                }
             } else {
                 exceptions.set(FarCallExceptionFlags::INVALID_CODE_HASH_FORMAT, true);
                 // we still return placeholders
@@ -408,6 +439,7 @@ impl<const N: usize, E: VmEncodingMode<N>> DecodedOpcode<N, E> {
 
             let code_memory_page = if !exceptions.is_empty() {
                 vm_state.set_shorthand_panic();
+                evm_code_length_in_bytes = None;
 
                 // we also do not return back cost of decommittment as it wasn't subtracted
                 MemoryPage(UNMAPPED_PAGE)
@@ -429,7 +461,12 @@ impl<const N: usize, E: VmEncodingMode<N>> DecodedOpcode<N, E> {
                 processed_decommittment_query.memory_page
             };
 
-            (code_memory_page, remaining_ergs_after_decommittment, msg_value_stipend)
+            (
+                code_memory_page,
+                remaining_ergs_after_decommittment,
+                msg_value_stipend,
+                evm_code_length_in_bytes,
+            )
         };
 
         // we have taken everything that we want from caller and now can try to pass to callee
@@ -535,6 +572,24 @@ impl<const N: usize, E: VmEncodingMode<N>> DecodedOpcode<N, E> {
             Timestamp(vm_state.local_state.timestamp),
         );
 
+        if DT::CALL_ON_FAR_CALL {
+            let data = OnFarCallData {
+                far_call_variant: inner_variant,
+                caller: current_address,
+                callee: code_address_for_next,
+                msg_sender: msg_sender_for_next,
+                calldata_fat_pointer: far_call_abi.memory_quasi_fat_pointer,
+                ergs_passed: passed_ergs,
+                is_static: new_context_is_static,
+                is_static_call,
+            };
+            tracer.on_far_call(
+                VmLocalStateData { vm_local_state: &vm_state.local_state },
+                data,
+                &vm_state.memory,
+            );
+        }
+
         // write down calldata information
 
         let r1_value = PrimitiveValue {
@@ -573,6 +628,12 @@ impl<const N: usize, E: VmEncodingMode<N>> DecodedOpcode<N, E> {
         vm_state.local_state.registers[CALL_IMPLICIT_PARAMETER_REG_IDX as usize] =
             PrimitiveValue::empty();
 
+        // the callee runs in the interpreter from the next cycle
+        if let Some(code_length_in_bytes) = evm_code_length_in_bytes {
+            vm_state
+                .start_evm_call_frame(code_length_in_bytes, far_call_abi.memory_quasi_fat_pointer);
+        }
+
         Ok(())
     }
 }
@@ -1,5 +1,14 @@
 use super::*;
 
+/// Multiplies the operands into the full 512-bit product, returned as the low and high words.
+pub(crate) fn full_width_mul(src0: U256, src1: U256) -> (U256, U256) {
+    let tmp = src0.widening_mul::<256, 4, 512, 8>(src1).into_limbs();
+    let low = U256::from_limbs([tmp[0], tmp[1], tmp[2], tmp[3]]);
+    let high = U256::from_limbs([tmp[4], tmp[5], tmp[6], tmp[7]]);
+
+    (low, high)
+}
+
 impl<const N: usize, E: VmEncodingMode<N>> DecodedOpcode<N, E> {
     pub fn mul_opcode_apply<
         S: zkvm_primitives::vm::Storage,
@@ -20,9 +29,7 @@ impl<const N: usize, E: VmEncodingMode<N>> DecodedOpcode<N, E> {
         use zkvm_opcodes::SET_FLAGS_FLAG_IDX;
         let set_flags = self.variant.flags[SET_FLAGS_FLAG_IDX];
         vm_state.local_state.callstack.get_current_stack_mut().pc = new_pc;
-        let tmp = src0.widening_mul::<256, 4, 312, 8>(src1).into_limbs();
-        let low = U256::from_limbs([tmp[0], tmp[1], tmp[2], tmp[3]]);
-        let high = U256::from_limbs([tmp[4], tmp[5], tmp[6], tmp[7]]);
+        let (low, high) = full_width_mul(src0, src1);
 
         if set_flags {
             let of = !high.is_zero();
@@ -2,9 +2,9 @@ use zkvm_opcodes::{
     definitions::ret::*, FatPointer, FatPointerValidationException, Opcode, RetABI,
     RetForwardPageType, RetOpcode,
 };
-use zkvm_primitives::aux::Timestamp;
 
 use super::*;
+use crate::tracing::{OnReturnData, VmLocalStateData};
 
 impl<const N: usize, E: VmEncodingMode<N>> DecodedOpcode<N, E> {
     pub fn ret_opcode_apply<
@@ -14,10 +14,12 @@ impl<const N: usize, E: VmEncodingMode<N>> DecodedOpcode<N, E> {
         PP: zkvm_primitives::vm::PrecompilesProcessor,
         DP: zkvm_primitives::vm::DecommittmentProcessor,
         WT: crate::witness_trace::VmWitnessTracer<N, E>,
+        DT: crate::tracing::Tracer<N, E, SupportedMemory = M>,
     >(
         &self,
         vm_state: &mut VmState<S, M, EV, PP, DP, WT, N, E>,
         prestate: PreState<N, E>,
+        tracer: &mut DT,
     ) {
         let PreState { src0, .. } = prestate;
         let mut inner_variant = match self.variant.opcode {
@@ -187,6 +189,21 @@ impl<const N: usize, E: VmEncodingMode<N>> DecodedOpcode<N, E> {
         #[allow(dropping_references)]
         drop(current_callstack);
 
+        if DT::CALL_ON_RETURN {
+            if let Some(returndata_fat_pointer) = fat_ptr_for_returndata {
+                let data = OnReturnData {
+                    return_kind: inner_variant,
+                    returndata_fat_pointer,
+                    ergs_left: ergs_remaining,
+                };
+                tracer.on_return(
+                    VmLocalStateData { vm_local_state: &vm_state.local_state },
+                    data,
+                    &vm_state.memory,
+                );
+            }
+        }
+
         // done with exceptions, so we can pop the callstack entry
         let panicked = inner_variant == RetOpcode::Revert || inner_variant == RetOpcode::Panic;
 
@@ -199,33 +216,10 @@ impl<const N: usize, E: VmEncodingMode<N>> DecodedOpcode<N, E> {
         if !finished_callstack.is_local_frame {
             let returndata_fat_pointer = fat_ptr_for_returndata.unwrap();
 
-            vm_state.memory.finish_global_frame(
+            vm_state.pass_returndata_to_caller(
                 finished_callstack.base_memory_page,
                 returndata_fat_pointer,
-                Timestamp(vm_state.local_state.timestamp),
             );
-
-            vm_state.local_state.registers[RET_IMPLICIT_RETURNDATA_PARAMS_REGISTER as usize] =
-                PrimitiveValue { value: returndata_fat_pointer.to_u256(), is_pointer: true };
-            vm_state.local_state.registers[RET_RESERVED_REGISTER_0 as usize] =
-                PrimitiveValue::empty();
-            vm_state.local_state.registers[RET_RESERVED_REGISTER_1 as usize] =
-                PrimitiveValue::empty();
-            vm_state.local_state.registers[RET_RESERVED_REGISTER_2 as usize] =
-                PrimitiveValue::empty();
-
-            // ALL other registers are zeroed out!
-            for dst in vm_state
-                .local_state
-                .registers
-                .iter_mut()
-                .skip((RET_RESERVED_REGISTER_2 as usize) + 1)
-            {
-                *dst = PrimitiveValue::empty();
-            }
-
-            // clean up context register
-            vm_state.local_state.context_u128_register = 0u128;
         } else {
             debug_assert!(fat_ptr_for_returndata.is_none());
         }
@@ -310,7 +310,7 @@ impl<const N: usize, E: VmEncodingMode<N>> DecodedOpcode<N, E> {
                     if increment_offset {
                         let mut value_limbs = src0_value.into_limbs();
                         value_limbs[0] =
-                            (value_limbs[0] & U64_TOP_32_BITS_MASK) + (increment_offset as u64);
+                            (value_limbs[0] & U64_TOP_32_BITS_MASK) + (incremented_offset as u64);
                         let reg_value = PrimitiveValue {
                             value: U256::from_limbs(value_limbs),
                             is_pointer: src0_is_ptr,
@@ -50,10 +50,12 @@ impl<const N: usize, E: VmEncodingMode<N>> DecodedOpcode<N, E> {
         PP: zkvm_primitives::vm::PrecompilesProcessor,
         DP: zkvm_primitives::vm::DecommittmentProcessor,
         WT: crate::witness_trace::VmWitnessTracer<N, E>,
+        DT: crate::tracing::Tracer<N, E, SupportedMemory = M>,
     >(
         &self,
         vm_state: &mut VmState<S, M, EV, PP, DP, WT, N, E>,
         prestate: PreState<N, E>,
+        tracer: &mut DT,
     ) -> anyhow::Result<()> {
         use zkvm_opcodes::Opcode;
 
@@ -70,8 +72,8 @@ impl<const N: usize, E: VmEncodingMode<N>> DecodedOpcode<N, E> {
             Opcode::Ptr(_) => self.ptr_opcode_apply(vm_state, prestate),
             Opcode::Log(_) => self.log_opcode_apply(vm_state, prestate),
             Opcode::NearCall(_) => self.near_call_opcode_apply(vm_state, prestate),
-            Opcode::FarCall(_) => self.far_call_opcode_apply(vm_state, prestate)?,
-            Opcode::Ret(_) => self.ret_opcode_apply(vm_state, prestate),
+            Opcode::FarCall(_) => self.far_call_opcode_apply(vm_state, prestate, tracer)?,
+            Opcode::Ret(_) => self.ret_opcode_apply(vm_state, prestate, tracer),
             Opcode::UMA(_) => self.uma_opcode_apply(vm_state, prestate),
             Opcode::Invalid(_) => unreachable!(),
         };
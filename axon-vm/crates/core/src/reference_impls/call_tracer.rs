@@ -0,0 +1,152 @@
+use alloy_primitives::{Bytes, U64};
+use zkvm_opcodes::{decoding::VmEncodingMode, FarCallOpcode, FatPointer, RetOpcode};
+
+use super::*;
+use crate::{reference_impls::memory::SimpleMemory, tracing::*};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CallType {
+    Call,
+    DelegateCall,
+    StaticCall,
+}
+
+/// Frame of the call tree in the format of the geth `callTracer`. Ergs are reported
+/// in place of gas, and there is no `value` as the value is passed by the system contracts.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallFrame {
+    #[serde(rename = "type")]
+    pub call_type: CallType,
+    pub from: Address,
+    pub to: Address,
+    pub gas: U64,
+    pub gas_used: U64,
+    pub input: Bytes,
+    #[serde(default, skip_serializing_if = "<[u8]>::is_empty")]
+    pub output: Bytes,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<CallFrame>,
+}
+
+/// Builds the tree of the far calls from the `on_far_call`/`on_return` hooks. Every far call
+/// made from the root frame becomes a separate tree, and the return from the root frame
+/// itself is ignored.
+#[derive(Clone, Debug, Default)]
+pub struct CallTracer {
+    pub stack: Vec<CallFrame>,
+    pub calls: Vec<CallFrame>,
+}
+
+impl CallTracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_calls(self) -> Vec<CallFrame> {
+        assert!(self.stack.is_empty(), "all the calls must be finished");
+
+        self.calls
+    }
+}
+
+fn read_fat_pointer_content(memory: &SimpleMemory, fat_pointer: FatPointer) -> Vec<u8> {
+    fat_pointer_content(fat_pointer, |page, range| {
+        memory.dump_page_content_as_u256_words(page, range)
+    })
+}
+
+impl<const N: usize, E: VmEncodingMode<N>> Tracer<N, E> for CallTracer {
+    const CALL_ON_FAR_CALL: bool = true;
+    const CALL_ON_RETURN: bool = true;
+
+    type SupportedMemory = SimpleMemory;
+    fn before_decoding(
+        &mut self,
+        _state: VmLocalStateData<'_, N, E>,
+        _memory: &Self::SupportedMemory,
+    ) {
+    }
+    fn after_decoding(
+        &mut self,
+        _state: VmLocalStateData<'_, N, E>,
+        _data: CycleData<AfterDecodingData<N, E>>,
+        _memory: &Self::SupportedMemory,
+    ) {
+    }
+    fn before_execution(
+        &mut self,
+        _state: VmLocalStateData<'_, N, E>,
+        _data: CycleData<BeforeExecutionData<N, E>>,
+        _memory: &Self::SupportedMemory,
+    ) {
+    }
+    fn after_execution(
+        &mut self,
+        _state: VmLocalStateData<'_, N, E>,
+        _data: CycleData<AfterExecutionData<N, E>>,
+        _memory: &Self::SupportedMemory,
+    ) {
+    }
+    fn on_far_call(
+        &mut self,
+        _state: VmLocalStateData<'_, N, E>,
+        data: OnFarCallData,
+        memory: &Self::SupportedMemory,
+    ) {
+        let (call_type, from) = match data.far_call_variant {
+            FarCallOpcode::Delegate => (CallType::DelegateCall, data.caller),
+            // mimic call is seen by the callee as a normal call from the mimicked address
+            FarCallOpcode::Mimic => (CallType::Call, data.msg_sender),
+            FarCallOpcode::Normal => (CallType::Call, data.caller),
+        };
+        // static context is inherited, so only the calls made with the static flag are marked
+        let call_type = if data.is_static_call && call_type == CallType::Call {
+            CallType::StaticCall
+        } else {
+            call_type
+        };
+
+        self.stack.push(CallFrame {
+            call_type,
+            from,
+            to: data.callee,
+            gas: U64::from(data.ergs_passed),
+            gas_used: U64::ZERO,
+            input: read_fat_pointer_content(memory, data.calldata_fat_pointer).into(),
+            output: Bytes::new(),
+            error: None,
+            calls: vec![],
+        });
+    }
+    fn on_return(
+        &mut self,
+        _state: VmLocalStateData<'_, N, E>,
+        data: OnReturnData,
+        memory: &Self::SupportedMemory,
+    ) {
+        let Some(mut frame) = self.stack.pop() else {
+            // return from the root frame
+            return;
+        };
+
+        frame.gas_used = frame.gas.saturating_sub(U64::from(data.ergs_left));
+        match data.return_kind {
+            RetOpcode::Ok => {}
+            RetOpcode::Revert => frame.error = Some("execution reverted".to_owned()),
+            RetOpcode::Panic => frame.error = Some("panic".to_owned()),
+        }
+        if data.return_kind != RetOpcode::Panic {
+            frame.output = read_fat_pointer_content(memory, data.returndata_fat_pointer).into();
+        }
+
+        if let Some(parent) = self.stack.last_mut() {
+            parent.calls.push(frame);
+        } else {
+            self.calls.push(frame);
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashSet;
+
+use zkvm_opcodes::{
+    circuit_capacity::BaseLayerCircuitType,
+    decoding::VmEncodingMode,
+    system_params::{EVENT_AUX_BYTE, L1_MESSAGE_AUX_BYTE, STORAGE_AUX_BYTE},
+};
+use zkvm_primitives::{
+    queries::{DecommittmentQuery, LogQuery, MemoryQuery},
+    vm::PrecompileCyclesWitness,
+};
+
+use super::*;
+use crate::{
+    vm_state::{CallStackEntry, VmLocalState},
+    witness_trace::VmWitnessTracer,
+};
+
+type CircuitCycles = [u32; BaseLayerCircuitType::ALL.len()];
+
+/// Estimates how many base layer circuits of every type the executed code needs, so that
+/// the batch can be sealed before it exceeds the capacity of the scheduler.
+///
+/// Rounds of the decommitter and the precompiles are only known from the witness, so the VM
+/// should use the decommitter and the precompiles processor that generate it.
+#[derive(Clone, Debug, Default)]
+pub struct CircuitCapacityTracer {
+    cycles: CircuitCycles,
+    // storage application processes every accessed slot once
+    storage_slots: HashSet<(u8, Address, U256)>,
+    // log queries that would be rolled back if the corresponding frame reverts, as every
+    // rollback passes through the demuxer and the sorters once again
+    pending_rollbacks: Vec<CircuitCycles>,
+}
+
+impl CircuitCapacityTracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of cycles of the circuit used so far.
+    pub fn cycles(&self, circuit: BaseLayerCircuitType) -> u32 {
+        match circuit {
+            BaseLayerCircuitType::StorageApplication => self.storage_slots.len() as u32,
+            circuit => self.cycles[circuit as usize],
+        }
+    }
+
+    pub fn projected_circuits(&self, circuit: BaseLayerCircuitType) -> u32 {
+        circuit.circuits_for_cycles(self.cycles(circuit))
+    }
+
+    pub fn projected_circuits_per_type(&self) -> Vec<(BaseLayerCircuitType, u32)> {
+        BaseLayerCircuitType::ALL
+            .iter()
+            .map(|&circuit| (circuit, self.projected_circuits(circuit)))
+            .collect()
+    }
+
+    pub fn total_projected_circuits(&self) -> u32 {
+        BaseLayerCircuitType::ALL
+            .iter()
+            .map(|&circuit| self.projected_circuits(circuit))
+            .sum()
+    }
+
+    fn add_cycles(&mut self, circuit: BaseLayerCircuitType, cycles: u32) {
+        self.cycles[circuit as usize] += cycles;
+    }
+
+    fn add_pending_rollback(&mut self, circuit: BaseLayerCircuitType) {
+        if let Some(rollbacks) = self.pending_rollbacks.last_mut() {
+            rollbacks[BaseLayerCircuitType::LogDemuxer as usize] += 1;
+            if circuit != BaseLayerCircuitType::LogDemuxer {
+                rollbacks[circuit as usize] += 1;
+            }
+        }
+    }
+}
+
+fn precompile_rounds(
+    round_witness: &PrecompileCyclesWitness,
+) -> Option<(BaseLayerCircuitType, u32)> {
+    let (circuit, rounds) = match round_witness {
+        PrecompileCyclesWitness::Keccak256(witness) => {
+            (BaseLayerCircuitType::Keccak256Precompile, witness.len())
+        }
+        PrecompileCyclesWitness::Sha256(witness) => {
+            (BaseLayerCircuitType::Sha256Precompile, witness.len())
+        }
+        PrecompileCyclesWitness::ECRecover(witness) => {
+            (BaseLayerCircuitType::ECRecoverPrecompile, witness.len())
+        }
+        PrecompileCyclesWitness::Secp256r1Verify(witness) => {
+            (BaseLayerCircuitType::Secp256r1VerifyPrecompile, witness.len())
+        }
+        PrecompileCyclesWitness::Ripemd160(witness) => {
+            (BaseLayerCircuitType::Ripemd160Precompile, witness.len())
+        }
+        PrecompileCyclesWitness::Identity(witness) => {
+            (BaseLayerCircuitType::IdentityPrecompile, witness.len())
+        }
+        PrecompileCyclesWitness::Modexp(witness) => {
+            (BaseLayerCircuitType::ModexpPrecompile, witness.len())
+        }
+        PrecompileCyclesWitness::Blake2f(witness) => {
+            (BaseLayerCircuitType::Blake2fPrecompile, witness.len())
+        }
+        PrecompileCyclesWitness::ECAdd(witness) => {
+            (BaseLayerCircuitType::ECAddPrecompile, witness.len())
+        }
+        PrecompileCyclesWitness::ECMul(witness) => {
+            (BaseLayerCircuitType::ECMulPrecompile, witness.len())
+        }
+        PrecompileCyclesWitness::ECPairing(witness) => {
+            (BaseLayerCircuitType::ECPairingPrecompile, witness.len())
+        }
+        // there are no circuits for the precompiles registered outside of this crate
+        PrecompileCyclesWitness::Erased(_) => return None,
+    };
+
+    Some((circuit, rounds as u32))
+}
+
+impl<const N: usize, E: VmEncodingMode<N>> VmWitnessTracer<N, E> for CircuitCapacityTracer {
+    fn start_new_execution_cycle(&mut self, _current_state: &VmLocalState<N, E>) {
+        self.add_cycles(BaseLayerCircuitType::Vm, 1);
+    }
+
+    fn add_memory_query(&mut self, _monotonic_cycle_counter: u32, _memory_query: MemoryQuery) {
+        self.add_cycles(BaseLayerCircuitType::RamPermutation, 1);
+    }
+
+    fn add_log_query(&mut self, _monotonic_cycle_counter: u32, log_query: LogQuery) {
+        self.add_cycles(BaseLayerCircuitType::LogDemuxer, 1);
+        match log_query.aux_byte {
+            STORAGE_AUX_BYTE => {
+                self.add_cycles(BaseLayerCircuitType::StorageSorter, 1);
+                self.storage_slots
+                    .insert((log_query.shard_id, log_query.address, log_query.key));
+                if log_query.rw_flag {
+                    self.add_pending_rollback(BaseLayerCircuitType::StorageSorter);
+                }
+            }
+            EVENT_AUX_BYTE => {
+                self.add_cycles(BaseLayerCircuitType::EventsSorter, 1);
+                self.add_pending_rollback(BaseLayerCircuitType::EventsSorter);
+            }
+            L1_MESSAGE_AUX_BYTE => {
+                self.add_cycles(BaseLayerCircuitType::L1MessagesSorter, 1);
+                self.add_pending_rollback(BaseLayerCircuitType::L1MessagesSorter);
+            }
+            // precompile calls and queries with unknown aux bytes only pass through the demuxer
+            _ => {}
+        }
+    }
+
+    fn add_decommittment(
+        &mut self,
+        _monotonic_cycle_counter: u32,
+        _decommittment_query: DecommittmentQuery,
+        mem_witness: Vec<U256>,
+    ) {
+        self.add_cycles(BaseLayerCircuitType::CodeDecommitterSorter, 1);
+        // every round of the decommitter outputs two words of the code
+        self.add_cycles(
+            BaseLayerCircuitType::CodeDecommitter,
+            mem_witness.len().div_ceil(2) as u32,
+        );
+        self.add_cycles(BaseLayerCircuitType::RamPermutation, mem_witness.len() as u32);
+    }
+
+    fn add_precompile_call_result(
+        &mut self,
+        _monotonic_cycle_counter: u32,
+        _call_params: LogQuery,
+        mem_witness_in: Vec<MemoryQuery>,
+        memory_witness_out: Vec<MemoryQuery>,
+        round_witness: PrecompileCyclesWitness,
+    ) {
+        self.add_cycles(
+            BaseLayerCircuitType::RamPermutation,
+            (mem_witness_in.len() + memory_witness_out.len()) as u32,
+        );
+        if let Some((circuit, rounds)) = precompile_rounds(&round_witness) {
+            self.add_cycles(circuit, rounds);
+        }
+    }
+
+    fn add_revertable_precompile_call(
+        &mut self,
+        _monotonic_cycle_counter: u32,
+        _call_params: LogQuery,
+    ) {
+        self.add_pending_rollback(BaseLayerCircuitType::LogDemuxer);
+    }
+
+    fn start_new_execution_context(
+        &mut self,
+        _monotonic_cycle_counter: u32,
+        _previous_context: &CallStackEntry<N, E>,
+        _new_context: &CallStackEntry<N, E>,
+    ) {
+        self.pending_rollbacks.push(CircuitCycles::default());
+    }
+
+    fn finish_execution_context(&mut self, _monotonic_cycle_counter: u32, panicked: bool) {
+        let Some(rollbacks) = self.pending_rollbacks.pop() else {
+            return;
+        };
+        // the rollbacks are applied on revert, otherwise the parent frame may still revert them
+        let target = match (panicked, self.pending_rollbacks.last_mut()) {
+            (true, _) => &mut self.cycles,
+            (false, Some(parent)) => parent,
+            (false, None) => return,
+        };
+        for (cycles, rollback_cycles) in target.iter_mut().zip(rollbacks) {
+            *cycles += rollback_cycles;
+        }
+    }
+}
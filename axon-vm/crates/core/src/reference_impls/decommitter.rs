@@ -1,6 +1,7 @@
 use zkvm_primitives::{aux::*, queries::*, vm::*};
 
 use super::*;
+use crate::vm_state::Snapshottable;
 
 pub const MEMORY_CELLS_PER_PAGE: usize = (1 << 16) - 1;
 
@@ -29,6 +30,35 @@ impl<const B: bool> SimpleDecommitter<B> {
     }
 }
 
+// both maps are sorted by the code hash, so equal decommitters give equal snapshots
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SimpleDecommitterSnapshot {
+    pub known_hashes: Vec<(U256, Vec<U256>)>,
+    pub history: Vec<(U256, (u32, u16))>,
+}
+
+impl<const B: bool> Snapshottable for SimpleDecommitter<B> {
+    type Snapshot = SimpleDecommitterSnapshot;
+
+    fn snapshot(&self) -> Self::Snapshot {
+        let mut known_hashes: Vec<_> = self
+            .known_hashes
+            .iter()
+            .map(|(hash, code)| (*hash, code.clone()))
+            .collect();
+        known_hashes.sort_by_key(|(hash, _)| *hash);
+        let mut history: Vec<_> = self.history.iter().map(|(hash, el)| (*hash, *el)).collect();
+        history.sort_by_key(|(hash, _)| *hash);
+
+        SimpleDecommitterSnapshot { known_hashes, history }
+    }
+
+    fn restore_from_snapshot(&mut self, snapshot: Self::Snapshot) {
+        self.known_hashes = snapshot.known_hashes.into_iter().collect();
+        self.history = snapshot.history.into_iter().collect();
+    }
+}
+
 impl<const B: bool> DecommittmentProcessor for SimpleDecommitter<B> {
     fn decommit_into_memory<M: Memory>(
         &mut self,
@@ -0,0 +1,180 @@
+use std::io::Write;
+
+use zkvm_opcodes::decoding::{AllowedPcOrImm, VmEncodingMode};
+
+use super::*;
+use crate::{reference_impls::memory::SimpleMemory, tracing::*, vm_state::Callstack};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ProfiledFrame {
+    // code address of the far call, that is different from the `this` address for delegate calls
+    Far(Address),
+    // the entry PC is unknown for the near calls made before the profiling has started
+    Near { code_address: Address, entry_pc: Option<u64> },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameCosts {
+    pub ergs: u64,
+    pub cycles: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProfileWeight {
+    Ergs,
+    Cycles,
+}
+
+/// Attributes the spent ergs and the executed cycles to the stack of the far and near calls
+/// that executed them. Ergs are measured by the change of `ergs_remaining` summed over the
+/// whole callstack, so the ergs passed to the callees and returned back are not counted. Cycles
+/// are measured by the monotonic cycle counter, as the instructions of the EVM frames may take
+/// several cycles, and the gas they spend is charged from the ergs like for the native code.
+///
+/// Near calls are identified by their entry PC and named by the range of PCs they have
+/// executed, so the result can be matched against the `Opcode::ergs_price` of the code. The near
+/// calls that were already running when the profiling has started are named `near_call unknown`.
+#[derive(Clone, Debug, Default)]
+pub struct ErgsProfiler {
+    pub stacks: HashMap<Vec<ProfiledFrame>, FrameCosts>,
+    // executed PCs of the near calls, not including the ones of their callees
+    pub near_call_pc_ranges: HashMap<(Address, u64), (u64, u64)>,
+    stack: Vec<ProfiledFrame>,
+    ergs_before_cycle: u64,
+    cycle_counter_before_cycle: u32,
+}
+
+impl ErgsProfiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes the stacks in the folded format of `flamegraph.pl` and `inferno`, one
+    /// `frame;frame;frame weight` line per stack.
+    pub fn write_folded<W: Write>(
+        &self,
+        mut writer: W,
+        weight: ProfileWeight,
+    ) -> std::io::Result<()> {
+        let mut lines: Vec<_> = self
+            .stacks
+            .iter()
+            .map(|(stack, costs)| {
+                let value = match weight {
+                    ProfileWeight::Ergs => costs.ergs,
+                    ProfileWeight::Cycles => costs.cycles,
+                };
+                (self.folded_stack(stack), value)
+            })
+            .filter(|&(_, value)| value > 0)
+            .collect();
+        lines.sort();
+
+        for (stack, value) in lines {
+            writeln!(writer, "{stack} {value}")?;
+        }
+
+        Ok(())
+    }
+
+    fn folded_stack(&self, stack: &[ProfiledFrame]) -> String {
+        let frames: Vec<_> = stack
+            .iter()
+            .map(|frame| match *frame {
+                ProfiledFrame::Far(address) => address.to_string(),
+                ProfiledFrame::Near { entry_pc: None, .. } => "near_call unknown".to_owned(),
+                ProfiledFrame::Near { code_address, entry_pc: Some(entry_pc) } => {
+                    let (first_pc, last_pc) = self
+                        .near_call_pc_ranges
+                        .get(&(code_address, entry_pc))
+                        .copied()
+                        .unwrap_or((entry_pc, entry_pc));
+                    format!("near_call {first_pc:#06x}-{last_pc:#06x}")
+                }
+            })
+            .collect();
+
+        frames.join(";")
+    }
+
+    // brings the stack in line with the callstack, that changes by at most one frame per cycle.
+    // Several frames are only pushed at once if the profiling starts in the middle of the
+    // execution, and then their entry PCs are lost: the callers' entries hold the return PCs
+    fn sync_stack<const N: usize, E: VmEncodingMode<N>>(&mut self, callstack: &Callstack<N, E>) {
+        let depth = callstack.depth();
+        self.stack.truncate(depth);
+        let is_new_frame = self.stack.len() + 1 == depth;
+        while self.stack.len() < depth {
+            // the first entry is the empty context below the initial frame
+            let entry = callstack
+                .inner
+                .get(self.stack.len() + 1)
+                .unwrap_or(&callstack.current);
+            let code_address = entry.code_address;
+            let frame = if entry.is_local_frame {
+                let entry_pc = is_new_frame.then(|| entry.pc.as_u64());
+                ProfiledFrame::Near { code_address, entry_pc }
+            } else {
+                ProfiledFrame::Far(code_address)
+            };
+            self.stack.push(frame);
+        }
+    }
+}
+
+impl<const N: usize, E: VmEncodingMode<N>> Tracer<N, E> for ErgsProfiler {
+    const CALL_BEFORE_DECODING: bool = true;
+    const CALL_AFTER_EXECUTION: bool = true;
+
+    type SupportedMemory = SimpleMemory;
+    fn before_decoding(
+        &mut self,
+        state: VmLocalStateData<'_, N, E>,
+        _memory: &Self::SupportedMemory,
+    ) {
+        let callstack = &state.vm_local_state.callstack;
+        self.sync_stack(callstack);
+        self.ergs_before_cycle = callstack.total_ergs_remaining();
+        self.cycle_counter_before_cycle = state.vm_local_state.monotonic_cycle_counter;
+
+        if let Some(&ProfiledFrame::Near { code_address, entry_pc: Some(entry_pc) }) =
+            self.stack.last()
+        {
+            let pc = callstack.current.pc.as_u64();
+            let (first_pc, last_pc) = self
+                .near_call_pc_ranges
+                .entry((code_address, entry_pc))
+                .or_insert((pc, pc));
+            *first_pc = (*first_pc).min(pc);
+            *last_pc = (*last_pc).max(pc);
+        }
+    }
+    fn after_decoding(
+        &mut self,
+        _state: VmLocalStateData<'_, N, E>,
+        _data: CycleData<AfterDecodingData<N, E>>,
+        _memory: &Self::SupportedMemory,
+    ) {
+    }
+    fn before_execution(
+        &mut self,
+        _state: VmLocalStateData<'_, N, E>,
+        _data: CycleData<BeforeExecutionData<N, E>>,
+        _memory: &Self::SupportedMemory,
+    ) {
+    }
+    fn after_execution(
+        &mut self,
+        state: VmLocalStateData<'_, N, E>,
+        _data: CycleData<AfterExecutionData<N, E>>,
+        _memory: &Self::SupportedMemory,
+    ) {
+        let ergs_spent = self
+            .ergs_before_cycle
+            .saturating_sub(state.vm_local_state.callstack.total_ergs_remaining());
+        let cycles = state.vm_local_state.monotonic_cycle_counter - self.cycle_counter_before_cycle;
+        let costs = self.stacks.entry(self.stack.clone()).or_default();
+        costs.ergs += ergs_spent;
+        costs.cycles += cycles as u64;
+    }
+}
@@ -1,7 +1,12 @@
 use zkvm_opcodes::system_params::{EVENT_AUX_BYTE, L1_MESSAGE_AUX_BYTE};
-use zkvm_primitives::{aux::Timestamp, queries::LogQuery, vm::EventSink};
+use zkvm_primitives::{
+    aux::{SnapshotId, Timestamp},
+    queries::LogQuery,
+    vm::EventSink,
+};
 
 use super::*;
+use crate::vm_state::{EventDeltas, Snapshottable};
 
 #[derive(Clone, Copy)]
 pub struct EventMessage {
@@ -26,7 +31,7 @@ impl std::fmt::Debug for EventMessage {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct ApplicationData<T> {
     pub forward: Vec<T>,
     pub rollbacks: Vec<T>,
@@ -44,9 +49,33 @@ impl<T> ApplicationData<T> {
     }
 }
 
+/// Position in the history of the frames stack that a snapshot can be rolled back to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct HistoryMarker {
+    pub id: SnapshotId,
+    pub frames_depth: usize,
+    pub forward_len: usize,
+    pub rollbacks_len: usize,
+}
+
+impl HistoryMarker {
+    pub fn new<T>(id: SnapshotId, frames_stack: &[ApplicationData<T>]) -> Self {
+        let current_frame = frames_stack.last().expect("frame must be started");
+
+        Self {
+            id,
+            frames_depth: frames_stack.len(),
+            forward_len: current_frame.forward.len(),
+            rollbacks_len: current_frame.rollbacks.len(),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct InMemoryEventSink {
     pub frames_stack: Vec<ApplicationData<LogQuery>>,
+    pub snapshots: Vec<HistoryMarker>,
+    pub next_snapshot_id: u32,
 }
 
 // as usual, if we rollback the current frame then we apply changes to storage immediately,
@@ -63,6 +92,8 @@ impl InMemoryEventSink {
         Self {
             // we add single frame that will serve as a last one
             frames_stack: vec![ApplicationData::empty()],
+            snapshots: vec![],
+            next_snapshot_id: 0,
         }
     }
 
@@ -71,60 +102,66 @@ impl InMemoryEventSink {
         let full_history = self.frames_stack.pop().unwrap();
         // we forget rollbacks as we have finished the execution and can just apply them
         let ApplicationData { forward, rollbacks: _ } = full_history;
-        let history = forward.clone();
-        let mut tmp = HashMap::<u32, LogQuery>::with_capacity(forward.len());
-
-        // note that we only use "forward" part and discard the rollbacks at the end,
-        // since if rollbacks of parents were not appended anywhere we just still keep them
-        for el in forward.into_iter() {
-            // we are time ordered here in terms of rollbacks
-            if tmp.get(&el.timestamp.0).is_some() {
-                assert!(el.rollback);
-                tmp.remove(&el.timestamp.0);
-            } else {
-                assert!(!el.rollback);
-                tmp.insert(el.timestamp.0, el);
-            }
-        }
+        let (events, l1_messages) = net_messages(&forward);
 
-        // naturally sorted by timestamp
-        let mut keys: Vec<_> = tmp.keys().cloned().collect();
-        keys.sort();
-
-        let mut events = vec![];
-        let mut l1_messages = vec![];
-
-        for k in keys.into_iter() {
-            let el = tmp.remove(&k).unwrap();
-            let LogQuery {
-                shard_id,
-                is_service,
-                tx_number_in_block,
-                address,
-                key,
-                written_value,
-                aux_byte,
-                ..
-            } = el;
-
-            let event = EventMessage {
-                shard_id,
-                is_first: is_service,
-                tx_number_in_block,
-                address,
-                key,
-                value: written_value,
-            };
-
-            if aux_byte == EVENT_AUX_BYTE {
-                events.push(event);
-            } else {
-                l1_messages.push(event);
-            }
+        (forward, events, l1_messages)
+    }
+}
+
+// note that we only use "forward" part and discard the rollbacks at the end,
+// since if rollbacks of parents were not appended anywhere we just still keep them
+fn net_messages<'a>(
+    history: impl IntoIterator<Item = &'a LogQuery>,
+) -> (Vec<EventMessage>, Vec<EventMessage>) {
+    let mut tmp = HashMap::<u32, LogQuery>::new();
+    for el in history.into_iter() {
+        // we are time ordered here in terms of rollbacks
+        if tmp.get(&el.timestamp.0).is_some() {
+            assert!(el.rollback);
+            tmp.remove(&el.timestamp.0);
+        } else {
+            assert!(!el.rollback);
+            tmp.insert(el.timestamp.0, *el);
         }
+    }
+
+    // naturally sorted by timestamp
+    let mut keys: Vec<_> = tmp.keys().cloned().collect();
+    keys.sort();
+
+    let mut events = vec![];
+    let mut l1_messages = vec![];
 
-        (history, events, l1_messages)
+    for k in keys.into_iter() {
+        let el = tmp.remove(&k).unwrap();
+        let LogQuery {
+            shard_id,
+            is_service,
+            tx_number_in_block,
+            address,
+            key,
+            written_value,
+            aux_byte,
+            ..
+        } = el;
+
+        let event = EventMessage {
+            shard_id,
+            is_first: is_service,
+            tx_number_in_block,
+            address,
+            key,
+            value: written_value,
+        };
+
+        if aux_byte == EVENT_AUX_BYTE {
+            events.push(event);
+        } else {
+            l1_messages.push(event);
+        }
     }
+
+    (events, l1_messages)
 }
 
 impl EventSink for InMemoryEventSink {
@@ -168,5 +205,77 @@ impl EventSink for InMemoryEventSink {
             // we need to prepend rollbacks. No reverse here, as we do not care yet!
             parent_data.rollbacks.extend(rollbacks);
         }
+
+        // snapshots made in the finished frame can not be rolled back to anymore
+        let frames_depth = self.frames_stack.len();
+        self.snapshots
+            .retain(|marker| marker.frames_depth <= frames_depth);
+    }
+    fn make_snapshot(&mut self) -> SnapshotId {
+        let id = SnapshotId(self.next_snapshot_id);
+        self.next_snapshot_id += 1;
+        self.snapshots
+            .push(HistoryMarker::new(id, &self.frames_stack));
+
+        id
+    }
+    fn rollback_to(&mut self, snapshot: SnapshotId) {
+        let position = self
+            .snapshots
+            .iter()
+            .position(|marker| marker.id == snapshot)
+            .expect("snapshot must be valid");
+        let marker = self.snapshots[position];
+        self.snapshots.truncate(position + 1);
+        assert_eq!(
+            self.frames_stack.len(),
+            marker.frames_depth,
+            "rollback must be done in the frame of the snapshot"
+        );
+
+        // events do not change any state, so it's enough to forget them
+        let frame_data = self.frames_stack.last_mut().unwrap();
+        frame_data.forward.truncate(marker.forward_len);
+        frame_data.rollbacks.truncate(marker.rollbacks_len);
+    }
+}
+
+impl EventDeltas for InMemoryEventSink {
+    fn event_deltas_since(&self, timestamp: Timestamp) -> (Vec<EventMessage>, Vec<EventMessage>) {
+        // the frames that have not finished yet still keep their own history
+        let history = self
+            .frames_stack
+            .iter()
+            .flat_map(|frame| &frame.forward)
+            .filter(|query| query.timestamp.0 >= timestamp.0);
+
+        net_messages(history)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct InMemoryEventSinkSnapshot {
+    pub frames_stack: Vec<ApplicationData<LogQuery>>,
+    pub snapshots: Vec<HistoryMarker>,
+    pub next_snapshot_id: u32,
+}
+
+impl Snapshottable for InMemoryEventSink {
+    type Snapshot = InMemoryEventSinkSnapshot;
+
+    fn snapshot(&self) -> Self::Snapshot {
+        InMemoryEventSinkSnapshot {
+            frames_stack: self.frames_stack.clone(),
+            snapshots: self.snapshots.clone(),
+            next_snapshot_id: self.next_snapshot_id,
+        }
+    }
+
+    fn restore_from_snapshot(&mut self, snapshot: Self::Snapshot) {
+        let InMemoryEventSinkSnapshot { frames_stack, snapshots, next_snapshot_id } = snapshot;
+
+        self.frames_stack = frames_stack;
+        self.snapshots = snapshots;
+        self.next_snapshot_id = next_snapshot_id;
     }
 }
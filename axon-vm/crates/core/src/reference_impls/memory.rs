@@ -11,7 +11,7 @@ use zkvm_primitives::{
 };
 
 use super::*;
-use crate::vm_state::{CallStackEntry, PrimitiveValue};
+use crate::vm_state::{CallStackEntry, PageSnapshot, PrimitiveValue, Snapshottable};
 
 pub struct ReusablePool<T: Sized, InitFn: Fn() -> T, OnPullFn: Fn(&mut T), OnReturnFn: Fn(&mut T)> {
     pool: Vec<T>,
@@ -57,7 +57,7 @@ impl<T: Sized, InitFn: Fn() -> T, OnPullFn: Fn(&mut T), OnReturnFn: Fn(&mut T)>
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum Indirection {
     Heap(usize),
     AuxHeap(usize),
@@ -397,6 +397,110 @@ impl<S: BuildHasher + Default> SimpleMemory<S> {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SimpleMemorySnapshot {
+    pub stack_pages: Vec<(u32, PageSnapshot<PrimitiveValue>)>,
+    pub heaps: Vec<((u32, PageSnapshot<U256>), (u32, PageSnapshot<U256>))>,
+    // maps are sorted by the page number, so equal memories give equal snapshots
+    pub code_pages: Vec<(u32, PageSnapshot<U256>)>,
+    pub pages_with_extended_lifetime: Vec<(u32, PageSnapshot<U256>)>,
+    pub page_numbers_indirections: Vec<(u32, Indirection)>,
+    pub indirections_to_cleanup_on_return: Vec<Vec<u32>>,
+}
+
+fn sorted_pages_snapshot<S: BuildHasher>(
+    pages: &HashMap<u32, Vec<U256>, S>,
+) -> Vec<(u32, PageSnapshot<U256>)> {
+    let mut result: Vec<_> = pages
+        .iter()
+        .map(|(page, content)| (*page, PageSnapshot::from_page(content)))
+        .collect();
+    result.sort_by_key(|(page, _)| *page);
+
+    result
+}
+
+// pools are not a part of the snapshot, the restored memory keeps its own ones
+impl<S: BuildHasher + Default> Snapshottable for SimpleMemory<S> {
+    type Snapshot = SimpleMemorySnapshot;
+
+    fn snapshot(&self) -> Self::Snapshot {
+        let mut page_numbers_indirections: Vec<_> = self
+            .page_numbers_indirections
+            .iter()
+            .map(|(page, indirection)| (*page, *indirection))
+            .collect();
+        page_numbers_indirections.sort_by_key(|(page, _)| *page);
+
+        let indirections_to_cleanup_on_return = self
+            .indirections_to_cleanup_on_return
+            .iter()
+            .map(|set| {
+                let mut pages: Vec<_> = set.iter().copied().collect();
+                pages.sort_unstable();
+                pages
+            })
+            .collect();
+
+        SimpleMemorySnapshot {
+            stack_pages: self
+                .stack_pages
+                .iter()
+                .map(|(page, content)| (*page, PageSnapshot::from_page(content)))
+                .collect(),
+            heaps: self
+                .heaps
+                .iter()
+                .map(|((heap_page, heap), (aux_heap_page, aux_heap))| {
+                    (
+                        (*heap_page, PageSnapshot::from_page(heap)),
+                        (*aux_heap_page, PageSnapshot::from_page(aux_heap)),
+                    )
+                })
+                .collect(),
+            code_pages: sorted_pages_snapshot(&self.code_pages),
+            pages_with_extended_lifetime: sorted_pages_snapshot(&self.pages_with_extended_lifetime),
+            page_numbers_indirections,
+            indirections_to_cleanup_on_return,
+        }
+    }
+
+    fn restore_from_snapshot(&mut self, snapshot: Self::Snapshot) {
+        let SimpleMemorySnapshot {
+            stack_pages,
+            heaps,
+            code_pages,
+            pages_with_extended_lifetime,
+            page_numbers_indirections,
+            indirections_to_cleanup_on_return,
+        } = snapshot;
+
+        self.stack_pages = stack_pages
+            .into_iter()
+            .map(|(page, content)| (page, content.into_page()))
+            .collect();
+        self.heaps = heaps
+            .into_iter()
+            .map(|((heap_page, heap), (aux_heap_page, aux_heap))| {
+                ((heap_page, heap.into_page()), (aux_heap_page, aux_heap.into_page()))
+            })
+            .collect();
+        self.code_pages = code_pages
+            .into_iter()
+            .map(|(page, content)| (page, content.into_page()))
+            .collect();
+        self.pages_with_extended_lifetime = pages_with_extended_lifetime
+            .into_iter()
+            .map(|(page, content)| (page, content.into_page()))
+            .collect();
+        self.page_numbers_indirections = page_numbers_indirections.into_iter().collect();
+        self.indirections_to_cleanup_on_return = indirections_to_cleanup_on_return
+            .into_iter()
+            .map(|pages| pages.into_iter().collect())
+            .collect();
+    }
+}
+
 impl Memory for SimpleMemory {
     fn execute_partial_query(
         &mut self,
@@ -2,6 +2,9 @@ use std::collections::HashMap;
 
 use super::*;
 
+pub mod call_tracer;
+pub mod circuit_capacity;
 pub mod decommitter;
+pub mod ergs_profiler;
 pub mod event_sink;
 pub mod memory;
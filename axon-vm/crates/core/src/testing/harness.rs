@@ -0,0 +1,242 @@
+use zkvm_opcodes::{
+    REGISTERS_COUNT,
+    assembly::assemble,
+    bytecode_to_code_hash_for_mode,
+    decoding::{EncodingModeProduction, VmEncodingMode},
+    evm_bytecode_to_code_hash,
+    system_params::{BOOTLOADER_FORMAL_ADDRESS, DEPLOYER_SYSTEM_CONTRACT_ADDRESS},
+};
+use zkvm_primitives::aux::MemoryPage;
+
+use super::*;
+use crate::{
+    block_properties::BlockProperties,
+    tracing::*,
+    vm_state::{CallStackEntry, ExecutionResult, PrimitiveValue, RunLimits, VmState},
+    witness_trace::VmWitnessTracer,
+};
+
+pub type TestVmState<WT = DummyTracer> = VmState<
+    InMemoryStorage,
+    SimpleMemory,
+    InMemoryEventSink,
+    DefaultPrecompilesProcessor<true>,
+    SimpleDecommitter<true>,
+    WT,
+>;
+
+pub const DEFAULT_CYCLE_LIMIT: u32 = 10_000;
+pub const DEFAULT_ERGS_LIMIT: u32 = 10_000_000;
+
+#[derive(Clone, Debug)]
+pub struct TestRunResult {
+    pub execution: ExecutionResult,
+    // registers of the bootloader right before it has returned, as the return clears them
+    pub registers: [PrimitiveValue; REGISTERS_COUNT],
+}
+
+impl TestRunResult {
+    // registers are numbered as in the assembly, so r0 is always zero
+    pub fn register(&self, idx: usize) -> U256 {
+        match idx {
+            0 => U256::ZERO,
+            idx => self.registers[idx - 1].value,
+        }
+    }
+}
+
+/// Runs the programs written in assembly on the reference implementations of the VM
+/// components. The bootloader program is executed in the kernel mode with `ergs_limit` ergs,
+/// and can call the contracts deployed with [`TestHarness::deploy`].
+pub struct TestHarness<WT: VmWitnessTracer<8, EncodingModeProduction> = DummyTracer> {
+    pub vm: TestVmState<WT>,
+    pub cycle_limit: u32,
+    pub ergs_limit: u32,
+    known_code_hashes: HashSet<U256>,
+}
+
+impl Default for TestHarness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestHarness {
+    pub fn new() -> Self {
+        Self::with_witness_tracer(DummyTracer)
+    }
+}
+
+impl<WT: VmWitnessTracer<8, EncodingModeProduction>> TestHarness<WT> {
+    pub fn with_witness_tracer(witness_tracer: WT) -> Self {
+        let vm = VmState::empty_state(
+            InMemoryStorage::new(),
+            SimpleMemory::new_without_preallocations(),
+            InMemoryEventSink::new(),
+            DefaultPrecompilesProcessor::<true>,
+            SimpleDecommitter::<true>::new(),
+            witness_tracer,
+            BlockProperties { default_aa_code_hash: U256::ZERO, evm_block_env: Default::default() },
+        );
+
+        Self {
+            vm,
+            cycle_limit: DEFAULT_CYCLE_LIMIT,
+            ergs_limit: DEFAULT_ERGS_LIMIT,
+            known_code_hashes: HashSet::new(),
+        }
+    }
+
+    pub fn deploy(&mut self, address: Address, source: &str) {
+        self.deploy_code(address, assemble_code(source));
+    }
+
+    pub fn deploy_code(&mut self, address: Address, mut code: Vec<U256>) {
+        // code hash is only defined for the odd number of words
+        if code.len().is_multiple_of(2) {
+            code.push(U256::ZERO);
+        }
+        let code_bytes: Vec<[u8; 32]> = code.iter().map(|word| word.to_be_bytes()).collect();
+        let code_hash = bytecode_to_code_hash_for_mode::<8, EncodingModeProduction>(&code_bytes)
+            .expect("code must be of valid length");
+        let code_hash = U256::from_be_bytes(code_hash);
+
+        self.vm.storage.populate(vec![(
+            0,
+            *DEPLOYER_SYSTEM_CONTRACT_ADDRESS,
+            U256::from_be_slice(address.as_slice()),
+            code_hash,
+        )]);
+        if self.known_code_hashes.insert(code_hash) {
+            self.vm
+                .decommittment_processor
+                .populate(vec![(code_hash, code)]);
+        }
+    }
+
+    /// Deploys the EVM `code` under its EVM versioned hash, so the far calls to `address` run it
+    /// in the interpreter.
+    pub fn deploy_evm(&mut self, address: Address, code: &[u8]) {
+        let code_hash = evm_bytecode_to_code_hash(code).expect("code must be of valid length");
+        let code_hash = U256::from_be_bytes(code_hash);
+        let code_words = code
+            .chunks(32)
+            .map(|chunk| {
+                let mut word = [0u8; 32];
+                word[..chunk.len()].copy_from_slice(chunk);
+                U256::from_be_bytes(word)
+            })
+            .collect();
+
+        self.vm.storage.populate(vec![(
+            0,
+            *DEPLOYER_SYSTEM_CONTRACT_ADDRESS,
+            U256::from_be_slice(address.as_slice()),
+            code_hash,
+        )]);
+        if self.known_code_hashes.insert(code_hash) {
+            self.vm
+                .decommittment_processor
+                .populate(vec![(code_hash, code_words)]);
+        }
+    }
+
+    /// Places the bootloader code and starts its frame without running it.
+    pub fn load_bootloader(&mut self, code: Vec<U256>) {
+        let base_page = self.vm.new_base_memory_page_on_call();
+        let code_page = CallStackEntry::<8>::code_page_candidate_from_base(base_page);
+        self.vm.memory.populate_code(vec![(code_page.0, code)]);
+
+        let mut bootloader_context = CallStackEntry::empty_context();
+        bootloader_context.this_address = *BOOTLOADER_FORMAL_ADDRESS;
+        bootloader_context.msg_sender = *BOOTLOADER_FORMAL_ADDRESS;
+        bootloader_context.code_address = *BOOTLOADER_FORMAL_ADDRESS;
+        bootloader_context.base_memory_page = base_page;
+        bootloader_context.code_page = MemoryPage(code_page.0);
+        bootloader_context.ergs_remaining = self.ergs_limit;
+        self.vm.push_bootloader_context(0, bootloader_context);
+        self.vm.increment_memory_pages_on_call();
+    }
+
+    pub fn run(&mut self, bootloader_source: &str) -> TestRunResult {
+        self.run_code(assemble_code(bootloader_source))
+    }
+
+    pub fn run_code(&mut self, bootloader_code: Vec<U256>) -> TestRunResult {
+        self.load_bootloader(bootloader_code);
+
+        let mut tracer = BootloaderRegistersTracer::default();
+        let limits = RunLimits { cycles: Some(self.cycle_limit), ergs: None };
+        let execution = self
+            .vm
+            .run(&mut tracer, limits)
+            .expect("execution must succeed");
+        let registers = tracer.registers.unwrap_or(self.vm.local_state.registers);
+
+        TestRunResult { execution, registers }
+    }
+
+    pub fn storage_value(&self, address: Address, key: U256) -> U256 {
+        self.vm.storage.inner[0]
+            .get(&address)
+            .and_then(|slots| slots.get(&key))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+fn assemble_code(source: &str) -> Vec<U256> {
+    assemble::<8, EncodingModeProduction>(source)
+        .unwrap_or_else(|err| panic!("invalid assembly: {err}"))
+        .code_words()
+}
+
+// Captures the registers at the return from the bootloader frame, as the return clears them
+#[derive(Debug, Default)]
+struct BootloaderRegistersTracer {
+    registers: Option<[PrimitiveValue; REGISTERS_COUNT]>,
+}
+
+impl<const N: usize, E: VmEncodingMode<N>> Tracer<N, E> for BootloaderRegistersTracer {
+    const CALL_ON_RETURN: bool = true;
+
+    type SupportedMemory = SimpleMemory;
+    fn before_decoding(
+        &mut self,
+        _state: VmLocalStateData<'_, N, E>,
+        _memory: &Self::SupportedMemory,
+    ) {
+    }
+    fn after_decoding(
+        &mut self,
+        _state: VmLocalStateData<'_, N, E>,
+        _data: CycleData<AfterDecodingData<N, E>>,
+        _memory: &Self::SupportedMemory,
+    ) {
+    }
+    fn before_execution(
+        &mut self,
+        _state: VmLocalStateData<'_, N, E>,
+        _data: CycleData<BeforeExecutionData<N, E>>,
+        _memory: &Self::SupportedMemory,
+    ) {
+    }
+    fn after_execution(
+        &mut self,
+        _state: VmLocalStateData<'_, N, E>,
+        _data: CycleData<AfterExecutionData<N, E>>,
+        _memory: &Self::SupportedMemory,
+    ) {
+    }
+    fn on_return(
+        &mut self,
+        state: VmLocalStateData<'_, N, E>,
+        _data: OnReturnData,
+        _memory: &Self::SupportedMemory,
+    ) {
+        // bootloader frame is the only one above the initial empty frame
+        if state.vm_local_state.callstack.depth() == 1 {
+            self.registers = Some(state.vm_local_state.registers);
+        }
+    }
+}
@@ -5,6 +5,7 @@ use super::*;
 pub const NUM_SHARDS: usize = 2;
 
 use crate::reference_impls::{decommitter::SimpleDecommitter, event_sink::*, memory::SimpleMemory};
+pub mod harness;
 pub mod simple_tracer;
 pub mod storage;
 
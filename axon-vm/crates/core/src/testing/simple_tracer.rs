@@ -11,7 +11,7 @@ impl Tracer for NoopTracer {
     fn after_decoding(
         &mut self,
         _state: VmLocalStateData<'_>,
-        _data: AfterDecodingData,
+        _data: CycleData<AfterDecodingData>,
         _memory: &Self::SupportedMemory,
     ) {
     }
@@ -19,7 +19,7 @@ impl Tracer for NoopTracer {
     fn before_execution(
         &mut self,
         _state: VmLocalStateData<'_>,
-        _data: BeforeExecutionData,
+        _data: CycleData<BeforeExecutionData>,
         _memory: &Self::SupportedMemory,
     ) {
     }
@@ -27,7 +27,7 @@ impl Tracer for NoopTracer {
     fn after_execution(
         &mut self,
         _state: VmLocalStateData<'_>,
-        _data: AfterExecutionData,
+        _data: CycleData<AfterExecutionData>,
         _memory: &Self::SupportedMemory,
     ) {
     }
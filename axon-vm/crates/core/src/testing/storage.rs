@@ -1,15 +1,22 @@
 use zkvm_primitives::{
-    aux::Timestamp,
+    aux::{SnapshotId, Timestamp},
     vm::{RefundType, Storage},
 };
 
 use super::{ApplicationData, *};
+use crate::reference_impls::event_sink::HistoryMarker;
+use crate::vm_state::{Snapshottable, StorageDelta, StorageDeltas};
 
 #[derive(Debug, Clone)]
 pub struct InMemoryStorage {
     pub inner: [HashMap<Address, HashMap<U256, U256>>; NUM_SHARDS],
     pub cold_warm_markers: [HashMap<Address, HashSet<U256>>; NUM_SHARDS],
     pub frames_stack: Vec<ApplicationData<LogQuery>>,
+    // together with the length of `warmed_since_snapshot` at the moment of the snapshot
+    pub snapshots: Vec<(HistoryMarker, usize)>,
+    // slots that became warm while there were any snapshots, so we can make them cold on rollback
+    pub warmed_since_snapshot: Vec<(u8, Address, U256)>,
+    pub next_snapshot_id: u32,
 }
 
 // as usual, if we rollback the current frame then we apply changes to storage immediately,
@@ -27,6 +34,9 @@ impl InMemoryStorage {
             inner: [(); NUM_SHARDS].map(|_| HashMap::default()),
             cold_warm_markers: [(); NUM_SHARDS].map(|_| HashMap::default()),
             frames_stack: vec![ApplicationData::empty()],
+            snapshots: vec![],
+            warmed_since_snapshot: vec![],
+            next_snapshot_id: 0,
         }
     }
 
@@ -102,6 +112,10 @@ impl Storage for InMemoryStorage {
             let warm = address_level_warm_map.contains(&query.key);
             if !warm {
                 address_level_warm_map.insert(query.key);
+                if !self.snapshots.is_empty() {
+                    self.warmed_since_snapshot
+                        .push((query.shard_id, query.address, query.key));
+                }
             }
             query.read_value = current_value;
 
@@ -123,6 +137,10 @@ impl Storage for InMemoryStorage {
             let warm = address_level_warm_map.contains(&query.key);
             if !warm {
                 address_level_warm_map.insert(query.key);
+                if !self.snapshots.is_empty() {
+                    self.warmed_since_snapshot
+                        .push((query.shard_id, query.address, query.key));
+                }
             }
             query.read_value = current_value;
             frame_data.forward.push(query);
@@ -149,16 +167,7 @@ impl Storage for InMemoryStorage {
         if panicked {
             // perform actual rollback
             for query in rollbacks.iter().rev() {
-                let LogQuery { shard_id, address, key, read_value, written_value, .. } = *query;
-                let shard_level_map = &mut self.inner[shard_id as usize];
-                let address_level_map = shard_level_map
-                    .get_mut(&address)
-                    .expect("must always exist on rollback");
-                let current_value_ref = address_level_map
-                    .get_mut(&key)
-                    .expect("must always exist on rollback");
-                assert_eq!(*current_value_ref, written_value); // compare current value
-                *current_value_ref = read_value; // write back an old value
+                revert_write(&mut self.inner, query);
             }
 
             parent_data.forward.extend(forward);
@@ -169,5 +178,182 @@ impl Storage for InMemoryStorage {
             // we need to prepend rollbacks. No reverse here, as we do not care yet!
             parent_data.rollbacks.extend(rollbacks);
         }
+
+        // snapshots made in the finished frame can not be rolled back to anymore
+        let frames_depth = self.frames_stack.len();
+        self.snapshots
+            .retain(|(marker, _)| marker.frames_depth <= frames_depth);
+        if self.snapshots.is_empty() {
+            self.warmed_since_snapshot.clear();
+        }
+    }
+    fn make_snapshot(&mut self) -> SnapshotId {
+        let id = SnapshotId(self.next_snapshot_id);
+        self.next_snapshot_id += 1;
+        let marker = HistoryMarker::new(id, &self.frames_stack);
+        self.snapshots
+            .push((marker, self.warmed_since_snapshot.len()));
+
+        id
+    }
+    fn rollback_to(&mut self, snapshot: SnapshotId) {
+        let position = self
+            .snapshots
+            .iter()
+            .position(|(marker, _)| marker.id == snapshot)
+            .expect("snapshot must be valid");
+        let (marker, warmed_len) = self.snapshots[position];
+        self.snapshots.truncate(position + 1);
+        assert_eq!(
+            self.frames_stack.len(),
+            marker.frames_depth,
+            "rollback must be done in the frame of the snapshot"
+        );
+
+        // rollbacks of the successfully finished child frames were carried to this frame,
+        // and the ones of the panicked frames are already applied
+        let frame_data = self.frames_stack.last_mut().unwrap();
+        for query in frame_data.rollbacks.drain(marker.rollbacks_len..).rev() {
+            revert_write(&mut self.inner, &query);
+        }
+        frame_data.forward.truncate(marker.forward_len);
+
+        for (shard_id, address, key) in self.warmed_since_snapshot.drain(warmed_len..) {
+            let address_level_warm_map = self.cold_warm_markers[shard_id as usize]
+                .get_mut(&address)
+                .expect("must always exist on rollback");
+            address_level_warm_map.remove(&key);
+        }
+    }
+}
+
+impl StorageDeltas for InMemoryStorage {
+    fn storage_deltas_since(&self, timestamp: Timestamp) -> Vec<StorageDelta> {
+        // the first write into the slot has read the initial value, and the current value is
+        // the final one. Rollbacks only restore the values, so they are not needed here
+        let mut initial_values = HashMap::<(u8, Address, U256), (u32, U256)>::new();
+        for query in self.frames_stack.iter().flat_map(|frame| &frame.forward) {
+            if !query.rw_flag || query.rollback || query.timestamp.0 < timestamp.0 {
+                continue;
+            }
+            let slot = (query.shard_id, query.address, query.key);
+            let (first_write_timestamp, initial_value) = initial_values
+                .entry(slot)
+                .or_insert((query.timestamp.0, query.read_value));
+            if query.timestamp.0 < *first_write_timestamp {
+                *first_write_timestamp = query.timestamp.0;
+                *initial_value = query.read_value;
+            }
+        }
+
+        let mut deltas: Vec<_> = initial_values
+            .into_iter()
+            .map(|((shard_id, address, key), (_, initial_value))| StorageDelta {
+                shard_id,
+                address,
+                key,
+                initial_value,
+                final_value: self.inner[shard_id as usize][&address][&key],
+            })
+            .filter(|delta| delta.initial_value != delta.final_value)
+            .collect();
+        deltas.sort_by_key(|delta| (delta.shard_id, delta.address, delta.key));
+
+        deltas
+    }
+}
+
+fn revert_write(inner: &mut [HashMap<Address, HashMap<U256, U256>>; NUM_SHARDS], query: &LogQuery) {
+    let LogQuery { shard_id, address, key, read_value, written_value, .. } = *query;
+    let shard_level_map = &mut inner[shard_id as usize];
+    let address_level_map = shard_level_map
+        .get_mut(&address)
+        .expect("must always exist on rollback");
+    let current_value_ref = address_level_map
+        .get_mut(&key)
+        .expect("must always exist on rollback");
+    assert_eq!(*current_value_ref, written_value); // compare current value
+    *current_value_ref = read_value; // write back an old value
+}
+
+// maps are sorted by the address and the key, so equal storages give equal snapshots
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct InMemoryStorageSnapshot {
+    pub inner: [Vec<(Address, Vec<(U256, U256)>)>; NUM_SHARDS],
+    pub cold_warm_markers: [Vec<(Address, Vec<U256>)>; NUM_SHARDS],
+    pub frames_stack: Vec<ApplicationData<LogQuery>>,
+    pub snapshots: Vec<(HistoryMarker, usize)>,
+    pub warmed_since_snapshot: Vec<(u8, Address, U256)>,
+    pub next_snapshot_id: u32,
+}
+
+impl Snapshottable for InMemoryStorage {
+    type Snapshot = InMemoryStorageSnapshot;
+
+    fn snapshot(&self) -> Self::Snapshot {
+        let inner = self.inner.each_ref().map(|shard_level_map| {
+            let mut shard: Vec<_> = shard_level_map
+                .iter()
+                .map(|(address, address_level_map)| {
+                    let mut slots: Vec<_> = address_level_map
+                        .iter()
+                        .map(|(key, value)| (*key, *value))
+                        .collect();
+                    slots.sort_by_key(|(key, _)| *key);
+                    (*address, slots)
+                })
+                .collect();
+            shard.sort_by_key(|(address, _)| *address);
+            shard
+        });
+        let cold_warm_markers = self.cold_warm_markers.each_ref().map(|shard_level_map| {
+            let mut shard: Vec<_> = shard_level_map
+                .iter()
+                .map(|(address, address_level_set)| {
+                    let mut keys: Vec<_> = address_level_set.iter().copied().collect();
+                    keys.sort_unstable();
+                    (*address, keys)
+                })
+                .collect();
+            shard.sort_by_key(|(address, _)| *address);
+            shard
+        });
+
+        InMemoryStorageSnapshot {
+            inner,
+            cold_warm_markers,
+            frames_stack: self.frames_stack.clone(),
+            snapshots: self.snapshots.clone(),
+            warmed_since_snapshot: self.warmed_since_snapshot.clone(),
+            next_snapshot_id: self.next_snapshot_id,
+        }
+    }
+
+    fn restore_from_snapshot(&mut self, snapshot: Self::Snapshot) {
+        let InMemoryStorageSnapshot {
+            inner,
+            cold_warm_markers,
+            frames_stack,
+            snapshots,
+            warmed_since_snapshot,
+            next_snapshot_id,
+        } = snapshot;
+
+        self.inner = inner.map(|shard| {
+            shard
+                .into_iter()
+                .map(|(address, slots)| (address, slots.into_iter().collect()))
+                .collect()
+        });
+        self.cold_warm_markers = cold_warm_markers.map(|shard| {
+            shard
+                .into_iter()
+                .map(|(address, keys)| (address, keys.into_iter().collect()))
+                .collect()
+        });
+        self.frames_stack = frames_stack;
+        self.snapshots = snapshots;
+        self.warmed_since_snapshot = warmed_since_snapshot;
+        self.next_snapshot_id = next_snapshot_id;
     }
 }
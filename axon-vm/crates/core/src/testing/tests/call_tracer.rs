@@ -0,0 +1,305 @@
+use zkvm_opcodes::{
+    system_params::{BOOTLOADER_FORMAL_ADDRESS, DEPLOYER_SYSTEM_CONTRACT_ADDRESS},
+    Condition, DecodedOpcode, FarCallOpcode, ImmMemHandlerFlags, Opcode, OpcodeVariant, Operand,
+    RegOrImmFlags, RetOpcode, UMAOpcode, OPCODES_TABLE,
+};
+use zkvm_primitives::{aux::MemoryPage, precompiles::DefaultPrecompilesProcessor};
+
+use super::*;
+use crate::{
+    block_properties::BlockProperties,
+    reference_impls::{
+        call_tracer::{CallFrame, CallTracer, CallType},
+        decommitter::SimpleDecommitter,
+        event_sink::InMemoryEventSink,
+        memory::SimpleMemory,
+    },
+    testing::storage::InMemoryStorage,
+    vm_state::{CallStackEntry, PrimitiveValue, VmState},
+    witness_trace::DummyTracer,
+};
+
+type TestVmState = VmState<
+    InMemoryStorage,
+    SimpleMemory,
+    InMemoryEventSink,
+    DefaultPrecompilesProcessor<true>,
+    SimpleDecommitter<true>,
+    DummyTracer,
+>;
+
+const CALLER: Address = Address::new([0x0a; 20]);
+const STATIC_CALLEE: Address = Address::new([0x0b; 20]);
+const DELEGATE_CALLEE: Address = Address::new([0x0c; 20]);
+const PANICKING_CALLEE: Address = Address::new([0x0d; 20]);
+
+const ERGS_FOR_CALLER: u32 = 1_000_000;
+const ERGS_FOR_CALLEE: u32 = 100_000;
+
+fn instruction(
+    opcode: Opcode,
+    src0_operand_type: Operand,
+    flags: [bool; 2],
+    registers: [u8; 3],
+    imm_0: u16,
+) -> u64 {
+    let variant = OPCODES_TABLE
+        .iter()
+        .find(|variant| {
+            variant.opcode == opcode
+                && variant.src0_operand_type == src0_operand_type
+                && variant.flags == flags
+        })
+        .copied()
+        .unwrap_or_else(|| panic!("unknown variant of {opcode:?}"));
+    let [src0_reg_idx, src1_reg_idx, dst0_reg_idx] = registers;
+
+    DecodedOpcode::<8> {
+        variant: OpcodeVariant { dst0_operand_type: variant.dst0_operand_type, ..variant },
+        condition: Condition::Always,
+        src0_reg_idx,
+        src1_reg_idx,
+        dst0_reg_idx,
+        dst1_reg_idx: 0,
+        imm_0,
+        imm_1: 0,
+    }
+    .serialize_as_integer()
+}
+
+// add code[word], r0, dst
+fn load_constant(word: u16, dst: u8) -> u64 {
+    let src = Operand::Full(ImmMemHandlerFlags::UseCodePage);
+    instruction(Opcode::Add(zkvm_opcodes::AddOpcode::Add), src, [false; 2], [0, 0, dst], word)
+}
+
+fn far_call(variant: FarCallOpcode, is_static: bool, abi: u8, address: u8, handler: u16) -> u64 {
+    let flags = [is_static, false];
+    instruction(Opcode::FarCall(variant), Operand::RegOnly, flags, [abi, address, 0], handler)
+}
+
+fn ret(variant: RetOpcode, abi: u8) -> u64 {
+    instruction(Opcode::Ret(variant), Operand::RegOnly, [false; 2], [abi, 0, 0], 0)
+}
+
+// st.1 offset, value
+fn heap_write(offset: u16, value: u8) -> u64 {
+    let src = Operand::RegOrImm(RegOrImmFlags::UseImm16Only);
+    instruction(Opcode::UMA(UMAOpcode::HeapWrite), src, [false; 2], [0, value, 0], offset)
+}
+
+// instructions go first, four per word, and the constants follow them
+fn code_words(instructions: &[u64], constants: &[U256]) -> Vec<U256> {
+    let mut words: Vec<_> = instructions
+        .chunks(4)
+        .map(|chunk| {
+            let mut limbs = [0u64; 4];
+            for (idx, instruction) in chunk.iter().enumerate() {
+                limbs[3 - idx] = *instruction;
+            }
+            U256::from_limbs(limbs)
+        })
+        .collect();
+    words.extend_from_slice(constants);
+
+    words
+}
+
+fn far_call_abi(start: u32, length: u32, ergs: u32) -> U256 {
+    U256::from_limbs([0, (start as u64) | ((length as u64) << 32), 0, ergs as u64])
+}
+
+fn ret_abi(start: u32, length: u32) -> U256 {
+    U256::from_limbs([0, (start as u64) | ((length as u64) << 32), 0, 0])
+}
+
+fn address_to_u256(address: Address) -> U256 {
+    U256::from_be_slice(address.as_slice())
+}
+
+fn deploy(vm: &mut TestVmState, address: Address, code: Vec<U256>) {
+    let mut hash = [address.as_slice()[0]; 32];
+    hash[0] = 1;
+    hash[1] = 0;
+    hash[2..4].copy_from_slice(&(code.len() as u16).to_be_bytes());
+    let hash = U256::from_be_bytes(hash);
+
+    vm.storage.populate(vec![(
+        0,
+        *DEPLOYER_SYSTEM_CONTRACT_ADDRESS,
+        address_to_u256(address),
+        hash,
+    )]);
+    vm.decommittment_processor.populate(vec![(hash, code)]);
+}
+
+fn run_bootloader(vm: &mut TestVmState, code: Vec<U256>, tracer: &mut CallTracer) {
+    let base_page = vm.new_base_memory_page_on_call();
+    let code_page = CallStackEntry::<8>::code_page_candidate_from_base(base_page);
+    vm.memory.populate_code(vec![(code_page.0, code)]);
+
+    let mut bootloader_context = CallStackEntry::empty_context();
+    bootloader_context.this_address = *BOOTLOADER_FORMAL_ADDRESS;
+    bootloader_context.code_address = *BOOTLOADER_FORMAL_ADDRESS;
+    bootloader_context.base_memory_page = base_page;
+    bootloader_context.code_page = MemoryPage(code_page.0);
+    bootloader_context.ergs_remaining = 10 * ERGS_FOR_CALLER;
+    vm.push_bootloader_context(0, bootloader_context);
+    vm.increment_memory_pages_on_call();
+
+    for _ in 0..1000 {
+        if vm.execution_has_ended() {
+            return;
+        }
+        vm.cycle(tracer).unwrap();
+    }
+    panic!("bootloader has not finished");
+}
+
+fn create_vm_state() -> TestVmState {
+    VmState::empty_state(
+        InMemoryStorage::new(),
+        SimpleMemory::new_without_preallocations(),
+        InMemoryEventSink::new(),
+        DefaultPrecompilesProcessor::<true>,
+        SimpleDecommitter::<true>::new(),
+        DummyTracer,
+        BlockProperties { default_aa_code_hash: U256::ZERO, evm_block_env: Default::default() },
+    )
+}
+
+#[test]
+fn test_call_tracer_builds_call_tree() {
+    let mut vm = create_vm_state();
+
+    // caller makes static, delegate and normal calls, and handles the failures of the last two.
+    // Registers are cleared on return, so the ABI is loaded before every call
+    let caller_code = code_words(
+        &[
+            load_constant(3, 3),
+            load_constant(4, 4),
+            far_call(FarCallOpcode::Normal, true, 3, 4, 3),
+            load_constant(3, 3),
+            load_constant(5, 4),
+            far_call(FarCallOpcode::Delegate, false, 3, 4, 6),
+            load_constant(3, 3),
+            load_constant(6, 4),
+            far_call(FarCallOpcode::Normal, false, 3, 4, 9),
+            ret(RetOpcode::Ok, 0),
+        ],
+        &[
+            far_call_abi(0, 0, ERGS_FOR_CALLEE),
+            address_to_u256(STATIC_CALLEE),
+            address_to_u256(DELEGATE_CALLEE),
+            address_to_u256(PANICKING_CALLEE),
+        ],
+    );
+    deploy(&mut vm, CALLER, caller_code);
+    // returns 32 bytes from the heap
+    let static_callee_code = code_words(
+        &[load_constant(1, 1), heap_write(0, 1), load_constant(2, 2), ret(RetOpcode::Ok, 2)],
+        &[U256::from(0x1234), ret_abi(0, 32)],
+    );
+    deploy(&mut vm, STATIC_CALLEE, static_callee_code);
+    deploy(&mut vm, DELEGATE_CALLEE, code_words(&[ret(RetOpcode::Revert, 0)], &[]));
+    deploy(&mut vm, PANICKING_CALLEE, code_words(&[ret(RetOpcode::Panic, 0)], &[]));
+
+    // bootloader passes 4 bytes of its heap as calldata
+    let bootloader_code = code_words(
+        &[
+            load_constant(1, 3),
+            heap_write(0, 3),
+            far_call(FarCallOpcode::Normal, false, 1, 2, 3),
+            ret(RetOpcode::Ok, 0),
+        ],
+        &[U256::from(0xdeadbeefu64) << 224],
+    );
+    vm.local_state.registers[0] =
+        PrimitiveValue { value: far_call_abi(0, 4, ERGS_FOR_CALLER), is_pointer: false };
+    vm.local_state.registers[1] =
+        PrimitiveValue { value: address_to_u256(CALLER), is_pointer: false };
+
+    let mut tracer = CallTracer::new();
+    run_bootloader(&mut vm, bootloader_code, &mut tracer);
+    let calls = tracer.into_calls();
+
+    assert_eq!(calls.len(), 1);
+    let root = &calls[0];
+    assert_eq!(root.call_type, CallType::Call);
+    assert_eq!(root.from, *BOOTLOADER_FORMAL_ADDRESS);
+    assert_eq!(root.to, CALLER);
+    assert_eq!(root.input.as_ref(), &[0xde, 0xad, 0xbe, 0xef]);
+    assert!(root.output.is_empty());
+    assert!(root.error.is_none());
+    assert!(root.gas_used > alloy_primitives::U64::ZERO && root.gas_used < root.gas);
+
+    let [static_call, delegate_call, panicked_call] = &root.calls[..] else {
+        panic!("expected three subcalls, got {:?}", root.calls);
+    };
+    assert_eq!(static_call.call_type, CallType::StaticCall);
+    assert_eq!((static_call.from, static_call.to), (CALLER, STATIC_CALLEE));
+    assert_eq!(static_call.output.as_ref(), U256::from(0x1234).to_be_bytes::<32>());
+    assert!(static_call.error.is_none());
+
+    assert_eq!(delegate_call.call_type, CallType::DelegateCall);
+    assert_eq!((delegate_call.from, delegate_call.to), (CALLER, DELEGATE_CALLEE));
+    assert_eq!(delegate_call.error.as_deref(), Some("execution reverted"));
+
+    assert_eq!(panicked_call.call_type, CallType::Call);
+    assert_eq!(panicked_call.to, PANICKING_CALLEE);
+    assert_eq!(panicked_call.error.as_deref(), Some("panic"));
+
+    // the tree is serialized in the same shape as the geth `callTracer` output
+    let json = serde_json::to_value(root).unwrap();
+    assert_eq!(json["type"], "CALL");
+    assert_eq!(json["from"], format!("{:#x}", *BOOTLOADER_FORMAL_ADDRESS));
+    assert_eq!(json["input"], "0xdeadbeef");
+    assert_eq!(json["gas"], format!("{ERGS_FOR_CALLER:#x}"));
+    assert!(json.get("output").is_none());
+    assert_eq!(json["calls"][0]["type"], "STATICCALL");
+    assert_eq!(json["calls"][1]["type"], "DELEGATECALL");
+    assert_eq!(json["calls"][1]["error"], "execution reverted");
+    assert!(json["calls"][2].get("calls").is_none());
+    let decoded: CallFrame = serde_json::from_value(json).unwrap();
+    assert_eq!(&decoded, root);
+}
+
+#[test]
+fn test_call_tracer_marks_explicit_static_calls_in_static_context() {
+    let mut vm = create_vm_state();
+
+    // the statically called caller makes a static and a normal call to the same callee,
+    // and only the first one is a static call, even though both frames are static
+    let caller_code = code_words(
+        &[
+            load_constant(2, 3),
+            load_constant(3, 4),
+            far_call(FarCallOpcode::Normal, true, 3, 4, 6),
+            load_constant(2, 3),
+            load_constant(3, 4),
+            far_call(FarCallOpcode::Normal, false, 3, 4, 6),
+            ret(RetOpcode::Ok, 0),
+        ],
+        &[far_call_abi(0, 0, ERGS_FOR_CALLEE), address_to_u256(STATIC_CALLEE)],
+    );
+    deploy(&mut vm, CALLER, caller_code);
+    deploy(&mut vm, STATIC_CALLEE, code_words(&[ret(RetOpcode::Ok, 0)], &[]));
+
+    let bootloader_code =
+        code_words(&[far_call(FarCallOpcode::Normal, true, 1, 2, 1), ret(RetOpcode::Ok, 0)], &[]);
+    vm.local_state.registers[0] =
+        PrimitiveValue { value: far_call_abi(0, 0, ERGS_FOR_CALLER), is_pointer: false };
+    vm.local_state.registers[1] =
+        PrimitiveValue { value: address_to_u256(CALLER), is_pointer: false };
+
+    let mut tracer = CallTracer::new();
+    run_bootloader(&mut vm, bootloader_code, &mut tracer);
+    let calls = tracer.into_calls();
+
+    assert_eq!(calls.len(), 1);
+    let root = &calls[0];
+    assert_eq!(root.call_type, CallType::StaticCall);
+    let call_types: Vec<_> = root.calls.iter().map(|call| call.call_type).collect();
+    assert_eq!(call_types, [CallType::StaticCall, CallType::Call]);
+    assert!(root.calls.iter().all(|call| call.error.is_none()));
+}
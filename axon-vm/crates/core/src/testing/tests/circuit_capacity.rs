@@ -0,0 +1,201 @@
+use zkvm_opcodes::{
+    circuit_capacity::{BaseLayerCircuitType, CYCLES_PER_VM_SNAPSHOT},
+    decoding::EncodingModeProduction,
+    system_params::{ECADD_PRECOMPILE_FORMAL_ADDRESS, ECPAIRING_PRECOMPILE_FORMAL_ADDRESS},
+};
+use zkvm_primitives::{aux::Timestamp, queries::LogQuery};
+
+use super::*;
+use crate::{
+    reference_impls::circuit_capacity::CircuitCapacityTracer, testing::harness::TestHarness,
+    vm_state::ExecutionStatus, witness_trace::VmWitnessTracer,
+};
+
+const REVERTING_CALLEE: Address = Address::new([0x0b; 20]);
+
+const ERGS_FOR_CALLEE: u64 = 100_000;
+
+fn address_to_u256(address: Address) -> U256 {
+    U256::from_be_slice(address.as_slice())
+}
+
+#[test]
+fn test_circuit_capacity_counts_queries() {
+    let mut harness = TestHarness::with_witness_tracer(CircuitCapacityTracer::new());
+    let identity = Address::with_last_byte(0x04);
+    harness.deploy(
+        identity,
+        &format!(
+            "
+                add code[@precompile_abi], r0, r1
+                log.precompile r1, r0, r2
+                ret.ok r0
+            precompile_abi: .cell {}
+            ",
+            U256::from_limbs([1 << 32, 1 | (1 << 32), 0, 0]),
+        ),
+    );
+    // the write is rolled back, so it passes through the demuxer and the sorter twice
+    harness.deploy(
+        REVERTING_CALLEE,
+        "
+            add 1, r0, r1
+            log.swrite r1, r1
+            ret.revert r0
+        ",
+    );
+
+    let result = harness.run(&format!(
+        "
+            add 1, r0, r1
+            log.swrite r1, r1
+            log.sread r1, r2
+            log.event.first r1, r1
+            log.to_l1.first r1, r1
+            add code[@abi], r0, r1
+            add code[@identity], r0, r2
+            far_call r1, r2, @fail
+            add code[@abi], r0, r1
+            add code[@reverting_callee], r0, r2
+            far_call r1, r2, @reverted
+            ret.panic
+        reverted:
+            ret.ok r0
+        fail:
+            ret.panic
+        abi: .cell {}
+        identity: .cell {}
+        reverting_callee: .cell {}
+        ",
+        U256::from_limbs([0, 0, 0, ERGS_FOR_CALLEE]),
+        address_to_u256(identity),
+        address_to_u256(REVERTING_CALLEE),
+    ));
+    assert_eq!(result.execution.status, ExecutionStatus::Success);
+
+    let tracer = &harness.vm.witness_tracer;
+    assert_eq!(tracer.cycles(BaseLayerCircuitType::Vm), result.execution.cycles);
+    // the far calls also read the code hashes of the callees
+    assert_eq!(tracer.cycles(BaseLayerCircuitType::StorageSorter), 6);
+    assert_eq!(tracer.cycles(BaseLayerCircuitType::StorageApplication), 4);
+    assert_eq!(tracer.cycles(BaseLayerCircuitType::EventsSorter), 1);
+    assert_eq!(tracer.cycles(BaseLayerCircuitType::L1MessagesSorter), 1);
+    assert_eq!(tracer.cycles(BaseLayerCircuitType::LogDemuxer), 9);
+    assert_eq!(tracer.cycles(BaseLayerCircuitType::IdentityPrecompile), 1);
+    assert_eq!(tracer.cycles(BaseLayerCircuitType::Keccak256Precompile), 0);
+    assert_eq!(tracer.cycles(BaseLayerCircuitType::CodeDecommitterSorter), 2);
+    assert!(tracer.cycles(BaseLayerCircuitType::CodeDecommitter) >= 2);
+    assert!(tracer.cycles(BaseLayerCircuitType::RamPermutation) > 0);
+
+    // everything fits into a single instance of every used circuit
+    let used_circuits = tracer
+        .projected_circuits_per_type()
+        .into_iter()
+        .filter(|&(_, circuits)| circuits > 0)
+        .inspect(|&(circuit, circuits)| assert_eq!(circuits, 1, "{circuit:?}"))
+        .count();
+    assert_eq!(tracer.total_projected_circuits(), used_circuits as u32);
+}
+
+#[test]
+fn test_circuit_capacity_counts_elliptic_curve_precompiles() {
+    let mut harness = TestHarness::with_witness_tracer(CircuitCapacityTracer::new());
+    let ecadd = *ECADD_PRECOMPILE_FORMAL_ADDRESS;
+    let ecpairing = *ECPAIRING_PRECOMPILE_FORMAL_ADDRESS;
+    // adds two points at infinity read from the empty heap
+    let ecadd_abi = U256::from_limbs([4 << 32, 4 | (3 << 32), 0, 0]);
+    // pairing check without pairs
+    let ecpairing_abi = U256::from_limbs([0, 2 << 32, 0, 0]);
+    for (address, precompile_abi) in [(ecadd, ecadd_abi), (ecpairing, ecpairing_abi)] {
+        harness.deploy(
+            address,
+            &format!(
+                "
+                    add code[@precompile_abi], r0, r1
+                    log.precompile r1, r0, r2
+                    ret.ok r0
+                precompile_abi: .cell {precompile_abi}
+                "
+            ),
+        );
+    }
+
+    let result = harness.run(&format!(
+        "
+            add code[@abi], r0, r1
+            add code[@ecadd], r0, r2
+            far_call r1, r2, @fail
+            add code[@abi], r0, r1
+            add code[@ecpairing], r0, r2
+            far_call r1, r2, @fail
+            ret.ok r0
+        fail:
+            ret.panic
+        abi: .cell {}
+        ecadd: .cell {}
+        ecpairing: .cell {}
+        ",
+        U256::from_limbs([0, 0, 0, ERGS_FOR_CALLEE]),
+        address_to_u256(ecadd),
+        address_to_u256(ecpairing),
+    ));
+    assert_eq!(result.execution.status, ExecutionStatus::Success);
+
+    let tracer = &harness.vm.witness_tracer;
+    assert_eq!(tracer.cycles(BaseLayerCircuitType::ECAddPrecompile), 1);
+    assert_eq!(tracer.cycles(BaseLayerCircuitType::ECMulPrecompile), 0);
+    assert_eq!(tracer.cycles(BaseLayerCircuitType::ECPairingPrecompile), 1);
+    assert_eq!(tracer.projected_circuits(BaseLayerCircuitType::ECAddPrecompile), 1);
+    assert_eq!(tracer.projected_circuits(BaseLayerCircuitType::ECPairingPrecompile), 1);
+}
+
+#[test]
+fn test_circuit_capacity_projects_vm_circuits() {
+    let mut harness = TestHarness::with_witness_tracer(CircuitCapacityTracer::new());
+    harness.cycle_limit = 2 * CYCLES_PER_VM_SNAPSHOT;
+    // two cycles per iteration, so a bit more than a single VM circuit can take
+    let result = harness.run(&format!(
+        "
+            add {}, r0, r1
+        loop:
+            sub.s! 1, r1, r1
+            jump.ne @loop
+            ret.ok r0
+        ",
+        CYCLES_PER_VM_SNAPSHOT / 2,
+    ));
+    assert_eq!(result.execution.status, ExecutionStatus::Success);
+
+    let tracer = &harness.vm.witness_tracer;
+    assert_eq!(tracer.cycles(BaseLayerCircuitType::Vm), CYCLES_PER_VM_SNAPSHOT + 2);
+    assert_eq!(tracer.projected_circuits(BaseLayerCircuitType::Vm), 2);
+    assert_eq!(tracer.projected_circuits(BaseLayerCircuitType::StorageSorter), 0);
+}
+
+#[test]
+fn test_circuit_capacity_counts_unknown_log_queries_in_demuxer_only() {
+    let mut tracer = CircuitCapacityTracer::new();
+    let query = LogQuery {
+        timestamp: Timestamp(1),
+        tx_number_in_block: 0,
+        aux_byte: 0xff,
+        shard_id: 0,
+        address: REVERTING_CALLEE,
+        key: U256::from(1),
+        read_value: U256::ZERO,
+        written_value: U256::from(1),
+        rw_flag: true,
+        rollback: false,
+        is_service: false,
+    };
+    VmWitnessTracer::<8, EncodingModeProduction>::add_log_query(&mut tracer, 0, query);
+
+    assert_eq!(tracer.cycles(BaseLayerCircuitType::LogDemuxer), 1);
+    for circuit in [
+        BaseLayerCircuitType::StorageSorter,
+        BaseLayerCircuitType::EventsSorter,
+        BaseLayerCircuitType::L1MessagesSorter,
+    ] {
+        assert_eq!(tracer.cycles(circuit), 0, "{circuit:?}");
+    }
+}
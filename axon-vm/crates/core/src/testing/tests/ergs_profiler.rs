@@ -0,0 +1,168 @@
+use zkvm_opcodes::{
+    assembly::assemble, decoding::EncodingModeProduction, system_params::BOOTLOADER_FORMAL_ADDRESS,
+};
+
+use super::*;
+use crate::{
+    reference_impls::{
+        ergs_profiler::{ErgsProfiler, ProfileWeight},
+        memory::SimpleMemory,
+    },
+    testing::harness::TestHarness,
+    vm_state::{ExecutionStatus, RunLimits},
+    GenericNoopTracer,
+};
+
+const CALLEE: Address = Address::new([0x0a; 20]);
+
+fn folded(profiler: &ErgsProfiler, weight: ProfileWeight) -> Vec<(String, u64)> {
+    let mut output = vec![];
+    profiler.write_folded(&mut output, weight).unwrap();
+
+    String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let (stack, value) = line.rsplit_once(' ').unwrap();
+            (stack.to_owned(), value.parse().unwrap())
+        })
+        .collect()
+}
+
+#[test]
+fn test_ergs_profiler_attributes_costs_to_call_stacks() {
+    let mut harness = TestHarness::new();
+    harness.deploy(
+        CALLEE,
+        "
+            add 1, r0, r1
+            ret.ok r0
+        ",
+    );
+    let code = assemble::<8, EncodingModeProduction>(&format!(
+        "
+            add code[@abi], r0, r1
+            add code[@callee], r0, r2
+            far_call r1, r2, @fail
+            near_call r0, @subroutine, @fail
+            near_call r0, @subroutine, @fail
+            ret.ok r0
+        subroutine:
+            add 1, r0, r3
+            add 2, r3, r3
+            ret.ok r0
+        fail:
+            ret.panic
+        abi: .cell {}
+        callee: .cell {}
+        ",
+        U256::from_limbs([0, 0, 0, 100_000]),
+        U256::from_be_slice(CALLEE.as_slice()),
+    ))
+    .unwrap();
+    harness.load_bootloader(code.code_words());
+
+    let mut profiler = ErgsProfiler::new();
+    let result = harness.vm.run(&mut profiler, RunLimits::default()).unwrap();
+    assert_eq!(result.status, ExecutionStatus::Success);
+
+    // both near calls are folded into the same frame
+    let bootloader = BOOTLOADER_FORMAL_ADDRESS.to_string();
+    let cycles = folded(&profiler, ProfileWeight::Cycles);
+    assert_eq!(
+        cycles,
+        vec![
+            (bootloader.clone(), 6),
+            (format!("{bootloader};{CALLEE}"), 2),
+            (format!("{bootloader};near_call 0x0006-0x0008"), 6),
+        ]
+    );
+
+    let ergs = folded(&profiler, ProfileWeight::Ergs);
+    assert_eq!(ergs.len(), 3);
+    let total_ergs: u64 = ergs.iter().map(|(_, ergs)| ergs).sum();
+    assert_eq!(total_ergs, result.ergs_used as u64);
+}
+
+#[test]
+fn test_ergs_profiler_started_inside_near_calls() {
+    let mut harness = TestHarness::new();
+    let code = assemble::<8, EncodingModeProduction>(
+        "
+            near_call r0, @outer, @fail
+            ret.ok r0
+        outer:
+            near_call r0, @inner, @fail
+            ret.ok r0
+        inner:
+            add 1, r0, r1
+            add 2, r1, r1
+            ret.ok r0
+        fail:
+            ret.panic
+        ",
+    )
+    .unwrap();
+    harness.load_bootloader(code.code_words());
+
+    // stop at the second instruction of the inner near call
+    let limits = RunLimits { cycles: Some(3), ergs: None };
+    let result = harness
+        .vm
+        .run(&mut GenericNoopTracer::<SimpleMemory>::new(), limits)
+        .unwrap();
+    assert_eq!(result.status, ExecutionStatus::CycleBudgetExceeded);
+
+    let mut profiler = ErgsProfiler::new();
+    let result = harness.vm.run(&mut profiler, RunLimits::default()).unwrap();
+    assert_eq!(result.status, ExecutionStatus::Success);
+
+    // the return PCs saved in the callstack are not mistaken for the entry PCs
+    let bootloader = BOOTLOADER_FORMAL_ADDRESS.to_string();
+    let cycles = folded(&profiler, ProfileWeight::Cycles);
+    assert_eq!(
+        cycles,
+        vec![
+            (bootloader.clone(), 1),
+            (format!("{bootloader};near_call unknown"), 1),
+            (format!("{bootloader};near_call unknown;near_call unknown"), 2),
+        ]
+    );
+}
+
+#[test]
+fn test_ergs_profiler_attributes_evm_gas_to_evm_frames() {
+    let mut harness = TestHarness::new();
+    // stores 1 into the cold empty slot, the store takes two cycles
+    harness.deploy_evm(CALLEE, &[0x60, 0x01, 0x60, 0x00, 0x55, 0x00]);
+    let code = assemble::<8, EncodingModeProduction>(&format!(
+        "
+            add code[@abi], r0, r1
+            add code[@callee], r0, r2
+            far_call r1, r2, @fail
+            ret.ok r0
+        fail:
+            ret.panic
+        abi: .cell {}
+        callee: .cell {}
+        ",
+        U256::from_limbs([0, 0, 0, 100_000]),
+        U256::from_be_slice(CALLEE.as_slice()),
+    ))
+    .unwrap();
+    harness.load_bootloader(code.code_words());
+
+    let mut profiler = ErgsProfiler::new();
+    let result = harness.vm.run(&mut profiler, RunLimits::default()).unwrap();
+    assert_eq!(result.status, ExecutionStatus::Success);
+
+    let bootloader = BOOTLOADER_FORMAL_ADDRESS.to_string();
+    let callee = format!("{bootloader};{CALLEE}");
+    let cycles = folded(&profiler, ProfileWeight::Cycles);
+    assert_eq!(cycles, vec![(bootloader.clone(), 4), (callee.clone(), 5)]);
+
+    let ergs = folded(&profiler, ProfileWeight::Ergs);
+    assert_eq!(ergs[1], (callee, 2 * 3 + 2100 + 20000));
+    let total_ergs: u64 = ergs.iter().map(|(_, ergs)| ergs).sum();
+    assert_eq!(total_ergs, result.ergs_used as u64);
+}
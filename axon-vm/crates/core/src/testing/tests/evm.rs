@@ -0,0 +1,590 @@
+use std::collections::BTreeMap;
+
+use alloy_primitives::hex;
+use zkvm_opcodes::decoding::EncodingModeProduction;
+use zkvm_primitives::precompiles::DefaultPrecompilesProcessor;
+
+use super::*;
+use crate::{
+    block_properties::BlockProperties,
+    errors::EvmError,
+    evm::*,
+    reference_impls::{
+        decommitter::SimpleDecommitter, event_sink::InMemoryEventSink, memory::SimpleMemory,
+    },
+    testing::{harness::TestHarness, storage::InMemoryStorage},
+    tracing::*,
+    utils::GenericNoopTracer,
+    vm_state::{ExecutionStatus, VmLocalState, VmState},
+    witness_trace::{DummyTracer, VmWitnessTracer},
+};
+
+type TestVmState<WT = DummyTracer> = VmState<
+    InMemoryStorage,
+    SimpleMemory,
+    InMemoryEventSink,
+    DefaultPrecompilesProcessor<true>,
+    SimpleDecommitter<true>,
+    WT,
+>;
+
+const CONTRACT_ADDRESS: Address = Address::new([0x0f; 20]);
+const CALLER_ADDRESS: Address = Address::new([0x0c; 20]);
+const GAS_LIMIT: u64 = 1_000_000;
+
+// fixtures written for this repo in the layout of the GeneralStateTests fillers
+const GENERAL_STATE_TESTS: &[(&str, &str)] = &[
+    ("stExample", include_str!("general_state_tests/stExample.json")),
+    ("vmArithmeticTest", include_str!("general_state_tests/vmArithmeticTest.json")),
+    ("vmBitwiseLogicOperation", include_str!("general_state_tests/vmBitwiseLogicOperation.json")),
+    ("vmIOandFlowOperations", include_str!("general_state_tests/vmIOandFlowOperations.json")),
+    ("vmTests", include_str!("general_state_tests/vmTests.json")),
+    ("stPreCompiledContracts", include_str!("general_state_tests/stPreCompiledContracts.json")),
+    ("stRevertTest", include_str!("general_state_tests/stRevertTest.json")),
+];
+
+/// State test in the format of the GeneralStateTests fillers. The code of the accounts must be
+/// given as the raw bytecode, and only the storage of the expected post-state is compared, as
+/// there are no balances, nonces or state root. Failed transactions are expected to leave the
+/// storage of the pre-state in place.
+#[derive(Debug, serde::Deserialize)]
+struct StateTest {
+    env: StateTestEnv,
+    pre: BTreeMap<Address, StateTestAccount>,
+    transaction: StateTestTransaction,
+    expect: Vec<StateTestExpectation>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StateTestEnv {
+    current_base_fee: U256,
+    current_coinbase: Address,
+    current_gas_limit: U256,
+    current_number: U256,
+    current_random: U256,
+    current_timestamp: U256,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct StateTestAccount {
+    code: String,
+    storage: BTreeMap<U256, U256>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StateTestTransaction {
+    data: Vec<String>,
+    gas_limit: Vec<U256>,
+    gas_price: U256,
+    sender: Address,
+    to: Address,
+    value: Vec<U256>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct StateTestExpectation {
+    indexes: StateTestIndexes,
+    result: BTreeMap<Address, StateTestAccountResult>,
+}
+
+// -1 matches any index
+#[derive(Debug, serde::Deserialize)]
+struct StateTestIndexes {
+    data: i64,
+    gas: i64,
+    value: i64,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct StateTestAccountResult {
+    storage: BTreeMap<U256, U256>,
+}
+
+fn push32(hex_value: &str) -> String {
+    format!("7f{:0>64}", hex_value)
+}
+
+fn decode_hex(value: &str) -> Vec<u8> {
+    hex::decode(value.trim_start_matches("0x")).unwrap()
+}
+
+fn intrinsic_gas(calldata: &[u8]) -> u64 {
+    let calldata_gas: u64 = calldata
+        .iter()
+        .map(|&byte| if byte == 0 { 4 } else { 16 })
+        .sum();
+    21_000 + calldata_gas
+}
+
+fn create_vm_state() -> TestVmState {
+    create_vm_state_with(DummyTracer)
+}
+
+fn create_vm_state_with<WT: VmWitnessTracer<8, EncodingModeProduction>>(
+    witness_tracer: WT,
+) -> TestVmState<WT> {
+    VmState::empty_state(
+        InMemoryStorage::new(),
+        SimpleMemory::new_without_preallocations(),
+        InMemoryEventSink::new(),
+        DefaultPrecompilesProcessor::<true>,
+        SimpleDecommitter::<true>::new(),
+        witness_tracer,
+        BlockProperties { default_aa_code_hash: U256::ZERO, evm_block_env: Default::default() },
+    )
+}
+
+fn test_context(calldata: &str) -> EvmContext {
+    EvmContext {
+        address: CONTRACT_ADDRESS,
+        caller: CALLER_ADDRESS,
+        origin: CALLER_ADDRESS,
+        calldata: hex::decode(calldata).unwrap(),
+        gas_limit: GAS_LIMIT,
+        ..Default::default()
+    }
+}
+
+fn storage_value(vm: &TestVmState, address: Address, key: U256) -> U256 {
+    vm.storage.inner[0]
+        .get(&address)
+        .and_then(|storage| storage.get(&key).copied())
+        .unwrap_or_default()
+}
+
+fn stored_value(vm: &TestVmState, key: u64) -> U256 {
+    storage_value(vm, CONTRACT_ADDRESS, U256::from(key))
+}
+
+fn run_code(vm: &mut TestVmState, context: &EvmContext, code: &str) -> EvmExecutionResult {
+    let code = hex::decode(code).unwrap();
+    vm.run_evm_frame(&mut GenericNoopTracer::<SimpleMemory>::new(), context, &code)
+}
+
+// runs the transaction for every combination of the data, gas and value indexes
+fn run_state_test(name: &str, test: &StateTest) {
+    let index_matches = |expected: i64, idx: usize| expected == -1 || expected == idx as i64;
+    let code = test.pre[&test.transaction.to]
+        .code
+        .strip_prefix(":raw ")
+        .unwrap_or_else(|| panic!("{name}: only the raw code is supported"));
+    let block = EvmBlockEnv {
+        number: test.env.current_number.to(),
+        timestamp: test.env.current_timestamp.to(),
+        coinbase: test.env.current_coinbase,
+        gas_limit: test.env.current_gas_limit.to(),
+        chain_id: 1,
+        base_fee: test.env.current_base_fee,
+        prevrandao: test.env.current_random,
+    };
+
+    let transaction = &test.transaction;
+    for (data_idx, data) in transaction.data.iter().enumerate() {
+        for (gas_idx, gas_limit) in transaction.gas_limit.iter().enumerate() {
+            for (value_idx, value) in transaction.value.iter().enumerate() {
+                let mut vm = create_vm_state();
+                vm.storage.populate(
+                    test.pre
+                        .iter()
+                        .flat_map(|(address, account)| {
+                            account
+                                .storage
+                                .iter()
+                                .map(|(key, value)| (0, *address, *key, *value))
+                        })
+                        .collect(),
+                );
+
+                let calldata = decode_hex(data);
+                let context = EvmContext {
+                    address: transaction.to,
+                    caller: transaction.sender,
+                    origin: transaction.sender,
+                    call_value: *value,
+                    gas_price: transaction.gas_price,
+                    gas_limit: gas_limit.to::<u64>() - intrinsic_gas(&calldata),
+                    calldata,
+                    is_static: false,
+                    block,
+                };
+                vm.run_evm_frame(
+                    &mut GenericNoopTracer::<SimpleMemory>::new(),
+                    &context,
+                    &decode_hex(code),
+                );
+                let case = format!("{name} (data {data_idx}, gas {gas_idx}, value {value_idx})");
+
+                let expectations = test.expect.iter().filter(|expectation| {
+                    index_matches(expectation.indexes.data, data_idx)
+                        && index_matches(expectation.indexes.gas, gas_idx)
+                        && index_matches(expectation.indexes.value, value_idx)
+                });
+                for expectation in expectations {
+                    for (address, account) in &expectation.result {
+                        for (key, expected) in &account.storage {
+                            let actual = storage_value(&vm, *address, *key);
+                            assert_eq!(actual, *expected, "{case}: slot {key} of {address}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_evm_general_state_tests() {
+    for (suite, fixture) in GENERAL_STATE_TESTS {
+        let tests: BTreeMap<String, StateTest> = serde_json::from_str(fixture).unwrap();
+        assert!(!tests.is_empty(), "{suite}: no tests");
+        for (name, test) in &tests {
+            run_state_test(&format!("{suite}/{name}"), test);
+        }
+    }
+}
+
+#[test]
+fn test_evm_gas_accounting() {
+    let mut vm = create_vm_state();
+    // 1 + 2 stored into the cold empty slot
+    let result = run_code(&mut vm, &test_context(""), "600160020160005500");
+    assert_eq!(result.status, EvmExitStatus::Stopped);
+    assert_eq!(result.gas_used, 4 * 3 + 2100 + 20000);
+    assert_eq!(result.gas_refunded, 0);
+
+    // clearing the slot is refunded
+    let result = run_code(&mut vm, &test_context(""), "600060005500");
+    assert_eq!(result.gas_used, 2 * 3 + 2100 + 2900);
+    assert_eq!(result.gas_refunded, 4800);
+    assert_eq!(stored_value(&vm, 0), U256::ZERO);
+
+    // memory expansion and keccak
+    let result = run_code(&mut vm, &test_context(""), "6040600020");
+    assert_eq!(result.gas_used, 2 * 3 + 30 + 2 * 6 + 2 * 3);
+}
+
+#[test]
+fn test_evm_revert_rolls_back_storage() {
+    let mut vm = create_vm_state();
+    let result = run_code(&mut vm, &test_context(""), "60016000556001601ff3");
+    assert_eq!(result.status, EvmExitStatus::Returned);
+    assert_eq!(result.return_data, vec![0]);
+    assert_eq!(stored_value(&vm, 0), U256::from(1));
+
+    // store 2 and revert with a single byte of data
+    let result = run_code(&mut vm, &test_context(""), "600260005560ab60005360016000fd");
+    assert_eq!(result.status, EvmExitStatus::Reverted);
+    assert_eq!(result.return_data, vec![0xab]);
+    assert_eq!(stored_value(&vm, 0), U256::from(1));
+
+    // read and write of both frames and the rollback of the second one, in monotonic time
+    let (_, per_slot_history) = vm.storage.flatten_and_net_history();
+    assert_eq!(per_slot_history[&(0, CONTRACT_ADDRESS, U256::ZERO)].len(), 5);
+}
+
+#[test]
+fn test_evm_transaction_state_is_rolled_back_with_the_frame() {
+    let mut vm = create_vm_state();
+    let is_warm = |vm: &TestVmState| vm.evm_transaction.is_warm(CONTRACT_ADDRESS, U256::ZERO);
+    let transient_value = |vm: &TestVmState| {
+        vm.evm_transaction
+            .transient_value(CONTRACT_ADDRESS, U256::from(0x0a))
+    };
+    // store 1, write 2 to the transient storage and revert
+    let result = run_code(&mut vm, &test_context(""), "60016000556002600a5d60006000fd");
+    assert_eq!(result.status, EvmExitStatus::Reverted);
+    assert!(!is_warm(&vm));
+    assert_eq!(transient_value(&vm), U256::ZERO);
+
+    // same, but stop
+    let result = run_code(&mut vm, &test_context(""), "60016000556002600a5d00");
+    assert_eq!(result.status, EvmExitStatus::Stopped);
+    assert!(is_warm(&vm));
+    assert_eq!(transient_value(&vm), U256::from(2));
+
+    // the next frame starts a new transaction
+    let result = run_code(&mut vm, &test_context(""), "00");
+    assert_eq!(result.status, EvmExitStatus::Stopped);
+    assert!(!is_warm(&vm));
+    assert_eq!(transient_value(&vm), U256::ZERO);
+}
+
+#[test]
+fn test_evm_exceptional_halts() {
+    let cases = [
+        // jump into the PUSH data
+        ("6001600055600956605b", EvmExitStatus::Halted(EvmError::InvalidJump)),
+        ("01", EvmExitStatus::Halted(EvmError::StackUnderflow)),
+        ("6001600055fe", EvmExitStatus::Halted(EvmError::InvalidOpcode(0xfe))),
+        ("3031600055", EvmExitStatus::Halted(EvmError::UnsupportedOpcode(0x31))),
+        // call to a contract that is not a precompile
+        (
+            "600060006000600060006110005af1",
+            EvmExitStatus::Halted(EvmError::UnsupportedOpcode(0xf1)),
+        ),
+        ("5b600056", EvmExitStatus::Halted(EvmError::OutOfGas)),
+    ];
+    for (code, expected_status) in cases {
+        let mut vm = create_vm_state();
+        let context = EvmContext { gas_limit: 100_000, ..test_context("") };
+        let result = run_code(&mut vm, &context, code);
+        assert_eq!(result.status, expected_status, "{}", code);
+        assert_eq!(result.gas_used, 100_000);
+        assert_eq!(stored_value(&vm, 0), U256::ZERO);
+    }
+
+    let mut vm = create_vm_state();
+    let context = EvmContext { is_static: true, ..test_context("") };
+    let result = run_code(&mut vm, &context, "6001600055");
+    assert_eq!(result.status, EvmExitStatus::Halted(EvmError::StateChangeDuringStaticCall));
+}
+
+#[test]
+fn test_evm_logs_are_emitted_into_event_sink() {
+    let mut vm = create_vm_state();
+    // log1 with 33 bytes of data
+    let code = format!(
+        "{}600052600160205361123460216000a1",
+        push32("00000000000000000000000000000000000000000000000000000000000000aa")
+    );
+    let result = run_code(&mut vm, &test_context(""), &code);
+    assert!(result.is_success());
+
+    let (_, events, _) = vm.event_sink.clone().flatten();
+    let events: Vec<_> = events
+        .iter()
+        .map(|event| (event.is_first, event.address, event.key, event.value))
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            (true, CONTRACT_ADDRESS, U256::from(1), U256::from(33)),
+            (false, CONTRACT_ADDRESS, U256::ZERO, U256::from(0x1234)),
+            (false, CONTRACT_ADDRESS, U256::ZERO, U256::from(0xaa)),
+            (false, CONTRACT_ADDRESS, U256::from(1), U256::from(1) << 248),
+        ]
+    );
+
+    // logs of the reverted frame are rolled back
+    let result = run_code(&mut vm, &test_context(""), "60006000a060006000fd");
+    assert_eq!(result.status, EvmExitStatus::Reverted);
+    let (_, events_after_revert, _) = vm.event_sink.clone().flatten();
+    assert_eq!(events_after_revert.len(), events.len());
+}
+
+// records the EVM steps passed to the tracer hooks
+#[derive(Debug, Default)]
+struct EvmStepRecorder {
+    before_execution: Vec<EvmStepData>,
+    after_execution: Vec<EvmStepData>,
+}
+
+impl Tracer for EvmStepRecorder {
+    const CALL_BEFORE_EXECUTION: bool = true;
+    const CALL_AFTER_EXECUTION: bool = true;
+
+    type SupportedMemory = SimpleMemory;
+    fn before_decoding(&mut self, _state: VmLocalStateData<'_>, _memory: &Self::SupportedMemory) {}
+    fn after_decoding(
+        &mut self,
+        _state: VmLocalStateData<'_>,
+        _data: CycleData<AfterDecodingData>,
+        _memory: &Self::SupportedMemory,
+    ) {
+    }
+    fn before_execution(
+        &mut self,
+        _state: VmLocalStateData<'_>,
+        data: CycleData<BeforeExecutionData>,
+        _memory: &Self::SupportedMemory,
+    ) {
+        let CycleData::Evm(step) = data else { panic!("expected an EVM step") };
+        self.before_execution.push(step);
+    }
+    fn after_execution(
+        &mut self,
+        _state: VmLocalStateData<'_>,
+        data: CycleData<AfterExecutionData>,
+        _memory: &Self::SupportedMemory,
+    ) {
+        let CycleData::Evm(step) = data else { panic!("expected an EVM step") };
+        self.after_execution.push(step);
+    }
+}
+
+// counts the cycles recorded by the witness tracer
+#[derive(Clone, Debug, Default)]
+struct CycleCounter {
+    started: u32,
+    ended: u32,
+}
+
+impl VmWitnessTracer<8, EncodingModeProduction> for CycleCounter {
+    fn start_new_execution_cycle(&mut self, _current_state: &VmLocalState) {
+        assert_eq!(self.started, self.ended, "cycle started twice");
+        self.started += 1;
+    }
+
+    fn end_execution_cycle(&mut self, _current_state: &VmLocalState) {
+        self.ended += 1;
+        assert_eq!(self.started, self.ended, "cycle ended twice");
+    }
+}
+
+#[test]
+fn test_evm_steps_are_traced() {
+    let mut vm = create_vm_state_with(CycleCounter::default());
+    let mut tracer = EvmStepRecorder::default();
+    // store 1, emit an event with a single topic and stop
+    let code = hex::decode("600160005560aa60006000a100").unwrap();
+    let result = vm.run_evm_frame(&mut tracer, &test_context(""), &code);
+    assert_eq!(result.status, EvmExitStatus::Stopped);
+
+    let steps: Vec<_> = [0x60, 0x60, 0x55, 0x60, 0x60, 0x60, 0xa1, 0x00]
+        .into_iter()
+        .zip([0, 2, 4, 5, 7, 9, 11, 12])
+        .map(|(opcode, pc)| EvmStepData { opcode, pc })
+        .collect();
+    assert_eq!(tracer.before_execution, steps);
+    assert_eq!(tracer.after_execution, steps);
+
+    // the store and the event take two cycles each, and every cycle is recorded
+    assert_eq!(vm.local_state.monotonic_cycle_counter, 10);
+    assert_eq!(vm.witness_tracer.started, 10);
+    assert_eq!(vm.witness_tracer.ended, 10);
+}
+
+fn far_call_abi(start: u32, length: u32, ergs: u32) -> U256 {
+    U256::from_limbs([0, (start as u64) | ((length as u64) << 32), 0, ergs as u64])
+}
+
+#[test]
+fn test_evm_contract_called_from_native_code() {
+    let mut harness = TestHarness::new();
+    // stores and returns the first calldata word plus one
+    harness.deploy_evm(
+        CONTRACT_ADDRESS,
+        &hex::decode("6000356001018060005560005260206000f3").unwrap(),
+    );
+
+    let result = harness.run(&format!(
+        "
+            add 41, r0, r2
+            st.1 0, r2, r0
+            add code[@abi], r0, r1
+            add code[@callee], r0, r2
+            far_call r1, r2, @fail
+            ld r1, r3, r0
+            ret.ok r0
+        fail:
+            ret.panic
+        abi: .cell {}
+        callee: .cell {}
+        ",
+        far_call_abi(0, 32, GAS_LIMIT as u32),
+        address_to_u256(&CONTRACT_ADDRESS),
+    ));
+
+    assert_eq!(result.execution.status, ExecutionStatus::Success);
+    assert_eq!(result.register(3), U256::from(42));
+    assert_eq!(harness.storage_value(CONTRACT_ADDRESS, U256::ZERO), U256::from(42));
+    // the gas of the EVM code is charged from the ergs of the caller
+    assert!(result.execution.ergs_used > 2100 + 20000);
+}
+
+#[test]
+fn test_evm_far_calls_share_the_transaction_state() {
+    let mut harness = TestHarness::new();
+    // returns the gas spent on reading slot 0, and the number of the call counted in the
+    // transient storage
+    harness.deploy_evm(
+        CONTRACT_ADDRESS,
+        &hex::decode("5a600054505a900360005260005c6001018060005d60205260406000f3").unwrap(),
+    );
+
+    let result = harness.run(&format!(
+        "
+            add code[@abi], r0, r1
+            add code[@callee], r0, r2
+            far_call r1, r2, @fail
+            ld.inc r1, r3, r5
+            ld r5, r4, r0
+            st.1 0, r3, r0          ; registers are cleared by the next call
+            st.1 32, r4, r0
+            add code[@abi], r0, r1
+            add code[@callee], r0, r2
+            far_call r1, r2, @fail
+            ld.inc r1, r5, r7
+            ld r7, r6, r0
+            ld.1 0, r3, r0
+            ld.1 32, r4, r0
+            ret.ok r0
+        fail:
+            ret.panic
+        abi: .cell {}
+        callee: .cell {}
+        ",
+        far_call_abi(0, 0, GAS_LIMIT as u32),
+        address_to_u256(&CONTRACT_ADDRESS),
+    ));
+
+    assert_eq!(result.execution.status, ExecutionStatus::Success);
+    // PUSH1, SLOAD, POP and GAS, where the slot is only cold in the first call
+    assert_eq!(result.register(3), U256::from(3 + 2100 + 2 + 2));
+    assert_eq!(result.register(4), U256::from(1));
+    assert_eq!(result.register(5), U256::from(3 + 100 + 2 + 2));
+    assert_eq!(result.register(6), U256::from(2));
+}
+
+#[test]
+fn test_evm_contract_failures_return_to_exception_handler() {
+    let mut harness = TestHarness::new();
+    // store 1 and revert with a single byte of data
+    harness.deploy_evm(CONTRACT_ADDRESS, &hex::decode("600160005560ab60005360016000fd").unwrap());
+    // invalid opcode
+    harness.deploy_evm(CALLER_ADDRESS, &hex::decode("fe").unwrap());
+
+    let result = harness.run(&format!(
+        "
+            add code[@abi], r0, r1
+            add code[@reverting], r0, r2
+            far_call r1, r2, @reverted
+            ret.panic
+        reverted:
+            jump.lt @fail           ; revert does not set the lt flag
+            ld r1, r3, r0
+            st.1 0, r3, r0          ; registers are cleared by the next call
+            add code[@abi], r0, r1
+            add code[@halting], r0, r2
+            far_call r1, r2, @halted
+            ret.panic
+        halted:
+            jump.lt @done           ; panics set the lt flag
+            ret.panic
+        done:
+            add code[@result], r0, r1
+            ret.ok r1
+        fail:
+            ret.panic
+        abi: .cell {}
+        reverting: .cell {}
+        halting: .cell {}
+        result: .cell {}
+        ",
+        far_call_abi(0, 0, 100_000),
+        address_to_u256(&CONTRACT_ADDRESS),
+        address_to_u256(&CALLER_ADDRESS),
+        U256::from_limbs([0, 32 << 32, 0, 0]),
+    ));
+
+    assert_eq!(result.execution.status, ExecutionStatus::Success);
+    // returndata of the revert is padded with zeros
+    let mut expected_return_data = vec![0u8; 32];
+    expected_return_data[0] = 0xab;
+    assert_eq!(result.execution.return_data, expected_return_data);
+    assert_eq!(harness.storage_value(CONTRACT_ADDRESS, U256::ZERO), U256::ZERO);
+    // exceptional halt consumes all the passed ergs
+    assert!(result.execution.ergs_used > 100_000);
+}
@@ -1,4 +1,22 @@
 use super::*;
 
+#[cfg(test)]
+mod call_tracer;
+#[cfg(test)]
+mod circuit_capacity;
+#[cfg(test)]
+mod ergs_profiler;
+#[cfg(test)]
+mod evm;
+#[cfg(test)]
+mod mul;
+#[cfg(test)]
+mod opcodes;
 #[cfg(test)]
 mod precompiles;
+#[cfg(test)]
+mod rollback;
+#[cfg(test)]
+mod run;
+#[cfg(test)]
+mod snapshot;
@@ -0,0 +1,15 @@
+use super::*;
+use crate::opcodes::execution::mul::full_width_mul;
+
+#[test]
+fn test_full_width_mul_keeps_high_word() {
+    let (low, high) = full_width_mul(U256::from(3), U256::from(5));
+    assert_eq!((low, high), (U256::from(15), U256::ZERO));
+
+    let (low, high) = full_width_mul(U256::from(1) << 255, U256::from(4));
+    assert_eq!((low, high), (U256::ZERO, U256::from(2)));
+
+    // (2^256 - 1)^2 = (2^256 - 2) * 2^256 + 1
+    let (low, high) = full_width_mul(U256::MAX, U256::MAX);
+    assert_eq!((low, high), (U256::from(1), U256::MAX - U256::from(1)));
+}
@@ -0,0 +1,565 @@
+use zkvm_opcodes::system_params::BOOTLOADER_FORMAL_ADDRESS;
+
+use super::*;
+use crate::{testing::harness::TestHarness, vm_state::ExecutionStatus};
+
+const CALLEE: Address = Address::new([0x0a; 20]);
+const OTHER_CALLEE: Address = Address::new([0x0b; 20]);
+
+const ERGS_FOR_CALLEE: u32 = 100_000;
+
+fn far_call_abi(start: u32, length: u32, ergs: u32) -> U256 {
+    U256::from_limbs([0, (start as u64) | ((length as u64) << 32), 0, ergs as u64])
+}
+
+fn ret_abi(start: u32, length: u32) -> U256 {
+    U256::from_limbs([0, (start as u64) | ((length as u64) << 32), 0, 0])
+}
+
+// high half of the pointer that makes `ret` forward the fat pointer instead of using the heap
+fn forward_fat_pointer_mask() -> U256 {
+    U256::from_limbs([0, 0, 0, 1 << 32])
+}
+
+fn address_to_u256(address: Address) -> U256 {
+    U256::from_be_slice(address.as_slice())
+}
+
+fn word(value: U256) -> Vec<u8> {
+    value.to_be_bytes::<32>().to_vec()
+}
+
+#[test]
+fn test_add_sub_and_conditions() {
+    let mut harness = TestHarness::new();
+    let result = harness.run(
+        "
+            add code[@value], r0, r1
+            add 3, r1, r2
+            sub.s 1, r2, r3         ; r3 = r2 - 1
+            sub! r0, r0, r4         ; sets the eq flag
+            add.eq 7, r0, r5
+            add.ne 9, r0, r6        ; skipped
+            sub.s! 1, r0, r7        ; underflows and sets the lt flag
+            add.lt 11, r0, r8
+            add.ge 13, r0, r9       ; skipped
+            add! code[@max], r1, r10 ; overflows and sets the lt flag as well
+            add.gtlt 15, r0, r11
+            ret.ok r0
+        value: .cell 100
+        max: .cell 0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff
+        ",
+    );
+
+    assert_eq!(result.execution.status, ExecutionStatus::Success);
+    assert_eq!(result.register(1), U256::from(100));
+    assert_eq!(result.register(2), U256::from(103));
+    assert_eq!(result.register(3), U256::from(102));
+    assert_eq!(result.register(5), U256::from(7));
+    assert_eq!(result.register(6), U256::ZERO);
+    assert_eq!(result.register(7), U256::MAX);
+    assert_eq!(result.register(8), U256::from(11));
+    assert_eq!(result.register(9), U256::ZERO);
+    assert_eq!(result.register(10), U256::from(99));
+    assert_eq!(result.register(11), U256::from(15));
+}
+
+#[test]
+fn test_mul_and_div() {
+    let mut harness = TestHarness::new();
+    // (2^200 + 1) * 2^100 = 2^300 + 2^100
+    let result = harness.run(
+        "
+            add code[@a], r0, r1
+            add code[@b], r0, r2
+            mul r1, r2, r3, r4
+            add 100, r0, r5
+            div.s 7, r5, r6, r7     ; 100 / 7
+            div! r0, r5, r8, r9     ; division by zero
+            ret.ok r0
+        a: .cell 0x100000000000000000000000000000000000000000000000001
+        b: .cell 0x10000000000000000000000000
+        ",
+    );
+
+    assert_eq!(result.execution.status, ExecutionStatus::Success);
+    assert_eq!(result.register(3), U256::from(1) << 100);
+    assert_eq!(result.register(4), U256::from(1) << 44);
+    assert_eq!(result.register(6), U256::from(14));
+    assert_eq!(result.register(7), U256::from(2));
+    assert_eq!(result.register(8), U256::ZERO);
+    assert_eq!(result.register(9), U256::ZERO);
+}
+
+#[test]
+fn test_bitwise_and_shifts() {
+    let mut harness = TestHarness::new();
+    let result = harness.run(
+        "
+            add 0xff0, r0, r1
+            and 0x0ff, r1, r2
+            or 0x00f, r1, r3
+            xor 0xfff, r1, r4
+            shl.s 8, r1, r5
+            shr.s 4, r1, r6
+            add code[@high], r0, r7
+            rol.s 4, r7, r8
+            ror.s 4, r1, r9
+            ret.ok r0
+        high: .cell 0xf000000000000000000000000000000000000000000000000000000000000001
+        ",
+    );
+
+    assert_eq!(result.execution.status, ExecutionStatus::Success);
+    assert_eq!(result.register(2), U256::from(0x0f0));
+    assert_eq!(result.register(3), U256::from(0xfff));
+    assert_eq!(result.register(4), U256::from(0x00f));
+    assert_eq!(result.register(5), U256::from(0xff000));
+    assert_eq!(result.register(6), U256::from(0xff));
+    assert_eq!(result.register(8), U256::from(0x1f));
+    assert_eq!(result.register(9), U256::from(0xff));
+}
+
+#[test]
+fn test_jump_loop() {
+    let mut harness = TestHarness::new();
+    let result = harness.run(
+        "
+            add 5, r0, r1
+        loop:
+            add 2, r2, r2
+            sub.s! 1, r1, r1
+            jump.ne @loop
+            ret.ok r0
+        ",
+    );
+
+    assert_eq!(result.execution.status, ExecutionStatus::Success);
+    assert_eq!(result.register(1), U256::ZERO);
+    assert_eq!(result.register(2), U256::from(10));
+    assert_eq!(result.execution.cycles, 1 + 5 * 3 + 1);
+}
+
+#[test]
+fn test_stack_and_nop() {
+    let mut harness = TestHarness::new();
+    let result = harness.run(
+        "
+            context.sp r1
+            add 11, r0, stack+=[1]
+            add 22, r0, stack+=[1]
+            context.sp r2
+            add stack-[1], r0, r3   ; top of the stack without popping it
+            add stack-=[1], r0, r4
+            add stack-=[1], r0, r5
+            add 33, r0, stack[r1+5]
+            add stack[5], r0, r6
+            nop r0, stack+=[3]
+            context.sp r7
+            nop stack-=[2], r0
+            context.sp r8
+            ret.ok r0
+        ",
+    );
+
+    assert_eq!(result.execution.status, ExecutionStatus::Success);
+    let sp = result.register(1);
+    assert_eq!(result.register(2), sp + U256::from(2));
+    assert_eq!(result.register(3), U256::from(22));
+    assert_eq!(result.register(4), U256::from(22));
+    assert_eq!(result.register(5), U256::from(11));
+    assert_eq!(result.register(6), U256::from(33));
+    assert_eq!(result.register(7), sp + U256::from(3));
+    assert_eq!(result.register(8), sp + U256::from(1));
+}
+
+#[test]
+fn test_context() {
+    let mut harness = TestHarness::new();
+    let result = harness.run(
+        "
+            context.this r1
+            context.caller r2
+            context.code_source r3
+            context.ergs_left r4
+            add 17, r0, r5
+            context.set_ergs_per_pubdata r5
+            context.meta r6
+            context.inc_tx_num
+            context.get_context_u128 r7
+            ret.ok r0
+        ",
+    );
+
+    assert_eq!(result.execution.status, ExecutionStatus::Success);
+    let bootloader = address_to_u256(*BOOTLOADER_FORMAL_ADDRESS);
+    assert_eq!(result.register(1), bootloader);
+    assert_eq!(result.register(2), bootloader);
+    assert_eq!(result.register(3), bootloader);
+    let ergs_left = result.register(4).to::<u32>();
+    assert!(ergs_left < harness.ergs_limit && ergs_left > harness.ergs_limit - 100);
+    assert_eq!(result.register(6).as_limbs()[0], 17);
+    assert_eq!(result.register(7), U256::ZERO);
+    assert_eq!(harness.vm.local_state.tx_number_in_block, 1);
+}
+
+#[test]
+fn test_near_call() {
+    let mut harness = TestHarness::new();
+    let result = harness.run(
+        "
+            add 1, r0, r1
+            near_call r0, @double, @fail
+            add 1000, r0, r5
+            near_call r5, @boom, @handler
+            ret.panic
+        handler:
+            add 3, r1, r1
+            near_call r0, @skip, @fail
+            add 1000, r0, r4        ; skipped by the return to label
+        landing:
+            ret.ok r0
+        double:
+            add r1, r1, r1
+            ret.ok r0
+        boom:
+            add 100, r0, r2
+            context.ergs_left r3
+            ret.panic
+        skip:
+            ret.ok.to_label r0, @landing
+        fail:
+            ret.panic
+        ",
+    );
+
+    assert_eq!(result.execution.status, ExecutionStatus::Success);
+    // near calls share the registers with the caller
+    assert_eq!(result.register(1), U256::from(5));
+    assert_eq!(result.register(2), U256::from(100));
+    assert!(result.register(3) < U256::from(1000));
+    assert_eq!(result.register(4), U256::ZERO);
+}
+
+#[test]
+fn test_heap_access() {
+    let mut harness = TestHarness::new();
+    let result = harness.run(
+        "
+            add code[@value], r0, r1
+            st.1 64, r1, r0
+            st.1.inc 96, r1, r2
+            ld.1 64, r3, r0
+            ld.1.inc r2, r4, r5     ; beyond the written data
+            st.2 0, r1, r0
+            ld.2 0, r6, r0
+            ld.1 0, r7, r0          ; aux heap is a different page
+            ld.1 80, r8, r0         ; unaligned
+            ret.ok r0
+        value: .cell 0x1234
+        ",
+    );
+
+    assert_eq!(result.execution.status, ExecutionStatus::Success);
+    assert_eq!(result.register(2), U256::from(128));
+    assert_eq!(result.register(3), U256::from(0x1234));
+    assert_eq!(result.register(4), U256::ZERO);
+    assert_eq!(result.register(5), U256::from(160));
+    assert_eq!(result.register(6), U256::from(0x1234));
+    assert_eq!(result.register(7), U256::ZERO);
+    assert_eq!(result.register(8), U256::from(0x1234) << 128);
+}
+
+#[test]
+fn test_storage_events_and_l1_messages() {
+    let mut harness = TestHarness::new();
+    let result = harness.run(
+        "
+            add 7, r0, r1
+            add 42, r0, r2
+            log.swrite r1, r2
+            log.sread r1, r3
+            log.sread r2, r4
+            log.event.first r1, r2
+            log.event r2, r1
+            log.to_l1.first r1, r2
+            ret.ok r0
+        ",
+    );
+
+    assert_eq!(result.execution.status, ExecutionStatus::Success);
+    assert_eq!(result.register(3), U256::from(42));
+    assert_eq!(result.register(4), U256::ZERO);
+    assert_eq!(harness.storage_value(*BOOTLOADER_FORMAL_ADDRESS, U256::from(7)), U256::from(42));
+
+    let events = &result.execution.events;
+    assert_eq!(events.len(), 2);
+    assert!(events[0].is_first);
+    assert_eq!(events[0].address, *BOOTLOADER_FORMAL_ADDRESS);
+    assert_eq!((events[0].key, events[0].value), (U256::from(7), U256::from(42)));
+    assert!(!events[1].is_first);
+    assert_eq!((events[1].key, events[1].value), (U256::from(42), U256::from(7)));
+
+    let l1_messages = &result.execution.l1_messages;
+    assert_eq!(l1_messages.len(), 1);
+    assert!(l1_messages[0].is_first);
+    assert_eq!((l1_messages[0].key, l1_messages[0].value), (U256::from(7), U256::from(42)));
+}
+
+#[test]
+fn test_far_call_returndata_and_pointers() {
+    let mut harness = TestHarness::new();
+    harness.deploy(
+        CALLEE,
+        &format!(
+            "
+                add 0x1111, r0, r2
+                st.1 0, r2, r0
+                add 0x2222, r0, r2
+                st.1 32, r2, r0
+                add code[@abi], r0, r1
+                ret.ok r1
+            abi: .cell {}
+            ",
+            ret_abi(0, 64)
+        ),
+    );
+
+    let result = harness.run(&format!(
+        "
+            add code[@abi], r0, r1
+            add code[@callee], r0, r2
+            far_call r1, r2, @fail
+            add 32, r0, r7
+            ptr.add r1, r7, r1
+            ld r1, r3, r0
+            ptr.sub r1, r7, r1
+            ld.inc r1, r4, r5
+            ld r5, r6, r0
+            ptr.shrink r1, r7, r1
+            add code[@mask], r0, r8
+            ptr.pack r1, r8, r1
+            ret.ok r1
+        fail:
+            ret.panic
+        abi: .cell {}
+        callee: .cell {}
+        mask: .cell {}
+        ",
+        far_call_abi(0, 0, ERGS_FOR_CALLEE),
+        address_to_u256(CALLEE),
+        forward_fat_pointer_mask(),
+    ));
+
+    assert_eq!(result.execution.status, ExecutionStatus::Success);
+    assert_eq!(result.register(3), U256::from(0x2222));
+    assert_eq!(result.register(4), U256::from(0x1111));
+    assert_eq!(result.register(6), U256::from(0x2222));
+    // the pointer was shrunk to the first word before it was forwarded
+    assert_eq!(result.execution.return_data, word(U256::from(0x1111)));
+}
+
+#[test]
+fn test_far_call_kinds_and_context() {
+    let mut harness = TestHarness::new();
+    // stores the context value and the caller of the frame
+    let callee_source = "
+        context.get_context_u128 r1
+        log.swrite r0, r1
+        context.caller r2
+        add 1, r0, r3
+        log.swrite r3, r2
+        ret.ok r0
+    ";
+    harness.deploy(CALLEE, callee_source);
+    harness.deploy(OTHER_CALLEE, callee_source);
+
+    let result = harness.run(&format!(
+        "
+            add 0x55, r0, r1
+            context.set_context_u128 r1
+            add code[@abi], r0, r1
+            add code[@callee], r0, r2
+            far_call r1, r2, @fail
+            add code[@abi], r0, r1
+            add code[@callee], r0, r2
+            far_call.delegate r1, r2, @fail
+            add code[@abi], r0, r1
+            add code[@other], r0, r2
+            add code[@mimicked], r0, r15
+            far_call.mimic r1, r2, @fail
+            ret.ok r0
+        fail:
+            ret.panic
+        abi: .cell {}
+        callee: .cell {}
+        other: .cell {}
+        mimicked: .cell 0x1234
+        ",
+        far_call_abi(0, 0, ERGS_FOR_CALLEE),
+        address_to_u256(CALLEE),
+        address_to_u256(OTHER_CALLEE),
+    ));
+
+    assert_eq!(result.execution.status, ExecutionStatus::Success);
+    let bootloader = address_to_u256(*BOOTLOADER_FORMAL_ADDRESS);
+    assert_eq!(harness.storage_value(CALLEE, U256::ZERO), U256::from(0x55));
+    assert_eq!(harness.storage_value(CALLEE, U256::from(1)), bootloader);
+    // delegate call runs in the context of the bootloader, and the context value is cleared
+    // by the return from the previous call
+    assert_eq!(harness.storage_value(*BOOTLOADER_FORMAL_ADDRESS, U256::ZERO), U256::ZERO);
+    assert_eq!(harness.storage_value(*BOOTLOADER_FORMAL_ADDRESS, U256::from(1)), bootloader);
+    assert_eq!(harness.storage_value(OTHER_CALLEE, U256::from(1)), U256::from(0x1234));
+}
+
+#[test]
+fn test_far_call_revert_and_panic() {
+    let mut harness = TestHarness::new();
+    harness.deploy(
+        CALLEE,
+        &format!(
+            "
+                add 1, r0, r1
+                log.swrite r1, r1       ; rolled back by the revert
+                add 0xdead, r0, r2
+                st.1 0, r2, r0
+                add code[@abi], r0, r1
+                ret.revert r1
+            abi: .cell {}
+            ",
+            ret_abi(0, 32)
+        ),
+    );
+    // writes the storage in the static call
+    harness.deploy(
+        OTHER_CALLEE,
+        "
+            log.swrite r0, r0
+            ret.ok r0
+        ",
+    );
+
+    let result = harness.run(&format!(
+        "
+            add code[@abi], r0, r1
+            add code[@callee], r0, r2
+            far_call r1, r2, @reverted
+            ret.panic
+        reverted:
+            ld r1, r3, r0
+            st.1 0, r3, r0          ; registers are cleared by the next call
+            add code[@abi], r0, r1
+            add code[@other], r0, r2
+            far_call.static r1, r2, @panicked
+            ret.panic
+        panicked:
+            add 1, r0, r4
+            jump.lt @done           ; panics set the lt flag
+            ret.panic
+        done:
+            add code[@result], r0, r1
+            ret.revert r1
+        abi: .cell {}
+        callee: .cell {}
+        other: .cell {}
+        result: .cell {}
+        ",
+        far_call_abi(0, 0, ERGS_FOR_CALLEE),
+        address_to_u256(CALLEE),
+        address_to_u256(OTHER_CALLEE),
+        ret_abi(0, 32),
+    ));
+
+    assert_eq!(result.execution.status, ExecutionStatus::Revert);
+    assert_eq!(result.register(4), U256::from(1));
+    assert_eq!(result.execution.return_data, word(U256::from(0xdead)));
+    assert_eq!(harness.storage_value(CALLEE, U256::from(1)), U256::ZERO);
+}
+
+#[test]
+fn test_precompile_call() {
+    let mut harness = TestHarness::new();
+    let identity = Address::with_last_byte(0x04);
+    // copies the first word of the heap into the second one, and returns the latter
+    harness.deploy(
+        identity,
+        &format!(
+            "
+                add code[@input], r0, r1
+                st.1 0, r1, r0
+                add code[@precompile_abi], r0, r1
+                add 10, r0, r2
+                log.precompile r1, r2, r3
+                add code[@abi], r0, r1
+                ret.ok r1
+            input: .cell 0xabcdef
+            precompile_abi: .cell {}
+            abi: .cell {}
+            ",
+            U256::from_limbs([1 << 32, 1 | (1 << 32), 0, 0]),
+            ret_abi(32, 32)
+        ),
+    );
+
+    let result = harness.run(&format!(
+        "
+            add code[@abi], r0, r1
+            add code[@identity], r0, r2
+            far_call r1, r2, @fail
+            add code[@mask], r0, r3
+            ptr.pack r1, r3, r1
+            ret.ok r1
+        fail:
+            ret.panic
+        abi: .cell {}
+        identity: .cell {}
+        mask: .cell {}
+        ",
+        far_call_abi(0, 0, ERGS_FOR_CALLEE),
+        address_to_u256(identity),
+        forward_fat_pointer_mask(),
+    ));
+
+    assert_eq!(result.execution.status, ExecutionStatus::Success);
+    assert_eq!(result.execution.return_data, word(U256::from(0xabcdef)));
+}
+
+#[test]
+fn test_exceptions_and_limits() {
+    let mut harness = TestHarness::new();
+    let result = harness.run("invalid");
+    assert_eq!(result.execution.status, ExecutionStatus::Panic);
+    assert_eq!(result.execution.ergs_used, harness.ergs_limit);
+
+    // not a pointer
+    let mut harness = TestHarness::new();
+    let result = harness.run(
+        "
+            ptr.add r1, r0, r1
+            ret.ok r0
+        ",
+    );
+    assert_eq!(result.execution.status, ExecutionStatus::Panic);
+
+    let mut harness = TestHarness::new();
+    harness.cycle_limit = 100;
+    let result = harness.run(
+        "
+        loop:
+            jump @loop
+        ",
+    );
+    assert_eq!(result.execution.status, ExecutionStatus::CycleBudgetExceeded);
+    assert_eq!(result.execution.cycles, 100);
+
+    let mut harness = TestHarness::new();
+    harness.ergs_limit = 100;
+    let result = harness.run(
+        "
+            st.1 10000, r0, r0
+            ret.ok r0
+        ",
+    );
+    assert_eq!(result.execution.status, ExecutionStatus::Panic);
+    assert_eq!(result.execution.ergs_used, 100);
+}
@@ -0,0 +1,78 @@
+//! Tests of the Blake2f precompile against the EIP-152 test vectors.
+
+use zkvm_opcodes::system_params::BLAKE2F_PRECOMPILE_FORMAL_ADDRESS;
+use zkvm_primitives::precompiles::blake2f::MAX_ROUNDS;
+
+use super::*;
+
+// h, m and t of the EIP-152 vectors, i.e., Blake2b-512 compression of "abc"
+const STATE_AND_MESSAGE: &str = "48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5\
+                                 d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b\
+                                 6162630000000000000000000000000000000000000000000000000000000000\
+                                 0000000000000000000000000000000000000000000000000000000000000000\
+                                 0000000000000000000000000000000000000000000000000000000000000000\
+                                 0000000000000000000000000000000000000000000000000000000000000000\
+                                 03000000000000000000000000000000";
+
+fn blake2f(rounds: u32, f: u8) -> Vec<U256> {
+    // the 213 byte input is padded to the whole number of words
+    let input = format!("{rounds:08x}{STATE_AND_MESSAGE}{f:02x}{}", "00".repeat(11));
+    run_precompile(*BLAKE2F_PRECOMPILE_FORMAL_ADDRESS, &input, 3, 0)
+}
+
+fn expected_state(ok: bool, state: &str) -> Vec<U256> {
+    let state = hex::decode(state).unwrap();
+    let mut expected = vec![U256::from(ok as u64)];
+    expected.extend(state.chunks(32).map(U256::from_be_slice));
+    expected
+}
+
+#[test]
+fn test_blake2f_eip152_vectors() {
+    // vector 4
+    let expected = "08c9bcf367e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5\
+                    d282e6ad7f520e511f6c3e2b8c68059b9442be0454267ce079217e1319cde05b";
+    assert_eq!(blake2f(0, 1), expected_state(true, expected));
+
+    // vector 5
+    let expected = "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+                    7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923";
+    assert_eq!(blake2f(12, 1), expected_state(true, expected));
+
+    // vector 6
+    let expected = "75ab69d3190a562c51aef8d88f1c2775876944407270c42c9844252c26d28752\
+                    98743e7f6d5ea2f2d3e8d226039cd31b4e426ac4f2d3d666a610c2116fde4735";
+    assert_eq!(blake2f(12, 0), expected_state(true, expected));
+
+    // vector 7
+    let expected = "b63a380cb2897d521994a85234ee2c181b5f844d2c624c002677e9703449d2fb\
+                    a551b3a8333bcdf5f2f7e08993d53923de3d64fcc68c034e717b9293fed7a421";
+    assert_eq!(blake2f(1, 1), expected_state(true, expected));
+}
+
+#[test]
+fn test_blake2f_invalid_final_block_flag() {
+    // vector 3
+    assert_eq!(blake2f(12, 2), vec![U256::ZERO; 3]);
+}
+
+#[test]
+fn test_blake2f_too_many_rounds() {
+    // vector 8 requests 2 ** 32 - 1 rounds, which is rejected instead of running
+    let input = format!("{:08x}{STATE_AND_MESSAGE}01{}", u32::MAX, "00".repeat(11));
+    let (output, witness) = run_precompile_with(
+        &mut DefaultPrecompilesProcessor::<true>,
+        *BLAKE2F_PRECOMPILE_FORMAL_ADDRESS,
+        &input,
+        3,
+        0,
+    );
+    assert_eq!(output, vec![U256::ZERO; 3]);
+    let PrecompileCyclesWitness::Blake2f(witness) = witness else {
+        panic!("expected a blake2f witness");
+    };
+    assert_eq!(witness.len(), 1);
+
+    assert_eq!(blake2f(MAX_ROUNDS + 1, 1), vec![U256::ZERO; 3]);
+    assert_eq!(blake2f(MAX_ROUNDS, 1)[0], U256::from(1));
+}
@@ -0,0 +1,157 @@
+//! Differential tests of the alt_bn128 precompiles. Expected outputs are taken from the Ethereum
+//! precompile test vectors or computed with an independent reference implementation.
+
+use alloy_primitives::hex;
+use zkvm_opcodes::system_params::*;
+
+use super::*;
+
+const G1: &str = "0000000000000000000000000000000000000000000000000000000000000001\
+                  0000000000000000000000000000000000000000000000000000000000000002";
+const NEG_G1: &str = "0000000000000000000000000000000000000000000000000000000000000001\
+                      30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd45";
+const DOUBLE_G1: &str = "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3\
+                         15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4";
+const G2: &str = "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\
+                  1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\
+                  090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b\
+                  12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa";
+const DOUBLE_G2: &str = "203e205db4f19b37b60121b83a7333706db86431c6d835849957ed8c3928ad79\
+                         27dc7234fd11d3e8c36c59277c3e6f149d5cd3cfa9a62aee49f8130962b4b3b9\
+                         195e8aa5b7827463722b8c153931579d3505566b4edf48d498e185f0509de152\
+                         04bb53b8977e5f92a0bc372742c4830944a59b4fe6b1c0466e2a6dad122b5d2e";
+const ZERO_POINT: &str = "0000000000000000000000000000000000000000000000000000000000000000\
+                          0000000000000000000000000000000000000000000000000000000000000000";
+
+fn expected_point(ok: bool, point: &str) -> Vec<U256> {
+    let point = hex::decode(point).unwrap();
+    let mut expected = vec![U256::from(ok as u64)];
+    expected.extend(point.chunks(32).map(U256::from_be_slice));
+    expected
+}
+
+fn ecadd(input: &str) -> Vec<U256> {
+    run_precompile(*ECADD_PRECOMPILE_FORMAL_ADDRESS, input, 3, 0)
+}
+
+fn ecmul(input: &str) -> Vec<U256> {
+    run_precompile(*ECMUL_PRECOMPILE_FORMAL_ADDRESS, input, 3, 0)
+}
+
+fn ecpairing(pairs: &[(&str, &str)]) -> Vec<U256> {
+    let input: String = pairs.iter().map(|(g1, g2)| format!("{g1}{g2}")).collect();
+    run_precompile(*ECPAIRING_PRECOMPILE_FORMAL_ADDRESS, &input, 2, pairs.len() as u64)
+}
+
+#[test]
+fn test_ecadd_ethereum_vectors() {
+    // `chfast1` from the Ethereum test vectors
+    let input = "18b18acfb4c2c30276db5411368e7185b311dd124691610c5d3b74034e093dc9\
+                 063c909c4720840cb5134cb9f59fa749755796819658d32efc0d288198f37266\
+                 07c2b7f58a84bd6145f00c9c2bc0bb1a187f20ff2c92963a88019e7c6a014eed\
+                 06614e20c147e940f2d70da3f74c9a17df361706a4485c742bd6788478fa17d7";
+    let expected = "2243525c5efd4b9c3d3c45ac0ca3fe4dd85e830a4ce6b65fa1eeaee202839703\
+                    301d1d33be6da8e509df21cc35964723180eed7532537db9ae5e7d48f195c915";
+    assert_eq!(ecadd(input), expected_point(true, expected));
+
+    assert_eq!(ecadd(&format!("{G1}{G1}")), expected_point(true, DOUBLE_G1));
+    assert_eq!(ecadd(&format!("{G1}{ZERO_POINT}")), expected_point(true, G1));
+    assert_eq!(ecadd(&format!("{G1}{NEG_G1}")), expected_point(true, ZERO_POINT));
+    assert_eq!(ecadd(&format!("{ZERO_POINT}{ZERO_POINT}")), expected_point(true, ZERO_POINT));
+}
+
+#[test]
+fn test_ecadd_point_not_on_curve() {
+    let not_on_curve = "0000000000000000000000000000000000000000000000000000000000000001\
+                        0000000000000000000000000000000000000000000000000000000000000003";
+    assert_eq!(ecadd(&format!("{G1}{not_on_curve}")), expected_point(false, ZERO_POINT));
+
+    // coordinates must be reduced
+    let unreduced = "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd48\
+                     0000000000000000000000000000000000000000000000000000000000000002";
+    assert_eq!(ecadd(&format!("{unreduced}{G1}")), expected_point(false, ZERO_POINT));
+}
+
+#[test]
+fn test_ecmul_ethereum_vectors() {
+    let two = format!("{:064x}", 2);
+    assert_eq!(ecmul(&format!("{G1}{two}")), expected_point(true, DOUBLE_G1));
+
+    // (r - 1) * G1 = -G1, where r is the group order
+    let scalar = "30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000000";
+    assert_eq!(ecmul(&format!("{G1}{scalar}")), expected_point(true, NEG_G1));
+
+    // `chfast1` point from the Ethereum test vectors
+    let point = "18b18acfb4c2c30276db5411368e7185b311dd124691610c5d3b74034e093dc9\
+                 063c909c4720840cb5134cb9f59fa749755796819658d32efc0d288198f37266";
+    let scalar = "30644e72e131a029b85045b48181585d2833e84879b9709143e1f593f0000000";
+    let expected = "1fb78e8f3cc2e46757d04da953a4d9a28d19ecdf5f61bb01a3418df41384718c\
+                    2e282d91f19216b7205b96c09a43306c61c7f1e3895b40039a0a3070de527d57";
+    assert_eq!(ecmul(&format!("{point}{scalar}")), expected_point(true, expected));
+
+    // scalars are reduced modulo the group order
+    let scalar = "30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f000000a";
+    let expected = "039730ea8dff1254c0fee9c0ea777d29a9c710b7e616683f194f18c43b43b869\
+                    073a5ffcc6fc7a28c30723d6e58ce577356982d65b833a5a5c15bf9024b43d98";
+    assert_eq!(ecmul(&format!("{G1}{scalar}")), expected_point(true, expected));
+
+    let zero = format!("{:064x}", 0);
+    assert_eq!(ecmul(&format!("{G1}{zero}")), expected_point(true, ZERO_POINT));
+    assert_eq!(ecmul(&format!("{ZERO_POINT}{two}")), expected_point(true, ZERO_POINT));
+
+    let not_on_curve = "0000000000000000000000000000000000000000000000000000000000000001\
+                        0000000000000000000000000000000000000000000000000000000000000003";
+    assert_eq!(ecmul(&format!("{not_on_curve}{two}")), expected_point(false, ZERO_POINT));
+}
+
+#[test]
+fn test_ecpairing() {
+    let ok_and = |result: u64| vec![U256::from(1), U256::from(result)];
+
+    // e(2 * G1, G2) * e(-G1, 2 * G2) = 1
+    assert_eq!(ecpairing(&[(DOUBLE_G1, G2), (NEG_G1, DOUBLE_G2)]), ok_and(1));
+    // e(G1, G2) * e(-G1, G2) = 1
+    assert_eq!(ecpairing(&[(G1, G2), (NEG_G1, G2)]), ok_and(1));
+    // e(G1, G2) * e(G1, G2) != 1
+    assert_eq!(ecpairing(&[(G1, G2), (G1, G2)]), ok_and(0));
+    assert_eq!(ecpairing(&[(G1, G2)]), ok_and(0));
+    // pairs with a point at infinity are skipped
+    let zero_g2 = "00".repeat(128);
+    assert_eq!(ecpairing(&[(ZERO_POINT, G2)]), ok_and(1));
+    assert_eq!(ecpairing(&[(G1, &zero_g2), (G1, G2), (NEG_G1, G2)]), ok_and(1));
+}
+
+#[test]
+fn test_ecpairing_without_pairs() {
+    assert_eq!(ecpairing(&[]), vec![U256::from(1), U256::from(1)]);
+
+    let (_, witness) = run_precompile_with(
+        &mut DefaultPrecompilesProcessor::<true>,
+        *ECPAIRING_PRECOMPILE_FORMAL_ADDRESS,
+        "",
+        2,
+        0,
+    );
+    let PrecompileCyclesWitness::ECPairing(witness) = witness else {
+        panic!("expected an ecPairing witness");
+    };
+    assert_eq!(witness.len(), 1);
+    assert!(witness[0].new_request.is_some());
+    assert!(witness[0].writes.is_some());
+}
+
+#[test]
+fn test_ecpairing_invalid_points() {
+    let err = vec![U256::ZERO, U256::ZERO];
+
+    let not_on_curve = "0000000000000000000000000000000000000000000000000000000000000001\
+                        0000000000000000000000000000000000000000000000000000000000000003";
+    assert_eq!(ecpairing(&[(G1, G2), (not_on_curve, G2)]), err);
+
+    // swapped real and imaginary parts of G2 coordinates
+    let swapped_g2 = "1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\
+                      198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\
+                      12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa\
+                      090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b";
+    assert_eq!(ecpairing(&[(G1, swapped_g2)]), err);
+}
@@ -46,7 +46,7 @@ fn fill_memory<M: Memory>(hash: [u8; 32], r: [u8; 32], s: [u8; 32], v: bool, pag
 }
 
 fn ecrecover_test_inner(hash: [u8; 32], r: [u8; 32], s: [u8; 32], v: bool, expect_ok: bool, expected_address: [u8; 20]) -> (Vec<[u8; 32]>, std::ops::Range<u16>) {
-    let mut memory = SimpleMemory::new();
+    let mut memory = SimpleMemory::new_without_preallocations();
     let mut precompiles_processor = DefaultPrecompilesProcessor::<false>;
 
     // fill the memory
@@ -0,0 +1,23 @@
+use zkvm_opcodes::system_params::IDENTITY_PRECOMPILE_FORMAL_ADDRESS;
+
+use super::*;
+
+#[test]
+fn test_identity() {
+    let input = "0000000000000000000000000000000000000000000000000000000000000001\
+                 ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\
+                 00112233445566778899aabbccddeeff00112233445566778899aabbccddeeff";
+    let output = run_precompile(*IDENTITY_PRECOMPILE_FORMAL_ADDRESS, input, 3, 0);
+    let expected: Vec<_> = hex::decode(input)
+        .unwrap()
+        .chunks(32)
+        .map(U256::from_be_slice)
+        .collect();
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn test_identity_empty_input() {
+    let output = run_precompile(*IDENTITY_PRECOMPILE_FORMAL_ADDRESS, "", 0, 0);
+    assert!(output.is_empty());
+}
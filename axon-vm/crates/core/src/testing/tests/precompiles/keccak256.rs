@@ -71,7 +71,7 @@ fn run_keccak256_test_inner(
     input: &[u8],
     unalignment: u32,
 ) -> (Vec<[u8; 32]>, std::ops::Range<u32>) {
-    let mut memory = SimpleMemory::new();
+    let mut memory = SimpleMemory::new_without_preallocations();
 
     let input_memory_page = 4u32;
     let output_memory_page = 4u32;
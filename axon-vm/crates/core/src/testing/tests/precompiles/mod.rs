@@ -1,8 +1,21 @@
-use alloy_primitives::hex;
+use alloy_primitives::{hex, Address};
+use zkvm_opcodes::{system_params::PRECOMPILE_AUX_BYTE, PrecompileCallABI};
+use zkvm_primitives::{
+    auxiliary::*,
+    queries::MemoryQuery,
+    vm::{Memory, MemoryType, PrecompileCyclesWitness, PrecompilesProcessor},
+};
 
 use super::*;
 
+mod blake2f;
+mod bn254;
+mod identity;
 mod keccak256;
+mod modexp;
+mod registry;
+mod ripemd160;
+mod secp256r1_verify;
 // mod sha256;
 // mod ecrecover;
 
@@ -14,3 +27,99 @@ fn pretty_print_memory_dump(content: &[[u8; 32]], range: std::ops::Range<u32>) {
     }
     println!("-----------------------------------------");
 }
+
+/// Writes `input` to a fresh heap page, calls the precompile at `address` and returns
+/// `output_len` words written after the input.
+fn run_precompile(
+    address: Address,
+    input: &str,
+    output_len: u32,
+    precompile_interpreted_data: u64,
+) -> Vec<U256> {
+    let (output, _) = run_precompile_with(
+        &mut DefaultPrecompilesProcessor::<true>,
+        address,
+        input,
+        output_len,
+        precompile_interpreted_data,
+    );
+    output
+}
+
+/// Same as [`run_precompile`], but uses the provided processor and also returns the witness.
+fn run_precompile_with<PP: PrecompilesProcessor>(
+    precompiles_processor: &mut PP,
+    address: Address,
+    input: &str,
+    output_len: u32,
+    precompile_interpreted_data: u64,
+) -> (Vec<U256>, PrecompileCyclesWitness) {
+    let input = hex::decode(input).unwrap();
+    let mut memory = SimpleMemory::new_without_preallocations();
+
+    let memory_page = 4u32;
+    memory
+        .heaps
+        .push(((memory_page, vec![U256::ZERO; 1 << 10]), (0, vec![U256::ZERO; 0])));
+    memory
+        .page_numbers_indirections
+        .insert(memory_page, reference_impls::memory::Indirection::Heap(1));
+
+    let words: Vec<_> = input.chunks(32).map(U256::from_be_slice).collect();
+    let num_words_used = words.len() as u32;
+    for (index, word) in words.into_iter().enumerate() {
+        let location = MemoryLocation {
+            page: MemoryPage(memory_page),
+            index: MemoryIndex(index as u32),
+            memory_type: MemoryType::Heap,
+        };
+        let query = MemoryQuery {
+            timestamp: Timestamp(0u32),
+            location,
+            value: word,
+            value_is_pointer: false,
+            rw_flag: true,
+        };
+        let _ = memory.execute_partial_query(1, query);
+    }
+
+    let precompile_abi = PrecompileCallABI {
+        input_memory_offset: 0,
+        input_memory_length: num_words_used,
+        output_memory_offset: num_words_used,
+        output_memory_length: output_len,
+        memory_page_to_read: memory_page,
+        memory_page_to_write: memory_page,
+        precompile_interpreted_data,
+    };
+
+    let precompile_query = LogQuery {
+        timestamp: Timestamp(1),
+        tx_number_in_block: 0,
+        shard_id: 0,
+        aux_byte: PRECOMPILE_AUX_BYTE,
+        address,
+        key: precompile_abi.to_u256(),
+        read_value: U256::ZERO,
+        written_value: U256::ZERO,
+        rw_flag: false,
+        rollback: false,
+        is_service: false,
+    };
+
+    let (reads, writes, witness) = precompiles_processor
+        .execute_precompile(4, precompile_query, &mut memory)
+        .unwrap();
+    assert_eq!(reads.len(), num_words_used as usize);
+    assert_eq!(writes.len(), output_len as usize);
+
+    let range = 0u32..(num_words_used + output_len);
+    let content = memory.dump_page_content(memory_page, range.clone());
+    pretty_print_memory_dump(&content, range);
+    let output = content[num_words_used as usize..]
+        .iter()
+        .map(|word| U256::from_be_bytes(*word))
+        .collect();
+
+    (output, witness)
+}
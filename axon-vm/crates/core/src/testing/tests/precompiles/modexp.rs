@@ -0,0 +1,122 @@
+use zkvm_opcodes::system_params::MODEXP_PRECOMPILE_FORMAL_ADDRESS;
+use zkvm_primitives::precompiles::modexp::{ModexpLengths, MAX_OPERAND_WORDS};
+
+use super::*;
+
+fn modexp(base: &str, exponent: &str, modulus: &str) -> Vec<U256> {
+    let lengths = ModexpLengths {
+        base: (base.len() / 64) as u32,
+        exponent: (exponent.len() / 64) as u32,
+        modulus: (modulus.len() / 64) as u32,
+    };
+    run_precompile(
+        *MODEXP_PRECOMPILE_FORMAL_ADDRESS,
+        &format!("{base}{exponent}{modulus}"),
+        lengths.modulus,
+        lengths.to_interpreted_data(),
+    )
+}
+
+fn word(value: u64) -> String {
+    format!("{value:064x}")
+}
+
+#[test]
+fn test_modexp() {
+    assert_eq!(modexp(&word(3), &word(5), &word(7)), vec![U256::from(5)]);
+    assert_eq!(modexp(&word(2), &word(0), &word(10)), vec![U256::from(1)]);
+    assert_eq!(modexp(&word(0), &word(0), &word(10)), vec![U256::from(1)]);
+    assert_eq!(modexp(&word(12), &word(1), &word(1)), vec![U256::ZERO]);
+}
+
+#[test]
+fn test_modexp_fermat() {
+    // 3 ** (p - 1) % p = 1 by Fermat's little theorem, where p = 2 ** 256 - 2 ** 32 - 977
+    let p = "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f";
+    let p_minus_one = "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e";
+    assert_eq!(modexp(&word(3), p_minus_one, p), vec![U256::from(1)]);
+}
+
+#[test]
+fn test_modexp_multiword_operands() {
+    // (2 ** 256) ** 2 % (2 ** 384 + 1) = 2 ** 384 - 2 ** 128 + 1, as 2 ** 384 = -1
+    let base = format!("{}{}", word(1), word(0));
+    let modulus = "0000000000000000000000000000000100000000000000000000000000000000\
+                   0000000000000000000000000000000000000000000000000000000000000001";
+    let expected = "00000000000000000000000000000000ffffffffffffffffffffffffffffffff\
+                    ffffffffffffffffffffffffffffffff00000000000000000000000000000001";
+    let expected: Vec<_> = hex::decode(expected)
+        .unwrap()
+        .chunks(32)
+        .map(U256::from_be_slice)
+        .collect();
+    assert_eq!(modexp(&base, &word(2), modulus), expected);
+}
+
+#[test]
+fn test_modexp_zero_modulus() {
+    assert_eq!(modexp(&word(3), &word(5), &word(0)), vec![U256::ZERO]);
+    assert_eq!(
+        modexp(&word(3), &word(5), &format!("{}{}", word(0), word(0))),
+        vec![U256::ZERO, U256::ZERO]
+    );
+}
+
+#[test]
+fn test_modexp_zero_modulus_length() {
+    assert_eq!(modexp(&word(3), &word(5), ""), Vec::<U256>::new());
+    assert_eq!(modexp("", "", ""), Vec::<U256>::new());
+}
+
+fn modexp_rounds(exponent: &str, modulus_len: u32) -> usize {
+    let lengths =
+        ModexpLengths { base: 1, exponent: (exponent.len() / 64) as u32, modulus: modulus_len };
+    let modulus = word(7).repeat(modulus_len as usize);
+    let (_, witness) = run_precompile_with(
+        &mut DefaultPrecompilesProcessor::<true>,
+        *MODEXP_PRECOMPILE_FORMAL_ADDRESS,
+        &format!("{}{exponent}{modulus}", word(3)),
+        modulus_len,
+        lengths.to_interpreted_data(),
+    );
+    let PrecompileCyclesWitness::Modexp(witness) = witness else {
+        panic!("expected a modexp witness");
+    };
+    let num_words = 1 + lengths.exponent + modulus_len;
+    assert!(witness[0].new_request.is_some());
+    assert!(witness[1..].iter().all(|round| round.new_request.is_none()));
+    assert_eq!(witness[0].reads.len(), num_words as usize);
+    assert_eq!(witness.last().unwrap().writes.len(), modulus_len as usize);
+    witness.len()
+}
+
+#[test]
+fn test_modexp_rounds_are_proportional_to_work() {
+    assert_eq!(modexp_rounds(&word(0), 1), 1);
+    assert_eq!(modexp_rounds(&word(5), 0), 1);
+    assert_eq!(modexp_rounds(&"ff".repeat(32), 1), 1);
+    // twice the exponent bits
+    assert_eq!(modexp_rounds(&"ff".repeat(64), 1), 2);
+    // twice the modulus words, so four times the work per bit
+    assert_eq!(modexp_rounds(&"ff".repeat(64), 2), 8);
+    assert_eq!(modexp_rounds(&word(0x1_0000), 4), 2);
+}
+
+#[test]
+fn test_modexp_rejects_oversize_operands() {
+    // without the bound, this would take 2 ** 16 * 256 * 32 ** 2 / 256 rounds
+    let lengths = ModexpLengths { base: 1, exponent: 0xffff, modulus: MAX_OPERAND_WORDS };
+    // nothing is read or written, so the input may be omitted
+    let (_, witness) = run_precompile_with(
+        &mut DefaultPrecompilesProcessor::<true>,
+        *MODEXP_PRECOMPILE_FORMAL_ADDRESS,
+        "",
+        0,
+        lengths.to_interpreted_data(),
+    );
+    let PrecompileCyclesWitness::Modexp(witness) = witness else {
+        panic!("expected a modexp witness");
+    };
+    assert_eq!(witness.len(), 1);
+    assert!(witness[0].reads.is_empty() && witness[0].writes.is_empty());
+}
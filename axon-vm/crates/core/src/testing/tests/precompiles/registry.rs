@@ -0,0 +1,244 @@
+use std::{cell::Cell, rc::Rc};
+
+use zkvm_opcodes::system_params::{IDENTITY_PRECOMPILE_ADDRESS, IDENTITY_PRECOMPILE_FORMAL_ADDRESS};
+use zkvm_primitives::{
+    precompiles::{precompile_abi_in_log, registry::PrecompileRegistry},
+    vm::{IntoCyclesWitness, Precompile, RevertablePrecompile},
+};
+
+use super::*;
+
+const MOCK_PRECOMPILE_ADDRESS: u16 = 0x0200;
+const COUNTER_PRECOMPILE_ADDRESS: u16 = 0x0201;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct MockRoundWitness {
+    input: U256,
+}
+
+impl IntoCyclesWitness for MockRoundWitness {}
+
+/// Increments the input word.
+#[derive(Debug)]
+struct MockPrecompile;
+
+impl Precompile for MockPrecompile {
+    type CycleWitness = MockRoundWitness;
+
+    fn execute_precompile<M: Memory>(
+        &mut self,
+        monotonic_cycle_counter: u32,
+        query: LogQuery,
+        memory: &mut M,
+    ) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, Vec<Self::CycleWitness>)> {
+        let params = precompile_abi_in_log(query);
+        let read_query = MemoryQuery {
+            timestamp: query.timestamp,
+            location: MemoryLocation {
+                memory_type: MemoryType::Heap,
+                page: MemoryPage(params.memory_page_to_read),
+                index: MemoryIndex(params.input_memory_offset),
+            },
+            value: U256::ZERO,
+            value_is_pointer: false,
+            rw_flag: false,
+        };
+        let read_query = memory.execute_partial_query(monotonic_cycle_counter, read_query);
+
+        let write_query = MemoryQuery {
+            timestamp: Timestamp(query.timestamp.0 + 1),
+            location: MemoryLocation {
+                memory_type: MemoryType::Heap,
+                page: MemoryPage(params.memory_page_to_write),
+                index: MemoryIndex(params.output_memory_offset),
+            },
+            value: read_query.value + U256::from(1),
+            value_is_pointer: false,
+            rw_flag: true,
+        };
+        let write_query = memory.execute_partial_query(monotonic_cycle_counter, write_query);
+
+        Some((
+            vec![read_query],
+            vec![write_query],
+            vec![MockRoundWitness { input: read_query.value }],
+        ))
+    }
+}
+
+#[test]
+fn test_registry_with_default_precompiles() {
+    let mut registry = PrecompileRegistry::<true>::with_default_precompiles();
+    let input = format!("{:064x}{:064x}", 1, 2);
+    let (output, witness) =
+        run_precompile_with(&mut registry, *IDENTITY_PRECOMPILE_FORMAL_ADDRESS, &input, 2, 0);
+
+    assert_eq!(output, vec![U256::from(1), U256::from(2)]);
+    // witnesses of the known precompiles are not erased
+    assert!(matches!(witness, PrecompileCyclesWitness::Identity(witness) if witness.len() == 2));
+}
+
+#[test]
+fn test_registry_with_mock_precompile() {
+    let mut registry = PrecompileRegistry::<true>::new();
+    assert!(
+        registry
+            .register(MOCK_PRECOMPILE_ADDRESS, MockPrecompile)
+            .is_none()
+    );
+    assert!(registry.is_registered(MOCK_PRECOMPILE_ADDRESS));
+
+    let address = Address::left_padding_from(&MOCK_PRECOMPILE_ADDRESS.to_be_bytes());
+    let input = format!("{:064x}", 41);
+    let (output, witness) = run_precompile_with(&mut registry, address, &input, 1, 0);
+    assert_eq!(output, vec![U256::from(42)]);
+
+    let PrecompileCyclesWitness::Erased(witness) = witness else {
+        panic!("witness of the mock precompile must be erased");
+    };
+    assert!(!witness.is::<u64>());
+    let witness = witness
+        .downcast::<MockRoundWitness>()
+        .unwrap_or_else(|_| unreachable!());
+    assert_eq!(witness, vec![MockRoundWitness { input: U256::from(41) }]);
+}
+
+#[test]
+fn test_registry_overrides_default_precompile() {
+    let mut registry = PrecompileRegistry::<true>::with_default_precompiles();
+    assert!(
+        registry
+            .register(IDENTITY_PRECOMPILE_ADDRESS, MockPrecompile)
+            .is_some()
+    );
+
+    let input = format!("{:064x}", 1);
+    let (output, _) =
+        run_precompile_with(&mut registry, *IDENTITY_PRECOMPILE_FORMAL_ADDRESS, &input, 1, 0);
+    assert_eq!(output, vec![U256::from(2)]);
+
+    assert!(registry.unregister(IDENTITY_PRECOMPILE_ADDRESS).is_some());
+    assert!(!registry.is_registered(IDENTITY_PRECOMPILE_ADDRESS));
+}
+
+/// Writes nothing and generates no witness.
+#[derive(Debug)]
+struct NoWitnessPrecompile;
+
+impl Precompile for NoWitnessPrecompile {
+    type CycleWitness = MockRoundWitness;
+
+    fn execute_precompile<M: Memory>(
+        &mut self,
+        _monotonic_cycle_counter: u32,
+        _query: LogQuery,
+        _memory: &mut M,
+    ) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, Vec<Self::CycleWitness>)> {
+        None
+    }
+}
+
+#[test]
+#[should_panic(expected = "must generate intermediate witness")]
+fn test_registry_requires_witness() {
+    let mut registry = PrecompileRegistry::<true>::new();
+    registry.register(MOCK_PRECOMPILE_ADDRESS, NoWitnessPrecompile);
+    let address = Address::left_padding_from(&MOCK_PRECOMPILE_ADDRESS.to_be_bytes());
+    run_precompile_with(&mut registry, address, "", 0, 0);
+}
+
+/// Writes the number of the calls made so far, which is the state to be rolled back.
+#[derive(Debug)]
+struct CounterPrecompile {
+    counter: Rc<Cell<u64>>,
+}
+
+impl Precompile for CounterPrecompile {
+    type CycleWitness = MockRoundWitness;
+
+    fn execute_precompile<M: Memory>(
+        &mut self,
+        monotonic_cycle_counter: u32,
+        query: LogQuery,
+        memory: &mut M,
+    ) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, Vec<Self::CycleWitness>)> {
+        let params = precompile_abi_in_log(query);
+        let write_query = MemoryQuery {
+            timestamp: Timestamp(query.timestamp.0 + 1),
+            location: MemoryLocation {
+                memory_type: MemoryType::Heap,
+                page: MemoryPage(params.memory_page_to_write),
+                index: MemoryIndex(params.output_memory_offset),
+            },
+            value: U256::from(self.counter.get()),
+            value_is_pointer: false,
+            rw_flag: true,
+        };
+        let write_query = memory.execute_partial_query(monotonic_cycle_counter, write_query);
+        self.counter.set(self.counter.get() + 1);
+
+        Some((vec![], vec![write_query], vec![]))
+    }
+}
+
+impl RevertablePrecompile for CounterPrecompile {
+    fn rollback(&mut self, query: LogQuery) {
+        assert!(query.rollback);
+        self.counter.set(self.counter.get() - 1);
+    }
+}
+
+#[test]
+fn test_registry_rollbacks_revertable_precompile() {
+    let counter = Rc::new(Cell::new(0));
+    let mut registry = PrecompileRegistry::<true>::with_default_precompiles();
+    registry.register_revertable(
+        COUNTER_PRECOMPILE_ADDRESS,
+        CounterPrecompile { counter: counter.clone() },
+    );
+
+    let address = Address::left_padding_from(&COUNTER_PRECOMPILE_ADDRESS.to_be_bytes());
+    let call = |registry: &mut PrecompileRegistry<true>| {
+        let (output, _) = run_precompile_with(registry, address, "", 1, 0);
+        output[0]
+    };
+
+    let query = LogQuery {
+        timestamp: Timestamp(1),
+        tx_number_in_block: 0,
+        shard_id: 0,
+        aux_byte: PRECOMPILE_AUX_BYTE,
+        address,
+        key: U256::ZERO,
+        read_value: U256::ZERO,
+        written_value: U256::ZERO,
+        rw_flag: false,
+        rollback: false,
+        is_service: false,
+    };
+    assert!(registry.is_revertable_precompile_call(&query));
+    let identity_query = LogQuery { address: *IDENTITY_PRECOMPILE_FORMAL_ADDRESS, ..query };
+    assert!(!registry.is_revertable_precompile_call(&identity_query));
+
+    registry.start_frame();
+    assert_eq!(call(&mut registry), U256::from(0));
+    assert_eq!(call(&mut registry), U256::from(1));
+
+    // the nested frame panics, so its call is rolled back
+    registry.start_frame();
+    assert_eq!(call(&mut registry), U256::from(2));
+    registry.finish_frame(true);
+    assert_eq!(counter.get(), 2);
+
+    // calls of the successful nested frame are rolled back with the parent frame
+    registry.start_frame();
+    assert_eq!(call(&mut registry), U256::from(2));
+    registry.finish_frame(false);
+    assert_eq!(counter.get(), 3);
+    registry.finish_frame(true);
+    assert_eq!(counter.get(), 0);
+
+    let history = registry.revertable_calls_history();
+    let rollbacks: Vec<_> = history.iter().map(|query| query.rollback).collect();
+    assert_eq!(rollbacks, vec![false, false, false, true, false, true, true, true]);
+}
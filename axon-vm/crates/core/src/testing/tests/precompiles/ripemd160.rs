@@ -0,0 +1,48 @@
+use zkvm_opcodes::system_params::RIPEMD160_ROUND_FUNCTION_PRECOMPILE_FORMAL_ADDRESS;
+
+use super::*;
+
+/// Pads the message as RIPEMD-160 does: the caller is responsible for the padding, so the
+/// precompile only runs the round function over full blocks.
+fn pad_message(message: &[u8]) -> Vec<u8> {
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&((message.len() as u64) * 8).to_le_bytes());
+    padded
+}
+
+fn ripemd160(message: &[u8]) -> U256 {
+    let padded = pad_message(message);
+    let num_rounds = (padded.len() / 64) as u64;
+    let output = run_precompile(
+        *RIPEMD160_ROUND_FUNCTION_PRECOMPILE_FORMAL_ADDRESS,
+        &hex::encode(padded),
+        1,
+        num_rounds,
+    );
+    output[0]
+}
+
+fn expected_hash(hash: &str) -> U256 {
+    // the 20 byte hash is right-aligned in the output word
+    U256::from_be_slice(&hex::decode(hash).unwrap())
+}
+
+#[test]
+fn test_ripemd160() {
+    assert_eq!(ripemd160(b""), expected_hash("9c1185a5c5e9fc54612808977ee8f548b2258d31"));
+    assert_eq!(ripemd160(b"abc"), expected_hash("8eb208f7e05d987a9b044a8e98c6b087f15a0bfc"));
+    assert_eq!(
+        ripemd160(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+        expected_hash("12a053384a9c0c88e405a06c27dcf49ada62eb2b")
+    );
+    assert_eq!(
+        ripemd160(
+            b"12345678901234567890123456789012345678901234567890123456789012345678901234567890"
+        ),
+        expected_hash("9b752e45573d4b39f4dbd3323cab82bf63326bfb")
+    );
+}
@@ -0,0 +1,52 @@
+use alloy_primitives::hex;
+use zkvm_opcodes::system_params::SECP256R1_VERIFY_PRECOMPILE_FORMAL_ADDRESS;
+
+use super::*;
+
+// hash, r, s, x, y; the signature is produced by an independent P-256 implementation
+const VALID_INPUT: &str = "b6b6bc528cba9129d5e0587297a9eb4c207b00b637fca07dc6277565055431dd\
+                           2d8a757d47be9460ea25e7251fd9bdc6b10505bdc18386a8c26642bf52902908\
+                           e5a937702a8ad2a73a734592e24a5f4f224967b13af5b580d5063c331fc5142a\
+                           9fad84aeae08bbef7f010014d82cef6a09de2b0cf871b5ce0c4f1d13a59a5934\
+                           07cb45769f1070e2c2470fe5b1bfe63133c0b0cdc64ea4bf3791a8ec2a07fd4f";
+
+fn secp256r1_verify(input: &[u8]) -> Vec<U256> {
+    run_precompile(*SECP256R1_VERIFY_PRECOMPILE_FORMAL_ADDRESS, &hex::encode(input), 2, 0)
+}
+
+fn expected(ok: bool, result: bool) -> Vec<U256> {
+    vec![U256::from(ok as u64), U256::from(result as u64)]
+}
+
+#[test]
+fn test_valid_signature() {
+    let input = hex::decode(VALID_INPUT).unwrap();
+    assert_eq!(secp256r1_verify(&input), expected(true, true));
+}
+
+#[test]
+fn test_invalid_signature() {
+    // tamper with the message hash
+    let mut input = hex::decode(VALID_INPUT).unwrap();
+    input[0] ^= 1;
+    assert_eq!(secp256r1_verify(&input), expected(true, false));
+
+    // tamper with s
+    let mut input = hex::decode(VALID_INPUT).unwrap();
+    input[95] ^= 1;
+    assert_eq!(secp256r1_verify(&input), expected(true, false));
+}
+
+#[test]
+fn test_zero_r() {
+    let mut input = hex::decode(VALID_INPUT).unwrap();
+    input[32..64].fill(0);
+    assert_eq!(secp256r1_verify(&input), expected(false, false));
+}
+
+#[test]
+fn test_public_key_not_on_curve() {
+    let mut input = hex::decode(VALID_INPUT).unwrap();
+    input[159] ^= 1;
+    assert_eq!(secp256r1_verify(&input), expected(false, false));
+}
@@ -69,7 +69,7 @@ fn pad_and_fill_memory<M: Memory>(input: &[u8], page: u32, memory: &mut M) -> u1
 use sha2::*;
 
 fn run_sha256_test_inner(input: &[u8]) -> (Vec<[u8; 32]>, std::ops::Range<u16>) {
-    let mut memory = SimpleMemory::new();
+    let mut memory = SimpleMemory::new_without_preallocations();
     let mut precompiles_processor = DefaultPrecompilesProcessor::<false>;
 
     let mut hasher = Sha256::default();
@@ -0,0 +1,164 @@
+use alloy_primitives::hex;
+use zkvm_primitives::{
+    aux::Timestamp,
+    precompiles::DefaultPrecompilesProcessor,
+    vm::{EventSink, Storage},
+};
+
+use super::*;
+use crate::{
+    block_properties::BlockProperties,
+    evm::*,
+    reference_impls::{
+        decommitter::SimpleDecommitter, event_sink::InMemoryEventSink, memory::SimpleMemory,
+    },
+    testing::storage::InMemoryStorage,
+    utils::GenericNoopTracer,
+    vm_state::VmState,
+    witness_trace::DummyTracer,
+};
+
+type TestVmState = VmState<
+    InMemoryStorage,
+    SimpleMemory,
+    InMemoryEventSink,
+    DefaultPrecompilesProcessor<true>,
+    SimpleDecommitter<true>,
+    DummyTracer,
+>;
+
+const CONTRACT_ADDRESS: Address = Address::new([0x0f; 20]);
+
+fn create_vm_state() -> TestVmState {
+    VmState::empty_state(
+        InMemoryStorage::new(),
+        SimpleMemory::new_without_preallocations(),
+        InMemoryEventSink::new(),
+        DefaultPrecompilesProcessor::<true>,
+        SimpleDecommitter::<true>::new(),
+        DummyTracer,
+        BlockProperties { default_aa_code_hash: U256::ZERO, evm_block_env: Default::default() },
+    )
+}
+
+// every frame is executed as a separate transaction
+fn run_transaction(vm: &mut TestVmState, code: &str) -> EvmExecutionResult {
+    let context =
+        EvmContext { address: CONTRACT_ADDRESS, gas_limit: 1_000_000, ..Default::default() };
+    vm.run_evm_frame(
+        &mut GenericNoopTracer::<SimpleMemory>::new(),
+        &context,
+        &hex::decode(code).unwrap(),
+    )
+}
+
+// reverted writes leave the zero values in place of the slots that did not exist,
+// so only the non-zero ones are compared
+fn non_zero_slots(storage: &InMemoryStorage) -> Vec<(usize, Address, U256, U256)> {
+    let mut slots = vec![];
+    for (shard_id, shard_level_map) in storage.inner.iter().enumerate() {
+        for (address, address_level_map) in shard_level_map.iter() {
+            for (key, value) in address_level_map
+                .iter()
+                .filter(|(_, value)| !value.is_zero())
+            {
+                slots.push((shard_id, *address, *key, *value));
+            }
+        }
+    }
+    slots.sort();
+
+    slots
+}
+
+#[test]
+fn test_rollback_to_snapshot_undoes_transactions() {
+    let mut vm = create_vm_state();
+    vm.storage
+        .populate(vec![(0, CONTRACT_ADDRESS, U256::from(1), U256::from(11))]);
+    // the bootloader frame in which all the transactions are executed
+    vm.storage.start_frame(Timestamp(0));
+    vm.event_sink.start_frame(Timestamp(0));
+
+    // sstore(0, 1), log0
+    assert!(run_transaction(&mut vm, "600160005560006000a0").is_success());
+    let storage_before = vm.storage.clone();
+    let events_before = vm.event_sink.clone();
+
+    let storage_snapshot = vm.storage.make_snapshot();
+    let events_snapshot = vm.event_sink.make_snapshot();
+    // sstore(0, 2), sstore(1, 3), sstore(2, 4), log0
+    let code = "60026000556003600155600460025560006000a0";
+    assert!(run_transaction(&mut vm, code).is_success());
+    // reverted transaction is rolled back by its frame, but stays in the history
+    let result = run_transaction(&mut vm, "600560035560006000fd");
+    assert_eq!(result.status, EvmExitStatus::Reverted);
+    assert_ne!(non_zero_slots(&vm.storage), non_zero_slots(&storage_before));
+
+    vm.storage.rollback_to(storage_snapshot);
+    vm.event_sink.rollback_to(events_snapshot);
+    assert_eq!(non_zero_slots(&vm.storage), non_zero_slots(&storage_before));
+    assert_eq!(vm.storage.cold_warm_markers, storage_before.cold_warm_markers);
+    assert_eq!(vm.storage.frames_stack, storage_before.frames_stack);
+    assert_eq!(vm.event_sink.frames_stack, events_before.frames_stack);
+
+    // snapshot stays valid, so the next transaction can be rolled back to it too
+    assert!(run_transaction(&mut vm, "6007600055").is_success());
+    vm.storage.rollback_to(storage_snapshot);
+    assert_eq!(non_zero_slots(&vm.storage), non_zero_slots(&storage_before));
+
+    vm.storage.finish_frame(Timestamp(0), false);
+    vm.event_sink.finish_frame(false, Timestamp(0));
+    let (_, final_storage, _, events, _, _) = get_final_net_states(BasicTestingTools::<true> {
+        storage: vm.storage,
+        memory: vm.memory,
+        event_sink: vm.event_sink,
+        precompiles_processor: vm.precompiles_processor,
+        decommittment_processor: vm.decommittment_processor,
+        witness_tracer: vm.witness_tracer,
+    });
+    let contract_storage = &final_storage[0][&CONTRACT_ADDRESS];
+    assert_eq!(contract_storage[&U256::ZERO], U256::from(1));
+    assert_eq!(contract_storage[&U256::from(1)], U256::from(11));
+    assert_eq!(events.len(), 1);
+}
+
+#[test]
+fn test_nested_snapshots() {
+    let mut vm = create_vm_state();
+
+    let outer = vm.storage.make_snapshot();
+    assert!(run_transaction(&mut vm, "6001600055").is_success());
+    let after_first = non_zero_slots(&vm.storage);
+    let inner = vm.storage.make_snapshot();
+    assert!(run_transaction(&mut vm, "6002600155").is_success());
+
+    vm.storage.rollback_to(inner);
+    assert_eq!(non_zero_slots(&vm.storage), after_first);
+    vm.storage.rollback_to(outer);
+    assert!(non_zero_slots(&vm.storage).is_empty());
+    assert!(vm.storage.cold_warm_markers[0][&CONTRACT_ADDRESS].is_empty());
+    assert!(vm.storage.warmed_since_snapshot.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "snapshot must be valid")]
+fn test_rollback_to_snapshot_of_finished_frame() {
+    let mut storage = InMemoryStorage::new();
+    storage.start_frame(Timestamp(0));
+    let snapshot = storage.make_snapshot();
+    storage.finish_frame(Timestamp(0), false);
+
+    storage.rollback_to(snapshot);
+}
+
+#[test]
+#[should_panic(expected = "snapshot must be valid")]
+fn test_rollback_to_discarded_snapshot() {
+    let mut event_sink = InMemoryEventSink::new();
+    let outer = event_sink.make_snapshot();
+    let inner = event_sink.make_snapshot();
+    event_sink.rollback_to(outer);
+
+    event_sink.rollback_to(inner);
+}
@@ -0,0 +1,290 @@
+use zkvm_opcodes::{
+    assembly::assemble,
+    bytecode_to_code_hash_for_mode,
+    decoding::{EncodingModeProduction, VmEncodingMode},
+    system_params::{
+        BOOTLOADER_FORMAL_ADDRESS, DEPLOYER_SYSTEM_CONTRACT_ADDRESS,
+        INITIAL_STORAGE_WRITE_PUBDATA_BYTES,
+    },
+};
+use zkvm_primitives::{aux::MemoryPage, precompiles::DefaultPrecompilesProcessor};
+
+use super::*;
+use crate::{
+    block_properties::BlockProperties,
+    reference_impls::{
+        decommitter::SimpleDecommitter, event_sink::InMemoryEventSink, memory::SimpleMemory,
+    },
+    testing::storage::InMemoryStorage,
+    tracing::*,
+    utils::GenericNoopTracer,
+    vm_state::{CallStackEntry, ExecutionStatus, RunLimits, StorageDelta, VmState},
+    witness_trace::DummyTracer,
+};
+
+type TestVmState = VmState<
+    InMemoryStorage,
+    SimpleMemory,
+    InMemoryEventSink,
+    DefaultPrecompilesProcessor<true>,
+    SimpleDecommitter<true>,
+    DummyTracer,
+>;
+
+const CALLEE: Address = Address::new([0x0a; 20]);
+const REVERTING_CALLEE: Address = Address::new([0x0b; 20]);
+
+const ERGS_FOR_BOOTLOADER: u32 = 10_000_000;
+
+fn code(source: &str) -> Vec<U256> {
+    assemble::<8, EncodingModeProduction>(source)
+        .unwrap()
+        .code_words()
+}
+
+fn address_to_u256(address: Address) -> U256 {
+    U256::from_be_slice(address.as_slice())
+}
+
+fn new_vm() -> TestVmState {
+    VmState::empty_state(
+        InMemoryStorage::new(),
+        SimpleMemory::new_without_preallocations(),
+        InMemoryEventSink::new(),
+        DefaultPrecompilesProcessor::<true>,
+        SimpleDecommitter::<true>::new(),
+        DummyTracer,
+        BlockProperties { default_aa_code_hash: U256::ZERO, evm_block_env: Default::default() },
+    )
+}
+
+fn deploy(vm: &mut TestVmState, address: Address, source: &str) {
+    let mut code = code(source);
+    // code hash is only defined for the odd number of words
+    if code.len().is_multiple_of(2) {
+        code.push(U256::ZERO);
+    }
+    let code_bytes: Vec<[u8; 32]> = code.iter().map(|word| word.to_be_bytes()).collect();
+    let code_hash = U256::from_be_bytes(
+        bytecode_to_code_hash_for_mode::<8, EncodingModeProduction>(&code_bytes).unwrap(),
+    );
+
+    vm.storage.populate(vec![(
+        0,
+        *DEPLOYER_SYSTEM_CONTRACT_ADDRESS,
+        address_to_u256(address),
+        code_hash,
+    )]);
+    vm.decommittment_processor.populate(vec![(code_hash, code)]);
+}
+
+fn load_bootloader(vm: &mut TestVmState, source: &str) {
+    let base_page = vm.new_base_memory_page_on_call();
+    let code_page = CallStackEntry::<8>::code_page_candidate_from_base(base_page);
+    vm.memory.populate_code(vec![(code_page.0, code(source))]);
+
+    let mut bootloader_context = CallStackEntry::empty_context();
+    bootloader_context.this_address = *BOOTLOADER_FORMAL_ADDRESS;
+    bootloader_context.msg_sender = *BOOTLOADER_FORMAL_ADDRESS;
+    bootloader_context.code_address = *BOOTLOADER_FORMAL_ADDRESS;
+    bootloader_context.base_memory_page = base_page;
+    bootloader_context.code_page = MemoryPage(code_page.0);
+    bootloader_context.ergs_remaining = ERGS_FOR_BOOTLOADER;
+    vm.push_bootloader_context(0, bootloader_context);
+    vm.increment_memory_pages_on_call();
+}
+
+// asks to halt once the given number of opcodes were executed
+#[derive(Debug)]
+struct HaltingTracer {
+    executed: usize,
+    halt_after: usize,
+}
+
+impl<const N: usize, E: VmEncodingMode<N>> Tracer<N, E> for HaltingTracer {
+    const CALL_AFTER_EXECUTION: bool = true;
+
+    type SupportedMemory = SimpleMemory;
+    fn before_decoding(
+        &mut self,
+        _state: VmLocalStateData<'_, N, E>,
+        _memory: &Self::SupportedMemory,
+    ) {
+    }
+    fn after_decoding(
+        &mut self,
+        _state: VmLocalStateData<'_, N, E>,
+        _data: CycleData<AfterDecodingData<N, E>>,
+        _memory: &Self::SupportedMemory,
+    ) {
+    }
+    fn before_execution(
+        &mut self,
+        _state: VmLocalStateData<'_, N, E>,
+        _data: CycleData<BeforeExecutionData<N, E>>,
+        _memory: &Self::SupportedMemory,
+    ) {
+    }
+    fn after_execution(
+        &mut self,
+        _state: VmLocalStateData<'_, N, E>,
+        _data: CycleData<AfterExecutionData<N, E>>,
+        _memory: &Self::SupportedMemory,
+    ) {
+        self.executed += 1;
+    }
+    fn should_halt(&self) -> bool {
+        self.executed >= self.halt_after
+    }
+}
+
+#[test]
+fn test_run_reports_net_deltas() {
+    let mut vm = new_vm();
+    vm.storage
+        .populate(vec![(0, *BOOTLOADER_FORMAL_ADDRESS, U256::from(3), U256::from(9))]);
+    deploy(
+        &mut vm,
+        CALLEE,
+        "
+            add 1, r0, r1
+            add 11, r0, r2
+            log.swrite r1, r2
+            ret.ok r0
+        ",
+    );
+    // the writes and the events of the reverted frame are rolled back
+    deploy(
+        &mut vm,
+        REVERTING_CALLEE,
+        "
+            add 1, r0, r1
+            log.swrite r1, r1
+            log.event.first r1, r1
+            ret.revert r0
+        ",
+    );
+
+    let bootloader = format!(
+        "
+            add 1, r0, r1
+            context.set_ergs_per_pubdata r1
+            add 1, r0, r1
+            add 5, r0, r2
+            log.swrite r1, r2
+            add 2, r0, r1
+            log.swrite r1, r2
+            log.swrite r1, r0       ; restores the initial value
+            add 3, r0, r1
+            add 10, r0, r2
+            log.swrite r1, r2
+            log.event.first r1, r2
+            add code[@abi], r0, r1
+            add code[@callee], r0, r2
+            far_call r1, r2, @fail
+            add code[@abi], r0, r1
+            add code[@reverting_callee], r0, r2
+            far_call r1, r2, @reverted
+            ret.panic
+        reverted:
+            ret.ok r0
+        fail:
+            ret.panic
+        abi: .cell {}
+        callee: .cell {}
+        reverting_callee: .cell {}
+        ",
+        U256::from_limbs([0, 0, 0, 100_000]),
+        address_to_u256(CALLEE),
+        address_to_u256(REVERTING_CALLEE),
+    );
+    load_bootloader(&mut vm, &bootloader);
+
+    let execution = vm
+        .run(&mut GenericNoopTracer::<SimpleMemory>::new(), RunLimits::default())
+        .unwrap();
+    assert_eq!(execution.status, ExecutionStatus::Success);
+    assert!(execution.return_data.is_empty());
+
+    let delta = |address, key: u64, initial_value: u64, final_value: u64| StorageDelta {
+        shard_id: 0,
+        address,
+        key: U256::from(key),
+        initial_value: U256::from(initial_value),
+        final_value: U256::from(final_value),
+    };
+    let mut expected_deltas = vec![
+        delta(*BOOTLOADER_FORMAL_ADDRESS, 1, 0, 5),
+        delta(*BOOTLOADER_FORMAL_ADDRESS, 3, 9, 10),
+        delta(CALLEE, 1, 0, 11),
+    ];
+    expected_deltas.sort_by_key(|delta| delta.address);
+    assert_eq!(execution.storage_deltas, expected_deltas);
+
+    assert_eq!(execution.events.len(), 1);
+    assert_eq!(execution.events[0].address, *BOOTLOADER_FORMAL_ADDRESS);
+    assert_eq!(execution.events[0].value, U256::from(10));
+    assert!(execution.l1_messages.is_empty());
+
+    // every write is paid for, including the reverted ones
+    assert_eq!(execution.pubdata_ergs_spent, 6 * INITIAL_STORAGE_WRITE_PUBDATA_BYTES as u32);
+}
+
+#[test]
+fn test_run_stops_on_budgets_and_continues() {
+    let mut vm = new_vm();
+    load_bootloader(
+        &mut vm,
+        "
+            add 3, r0, r1
+        loop:
+            sub.s! 1, r1, r1
+            jump.ne @loop
+            ret.ok r0
+        ",
+    );
+
+    let limits = RunLimits { cycles: Some(2), ergs: None };
+    let result = vm
+        .run(&mut GenericNoopTracer::<SimpleMemory>::new(), limits)
+        .unwrap();
+    assert_eq!(result.status, ExecutionStatus::CycleBudgetExceeded);
+    assert_eq!(result.cycles, 2);
+    assert!(result.ergs_used > 0);
+
+    let limits = RunLimits { cycles: None, ergs: Some(1) };
+    let result = vm
+        .run(&mut GenericNoopTracer::<SimpleMemory>::new(), limits)
+        .unwrap();
+    assert_eq!(result.status, ExecutionStatus::ErgsBudgetExceeded);
+    assert_eq!(result.cycles, 1);
+
+    let result = vm
+        .run(&mut GenericNoopTracer::<SimpleMemory>::new(), RunLimits::default())
+        .unwrap();
+    assert_eq!(result.status, ExecutionStatus::Success);
+    // 8 cycles in total: `add`, three iterations of the loop and `ret`
+    assert_eq!(result.cycles, 8 - 3);
+
+    assert!(
+        vm.run(&mut GenericNoopTracer::<SimpleMemory>::new(), RunLimits::default())
+            .is_err()
+    );
+}
+
+#[test]
+fn test_run_halts_on_tracer_request() {
+    let mut vm = new_vm();
+    load_bootloader(
+        &mut vm,
+        "
+        loop:
+            jump @loop
+        ",
+    );
+
+    let mut tracer = HaltingTracer { executed: 0, halt_after: 5 };
+    let result = vm.run(&mut tracer, RunLimits::default()).unwrap();
+    assert_eq!(result.status, ExecutionStatus::HaltedByTracer);
+    assert_eq!(result.cycles, 5);
+}
@@ -0,0 +1,118 @@
+use alloy_primitives::hex;
+use zkvm_primitives::precompiles::DefaultPrecompilesProcessor;
+
+use super::*;
+use crate::{
+    block_properties::BlockProperties,
+    evm::*,
+    reference_impls::{
+        decommitter::SimpleDecommitter, event_sink::InMemoryEventSink, memory::SimpleMemory,
+    },
+    testing::storage::InMemoryStorage,
+    utils::GenericNoopTracer,
+    vm_state::{PageSnapshot, PrimitiveValue, VmState, VmStateSnapshot},
+    witness_trace::DummyTracer,
+};
+
+type TestVmState = VmState<
+    InMemoryStorage,
+    SimpleMemory,
+    InMemoryEventSink,
+    DefaultPrecompilesProcessor<true>,
+    SimpleDecommitter<true>,
+    DummyTracer,
+>;
+
+type TestVmStateSnapshot = VmStateSnapshot<
+    InMemoryStorage,
+    SimpleMemory,
+    InMemoryEventSink,
+    DefaultPrecompilesProcessor<true>,
+    SimpleDecommitter<true>,
+>;
+
+const CONTRACT_ADDRESS: Address = Address::new([0x0f; 20]);
+
+fn create_vm_state() -> TestVmState {
+    VmState::empty_state(
+        InMemoryStorage::new(),
+        SimpleMemory::new_without_preallocations(),
+        InMemoryEventSink::new(),
+        DefaultPrecompilesProcessor::<true>,
+        SimpleDecommitter::<true>::new(),
+        DummyTracer,
+        BlockProperties { default_aa_code_hash: U256::ZERO, evm_block_env: Default::default() },
+    )
+}
+
+fn run_code(vm: &mut TestVmState, code: &str) -> EvmExecutionResult {
+    let context =
+        EvmContext { address: CONTRACT_ADDRESS, gas_limit: 1_000_000, ..Default::default() };
+    vm.run_evm_frame(
+        &mut GenericNoopTracer::<SimpleMemory>::new(),
+        &context,
+        &hex::decode(code).unwrap(),
+    )
+}
+
+#[test]
+fn test_page_snapshot_cuts_trailing_zeros() {
+    let mut page = vec![U256::ZERO; 1 << 10];
+    page[1] = U256::from(1);
+    page[17] = U256::from(17);
+
+    let snapshot = PageSnapshot::from_page(&page);
+    assert_eq!(snapshot.len, 1 << 10);
+    assert_eq!(snapshot.words.len(), 18);
+    assert_eq!(snapshot.into_page(), page);
+
+    let empty = PageSnapshot::<U256>::from_page(&[U256::ZERO; 4]);
+    assert!(empty.words.is_empty());
+    assert_eq!(empty.into_page(), vec![U256::ZERO; 4]);
+}
+
+#[test]
+fn test_vm_state_snapshot_roundtrip_and_resume() {
+    let mut vm = create_vm_state();
+    vm.storage
+        .populate(vec![(0, CONTRACT_ADDRESS, U256::from(5), U256::from(55))]);
+    vm.decommittment_processor
+        .populate(vec![(U256::from(0xc0de), vec![U256::from(1); 3])]);
+    vm.memory.populate_code(vec![(7, vec![U256::from(0xabcd)])]);
+
+    // two writes and a log, so every component has some history
+    let result = run_code(&mut vm, "602a600055600760015560aa60005260206000a000");
+    assert!(result.is_success());
+
+    vm.local_state.registers[3] = PrimitiveValue { value: U256::from(0x1234), is_pointer: true };
+    vm.local_state.flags.equality_flag = true;
+    vm.local_state.context_u128_register = u128::MAX;
+    vm.local_state.tx_number_in_block = 3;
+
+    let encoded = serde_json::to_string(&vm.snapshot()).unwrap();
+    let decoded: TestVmStateSnapshot = serde_json::from_str(&encoded).unwrap();
+
+    let mut restored = create_vm_state();
+    restored.restore_from_snapshot(decoded);
+    assert_eq!(restored.local_state, vm.local_state);
+    assert_eq!(restored.storage.inner, vm.storage.inner);
+    assert_eq!(restored.storage.frames_stack, vm.storage.frames_stack);
+    assert_eq!(restored.event_sink.frames_stack, vm.event_sink.frames_stack);
+    assert_eq!(restored.memory.code_pages, vm.memory.code_pages);
+    // snapshots are deterministic, so they can be compared in the encoded form
+    assert_eq!(serde_json::to_string(&restored.snapshot()).unwrap(), encoded);
+
+    // resumed execution is the same as the uninterrupted one
+    let code = "600554600054016002556000600155";
+    let expected = run_code(&mut vm, code);
+    let resumed = run_code(&mut restored, code);
+    assert!(expected.is_success());
+    assert_eq!(resumed, expected);
+    assert_eq!(restored.storage.inner, vm.storage.inner);
+    assert_eq!(restored.local_state, vm.local_state);
+    assert_eq!(restored.event_sink.clone().flatten().0, vm.event_sink.clone().flatten().0);
+    assert_eq!(
+        serde_json::to_string(&restored.snapshot()).unwrap(),
+        serde_json::to_string(&vm.snapshot()).unwrap()
+    );
+}
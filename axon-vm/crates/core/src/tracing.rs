@@ -1,4 +1,7 @@
-use zkvm_opcodes::decoding::{EncodingModeProduction, VmEncodingMode};
+use zkvm_opcodes::{
+    decoding::{EncodingModeProduction, VmEncodingMode},
+    FarCallOpcode, FatPointer, RetOpcode,
+};
 use zkvm_primitives::{aux::MemoryLocation, vm::Memory};
 
 use super::*;
@@ -36,6 +39,45 @@ pub struct AfterExecutionData<const N: usize = 8, E: VmEncodingMode<N> = Encodin
     pub dst0_mem_location: Option<MemoryLocation>,
 }
 
+/// Instruction of the EVM frame on top of the callstack that the cycle executes instead of
+/// decoding the native opcode, see [`crate::evm`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EvmStepData {
+    pub opcode: u8,
+    // PC in the EVM code, the PC of the callstack entry does not change
+    pub pc: usize,
+}
+
+/// Data of the cycle hooks, that is either the one of the native opcode or the EVM instruction.
+#[derive(Clone, Copy, Debug)]
+pub enum CycleData<T> {
+    Native(T),
+    Evm(EvmStepData),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct OnFarCallData {
+    pub far_call_variant: FarCallOpcode,
+    pub caller: Address,
+    // code address of the call, that is different from the new `this` address for delegate calls
+    pub callee: Address,
+    pub msg_sender: Address,
+    // empty if the far call has failed, in this case the callee will panic on the next cycle
+    pub calldata_fat_pointer: FatPointer,
+    pub ergs_passed: u32,
+    // whether the new frame is static, either by the call itself or inherited from the caller
+    pub is_static: bool,
+    // whether the static flag is set on the far call
+    pub is_static_call: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct OnReturnData {
+    pub return_kind: RetOpcode,
+    pub returndata_fat_pointer: FatPointer,
+    pub ergs_left: u32,
+}
+
 pub trait Tracer<const N: usize = 8, E: VmEncodingMode<N> = EncodingModeProduction>:
     std::fmt::Debug
 {
@@ -43,8 +85,11 @@ pub trait Tracer<const N: usize = 8, E: VmEncodingMode<N> = EncodingModeProducti
     const CALL_AFTER_DECODING: bool = false;
     const CALL_BEFORE_EXECUTION: bool = false;
     const CALL_AFTER_EXECUTION: bool = false;
+    const CALL_ON_FAR_CALL: bool = false;
+    const CALL_ON_RETURN: bool = false;
 
     type SupportedMemory: Memory;
+    // the cycle hooks are also called when the cycle executes an instruction of the EVM frame
     fn before_decoding(
         &mut self,
         state: VmLocalStateData<'_, N, E>,
@@ -53,19 +98,39 @@ pub trait Tracer<const N: usize = 8, E: VmEncodingMode<N> = EncodingModeProducti
     fn after_decoding(
         &mut self,
         state: VmLocalStateData<'_, N, E>,
-        data: AfterDecodingData<N, E>,
+        data: CycleData<AfterDecodingData<N, E>>,
         memory: &Self::SupportedMemory,
     );
     fn before_execution(
         &mut self,
         state: VmLocalStateData<'_, N, E>,
-        data: BeforeExecutionData<N, E>,
+        data: CycleData<BeforeExecutionData<N, E>>,
         memory: &Self::SupportedMemory,
     );
     fn after_execution(
         &mut self,
         state: VmLocalStateData<'_, N, E>,
-        data: AfterExecutionData<N, E>,
+        data: CycleData<AfterExecutionData<N, E>>,
         memory: &Self::SupportedMemory,
     );
+    // called when the new frame is already started, so the callstack has the callee on top
+    fn on_far_call(
+        &mut self,
+        _state: VmLocalStateData<'_, N, E>,
+        _data: OnFarCallData,
+        _memory: &Self::SupportedMemory,
+    ) {
+    }
+    // called only for the far frames, while the returning frame is still on top of the callstack
+    fn on_return(
+        &mut self,
+        _state: VmLocalStateData<'_, N, E>,
+        _data: OnReturnData,
+        _memory: &Self::SupportedMemory,
+    ) {
+    }
+    // checked by `VmState::run` before every cycle, so the tracer can stop the execution
+    fn should_halt(&self) -> bool {
+        false
+    }
 }
@@ -1,7 +1,7 @@
 use alloy_primitives::U256;
 use once_cell::sync::Lazy;
-use zkvm_opcodes::decoding::VmEncodingMode;
 pub use zkvm_opcodes::utils::*;
+use zkvm_opcodes::{decoding::VmEncodingMode, FatPointer};
 use zkvm_primitives::vm::Memory;
 
 use crate::tracing::*;
@@ -69,26 +69,49 @@ impl<M: Memory, const N: usize, E: VmEncodingMode<N>> Tracer<N, E> for GenericNo
     fn after_decoding(
         &mut self,
         _state: VmLocalStateData<'_, N, E>,
-        _data: AfterDecodingData<N, E>,
+        _data: CycleData<AfterDecodingData<N, E>>,
         _memory: &Self::SupportedMemory,
     ) {
     }
     fn before_execution(
         &mut self,
         _state: VmLocalStateData<'_, N, E>,
-        _data: BeforeExecutionData<N, E>,
+        _data: CycleData<BeforeExecutionData<N, E>>,
         _memory: &Self::SupportedMemory,
     ) {
     }
     fn after_execution(
         &mut self,
         _state: VmLocalStateData<'_, N, E>,
-        _data: AfterExecutionData<N, E>,
+        _data: CycleData<AfterExecutionData<N, E>>,
         _memory: &Self::SupportedMemory,
     ) {
     }
 }
 
+/// Bytes of the memory slice addressed by the fat pointer. `read_words` reads the given range of
+/// the words of the pointer's page.
+pub fn fat_pointer_content(
+    fat_pointer: FatPointer,
+    read_words: impl FnOnce(u32, std::ops::Range<u32>) -> Vec<U256>,
+) -> Vec<u8> {
+    let start = fat_pointer.start as u64 + fat_pointer.offset as u64;
+    let end = fat_pointer.start as u64 + fat_pointer.length as u64;
+    if start >= end {
+        return vec![];
+    }
+
+    let first_word = (start / 32) as u32;
+    let last_word = end.div_ceil(32) as u32;
+    let content: Vec<u8> = read_words(fat_pointer.memory_page, first_word..last_word)
+        .iter()
+        .flat_map(|word| word.to_be_bytes::<32>())
+        .collect();
+    let skip = (start % 32) as usize;
+
+    content[skip..(skip + (end - start) as usize)].to_vec()
+}
+
 pub fn low_u128_of_u256(value: &U256) -> u128 {
     let value_limbs = value.as_limbs();
     ((value_limbs[1] as u128) << 64) + value_limbs[0] as u128
@@ -216,7 +216,7 @@ pub fn read_and_decode<
             did_skip_cycle: false,
         };
 
-        tracer.after_decoding(local_state, data, memory);
+        tracer.after_decoding(local_state, CycleData::Native(data), memory);
     }
 
     (partially_decoded, delayed_changes, skip_cycle)
@@ -242,6 +242,11 @@ impl<
         &mut self,
         tracer: &mut DT,
     ) -> anyhow::Result<()> {
+        if self.is_evm_frame_running() {
+            self.evm_cycle(tracer);
+            return Ok(());
+        }
+
         let (after_masking_decoded, delayed_changes, skip_cycle) =
             read_and_decode(&self.local_state, &self.memory, &mut self.witness_tracer, tracer);
         delayed_changes.apply(&mut self.local_state);
@@ -334,7 +339,7 @@ impl<
                 new_pc,
             };
 
-            tracer.before_execution(local_state, data, &self.memory);
+            tracer.before_execution(local_state, CycleData::Native(data), &self.memory);
         }
 
         let is_kernel_mode = self
@@ -369,7 +374,7 @@ impl<
 
         let prestate = PreState { src0, src1, dst0_mem_location, new_pc, is_kernel_mode };
 
-        after_masking_decoded.apply(self, prestate)?;
+        after_masking_decoded.apply(self, prestate, tracer)?;
 
         if !skip_cycle {
             self.increment_timestamp_after_cycle();
@@ -383,7 +388,7 @@ impl<
 
             let data = AfterExecutionData { opcode: after_masking_decoded, dst0_mem_location };
 
-            tracer.after_execution(local_state, data, &self.memory);
+            tracer.after_execution(local_state, CycleData::Native(data), &self.memory);
         }
 
         Ok(())
@@ -23,7 +23,11 @@ pub struct CallStackEntry<const N: usize = 8, E: VmEncodingMode<N> = EncodingMod
     pub aux_heap_bound: u32,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(bound(
+    serialize = "E::PcOrImm: serde::Serialize",
+    deserialize = "E::PcOrImm: serde::Deserialize<'de>"
+))]
 pub struct Callstack<const N: usize = 8, E: VmEncodingMode<N> = EncodingModeProduction> {
     pub current: CallStackEntry<N, E>,
     pub inner: Vec<CallStackEntry<N, E>>,
@@ -122,6 +126,16 @@ impl<const N: usize, E: VmEncodingMode<N>> Callstack<N, E> {
         self.inner.len()
     }
 
+    // ergs of the callers are not included in the ergs of the callees, so the sum only changes
+    // when the ergs are spent
+    pub fn total_ergs_remaining(&self) -> u64 {
+        self.inner
+            .iter()
+            .chain(std::iter::once(&self.current))
+            .map(|entry| entry.ergs_remaining as u64)
+            .sum()
+    }
+
     #[track_caller]
     pub fn get_current_stack(&self) -> &CallStackEntry<N, E> {
         &self.current
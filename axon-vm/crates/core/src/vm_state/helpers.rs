@@ -1,4 +1,4 @@
-use zkvm_opcodes::UNMAPPED_PAGE;
+use zkvm_opcodes::{definitions::ret::*, UNMAPPED_PAGE};
 use zkvm_primitives::{
     aux::{MemoryKey, MemoryLocation},
     queries::{DecommittmentQuery, LogQuery, MemoryQuery},
@@ -182,11 +182,27 @@ impl<
                 .get_current_stack()
                 .is_kernel_mode()
         );
+        self.execute_precompile_query(monotonic_cycle_counter, query);
+    }
+
+    // same as `call_precompile`, but also used by the EVM frames that are not in kernel mode
+    pub(crate) fn execute_precompile_query(
+        &mut self,
+        monotonic_cycle_counter: u32,
+        query: LogQuery,
+    ) {
         debug_assert_eq!(query.timestamp, self.timestamp_for_first_decommit_or_precompile_read());
         debug_assert!(!query.rw_flag);
         // add to witness
         self.witness_tracer
             .add_log_query(monotonic_cycle_counter, query);
+        if self
+            .precompiles_processor
+            .is_revertable_precompile_call(&query)
+        {
+            self.witness_tracer
+                .add_revertable_precompile_call(monotonic_cycle_counter, query);
+        }
         // add execution aux data
         if let Some((mem_in, mem_out, round_witness)) = self
             .precompiles_processor
@@ -212,6 +228,7 @@ impl<
         self.storage.start_frame(timestamp);
         self.event_sink.start_frame(timestamp);
         self.precompiles_processor.start_frame();
+        self.evm_transaction.start_frame();
         let previous_context = self.local_state.callstack.get_current_stack();
 
         self.witness_tracer.start_new_execution_context(
@@ -235,12 +252,46 @@ impl<
         self.storage.finish_frame(timestamp, panicked);
         self.event_sink.finish_frame(panicked, timestamp);
         self.precompiles_processor.finish_frame(panicked);
+        self.evm_transaction.finish_frame(panicked);
         self.witness_tracer
             .finish_execution_context(monotonic_cycle_counter, panicked);
 
         self.local_state.callstack.pop_entry()
     }
 
+    /// Passes the returndata of the finished far call frame to the caller: the pages of the frame
+    /// go out of scope, and the returndata pointer is the only register that is not zeroed out.
+    pub fn pass_returndata_to_caller(
+        &mut self,
+        finished_base_page: MemoryPage,
+        returndata_fat_pointer: FatPointer,
+    ) {
+        self.memory.finish_global_frame(
+            finished_base_page,
+            returndata_fat_pointer,
+            Timestamp(self.local_state.timestamp),
+        );
+
+        self.local_state.registers[RET_IMPLICIT_RETURNDATA_PARAMS_REGISTER as usize] =
+            PrimitiveValue { value: returndata_fat_pointer.to_u256(), is_pointer: true };
+        self.local_state.registers[RET_RESERVED_REGISTER_0 as usize] = PrimitiveValue::empty();
+        self.local_state.registers[RET_RESERVED_REGISTER_1 as usize] = PrimitiveValue::empty();
+        self.local_state.registers[RET_RESERVED_REGISTER_2 as usize] = PrimitiveValue::empty();
+
+        // ALL other registers are zeroed out!
+        for dst in self
+            .local_state
+            .registers
+            .iter_mut()
+            .skip((RET_RESERVED_REGISTER_2 as usize) + 1)
+        {
+            *dst = PrimitiveValue::empty();
+        }
+
+        // clean up context register
+        self.local_state.context_u128_register = 0u128;
+    }
+
     pub fn perform_dst0_update(
         &mut self,
         monotonic_cycle_counter: u32,
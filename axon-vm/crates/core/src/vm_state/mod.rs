@@ -11,8 +11,10 @@ pub mod cycle;
 pub mod execution_stack;
 pub mod helpers;
 pub mod mem_ops;
+pub mod run;
+pub mod snapshot;
 
-pub use self::{cycle::*, execution_stack::*, helpers::*, mem_ops::*};
+pub use self::{cycle::*, execution_stack::*, helpers::*, mem_ops::*, run::*, snapshot::*};
 
 pub const SUPPORTED_ISA_VERSION: ISAVersion = ISAVersion(0);
 
@@ -22,7 +24,7 @@ const _: () = if SUPPORTED_ISA_VERSION.0 != zkvm_opcodes::DEFAULT_ISA_VERSION.0
 
 use zkvm_opcodes::{STARTING_BASE_PAGE, STARTING_TIMESTAMP};
 
-#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct PrimitiveValue {
     pub value: U256,
     pub is_pointer: bool,
@@ -38,7 +40,11 @@ impl PrimitiveValue {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(bound(
+    serialize = "E::PcOrImm: serde::Serialize",
+    deserialize = "E::PcOrImm: serde::Deserialize<'de>"
+))]
 pub struct VmLocalState<const N: usize = 8, E: VmEncodingMode<N> = EncodingModeProduction> {
     pub previous_code_word: U256,
     pub previous_code_memory_page: MemoryPage,
@@ -160,6 +166,9 @@ pub struct VmState<
     pub precompiles_processor: PP,
     pub decommittment_processor: DP,
     pub witness_tracer: WT,
+    // EVM frames of the callstack entries that run the EVM code, from the outermost one
+    pub evm_frames: Vec<crate::evm::EvmCallFrame>,
+    pub evm_transaction: crate::evm::EvmTransactionState,
 }
 
 impl<
@@ -191,6 +200,8 @@ impl<
             decommittment_processor,
             witness_tracer,
             block_properties,
+            evm_frames: vec![],
+            evm_transaction: Default::default(),
         }
     }
     pub fn reset_flags(&mut self) {
@@ -0,0 +1,256 @@
+use zkvm_opcodes::{FatPointer, RetOpcode};
+use zkvm_primitives::{
+    aux::{MemoryIndex, MemoryLocation},
+    queries::MemoryQuery,
+    vm::MemoryType,
+};
+
+use super::*;
+use crate::{reference_impls::event_sink::EventMessage, tracing::*};
+
+/// Storage that can report the net changes made by the queries with the given or a later
+/// timestamp, including the ones of the frames that have not finished yet.
+pub trait StorageDeltas {
+    fn storage_deltas_since(&self, timestamp: Timestamp) -> Vec<StorageDelta>;
+}
+
+/// Event sink that can report the events and L1 messages emitted with the given or a later
+/// timestamp, that were not rolled back.
+pub trait EventDeltas {
+    fn event_deltas_since(&self, timestamp: Timestamp) -> (Vec<EventMessage>, Vec<EventMessage>);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StorageDelta {
+    pub shard_id: u8,
+    pub address: Address,
+    pub key: U256,
+    pub initial_value: U256,
+    pub final_value: U256,
+}
+
+/// Budgets of a single [`VmState::run`]. Ergs are counted over all the frames of the callstack,
+/// so the ergs passed to the callees are not counted as used until the callees spend them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RunLimits {
+    pub cycles: Option<u32>,
+    pub ergs: Option<u32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExecutionStatus {
+    Success,
+    Revert,
+    Panic,
+    // the execution was stopped before the initial frame has returned
+    CycleBudgetExceeded,
+    ErgsBudgetExceeded,
+    HaltedByTracer,
+}
+
+#[derive(Clone, Debug)]
+pub struct ExecutionResult {
+    pub status: ExecutionStatus,
+    // content of the fat pointer returned from the initial frame
+    pub return_data: Vec<u8>,
+    pub ergs_used: u32,
+    pub cycles: u32,
+    // ergs spent on the pubdata, see `VmLocalState::spent_pubdata_counter`
+    pub pubdata_ergs_spent: u32,
+    pub storage_deltas: Vec<StorageDelta>,
+    pub events: Vec<EventMessage>,
+    pub l1_messages: Vec<EventMessage>,
+}
+
+impl<
+    S: zkvm_primitives::vm::Storage + StorageDeltas,
+    M: zkvm_primitives::vm::Memory,
+    EV: zkvm_primitives::vm::EventSink + EventDeltas,
+    PP: zkvm_primitives::vm::PrecompilesProcessor,
+    DP: zkvm_primitives::vm::DecommittmentProcessor,
+    WT: crate::witness_trace::VmWitnessTracer<N, E>,
+    const N: usize,
+    E: VmEncodingMode<N>,
+> VmState<S, M, EV, PP, DP, WT, N, E>
+{
+    /// Executes the cycles until the initial frame (usually the bootloader) returns, one of the
+    /// budgets is exceeded, or the tracer asks to halt. The execution stopped by the budget or
+    /// the tracer can be continued by another call.
+    pub fn run<DT: Tracer<N, E, SupportedMemory = M>>(
+        &mut self,
+        tracer: &mut DT,
+        limits: RunLimits,
+    ) -> anyhow::Result<ExecutionResult> {
+        if self.execution_has_ended() {
+            anyhow::bail!("execution has already ended");
+        }
+
+        let start_timestamp = Timestamp(self.local_state.timestamp);
+        let initial_ergs = self.total_ergs_remaining();
+        let initial_pubdata_ergs_spent = self.local_state.spent_pubdata_counter;
+
+        let mut tracer = RunTracer { inner: tracer, final_return: None };
+        let mut cycles = 0u32;
+        let interrupted_status = loop {
+            if self.execution_has_ended() {
+                break None;
+            }
+            if limits.cycles.is_some_and(|limit| cycles >= limit) {
+                break Some(ExecutionStatus::CycleBudgetExceeded);
+            }
+            let ergs_used = initial_ergs.saturating_sub(self.total_ergs_remaining());
+            if limits.ergs.is_some_and(|limit| ergs_used >= limit as u64) {
+                break Some(ExecutionStatus::ErgsBudgetExceeded);
+            }
+            if tracer.should_halt() {
+                break Some(ExecutionStatus::HaltedByTracer);
+            }
+
+            self.cycle(&mut tracer)?;
+            cycles += 1;
+        };
+
+        let (status, return_data) = match (interrupted_status, tracer.final_return) {
+            (Some(status), _) => (status, vec![]),
+            (None, Some(OnReturnData { return_kind, returndata_fat_pointer, .. })) => {
+                let status = match return_kind {
+                    RetOpcode::Ok => ExecutionStatus::Success,
+                    RetOpcode::Revert => ExecutionStatus::Revert,
+                    RetOpcode::Panic => ExecutionStatus::Panic,
+                };
+                (status, self.read_fat_pointer_content(returndata_fat_pointer))
+            }
+            (None, None) => unreachable!("initial frame can only finish by returning"),
+        };
+
+        let (events, l1_messages) = self.event_sink.event_deltas_since(start_timestamp);
+
+        Ok(ExecutionResult {
+            status,
+            return_data,
+            ergs_used: initial_ergs.saturating_sub(self.total_ergs_remaining()) as u32,
+            cycles,
+            pubdata_ergs_spent: self.local_state.spent_pubdata_counter - initial_pubdata_ergs_spent,
+            storage_deltas: self.storage.storage_deltas_since(start_timestamp),
+            events,
+            l1_messages,
+        })
+    }
+}
+
+impl<
+    S: zkvm_primitives::vm::Storage,
+    M: zkvm_primitives::vm::Memory,
+    EV: zkvm_primitives::vm::EventSink,
+    PP: zkvm_primitives::vm::PrecompilesProcessor,
+    DP: zkvm_primitives::vm::DecommittmentProcessor,
+    WT: crate::witness_trace::VmWitnessTracer<N, E>,
+    const N: usize,
+    E: VmEncodingMode<N>,
+> VmState<S, M, EV, PP, DP, WT, N, E>
+{
+    fn total_ergs_remaining(&self) -> u64 {
+        self.local_state.callstack.total_ergs_remaining()
+    }
+
+    pub(crate) fn read_fat_pointer_content(&mut self, fat_pointer: FatPointer) -> Vec<u8> {
+        let timestamp = Timestamp(self.local_state.timestamp);
+        let monotonic_cycle_counter = self.local_state.monotonic_cycle_counter;
+        let memory = &mut self.memory;
+        fat_pointer_content(fat_pointer, |page, range| {
+            range
+                .map(|word| {
+                    let query = MemoryQuery {
+                        timestamp,
+                        location: MemoryLocation {
+                            memory_type: MemoryType::FatPointer,
+                            page: MemoryPage(page),
+                            index: MemoryIndex(word),
+                        },
+                        value: U256::ZERO,
+                        rw_flag: false,
+                        value_is_pointer: false,
+                    };
+                    memory
+                        .execute_partial_query(monotonic_cycle_counter, query)
+                        .value
+                })
+                .collect()
+        })
+    }
+}
+
+// Forwards the hooks to the tracer of the caller, and captures the return from the initial frame
+#[derive(Debug)]
+struct RunTracer<'a, T> {
+    inner: &'a mut T,
+    final_return: Option<OnReturnData>,
+}
+
+impl<const N: usize, E: VmEncodingMode<N>, T: Tracer<N, E>> Tracer<N, E> for RunTracer<'_, T> {
+    const CALL_BEFORE_DECODING: bool = T::CALL_BEFORE_DECODING;
+    const CALL_AFTER_DECODING: bool = T::CALL_AFTER_DECODING;
+    const CALL_BEFORE_EXECUTION: bool = T::CALL_BEFORE_EXECUTION;
+    const CALL_AFTER_EXECUTION: bool = T::CALL_AFTER_EXECUTION;
+    const CALL_ON_FAR_CALL: bool = T::CALL_ON_FAR_CALL;
+    const CALL_ON_RETURN: bool = true;
+
+    type SupportedMemory = T::SupportedMemory;
+    fn before_decoding(
+        &mut self,
+        state: VmLocalStateData<'_, N, E>,
+        memory: &Self::SupportedMemory,
+    ) {
+        self.inner.before_decoding(state, memory);
+    }
+    fn after_decoding(
+        &mut self,
+        state: VmLocalStateData<'_, N, E>,
+        data: CycleData<AfterDecodingData<N, E>>,
+        memory: &Self::SupportedMemory,
+    ) {
+        self.inner.after_decoding(state, data, memory);
+    }
+    fn before_execution(
+        &mut self,
+        state: VmLocalStateData<'_, N, E>,
+        data: CycleData<BeforeExecutionData<N, E>>,
+        memory: &Self::SupportedMemory,
+    ) {
+        self.inner.before_execution(state, data, memory);
+    }
+    fn after_execution(
+        &mut self,
+        state: VmLocalStateData<'_, N, E>,
+        data: CycleData<AfterExecutionData<N, E>>,
+        memory: &Self::SupportedMemory,
+    ) {
+        self.inner.after_execution(state, data, memory);
+    }
+    fn on_far_call(
+        &mut self,
+        state: VmLocalStateData<'_, N, E>,
+        data: OnFarCallData,
+        memory: &Self::SupportedMemory,
+    ) {
+        self.inner.on_far_call(state, data, memory);
+    }
+    fn on_return(
+        &mut self,
+        state: VmLocalStateData<'_, N, E>,
+        data: OnReturnData,
+        memory: &Self::SupportedMemory,
+    ) {
+        // the returning frame is still on top, so the initial one is the only frame
+        // above the empty context
+        if state.vm_local_state.callstack.depth() == 1 {
+            self.final_return = Some(data);
+        }
+        if T::CALL_ON_RETURN {
+            self.inner.on_return(state, data, memory);
+        }
+    }
+    fn should_halt(&self) -> bool {
+        self.inner.should_halt()
+    }
+}
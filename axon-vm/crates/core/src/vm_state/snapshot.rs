@@ -0,0 +1,126 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::*;
+
+/// Component of the VM that can be saved in a serializable form and restored later.
+///
+/// Snapshots are taken between the cycles. Restoring a snapshot into a component and
+/// continuing the execution must give the same results as if the execution was never paused.
+pub trait Snapshottable {
+    type Snapshot: Clone + std::fmt::Debug + Serialize + DeserializeOwned;
+
+    fn snapshot(&self) -> Self::Snapshot;
+    fn restore_from_snapshot(&mut self, snapshot: Self::Snapshot);
+}
+
+/// Memory page with the trailing zero words cut off, as most of the pages are sparse.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageSnapshot<T> {
+    pub len: usize,
+    pub words: Vec<T>,
+}
+
+impl<T: Clone + Default + PartialEq> PageSnapshot<T> {
+    pub fn from_page(page: &[T]) -> Self {
+        let empty = T::default();
+        let used_len = page
+            .iter()
+            .rposition(|el| el != &empty)
+            .map_or(0, |pos| pos + 1);
+
+        Self { len: page.len(), words: page[..used_len].to_vec() }
+    }
+
+    pub fn into_page(self) -> Vec<T> {
+        let Self { len, mut words } = self;
+        words.resize(len, T::default());
+
+        words
+    }
+}
+
+// the default processor doesn't keep anything between the calls, while the registry can't
+// capture the state of the boxed precompiles, so it's not snapshottable
+impl<const B: bool> Snapshottable for zkvm_primitives::precompiles::DefaultPrecompilesProcessor<B> {
+    type Snapshot = ();
+
+    fn snapshot(&self) -> Self::Snapshot {}
+
+    fn restore_from_snapshot(&mut self, _snapshot: Self::Snapshot) {}
+}
+
+/// Full state of the paused VM. The witness tracer is not included, as it is provided by the
+/// caller that resumes the execution.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound(serialize = "E::PcOrImm: Serialize", deserialize = "E::PcOrImm: Deserialize<'de>"))]
+pub struct VmStateSnapshot<
+    S: Snapshottable,
+    M: Snapshottable,
+    EV: Snapshottable,
+    PP: Snapshottable,
+    DP: Snapshottable,
+    const N: usize = 8,
+    E: VmEncodingMode<N> = EncodingModeProduction,
+> {
+    pub local_state: VmLocalState<N, E>,
+    pub block_properties: crate::block_properties::BlockProperties,
+    pub storage: S::Snapshot,
+    pub memory: M::Snapshot,
+    pub event_sink: EV::Snapshot,
+    pub precompiles_processor: PP::Snapshot,
+    pub decommittment_processor: DP::Snapshot,
+    pub evm_frames: Vec<crate::evm::EvmCallFrame>,
+    pub evm_transaction: crate::evm::EvmTransactionState,
+}
+
+impl<
+    S: zkvm_primitives::vm::Storage + Snapshottable,
+    M: zkvm_primitives::vm::Memory + Snapshottable,
+    EV: zkvm_primitives::vm::EventSink + Snapshottable,
+    PP: zkvm_primitives::vm::PrecompilesProcessor + Snapshottable,
+    DP: zkvm_primitives::vm::DecommittmentProcessor + Snapshottable,
+    WT: crate::witness_trace::VmWitnessTracer<N, E>,
+    const N: usize,
+    E: VmEncodingMode<N>,
+> VmState<S, M, EV, PP, DP, WT, N, E>
+{
+    pub fn snapshot(&self) -> VmStateSnapshot<S, M, EV, PP, DP, N, E> {
+        VmStateSnapshot {
+            local_state: self.local_state.clone(),
+            block_properties: self.block_properties,
+            storage: self.storage.snapshot(),
+            memory: self.memory.snapshot(),
+            event_sink: self.event_sink.snapshot(),
+            precompiles_processor: self.precompiles_processor.snapshot(),
+            decommittment_processor: self.decommittment_processor.snapshot(),
+            evm_frames: self.evm_frames.clone(),
+            evm_transaction: self.evm_transaction.clone(),
+        }
+    }
+
+    pub fn restore_from_snapshot(&mut self, snapshot: VmStateSnapshot<S, M, EV, PP, DP, N, E>) {
+        let VmStateSnapshot {
+            local_state,
+            block_properties,
+            storage,
+            memory,
+            event_sink,
+            precompiles_processor,
+            decommittment_processor,
+            evm_frames,
+            evm_transaction,
+        } = snapshot;
+
+        self.local_state = local_state;
+        self.block_properties = block_properties;
+        self.storage.restore_from_snapshot(storage);
+        self.memory.restore_from_snapshot(memory);
+        self.event_sink.restore_from_snapshot(event_sink);
+        self.precompiles_processor
+            .restore_from_snapshot(precompiles_processor);
+        self.decommittment_processor
+            .restore_from_snapshot(decommittment_processor);
+        self.evm_frames = evm_frames;
+        self.evm_transaction = evm_transaction;
+    }
+}
@@ -0,0 +1,508 @@
+use std::collections::HashMap;
+
+use alloy_primitives::U256;
+
+use super::*;
+use crate::decoding::{AllowedPcOrImm, EncodingModeProduction};
+
+static MNEMONICS: Lazy<HashMap<&'static str, Opcode>> = Lazy::new(|| {
+    OPCODES_TABLE
+        .iter()
+        .map(|variant| (mnemonic(variant.opcode), variant.opcode))
+        .collect()
+});
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssemblyError {
+    // 1-based line of the source
+    pub line: usize,
+    pub message: String,
+}
+
+impl AssemblyError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self { line, message: message.into() }
+    }
+}
+
+impl std::fmt::Display for AssemblyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AssemblyError {}
+
+/// Assembled program. Instructions are placed from the start of the code page, and
+/// the constants are placed in the words that follow the last instruction.
+#[derive(Clone, Debug)]
+pub struct Assembly<const N: usize = 8, E: VmEncodingMode<N> = EncodingModeProduction> {
+    pub instructions: Vec<DecodedOpcode<N, E>>,
+    pub constants: Vec<U256>,
+    // labels of the instructions resolve into their pc, and labels of the constants
+    // into the index of their word on the code page
+    pub labels: HashMap<String, u64>,
+}
+
+impl<const N: usize, E: VmEncodingMode<N>> Assembly<N, E> {
+    pub const INSTRUCTIONS_PER_WORD: usize = 32 / N;
+
+    pub fn num_instruction_words(&self) -> usize {
+        self.instructions
+            .len()
+            .div_ceil(Self::INSTRUCTIONS_PER_WORD)
+    }
+
+    /// Content of the code page. The last word of the instructions is padded with zeroes,
+    /// that are decoded as invalid opcodes.
+    pub fn code_words(&self) -> Vec<U256> {
+        let mut words: Vec<U256> = self
+            .instructions
+            .chunks(Self::INSTRUCTIONS_PER_WORD)
+            .map(|chunk| {
+                let mut bytes = [0u8; 32];
+                for (dst, instruction) in bytes.chunks_exact_mut(N).zip(chunk) {
+                    dst.copy_from_slice(&instruction.serialize_as_bytes());
+                }
+                U256::from_be_bytes(bytes)
+            })
+            .collect();
+        words.extend_from_slice(&self.constants);
+
+        words
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Immediate {
+    Value(u64),
+    Label(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MemoryKind {
+    Code,
+    Stack,
+    StackWithOffset,
+    Pop,
+    Push,
+}
+
+#[derive(Clone, Debug)]
+enum ParsedOperand {
+    Register(u8),
+    Immediate(Immediate),
+    Memory(MemoryKind, u8, Immediate),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum LabelTarget {
+    Instruction(usize),
+    Constant(usize),
+}
+
+struct PendingInstruction<const N: usize, E: VmEncodingMode<N>> {
+    line: usize,
+    opcode: DecodedOpcode<N, E>,
+    imm_0: Option<Immediate>,
+    imm_1: Option<Immediate>,
+}
+
+/// Assembles the text program. Every line contains an instruction, a `.cell <value>` constant,
+/// or nothing, and may start with any number of `label:` definitions. Comments start with `;`.
+///
+/// Instructions are written as `mnemonic[.modifiers][.condition][!] operands`, where `!` sets
+/// the flags, and the operands go in the order src0, src1, dst0, dst1, followed by the
+/// immediates that are not used for addressing, such as the jump targets and exception
+/// handlers. Operands are registers `r0`-`r15`, immediates (decimal, `0x` hex or `@label`),
+/// and memory locations `code[r1+imm]`, `stack[r1+imm]`, `stack-[r1+imm]` (relative to sp),
+/// `stack-=[r1+imm]` (pop, only for src0) and `stack+=[r1+imm]` (push, only for dst0).
+pub fn assemble<const N: usize, E: VmEncodingMode<N>>(
+    source: &str,
+) -> Result<Assembly<N, E>, AssemblyError> {
+    let mut pending_instructions = vec![];
+    let mut constants = vec![];
+    let mut label_targets = HashMap::new();
+    let mut unbound_labels: Vec<(String, usize)> = vec![];
+
+    for (line_idx, line) in source.lines().enumerate() {
+        let line_number = line_idx + 1;
+        let mut line = line.split(';').next().unwrap_or_default().trim();
+
+        while let Some((name, rest)) = line.split_once(':') {
+            let name = name.trim();
+            if !is_identifier(name) {
+                return Err(AssemblyError::new(line_number, format!("invalid label `{name}`")));
+            }
+            if label_targets.contains_key(name)
+                || unbound_labels.iter().any(|(label, _)| label == name)
+            {
+                return Err(AssemblyError::new(line_number, format!("duplicate label `{name}`")));
+            }
+            unbound_labels.push((name.to_owned(), line_number));
+            line = rest.trim();
+        }
+        if line.is_empty() {
+            continue;
+        }
+
+        let target = if let Some(value) = line.strip_prefix(".cell") {
+            let value = value.trim();
+            let constant = value.parse::<U256>().map_err(|_| {
+                AssemblyError::new(line_number, format!("invalid constant `{value}`"))
+            })?;
+            constants.push(constant);
+
+            LabelTarget::Constant(constants.len() - 1)
+        } else {
+            pending_instructions.push(parse_instruction::<N, E>(line_number, line)?);
+
+            LabelTarget::Instruction(pending_instructions.len() - 1)
+        };
+        for (label, _) in unbound_labels.drain(..) {
+            label_targets.insert(label, target);
+        }
+    }
+
+    if let Some((label, line)) = unbound_labels.into_iter().next() {
+        return Err(AssemblyError::new(
+            line,
+            format!("label `{label}` is not followed by an instruction or a constant"),
+        ));
+    }
+
+    let num_instruction_words = pending_instructions
+        .len()
+        .div_ceil(Assembly::<N, E>::INSTRUCTIONS_PER_WORD);
+    let labels: HashMap<String, u64> = label_targets
+        .into_iter()
+        .map(|(label, target)| {
+            let location = match target {
+                LabelTarget::Instruction(idx) => idx,
+                LabelTarget::Constant(idx) => num_instruction_words + idx,
+            };
+            (label, location as u64)
+        })
+        .collect();
+
+    let mut instructions = Vec::with_capacity(pending_instructions.len());
+    for PendingInstruction { line, mut opcode, imm_0, imm_1 } in pending_instructions {
+        if let Some(imm_0) = imm_0 {
+            opcode.imm_0 = resolve_immediate::<N, E>(line, &imm_0, &labels)?;
+        }
+        if let Some(imm_1) = imm_1 {
+            opcode.imm_1 = resolve_immediate::<N, E>(line, &imm_1, &labels)?;
+        }
+        instructions.push(opcode);
+    }
+
+    Ok(Assembly { instructions, constants, labels })
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn resolve_immediate<const N: usize, E: VmEncodingMode<N>>(
+    line: usize,
+    immediate: &Immediate,
+    labels: &HashMap<String, u64>,
+) -> Result<E::PcOrImm, AssemblyError> {
+    let value = match immediate {
+        Immediate::Value(value) => *value,
+        Immediate::Label(label) => *labels
+            .get(label)
+            .ok_or_else(|| AssemblyError::new(line, format!("unknown label `{label}`")))?,
+    };
+    if value > E::PcOrImm::max().as_u64() {
+        return Err(AssemblyError::new(
+            line,
+            format!("immediate {value} does not fit into the instruction"),
+        ));
+    }
+
+    Ok(E::PcOrImm::from_u64_clipped(value))
+}
+
+fn parse_instruction<const N: usize, E: VmEncodingMode<N>>(
+    line: usize,
+    text: &str,
+) -> Result<PendingInstruction<N, E>, AssemblyError> {
+    let (full_mnemonic, operands_text) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let (full_mnemonic, set_flags) = match full_mnemonic.strip_suffix(SET_FLAGS_MODIFIER) {
+        Some(full_mnemonic) => (full_mnemonic, true),
+        None => (full_mnemonic, false),
+    };
+
+    // mnemonic itself may contain a dot, so the longest match wins
+    let parts: Vec<&str> = full_mnemonic.split('.').collect();
+    let (opcode, modifiers) = match parts
+        .get(..2)
+        .and_then(|base| MNEMONICS.get(&*base.join(".")))
+    {
+        Some(opcode) => (*opcode, &parts[2..]),
+        None => match MNEMONICS.get(parts[0]) {
+            Some(opcode) => (*opcode, &parts[1..]),
+            None => {
+                return Err(AssemblyError::new(
+                    line,
+                    format!("unknown mnemonic `{full_mnemonic}`"),
+                ));
+            }
+        },
+    };
+
+    let available_flags = flag_modifiers(opcode);
+    let mut flags = [false; NUM_NON_EXCLUSIVE_FLAGS];
+    let mut condition = None;
+    for modifier in modifiers {
+        if let Some(parsed) = ALL_CONDITIONS
+            .into_iter()
+            .find(|condition| condition_modifier(*condition) == Some(*modifier))
+        {
+            if condition.replace(parsed).is_some() {
+                return Err(AssemblyError::new(line, "duplicate condition"));
+            }
+        } else if let Some(idx) = available_flags
+            .iter()
+            .position(|flag| *flag != Some(SET_FLAGS_MODIFIER) && *flag == Some(*modifier))
+        {
+            if flags[idx] {
+                return Err(AssemblyError::new(line, format!("duplicate modifier `{modifier}`")));
+            }
+            flags[idx] = true;
+        } else {
+            return Err(AssemblyError::new(line, format!("unknown modifier `{modifier}`")));
+        }
+    }
+    if set_flags {
+        let Some(idx) = available_flags
+            .iter()
+            .position(|flag| *flag == Some(SET_FLAGS_MODIFIER))
+        else {
+            return Err(AssemblyError::new(
+                line,
+                format!("`{}` can not set flags", mnemonic(opcode)),
+            ));
+        };
+        flags[idx] = true;
+    }
+
+    let input_operands = opcode.input_operands(DEFAULT_ISA_VERSION);
+    let output_operands = opcode.output_operands(DEFAULT_ISA_VERSION);
+    // only the flags affect the number of extra immediates
+    let num_extra_immediates = InstructionFormat::of(&OpcodeVariant {
+        opcode,
+        src0_operand_type: Operand::RegOnly,
+        dst0_operand_type: Operand::RegOnly,
+        flags,
+    })
+    .num_extra_immediates;
+
+    let operands: Vec<&str> = if operands_text.trim().is_empty() {
+        vec![]
+    } else {
+        operands_text.split(',').map(str::trim).collect()
+    };
+    let num_operands = input_operands.len() + output_operands.len() + num_extra_immediates;
+    if operands.len() != num_operands {
+        return Err(AssemblyError::new(
+            line,
+            format!("expected {num_operands} operands, got {}", operands.len()),
+        ));
+    }
+    let mut operands = operands.into_iter();
+
+    let mut opcode = DecodedOpcode::<N, E> {
+        variant: OpcodeVariant {
+            opcode,
+            src0_operand_type: Operand::RegOnly,
+            dst0_operand_type: Operand::RegOnly,
+            flags,
+        },
+        condition: condition.unwrap_or(Condition::Always),
+        ..Default::default()
+    };
+    let mut imm_0 = None;
+    let mut imm_1 = None;
+
+    for (idx, operand_type) in input_operands.iter().enumerate() {
+        let text = operands.next().unwrap();
+        let operand = parse_operand(line, text)?;
+        if idx > 0 {
+            opcode.src1_reg_idx = expect_register(line, text, operand)?;
+            continue;
+        }
+
+        opcode.variant.src0_operand_type = match (operand_type, operand) {
+            (Operand::RegOnly, operand) => {
+                opcode.src0_reg_idx = expect_register(line, text, operand)?;
+                Operand::RegOnly
+            }
+            (Operand::RegOrImm(_), ParsedOperand::Register(reg)) => {
+                opcode.src0_reg_idx = reg;
+                Operand::RegOrImm(RegOrImmFlags::UseRegOnly)
+            }
+            (Operand::RegOrImm(_), ParsedOperand::Immediate(imm)) => {
+                imm_0 = Some(imm);
+                Operand::RegOrImm(RegOrImmFlags::UseImm16Only)
+            }
+            (Operand::RegOrImm(_), ParsedOperand::Memory(..)) => {
+                return Err(AssemblyError::new(
+                    line,
+                    format!("only a register or an immediate can be used in place of `{text}`"),
+                ));
+            }
+            (Operand::Full(_), ParsedOperand::Register(reg)) => {
+                opcode.src0_reg_idx = reg;
+                Operand::Full(ImmMemHandlerFlags::UseRegOnly)
+            }
+            (Operand::Full(_), ParsedOperand::Immediate(imm)) => {
+                imm_0 = Some(imm);
+                Operand::Full(ImmMemHandlerFlags::UseImm16Only)
+            }
+            (Operand::Full(_), ParsedOperand::Memory(kind, reg, imm)) => {
+                let flags = match kind {
+                    MemoryKind::Code => ImmMemHandlerFlags::UseCodePage,
+                    MemoryKind::Stack => ImmMemHandlerFlags::UseAbsoluteOnStack,
+                    MemoryKind::StackWithOffset => ImmMemHandlerFlags::UseStackWithOffset,
+                    MemoryKind::Pop => ImmMemHandlerFlags::UseStackWithPushPop,
+                    MemoryKind::Push => {
+                        return Err(AssemblyError::new(
+                            line,
+                            format!("`{text}` can not be used as a source"),
+                        ));
+                    }
+                };
+                opcode.src0_reg_idx = reg;
+                imm_0 = Some(imm);
+                Operand::Full(flags)
+            }
+        };
+    }
+
+    for (idx, operand_type) in output_operands.iter().enumerate() {
+        let text = operands.next().unwrap();
+        let operand = parse_operand(line, text)?;
+        if idx > 0 {
+            opcode.dst1_reg_idx = expect_register(line, text, operand)?;
+            continue;
+        }
+
+        opcode.variant.dst0_operand_type = match (operand_type, operand) {
+            (Operand::Full(_), ParsedOperand::Register(reg)) => {
+                opcode.dst0_reg_idx = reg;
+                Operand::Full(ImmMemHandlerFlags::UseRegOnly)
+            }
+            (Operand::Full(_), ParsedOperand::Memory(kind, reg, imm))
+                if !matches!(kind, MemoryKind::Code | MemoryKind::Pop) =>
+            {
+                let flags = match kind {
+                    MemoryKind::Stack => ImmMemHandlerFlags::UseAbsoluteOnStack,
+                    MemoryKind::StackWithOffset => ImmMemHandlerFlags::UseStackWithOffset,
+                    _ => ImmMemHandlerFlags::UseStackWithPushPop,
+                };
+                opcode.dst0_reg_idx = reg;
+                imm_1 = Some(imm);
+                Operand::Full(flags)
+            }
+            (Operand::Full(_), _) => {
+                return Err(AssemblyError::new(
+                    line,
+                    format!("`{text}` can not be used as a destination"),
+                ));
+            }
+            (_, operand) => {
+                opcode.dst0_reg_idx = expect_register(line, text, operand)?;
+                Operand::RegOnly
+            }
+        };
+    }
+
+    for idx in 0..num_extra_immediates {
+        let text = operands.next().unwrap();
+        let ParsedOperand::Immediate(imm) = parse_operand(line, text)? else {
+            return Err(AssemblyError::new(line, format!("expected an immediate, got `{text}`")));
+        };
+        if idx == 0 {
+            imm_0 = Some(imm);
+        } else {
+            imm_1 = Some(imm);
+        }
+    }
+
+    if !OPCODE_TO_CANONICAL_INDEX_LOOKUP_MAP.contains_key(&opcode.variant) {
+        return Err(AssemblyError::new(line, format!("unsupported variant `{text}`")));
+    }
+
+    Ok(PendingInstruction { line, opcode, imm_0, imm_1 })
+}
+
+fn expect_register(line: usize, text: &str, operand: ParsedOperand) -> Result<u8, AssemblyError> {
+    match operand {
+        ParsedOperand::Register(reg) => Ok(reg),
+        _ => Err(AssemblyError::new(line, format!("expected a register, got `{text}`"))),
+    }
+}
+
+fn parse_operand(line: usize, text: &str) -> Result<ParsedOperand, AssemblyError> {
+    const MEMORY_PREFIXES: [(&str, MemoryKind); 5] = [
+        ("code[", MemoryKind::Code),
+        ("stack[", MemoryKind::Stack),
+        ("stack-[", MemoryKind::StackWithOffset),
+        ("stack-=[", MemoryKind::Pop),
+        ("stack+=[", MemoryKind::Push),
+    ];
+
+    for (prefix, kind) in MEMORY_PREFIXES {
+        let Some(address) = text.strip_prefix(prefix) else {
+            continue;
+        };
+        let Some(address) = address.strip_suffix(']') else {
+            return Err(AssemblyError::new(line, format!("unclosed bracket in `{text}`")));
+        };
+
+        let (reg, imm) = match address.split_once('+') {
+            Some((reg, imm)) => {
+                (parse_register(line, reg.trim())?, parse_immediate(line, imm.trim())?)
+            }
+            None if address.trim().starts_with('r') => {
+                (parse_register(line, address.trim())?, Immediate::Value(0))
+            }
+            None => (0, parse_immediate(line, address.trim())?),
+        };
+
+        return Ok(ParsedOperand::Memory(kind, reg, imm));
+    }
+
+    if text.starts_with('r') {
+        parse_register(line, text).map(ParsedOperand::Register)
+    } else {
+        parse_immediate(line, text).map(ParsedOperand::Immediate)
+    }
+}
+
+fn parse_register(line: usize, text: &str) -> Result<u8, AssemblyError> {
+    text.strip_prefix('r')
+        .and_then(|idx| idx.parse::<u8>().ok())
+        .filter(|idx| *idx as usize <= REGISTERS_COUNT)
+        .ok_or_else(|| AssemblyError::new(line, format!("invalid register `{text}`")))
+}
+
+fn parse_immediate(line: usize, text: &str) -> Result<Immediate, AssemblyError> {
+    if let Some(label) = text.strip_prefix('@') {
+        return Ok(Immediate::Label(label.to_owned()));
+    }
+    let value = match text.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => text.parse::<u64>(),
+    };
+
+    value
+        .map(Immediate::Value)
+        .map_err(|_| AssemblyError::new(line, format!("invalid operand `{text}`")))
+}
@@ -0,0 +1,110 @@
+use alloy_primitives::U256;
+
+use super::*;
+use crate::decoding::AllowedPcOrImm;
+
+/// Text form of the opcode, in the syntax accepted by [`assemble`]. Fields that are
+/// not used by the variant are not printed.
+pub fn disassemble_opcode<const N: usize, E: VmEncodingMode<N>>(
+    opcode: &DecodedOpcode<N, E>,
+) -> String {
+    let variant = opcode.variant;
+    let format = InstructionFormat::of(&variant);
+
+    let mut text = mnemonic(variant.opcode).to_owned();
+    let mut set_flags = false;
+    for (is_set, modifier) in variant.flags.iter().zip(flag_modifiers(variant.opcode)) {
+        match modifier {
+            Some(SET_FLAGS_MODIFIER) => set_flags = *is_set,
+            Some(modifier) if *is_set => {
+                text.push('.');
+                text.push_str(modifier);
+            }
+            _ => {}
+        }
+    }
+    if let Some(condition) = condition_modifier(opcode.condition) {
+        text.push('.');
+        text.push_str(condition);
+    }
+    if set_flags {
+        text.push_str(SET_FLAGS_MODIFIER);
+    }
+
+    let mut operands = vec![];
+    if let Some(src0) = format.src0 {
+        operands.push(format_operand(src0, opcode.src0_reg_idx, opcode.imm_0, false));
+    }
+    if format.src1 {
+        operands.push(format!("r{}", opcode.src1_reg_idx));
+    }
+    if let Some(dst0) = format.dst0 {
+        operands.push(format_operand(dst0, opcode.dst0_reg_idx, opcode.imm_1, true));
+    }
+    if format.dst1 {
+        operands.push(format!("r{}", opcode.dst1_reg_idx));
+    }
+    for imm in [opcode.imm_0, opcode.imm_1]
+        .into_iter()
+        .take(format.num_extra_immediates)
+    {
+        operands.push(imm.as_u64().to_string());
+    }
+
+    if !operands.is_empty() {
+        text.push(' ');
+        text.push_str(&operands.join(", "));
+    }
+
+    text
+}
+
+fn format_operand<T: AllowedPcOrImm>(operand: Operand, reg: u8, imm: T, is_dst: bool) -> String {
+    let prefix = match operand {
+        Operand::RegOnly
+        | Operand::RegOrImm(RegOrImmFlags::UseRegOnly)
+        | Operand::Full(ImmMemHandlerFlags::UseRegOnly) => return format!("r{reg}"),
+        Operand::RegOrImm(RegOrImmFlags::UseImm16Only)
+        | Operand::Full(ImmMemHandlerFlags::UseImm16Only) => return imm.as_u64().to_string(),
+        Operand::Full(ImmMemHandlerFlags::UseCodePage) => "code",
+        Operand::Full(ImmMemHandlerFlags::UseAbsoluteOnStack) => "stack",
+        Operand::Full(ImmMemHandlerFlags::UseStackWithOffset) => "stack-",
+        Operand::Full(ImmMemHandlerFlags::UseStackWithPushPop) if is_dst => "stack+=",
+        Operand::Full(ImmMemHandlerFlags::UseStackWithPushPop) => "stack-=",
+    };
+
+    match (reg, imm.as_u64()) {
+        (0, imm) => format!("{prefix}[{imm}]"),
+        (reg, 0) => format!("{prefix}[r{reg}]"),
+        (reg, imm) => format!("{prefix}[r{reg}+{imm}]"),
+    }
+}
+
+/// Disassembles the code page, where the first `num_instruction_words` words contain the
+/// instructions and the rest are the constants. The output is assembled back into the same
+/// words, as long as the instructions are encoded canonically.
+pub fn disassemble_code_page<const N: usize, E: VmEncodingMode<N>>(
+    words: &[U256],
+    num_instruction_words: usize,
+) -> String {
+    let instructions_per_word = 32 / N;
+    let mut lines = vec![];
+    for (word_idx, word) in words.iter().enumerate() {
+        if word_idx >= num_instruction_words {
+            lines.push(format!("{:<48}; word {word_idx}", format!(".cell {word:#x}")));
+            continue;
+        }
+
+        for sub_pc in 0..instructions_per_word {
+            let raw = E::integer_representaiton_from_u256(
+                *word,
+                E::PcOrImm::from_u64_clipped(sub_pc as u64),
+            );
+            let (opcode, _) = E::parse_preliminary_variant_and_absolute_number(raw);
+            let pc = word_idx * instructions_per_word + sub_pc;
+            lines.push(format!("{:<48}; pc {pc}", disassemble_opcode(&opcode)));
+        }
+    }
+
+    lines.join("\n")
+}
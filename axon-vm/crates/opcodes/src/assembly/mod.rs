@@ -0,0 +1,349 @@
+use super::*;
+use crate::decoding::VmEncodingMode;
+
+pub mod assembler;
+pub mod disassembler;
+
+pub use self::{assembler::*, disassembler::*};
+
+/// Fields of the instruction that are used by its variant, in the order they are written
+/// in the text form. All the other fields are ignored by the VM and are zero in the encoding
+/// produced by the assembler.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InstructionFormat {
+    pub src0: Option<Operand>,
+    pub src1: bool,
+    pub dst0: Option<Operand>,
+    pub dst1: bool,
+    // immediates that are not used for addressing, such as the jump targets and
+    // exception handlers. They always start from `imm_0`
+    pub num_extra_immediates: usize,
+}
+
+impl InstructionFormat {
+    pub fn of(variant: &OpcodeVariant) -> Self {
+        let input_operands = variant.opcode.input_operands(DEFAULT_ISA_VERSION);
+        let output_operands = variant.opcode.output_operands(DEFAULT_ISA_VERSION);
+        let num_extra_immediates = match variant.opcode {
+            Opcode::NearCall(_) => 2,
+            Opcode::FarCall(_) => 1,
+            Opcode::Ret(_) if variant.flags[RET_TO_LABEL_BIT_IDX] => 1,
+            _ => 0,
+        };
+
+        Self {
+            src0: (!input_operands.is_empty()).then_some(variant.src0_operand_type),
+            src1: input_operands.len() > 1,
+            dst0: (!output_operands.is_empty()).then_some(variant.dst0_operand_type),
+            dst1: output_operands.len() > 1,
+            num_extra_immediates,
+        }
+    }
+
+    pub fn uses_src0_reg(&self) -> bool {
+        self.src0.is_some_and(|operand| !is_immediate(operand))
+    }
+
+    pub fn uses_imm_0(&self) -> bool {
+        self.num_extra_immediates > 0 || self.src0.is_some_and(uses_immediate)
+    }
+
+    pub fn uses_imm_1(&self) -> bool {
+        self.num_extra_immediates > 1 || self.dst0.is_some_and(uses_immediate)
+    }
+
+    /// Zeroes the fields that are ignored by the variant, so the opcode gets the same encoding
+    /// as the one produced by the assembler.
+    pub fn clear_unused_fields<const N: usize, E: VmEncodingMode<N>>(
+        &self,
+        opcode: &mut DecodedOpcode<N, E>,
+    ) {
+        if !self.uses_src0_reg() {
+            opcode.src0_reg_idx = 0;
+        }
+        if !self.src1 {
+            opcode.src1_reg_idx = 0;
+        }
+        if self.dst0.is_none() {
+            opcode.dst0_reg_idx = 0;
+        }
+        if !self.dst1 {
+            opcode.dst1_reg_idx = 0;
+        }
+        if !self.uses_imm_0() {
+            opcode.imm_0 = E::PcOrImm::default();
+        }
+        if !self.uses_imm_1() {
+            opcode.imm_1 = E::PcOrImm::default();
+        }
+    }
+}
+
+fn is_immediate(operand: Operand) -> bool {
+    matches!(
+        operand,
+        Operand::RegOrImm(RegOrImmFlags::UseImm16Only)
+            | Operand::Full(ImmMemHandlerFlags::UseImm16Only)
+    )
+}
+
+// immediate is either the value itself, or the offset of the memory location
+fn uses_immediate(operand: Operand) -> bool {
+    match operand {
+        Operand::RegOnly => false,
+        Operand::RegOrImm(flags) => flags == RegOrImmFlags::UseImm16Only,
+        Operand::Full(flags) => flags != ImmMemHandlerFlags::UseRegOnly,
+    }
+}
+
+// Mnemonics follow the syntax of the era assembler where possible
+pub fn mnemonic(opcode: Opcode) -> &'static str {
+    match opcode {
+        Opcode::Invalid(_) => "invalid",
+        Opcode::Nop(_) => "nop",
+        Opcode::Add(_) => "add",
+        Opcode::Sub(_) => "sub",
+        Opcode::Mul(_) => "mul",
+        Opcode::Div(_) => "div",
+        Opcode::Jump(_) => "jump",
+        Opcode::Context(ContextOpcode::This) => "context.this",
+        Opcode::Context(ContextOpcode::Caller) => "context.caller",
+        Opcode::Context(ContextOpcode::CodeAddress) => "context.code_source",
+        Opcode::Context(ContextOpcode::Meta) => "context.meta",
+        Opcode::Context(ContextOpcode::ErgsLeft) => "context.ergs_left",
+        Opcode::Context(ContextOpcode::Sp) => "context.sp",
+        Opcode::Context(ContextOpcode::GetContextU128) => "context.get_context_u128",
+        Opcode::Context(ContextOpcode::SetContextU128) => "context.set_context_u128",
+        Opcode::Context(ContextOpcode::SetErgsPerPubdataByte) => "context.set_ergs_per_pubdata",
+        Opcode::Context(ContextOpcode::IncrementTxNumber) => "context.inc_tx_num",
+        Opcode::Shift(ShiftOpcode::Shl) => "shl",
+        Opcode::Shift(ShiftOpcode::Shr) => "shr",
+        Opcode::Shift(ShiftOpcode::Rol) => "rol",
+        Opcode::Shift(ShiftOpcode::Ror) => "ror",
+        Opcode::Binop(BinopOpcode::Xor) => "xor",
+        Opcode::Binop(BinopOpcode::And) => "and",
+        Opcode::Binop(BinopOpcode::Or) => "or",
+        Opcode::Ptr(PtrOpcode::Add) => "ptr.add",
+        Opcode::Ptr(PtrOpcode::Sub) => "ptr.sub",
+        Opcode::Ptr(PtrOpcode::Pack) => "ptr.pack",
+        Opcode::Ptr(PtrOpcode::Shrink) => "ptr.shrink",
+        Opcode::NearCall(_) => "near_call",
+        Opcode::Log(LogOpcode::StorageRead) => "log.sread",
+        Opcode::Log(LogOpcode::StorageWrite) => "log.swrite",
+        Opcode::Log(LogOpcode::ToL1Message) => "log.to_l1",
+        Opcode::Log(LogOpcode::Event) => "log.event",
+        Opcode::Log(LogOpcode::PrecompileCall) => "log.precompile",
+        Opcode::FarCall(FarCallOpcode::Normal) => "far_call",
+        Opcode::FarCall(FarCallOpcode::Delegate) => "far_call.delegate",
+        Opcode::FarCall(FarCallOpcode::Mimic) => "far_call.mimic",
+        Opcode::Ret(RetOpcode::Ok) => "ret.ok",
+        Opcode::Ret(RetOpcode::Revert) => "ret.revert",
+        Opcode::Ret(RetOpcode::Panic) => "ret.panic",
+        Opcode::UMA(UMAOpcode::HeapRead) => "ld.1",
+        Opcode::UMA(UMAOpcode::HeapWrite) => "st.1",
+        Opcode::UMA(UMAOpcode::AuxHeapRead) => "ld.2",
+        Opcode::UMA(UMAOpcode::AuxHeapWrite) => "st.2",
+        Opcode::UMA(UMAOpcode::FatPointerRead) => "ld",
+    }
+}
+
+// "!" stands for the set flags modifier, that is written after the mnemonic
+// instead of being separated by a dot
+pub(crate) const SET_FLAGS_MODIFIER: &str = "!";
+
+pub(crate) fn flag_modifiers(opcode: Opcode) -> [Option<&'static str>; NUM_NON_EXCLUSIVE_FLAGS] {
+    match opcode {
+        Opcode::Add(_) | Opcode::Mul(_) | Opcode::Binop(_) => [Some(SET_FLAGS_MODIFIER), None],
+        Opcode::Sub(_) | Opcode::Div(_) | Opcode::Shift(_) => [Some(SET_FLAGS_MODIFIER), Some("s")],
+        Opcode::Ptr(_) => [Some("s"), None],
+        Opcode::FarCall(_) => [Some("static"), Some("shard")],
+        Opcode::Ret(_) => [Some("to_label"), None],
+        Opcode::UMA(_) => [Some("inc"), None],
+        Opcode::Log(LogOpcode::ToL1Message | LogOpcode::Event) => [Some("first"), None],
+        _ => [None, None],
+    }
+}
+
+pub(crate) fn condition_modifier(condition: Condition) -> Option<&'static str> {
+    match condition {
+        Condition::Always => None,
+        Condition::Gt => Some("gt"),
+        Condition::Lt => Some("lt"),
+        Condition::Eq => Some("eq"),
+        Condition::Ge => Some("ge"),
+        Condition::Le => Some("le"),
+        Condition::Ne => Some("ne"),
+        Condition::GtOrLt => Some("gtlt"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use alloy_primitives::U256;
+
+    use super::*;
+    use crate::decoding::{AllowedPcOrImm, EncodingModeProduction, EncodingModeTesting};
+
+    fn all_distinct_variants() -> Vec<OpcodeVariant> {
+        let mut seen = HashSet::new();
+        OPCODES_TABLE
+            .iter()
+            .copied()
+            .filter(|variant| seen.insert(*variant))
+            .collect()
+    }
+
+    // fills every field with some garbage, and then leaves only the used ones
+    fn sample_opcode<const N: usize, E: VmEncodingMode<N>>(
+        variant: OpcodeVariant,
+        condition: Condition,
+        seed: u64,
+    ) -> DecodedOpcode<N, E> {
+        let mut opcode = DecodedOpcode::<N, E> {
+            variant,
+            condition,
+            src0_reg_idx: (seed % 16) as u8,
+            src1_reg_idx: ((seed + 3) % 16) as u8,
+            dst0_reg_idx: ((seed + 7) % 16) as u8,
+            dst1_reg_idx: ((seed + 11) % 16) as u8,
+            imm_0: E::PcOrImm::from_u64_clipped(seed.wrapping_mul(0x9e3779b97f4a7c15) >> 7),
+            imm_1: E::PcOrImm::from_u64_clipped(seed.wrapping_mul(0x2545f4914f6cdd1d) >> 3),
+        };
+        InstructionFormat::of(&variant).clear_unused_fields(&mut opcode);
+
+        opcode
+    }
+
+    fn check_roundtrip_for_all_variants<const N: usize, E: VmEncodingMode<N>>() {
+        let conditions = [Condition::Always, Condition::Gt, Condition::Ne, Condition::GtOrLt];
+        for (idx, variant) in all_distinct_variants().into_iter().enumerate() {
+            let condition = conditions[idx % conditions.len()];
+            let opcode = sample_opcode::<N, E>(variant, condition, idx as u64);
+            let text = disassemble_opcode(&opcode);
+
+            let assembly = assemble::<N, E>(&text)
+                .unwrap_or_else(|err| panic!("failed to assemble `{text}`: {err}"));
+            assert_eq!(assembly.instructions.len(), 1);
+            let reassembled = assembly.instructions[0];
+            assert_eq!(
+                reassembled.serialize_as_bytes(),
+                opcode.serialize_as_bytes(),
+                "`{text}` is assembled into a different opcode"
+            );
+            assert_eq!(disassemble_opcode(&reassembled), text);
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_all_opcodes_production() {
+        check_roundtrip_for_all_variants::<8, EncodingModeProduction>();
+    }
+
+    #[test]
+    fn test_roundtrip_all_opcodes_testing() {
+        check_roundtrip_for_all_variants::<16, EncodingModeTesting>();
+    }
+
+    #[test]
+    fn test_assemble_with_labels_and_constants() {
+        let source = "
+            ; loads the constant and loops until it reaches zero
+                add code[@value], r0, r1
+            loop:
+                sub.s! 1, r1, r1
+                jump.ne @loop
+                near_call r0, @end, @end
+            end:
+                ret.ok r0
+            value:
+                .cell 0x2a
+            ";
+        let assembly = assemble::<8, EncodingModeProduction>(source).unwrap();
+        assert_eq!(assembly.instructions.len(), 5);
+        assert_eq!(assembly.constants, vec![U256::from(0x2a)]);
+
+        // constants are placed right after the instructions
+        assert_eq!(assembly.instructions[0].imm_0, 2);
+        assert_eq!(assembly.instructions[2].imm_0, 1);
+        assert_eq!(assembly.instructions[2].condition, Condition::Ne);
+        assert_eq!((assembly.instructions[3].imm_0, assembly.instructions[3].imm_1), (4, 4));
+        let sub = assembly.instructions[1];
+        assert!(sub.variant.swap_operands());
+        assert!(sub.variant.flags[SET_FLAGS_FLAG_IDX]);
+        assert_eq!(sub.variant.src0_operand_type, Operand::Full(ImmMemHandlerFlags::UseImm16Only));
+
+        let code = assembly.code_words();
+        assert_eq!(code.len(), 3);
+        assert_eq!(code[2], U256::from(0x2a));
+        // padding is filled with the invalid opcodes
+        assert_eq!(code[1].as_limbs()[0..3], [0, 0, 0]);
+        assert_eq!(
+            EncodingModeProduction::integer_representaiton_from_u256(code[0], 1),
+            assembly.instructions[1].serialize_as_integer()
+        );
+
+        let text = disassemble_code_page::<8, EncodingModeProduction>(&code, 2);
+        let reassembled = assemble::<8, EncodingModeProduction>(&text).unwrap();
+        assert_eq!(reassembled.code_words(), code);
+    }
+
+    #[test]
+    fn test_assemble_memory_operands() {
+        let source = "
+            add stack-=[r2+1], r3, stack+=[r4]
+            mul stack-[5], r1, stack[r2+0x10], r5
+            st.1.inc 64, r1, r2
+            far_call.mimic.static r1, r2, @handler
+            handler:
+            ret.panic.to_label @handler
+            ";
+        let assembly = assemble::<8, EncodingModeProduction>(source).unwrap();
+        let [add, mul, st, far_call, ret] = assembly.instructions[..] else {
+            panic!("expected five instructions");
+        };
+        assert_eq!(
+            add.variant.src0_operand_type,
+            Operand::Full(ImmMemHandlerFlags::UseStackWithPushPop)
+        );
+        assert_eq!((add.src0_reg_idx, add.imm_0, add.dst0_reg_idx, add.imm_1), (2, 1, 4, 0));
+        assert_eq!(
+            mul.variant.dst0_operand_type,
+            Operand::Full(ImmMemHandlerFlags::UseAbsoluteOnStack)
+        );
+        assert_eq!((mul.imm_0, mul.dst0_reg_idx, mul.imm_1, mul.dst1_reg_idx), (5, 2, 16, 5));
+        assert_eq!(st.variant.src0_operand_type, Operand::RegOrImm(RegOrImmFlags::UseImm16Only));
+        assert!(st.variant.flags[UMA_INCREMENT_FLAG_IDX]);
+        assert_eq!(far_call.variant.opcode, Opcode::FarCall(FarCallOpcode::Mimic));
+        assert!(far_call.variant.flags[FAR_CALL_STATIC_FLAG_IDX]);
+        assert_eq!(far_call.imm_0, 4);
+        assert_eq!(ret.imm_0, 4);
+    }
+
+    #[test]
+    fn test_assembly_errors() {
+        let cases = [
+            ("add r1, r2", 1, "expected 3 operands"),
+            ("\nfoo r1", 2, "unknown mnemonic `foo`"),
+            ("add.s r1, r2, r3", 1, "unknown modifier `s`"),
+            ("add.gt.eq r1, r2, r3", 1, "duplicate condition"),
+            ("add r1, r2, 5", 1, "can not be used as a destination"),
+            ("add r1, 5, r3", 1, "expected a register"),
+            ("ld.1 code[r1], r2, r3", 1, "only a register or an immediate"),
+            ("add r16, r2, r3", 1, "invalid register `r16`"),
+            ("add 65536, r2, r3", 1, "does not fit"),
+            ("jump @nowhere", 1, "unknown label `nowhere`"),
+            ("a:\na:\nnop r0, r0", 2, "duplicate label `a`"),
+            ("nop r0, r0\nend:", 2, "label `end` is not followed"),
+        ];
+        for (source, line, message) in cases {
+            let err = assemble::<8, EncodingModeProduction>(source).unwrap_err();
+            assert_eq!(err.line, line, "{source}: {err}");
+            assert!(err.message.contains(message), "{source}: {err}");
+        }
+
+        // testing mode has wider immediates
+        assert!(assemble::<16, EncodingModeTesting>("add 65536, r2, r3").is_ok());
+    }
+}
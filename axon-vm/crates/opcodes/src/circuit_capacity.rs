@@ -0,0 +1,109 @@
+/// The number of input "units" the corresponding circuits could take.
+/// It is assumed that the actual capacity of the circuits
+/// below is greater or equal to the values provided there.
+/// Some margin is suggested to not conduct a reprice upon every minor prover change.
+pub const CYCLES_PER_VM_SNAPSHOT: u32 = 23000;
+pub const CYCLES_PER_RAM_PERMUTATION: u32 = 260000;
+pub const CYCLES_PER_CODE_DECOMMITTER: u32 = 12100;
+pub const CYCLES_PER_STORAGE_APPLICATION: u32 = 118;
+pub const CYCLES_PER_KECCAK256_CIRCUIT: u32 = 2050;
+pub const CYCLES_PER_SHA256_CIRCUIT: u32 = 11500;
+pub const CYCLES_PER_ECRECOVER_CIRCUIT: u32 = 72;
+pub const CYCLES_PER_SECP256R1_VERIFY_CIRCUIT: u32 = 48;
+pub const CYCLES_PER_RIPEMD160_CIRCUIT: u32 = 11500;
+pub const CYCLES_PER_IDENTITY_CIRCUIT: u32 = 40000;
+pub const CYCLES_PER_MODEXP_CIRCUIT: u32 = 8;
+pub const CYCLES_PER_BLAKE2F_CIRCUIT: u32 = 3000;
+pub const CYCLES_PER_ECADD_CIRCUIT: u32 = 30;
+pub const CYCLES_PER_ECMUL_CIRCUIT: u32 = 3;
+pub const CYCLES_PER_ECPAIRING_CIRCUIT: u32 = 1;
+pub const CYCLES_FOR_CODE_DECOMMITTER_SORTER: u32 = 192500;
+pub const CYCLES_FOR_LOG_DEMUXER: u32 = 101500;
+pub const CYCLES_FOR_STORAGE_SORTER: u32 = 79000;
+pub const CYCLES_FOR_EVENTS_OR_L1_MESSAGES_SORTER: u32 = 88000;
+
+/// This kinds of circuit will always remain single-instance
+pub const LIMIT_FOR_L1_MESSAGES_MERKLIZER: u32 = 512;
+pub const LIMIT_FOR_INITIAL_WRITES_PUBDATA_HASHER: u32 = 4600;
+pub const LIMIT_FOR_REPEATED_WRITES_PUBDATA_HASHER: u32 = 7400;
+
+/// Base layer circuits that can have multiple instances in a batch, so their number depends
+/// on the executed code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum BaseLayerCircuitType {
+    Vm,
+    RamPermutation,
+    CodeDecommitter,
+    CodeDecommitterSorter,
+    LogDemuxer,
+    StorageSorter,
+    StorageApplication,
+    EventsSorter,
+    L1MessagesSorter,
+    Keccak256Precompile,
+    Sha256Precompile,
+    ECRecoverPrecompile,
+    Secp256r1VerifyPrecompile,
+    Ripemd160Precompile,
+    IdentityPrecompile,
+    ModexpPrecompile,
+    Blake2fPrecompile,
+    ECAddPrecompile,
+    ECMulPrecompile,
+    ECPairingPrecompile,
+}
+
+impl BaseLayerCircuitType {
+    pub const ALL: [Self; 20] = [
+        Self::Vm,
+        Self::RamPermutation,
+        Self::CodeDecommitter,
+        Self::CodeDecommitterSorter,
+        Self::LogDemuxer,
+        Self::StorageSorter,
+        Self::StorageApplication,
+        Self::EventsSorter,
+        Self::L1MessagesSorter,
+        Self::Keccak256Precompile,
+        Self::Sha256Precompile,
+        Self::ECRecoverPrecompile,
+        Self::Secp256r1VerifyPrecompile,
+        Self::Ripemd160Precompile,
+        Self::IdentityPrecompile,
+        Self::ModexpPrecompile,
+        Self::Blake2fPrecompile,
+        Self::ECAddPrecompile,
+        Self::ECMulPrecompile,
+        Self::ECPairingPrecompile,
+    ];
+
+    /// The number of cycles a single instance of the circuit can process.
+    pub const fn cycles_per_circuit(self) -> u32 {
+        match self {
+            Self::Vm => CYCLES_PER_VM_SNAPSHOT,
+            Self::RamPermutation => CYCLES_PER_RAM_PERMUTATION,
+            Self::CodeDecommitter => CYCLES_PER_CODE_DECOMMITTER,
+            Self::CodeDecommitterSorter => CYCLES_FOR_CODE_DECOMMITTER_SORTER,
+            Self::LogDemuxer => CYCLES_FOR_LOG_DEMUXER,
+            Self::StorageSorter => CYCLES_FOR_STORAGE_SORTER,
+            Self::StorageApplication => CYCLES_PER_STORAGE_APPLICATION,
+            Self::EventsSorter | Self::L1MessagesSorter => CYCLES_FOR_EVENTS_OR_L1_MESSAGES_SORTER,
+            Self::Keccak256Precompile => CYCLES_PER_KECCAK256_CIRCUIT,
+            Self::Sha256Precompile => CYCLES_PER_SHA256_CIRCUIT,
+            Self::ECRecoverPrecompile => CYCLES_PER_ECRECOVER_CIRCUIT,
+            Self::Secp256r1VerifyPrecompile => CYCLES_PER_SECP256R1_VERIFY_CIRCUIT,
+            Self::Ripemd160Precompile => CYCLES_PER_RIPEMD160_CIRCUIT,
+            Self::IdentityPrecompile => CYCLES_PER_IDENTITY_CIRCUIT,
+            Self::ModexpPrecompile => CYCLES_PER_MODEXP_CIRCUIT,
+            Self::Blake2fPrecompile => CYCLES_PER_BLAKE2F_CIRCUIT,
+            Self::ECAddPrecompile => CYCLES_PER_ECADD_CIRCUIT,
+            Self::ECMulPrecompile => CYCLES_PER_ECMUL_CIRCUIT,
+            Self::ECPairingPrecompile => CYCLES_PER_ECPAIRING_CIRCUIT,
+        }
+    }
+
+    /// The number of instances of the circuit needed to process the given number of cycles.
+    pub const fn circuits_for_cycles(self, cycles: u32) -> u32 {
+        cycles.div_ceil(self.cycles_per_circuit())
+    }
+}
@@ -27,3 +27,11 @@ pub const STORAGE_WRITE_HASHER_MIN_COST_IN_ERGS: u32 = 0;
 pub const KECCAK256_CIRCUIT_COST_IN_ERGS: u32 = 40;
 pub const SHA256_CIRCUIT_COST_IN_ERGS: u32 = 7;
 pub const ECRECOVER_CIRCUIT_COST_IN_ERGS: u32 = 1112;
+pub const SECP256R1_VERIFY_CIRCUIT_COST_IN_ERGS: u32 = 1667;
+pub const RIPEMD160_CIRCUIT_COST_IN_ERGS: u32 = 7;
+pub const IDENTITY_CIRCUIT_COST_IN_ERGS: u32 = 2;
+pub const MODEXP_CIRCUIT_COST_IN_ERGS: u32 = 10000;
+pub const BLAKE2F_CIRCUIT_COST_IN_ERGS: u32 = 27;
+pub const ECADD_CIRCUIT_COST_IN_ERGS: u32 = 2667;
+pub const ECMUL_CIRCUIT_COST_IN_ERGS: u32 = 26667;
+pub const ECPAIRING_CIRCUIT_COST_IN_ERGS: u32 = 80000;
@@ -1,31 +1,23 @@
 use std::{fs::File, io::Write};
 
-use zkvm_opcodes::system_params::{
-    ERGS_PER_CIRCUIT, INITIAL_STORAGE_WRITE_PUBDATA_BYTES, MAX_PUBDATA_PER_BLOCK,
-    MAX_TX_ERGS_LIMIT, REPEATED_STORAGE_WRITE_PUBDATA_BYTES,
+use zkvm_opcodes::{
+    circuit_capacity::{
+        CYCLES_FOR_CODE_DECOMMITTER_SORTER, CYCLES_FOR_EVENTS_OR_L1_MESSAGES_SORTER,
+        CYCLES_FOR_LOG_DEMUXER, CYCLES_FOR_STORAGE_SORTER, CYCLES_PER_BLAKE2F_CIRCUIT,
+        CYCLES_PER_CODE_DECOMMITTER, CYCLES_PER_ECADD_CIRCUIT, CYCLES_PER_ECMUL_CIRCUIT,
+        CYCLES_PER_ECPAIRING_CIRCUIT, CYCLES_PER_ECRECOVER_CIRCUIT, CYCLES_PER_IDENTITY_CIRCUIT,
+        CYCLES_PER_KECCAK256_CIRCUIT, CYCLES_PER_MODEXP_CIRCUIT, CYCLES_PER_RAM_PERMUTATION,
+        CYCLES_PER_RIPEMD160_CIRCUIT, CYCLES_PER_SECP256R1_VERIFY_CIRCUIT,
+        CYCLES_PER_SHA256_CIRCUIT, CYCLES_PER_STORAGE_APPLICATION, CYCLES_PER_VM_SNAPSHOT,
+        LIMIT_FOR_INITIAL_WRITES_PUBDATA_HASHER, LIMIT_FOR_L1_MESSAGES_MERKLIZER,
+        LIMIT_FOR_REPEATED_WRITES_PUBDATA_HASHER,
+    },
+    system_params::{
+        ERGS_PER_CIRCUIT, INITIAL_STORAGE_WRITE_PUBDATA_BYTES, MAX_PUBDATA_PER_BLOCK,
+        MAX_TX_ERGS_LIMIT, REPEATED_STORAGE_WRITE_PUBDATA_BYTES,
+    },
 };
 
-/// The number of input "units" the corresponding circuits could take.
-/// It is assumed that the actual capacity of the circuits
-/// below is greater or equal to the values provided there.
-/// Some margin is suggested to not conduct a reprice upon every minor prover change.
-pub const CYCLES_PER_VM_SNAPSHOT: u32 = 23000;
-pub const CYCLES_PER_RAM_PERMUTATION: u32 = 260000;
-pub const CYCLES_PER_CODE_DECOMMITTER: u32 = 12100;
-pub const CYCLES_PER_STORAGE_APPLICATION: u32 = 118;
-pub const CYCLES_PER_KECCAK256_CIRCUIT: u32 = 2050;
-pub const CYCLES_PER_SHA256_CIRCUIT: u32 = 11500;
-pub const CYCLES_PER_ECRECOVER_CIRCUIT: u32 = 72;
-pub const CYCLES_FOR_CODE_DECOMMITTER_SORTER: u32 = 192500;
-pub const CYCLES_FOR_LOG_DEMUXER: u32 = 101500;
-pub const CYCLES_FOR_STORAGE_SORTER: u32 = 79000;
-pub const CYCLES_FOR_EVENTS_OR_L1_MESSAGES_SORTER: u32 = 88000;
-
-/// This kinds of circuit will always remain single-instance
-pub const LIMIT_FOR_L1_MESSAGES_MERKLIZER: u32 = 512;
-pub const LIMIT_FOR_INITIAL_WRITES_PUBDATA_HASHER: u32 = 4600;
-pub const LIMIT_FOR_REPEATED_WRITES_PUBDATA_HASHER: u32 = 7400;
-
 /// Returns ceil(a/b)
 const fn ceil_div(a: u32, b: u32) -> u32 {
     (a + b - 1) / b
@@ -154,6 +146,38 @@ fn main() {
             "ECRECOVER_CIRCUIT_COST_IN_ERGS",
             ceil_div(ERGS_PER_CIRCUIT, CYCLES_PER_ECRECOVER_CIRCUIT),
         ),
+        ergs_constant(
+            "SECP256R1_VERIFY_CIRCUIT_COST_IN_ERGS",
+            ceil_div(ERGS_PER_CIRCUIT, CYCLES_PER_SECP256R1_VERIFY_CIRCUIT),
+        ),
+        ergs_constant(
+            "RIPEMD160_CIRCUIT_COST_IN_ERGS",
+            ceil_div(ERGS_PER_CIRCUIT, CYCLES_PER_RIPEMD160_CIRCUIT),
+        ),
+        ergs_constant(
+            "IDENTITY_CIRCUIT_COST_IN_ERGS",
+            ceil_div(ERGS_PER_CIRCUIT, CYCLES_PER_IDENTITY_CIRCUIT),
+        ),
+        ergs_constant(
+            "MODEXP_CIRCUIT_COST_IN_ERGS",
+            ceil_div(ERGS_PER_CIRCUIT, CYCLES_PER_MODEXP_CIRCUIT),
+        ),
+        ergs_constant(
+            "BLAKE2F_CIRCUIT_COST_IN_ERGS",
+            ceil_div(ERGS_PER_CIRCUIT, CYCLES_PER_BLAKE2F_CIRCUIT),
+        ),
+        ergs_constant(
+            "ECADD_CIRCUIT_COST_IN_ERGS",
+            ceil_div(ERGS_PER_CIRCUIT, CYCLES_PER_ECADD_CIRCUIT),
+        ),
+        ergs_constant(
+            "ECMUL_CIRCUIT_COST_IN_ERGS",
+            ceil_div(ERGS_PER_CIRCUIT, CYCLES_PER_ECMUL_CIRCUIT),
+        ),
+        ergs_constant(
+            "ECPAIRING_CIRCUIT_COST_IN_ERGS",
+            ceil_div(ERGS_PER_CIRCUIT, CYCLES_PER_ECPAIRING_CIRCUIT),
+        ),
     ]
     .concat();
 
@@ -97,3 +97,76 @@ impl VersionedHashDef for ContractCodeSha256 {
         Some(Self::StorageLayout { code_length_in_words, extra_marker, partial_hash })
     }
 }
+
+impl VersionedHashGeneric<EvmCodeKeccak256> {
+    pub fn from_digest_and_preimage_len(digest: [u8; 32], len_in_bytes: u16) -> Self {
+        let mut truncated_digest = [0u8; 28];
+        truncated_digest.copy_from_slice(&digest[4..]);
+
+        Self {
+            data: EvmCodeKeccak256Storage {
+                code_length_in_bytes: len_in_bytes,
+                extra_marker: 0u8,
+                partial_hash: truncated_digest,
+            },
+        }
+    }
+}
+
+/// Hash of the EVM bytecode. Unlike the native code, EVM code can have any length, so the
+/// length is kept in bytes, and the code is decommitted as the zero padded words.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EvmCodeKeccak256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EvmCodeKeccak256Storage {
+    pub code_length_in_bytes: u16,
+    pub extra_marker: u8,
+    pub partial_hash: [u8; 28],
+}
+
+impl EvmCodeKeccak256Storage {
+    pub const fn code_length_in_words(&self) -> u16 {
+        self.code_length_in_bytes.div_ceil(32)
+    }
+}
+
+impl EvmCodeKeccak256 {
+    // EVM contracts can not be deployed by the VM, so there is no constructor marker
+    pub const CODE_AT_REST_MARKER: u8 = 0;
+}
+
+impl VersionedHashDef for EvmCodeKeccak256 {
+    const VERSION_BYTE: u8 = 0x02;
+    type StorageLayout = EvmCodeKeccak256Storage;
+    fn serialize(storage: Self::StorageLayout) -> Option<[u8; 32]> {
+        let mut result = [0u8; 32];
+        result[0] = Self::VERSION_BYTE;
+        result[1] = storage.extra_marker;
+        result[2..4].copy_from_slice(&storage.code_length_in_bytes.to_be_bytes());
+        result[4..].copy_from_slice(&storage.partial_hash);
+
+        Some(result)
+    }
+    fn serialize_to_stored(storage: Self::StorageLayout) -> Option<[u8; 32]> {
+        let mut result = [0u8; 32];
+        result[0] = Self::VERSION_BYTE;
+        result[1] = 0;
+        result[2..4].copy_from_slice(&storage.code_length_in_bytes.to_be_bytes());
+        result[4..].copy_from_slice(&storage.partial_hash);
+
+        Some(result)
+    }
+    fn try_deserialize(input: [u8; 32]) -> Option<Self::StorageLayout> {
+        if input[0] != Self::VERSION_BYTE {
+            return None;
+        }
+
+        let extra_marker = input[1];
+
+        let code_length_in_bytes = u16::from_be_bytes([input[2], input[3]]);
+        let partial_hash: [u8; 28] = input[4..32].try_into().unwrap();
+
+        Some(Self::StorageLayout { code_length_in_bytes, extra_marker, partial_hash })
+    }
+}
@@ -1,5 +1,6 @@
 pub const REGISTERS_COUNT: usize = 15;
 
+pub mod assembly;
 pub mod decoding;
 pub mod definitions;
 pub mod imm_mem_modifiers;
@@ -7,6 +8,7 @@ pub mod opcode;
 pub mod system_params;
 pub mod utils;
 
+pub mod circuit_capacity;
 pub mod circuit_prices;
 
 use std::collections::HashMap;
@@ -26,11 +28,13 @@ pub use self::{
     imm_mem_modifiers::*,
     opcode::*,
     system_params::{
-        ADDRESS_ACCOUNT_CODE_STORAGE, ADDRESS_BOOTLOADER, ADDRESS_CONTRACT_DEPLOYER,
+        ADDRESS_ACCOUNT_CODE_STORAGE, ADDRESS_BLAKE2F, ADDRESS_BOOTLOADER,
+        ADDRESS_CONTRACT_DEPLOYER, ADDRESS_ECADD, ADDRESS_ECMUL, ADDRESS_ECPAIRING,
         ADDRESS_ECRECOVER, ADDRESS_ETH_TOKEN, ADDRESS_EVENT_WRITER, ADDRESS_FORCE_DEPLOYER,
         ADDRESS_IDENTITY, ADDRESS_IMMUTABLE_SIMULATOR, ADDRESS_KECCAK256,
-        ADDRESS_KNOWN_CODES_STORAGE, ADDRESS_L1_MESSENGER, ADDRESS_MSG_VALUE, ADDRESS_NONCE_HOLDER,
-        ADDRESS_RIPEMD160, ADDRESS_SHA256, ADDRESS_SYSTEM_CONTEXT, ADDRESS_UNRESTRICTED_SPACE,
+        ADDRESS_KNOWN_CODES_STORAGE, ADDRESS_L1_MESSENGER, ADDRESS_MODEXP, ADDRESS_MSG_VALUE,
+        ADDRESS_NONCE_HOLDER, ADDRESS_RIPEMD160, ADDRESS_SECP256R1_VERIFY, ADDRESS_SHA256,
+        ADDRESS_SYSTEM_CONTEXT, ADDRESS_UNRESTRICTED_SPACE,
     },
     utils::*,
 };
@@ -2,7 +2,7 @@ use alloy_primitives::Address;
 use once_cell::sync::Lazy;
 
 use crate::{
-    circuit_prices::STORAGE_WRITE_HASHER_MIN_COST_IN_ERGS, u64_to_address, CALL_LIKE_ERGS_COST,
+    CALL_LIKE_ERGS_COST, circuit_prices::STORAGE_WRITE_HASHER_MIN_COST_IN_ERGS, u64_to_address,
 };
 
 pub const MAX_TX_ERGS_LIMIT: u32 = 80_000_000;
@@ -26,6 +26,14 @@ const SYSTEM_CONTRACTS_OFFSET_ADDRESS: u16 = 1 << 15;
 pub const KECCAK256_ROUND_FUNCTION_PRECOMPILE_ADDRESS: u16 = SYSTEM_CONTRACTS_OFFSET_ADDRESS + 0x10;
 pub const SHA256_ROUND_FUNCTION_PRECOMPILE_ADDRESS: u16 = 0x02; // as in Cortex
 pub const ECRECOVER_INNER_FUNCTION_PRECOMPILE_ADDRESS: u16 = 0x01; // as in Cortex
+pub const RIPEMD160_ROUND_FUNCTION_PRECOMPILE_ADDRESS: u16 = 0x03; // as in Cortex
+pub const IDENTITY_PRECOMPILE_ADDRESS: u16 = 0x04; // as in Cortex
+pub const MODEXP_PRECOMPILE_ADDRESS: u16 = 0x05; // as in Cortex
+pub const ECADD_PRECOMPILE_ADDRESS: u16 = 0x06; // as in Cortex
+pub const ECMUL_PRECOMPILE_ADDRESS: u16 = 0x07; // as in Cortex
+pub const ECPAIRING_PRECOMPILE_ADDRESS: u16 = 0x08; // as in Cortex
+pub const BLAKE2F_PRECOMPILE_ADDRESS: u16 = 0x09; // as in Cortex
+pub const SECP256R1_VERIFY_PRECOMPILE_ADDRESS: u16 = 0x100; // as in RIP-7212
 
 pub const INITIAL_STORAGE_WRITE_PUBDATA_BYTES: usize = 64;
 pub const REPEATED_STORAGE_WRITE_PUBDATA_BYTES: usize = 40;
@@ -53,6 +61,12 @@ pub const ADDRESS_ECRECOVER: u16 = 0x0001;
 pub const ADDRESS_SHA256: u16 = 0x0002;
 pub const ADDRESS_RIPEMD160: u16 = 0x0003;
 pub const ADDRESS_IDENTITY: u16 = 0x0004;
+pub const ADDRESS_MODEXP: u16 = 0x0005;
+pub const ADDRESS_ECADD: u16 = 0x0006;
+pub const ADDRESS_ECMUL: u16 = 0x0007;
+pub const ADDRESS_ECPAIRING: u16 = 0x0008;
+pub const ADDRESS_BLAKE2F: u16 = 0x0009;
+pub const ADDRESS_SECP256R1_VERIFY: u16 = 0x0100;
 
 pub const ADDRESS_BOOTLOADER: u16 = 0x8001;
 pub const ADDRESS_ACCOUNT_CODE_STORAGE: u16 = 0x8002;
@@ -116,3 +130,19 @@ pub static SHA256_ROUND_FUNCTION_PRECOMPILE_FORMAL_ADDRESS: Lazy<Address> =
     Lazy::new(|| u64_to_address(SHA256_ROUND_FUNCTION_PRECOMPILE_ADDRESS as u64));
 pub static ECRECOVER_INNER_FUNCTION_PRECOMPILE_FORMAL_ADDRESS: Lazy<Address> =
     Lazy::new(|| u64_to_address(ECRECOVER_INNER_FUNCTION_PRECOMPILE_ADDRESS as u64));
+pub static RIPEMD160_ROUND_FUNCTION_PRECOMPILE_FORMAL_ADDRESS: Lazy<Address> =
+    Lazy::new(|| u64_to_address(RIPEMD160_ROUND_FUNCTION_PRECOMPILE_ADDRESS as u64));
+pub static IDENTITY_PRECOMPILE_FORMAL_ADDRESS: Lazy<Address> =
+    Lazy::new(|| u64_to_address(IDENTITY_PRECOMPILE_ADDRESS as u64));
+pub static MODEXP_PRECOMPILE_FORMAL_ADDRESS: Lazy<Address> =
+    Lazy::new(|| u64_to_address(MODEXP_PRECOMPILE_ADDRESS as u64));
+pub static ECADD_PRECOMPILE_FORMAL_ADDRESS: Lazy<Address> =
+    Lazy::new(|| u64_to_address(ECADD_PRECOMPILE_ADDRESS as u64));
+pub static ECMUL_PRECOMPILE_FORMAL_ADDRESS: Lazy<Address> =
+    Lazy::new(|| u64_to_address(ECMUL_PRECOMPILE_ADDRESS as u64));
+pub static ECPAIRING_PRECOMPILE_FORMAL_ADDRESS: Lazy<Address> =
+    Lazy::new(|| u64_to_address(ECPAIRING_PRECOMPILE_ADDRESS as u64));
+pub static BLAKE2F_PRECOMPILE_FORMAL_ADDRESS: Lazy<Address> =
+    Lazy::new(|| u64_to_address(BLAKE2F_PRECOMPILE_ADDRESS as u64));
+pub static SECP256R1_VERIFY_PRECOMPILE_FORMAL_ADDRESS: Lazy<Address> =
+    Lazy::new(|| u64_to_address(SECP256R1_VERIFY_PRECOMPILE_ADDRESS as u64));
@@ -64,6 +64,25 @@ pub fn bytecode_to_code_hash_for_mode<const N: usize, E: VmEncodingMode<N>>(
     Ok(versioned_hash_bytes)
 }
 
+#[allow(clippy::result_unit_err)]
+/// Versioned hash of the EVM bytecode, see [`EvmCodeKeccak256`](crate::EvmCodeKeccak256)
+pub fn evm_bytecode_to_code_hash(bytecode: &[u8]) -> Result<[u8; 32], ()> {
+    let len_in_bytes = u16::try_from(bytecode.len()).map_err(|_| ())?;
+
+    use sha3::{Digest, Keccak256};
+
+    let digest: [u8; 32] = Keccak256::digest(bytecode).into();
+
+    use crate::{EvmCodeKeccak256, VersionedHashGeneric};
+
+    let versioned_hash = VersionedHashGeneric::<EvmCodeKeccak256>::from_digest_and_preimage_len(
+        digest,
+        len_in_bytes,
+    );
+
+    versioned_hash.serialize().ok_or(())
+}
+
 /// Erase start and page number from a fat pointer. To be used in the case of a fat pointer
 /// being passed to an opcode which shouldn't receive one.
 pub fn erase_fat_pointer_metadata(ptr: &mut U256) {
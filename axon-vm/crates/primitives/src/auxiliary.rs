@@ -21,6 +21,10 @@ impl std::cmp::Ord for Timestamp {
     }
 }
 
+// identifies a point in the history of the storage or event sink to rollback to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct SnapshotId(pub u32);
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct MemoryIndex(pub u32);
 
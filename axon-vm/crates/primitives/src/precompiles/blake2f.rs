@@ -0,0 +1,266 @@
+use alloy_primitives::U256;
+
+use super::precompile_abi_in_log;
+use crate::{aux::*, queries::*, vm::*};
+
+/// Length of the EIP-152 input: rounds (4 bytes), h (64 bytes), m (128 bytes),
+/// t (16 bytes) and f (1 byte).
+pub const BLAKE2F_INPUT_BYTES: usize = 213;
+// the input is tightly packed into words, so we read it in the first cycle
+pub const MEMORY_READS_PER_CYCLE: usize = BLAKE2F_INPUT_BYTES.div_ceil(32);
+// ok or error marker and two words of the state
+pub const MEMORY_WRITES_PER_CYCLE: usize = 3;
+/// Maximum number of rounds. Every round takes a cycle, so inputs requesting more rounds are
+/// rejected as malformed instead of running and recording billions of cycles.
+pub const MAX_ROUNDS: u32 = 1 << 14;
+
+pub const BLAKE2B_IV: [u64; 8] = [
+    0x6a09_e667_f3bc_c908,
+    0xbb67_ae85_84ca_a73b,
+    0x3c6e_f372_fe94_f82b,
+    0xa54f_f53a_5f1d_36f1,
+    0x510e_527f_ade6_82d1,
+    0x9b05_688c_2b3e_6c1f,
+    0x1f83_d9ab_fb41_bd6b,
+    0x5be0_cd19_137e_2179,
+];
+
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Blake2fRoundWitness {
+    pub new_request: Option<LogQuery>,
+    pub reads: [Option<MemoryQuery>; MEMORY_READS_PER_CYCLE],
+    pub writes: Option<[MemoryQuery; MEMORY_WRITES_PER_CYCLE]>,
+}
+
+/// Parsed EIP-152 input of the `F` compression function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Blake2fInput {
+    pub rounds: u32,
+    pub h: [u64; 8],
+    pub m: [u64; 16],
+    pub t: [u64; 2],
+    pub f: bool,
+}
+
+impl Blake2fInput {
+    /// Parses the input, returns `None` if the final block flag is neither 0 nor 1 or
+    /// the number of rounds exceeds [`MAX_ROUNDS`].
+    pub fn parse(input: &[u8; BLAKE2F_INPUT_BYTES]) -> Option<Self> {
+        let rounds = u32::from_be_bytes(input[..4].try_into().unwrap());
+        if rounds > MAX_ROUNDS {
+            return None;
+        }
+        let h = std::array::from_fn(|i| read_u64_le(&input[4..68], i));
+        let m = std::array::from_fn(|i| read_u64_le(&input[68..196], i));
+        let t = std::array::from_fn(|i| read_u64_le(&input[196..212], i));
+        let f = match input[212] {
+            0 => false,
+            1 => true,
+            _ => return None,
+        };
+
+        Some(Self { rounds, h, m, t, f })
+    }
+}
+
+fn read_u64_le(bytes: &[u8], index: usize) -> u64 {
+    u64::from_le_bytes(bytes[(index * 8)..(index * 8 + 8)].try_into().unwrap())
+}
+
+/// Blake2b `F` compression function as specified by EIP-152.
+///
+/// The precompile reads the 213 byte EIP-152 input tightly packed into words and performs the
+/// requested number of rounds, one cycle per round (or a single cycle if zero rounds are
+/// requested). It writes the marker whether the input is valid followed by the resulting
+/// 64 byte state, or zeros if the input is malformed. Inputs with an invalid final block flag
+/// or more than [`MAX_ROUNDS`] rounds are malformed and take a single cycle.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Blake2fPrecompile<const B: bool>;
+
+impl<const B: bool> Precompile for Blake2fPrecompile<B> {
+    type CycleWitness = Blake2fRoundWitness;
+
+    fn execute_precompile<M: Memory>(
+        &mut self,
+        monotonic_cycle_counter: u32,
+        query: LogQuery,
+        memory: &mut M,
+    ) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, Vec<Self::CycleWitness>)> {
+        let precompile_call_params = query;
+        // read the parameters
+        let params = precompile_abi_in_log(precompile_call_params);
+        let timestamp_to_read = precompile_call_params.timestamp;
+        let timestamp_to_write = Timestamp(timestamp_to_read.0 + 1); // our default timestamping agreement
+
+        let mut read_queries = if B { Vec::with_capacity(MEMORY_READS_PER_CYCLE) } else { vec![] };
+        let mut write_queries =
+            if B { Vec::with_capacity(MEMORY_WRITES_PER_CYCLE) } else { vec![] };
+
+        let mut first_round_witness = Blake2fRoundWitness {
+            new_request: None,
+            reads: [None; MEMORY_READS_PER_CYCLE],
+            writes: None,
+        };
+        if B {
+            first_round_witness.new_request = Some(precompile_call_params);
+        }
+
+        let mut input = [0u8; MEMORY_READS_PER_CYCLE * 32];
+        for (idx, chunk) in input.chunks_exact_mut(32).enumerate() {
+            let data_query = MemoryQuery {
+                timestamp: timestamp_to_read,
+                location: MemoryLocation {
+                    memory_type: MemoryType::Heap,
+                    page: MemoryPage(params.memory_page_to_read),
+                    index: MemoryIndex(params.input_memory_offset + idx as u32),
+                },
+                value: U256::ZERO,
+                value_is_pointer: false,
+                rw_flag: false,
+            };
+            let data_query = memory.execute_partial_query(monotonic_cycle_counter, data_query);
+            if B {
+                first_round_witness.reads[idx] = Some(data_query);
+                read_queries.push(data_query);
+            }
+            chunk.copy_from_slice(&data_query.value.to_be_bytes::<32>());
+        }
+
+        let input = Blake2fInput::parse(input[..BLAKE2F_INPUT_BYTES].try_into().unwrap());
+        let num_rounds = input.map_or(1, |input| input.rounds.max(1) as usize);
+
+        let mut witness = if B { Vec::with_capacity(num_rounds) } else { vec![] };
+        let mut first_round_witness = Some(first_round_witness);
+
+        let mut internal_state = input.map(|input| blake2f_initial_state(&input));
+
+        for round in 0..num_rounds {
+            let mut round_witness = first_round_witness.take().unwrap_or(Blake2fRoundWitness {
+                new_request: None,
+                reads: [None; MEMORY_READS_PER_CYCLE],
+                writes: None,
+            });
+
+            let is_last = round == num_rounds - 1;
+
+            if let (Some(input), Some(state)) = (input.as_ref(), internal_state.as_mut()) {
+                if input.rounds > 0 {
+                    blake2f_round(state, &input.m, round);
+                }
+            }
+
+            if is_last {
+                let output = match (input, internal_state) {
+                    (Some(input), Some(state)) => {
+                        let h = blake2f_finalize(&input.h, &state);
+                        let mut output_bytes = [0u8; 64];
+                        for (chunk, word) in output_bytes.chunks_exact_mut(8).zip(h) {
+                            chunk.copy_from_slice(&word.to_le_bytes());
+                        }
+                        [
+                            U256::from(1),
+                            U256::from_be_slice(&output_bytes[..32]),
+                            U256::from_be_slice(&output_bytes[32..]),
+                        ]
+                    }
+                    _ => [U256::ZERO; MEMORY_WRITES_PER_CYCLE],
+                };
+
+                let mut writes = [MemoryQuery::empty(); MEMORY_WRITES_PER_CYCLE];
+                for (idx, (write, value)) in writes.iter_mut().zip(output).enumerate() {
+                    let result_query = MemoryQuery {
+                        timestamp: timestamp_to_write,
+                        location: MemoryLocation {
+                            memory_type: MemoryType::Heap, /* we default for some value, here
+                                                            * it's not that important */
+                            page: MemoryPage(params.memory_page_to_write),
+                            index: MemoryIndex(params.output_memory_offset + idx as u32),
+                        },
+                        value,
+                        value_is_pointer: false,
+                        rw_flag: true,
+                    };
+                    *write = memory.execute_partial_query(monotonic_cycle_counter, result_query);
+                }
+
+                if B {
+                    round_witness.writes = Some(writes);
+                    write_queries.extend(writes);
+                }
+            }
+
+            if B {
+                witness.push(round_witness);
+            }
+        }
+
+        if B { Some((read_queries, write_queries, witness)) } else { None }
+    }
+}
+
+pub fn blake2f_rounds_function<M: Memory, const B: bool>(
+    monotonic_cycle_counter: u32,
+    precompile_call_params: LogQuery,
+    memory: &mut M,
+) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, Vec<Blake2fRoundWitness>)> {
+    let mut processor = Blake2fPrecompile::<B>;
+    processor.execute_precompile(monotonic_cycle_counter, precompile_call_params, memory)
+}
+
+pub type Blake2fInnerState = [u64; 16];
+
+/// Initializes the local work vector of the compression function.
+pub fn blake2f_initial_state(input: &Blake2fInput) -> Blake2fInnerState {
+    let mut state = [0u64; 16];
+    state[..8].copy_from_slice(&input.h);
+    state[8..].copy_from_slice(&BLAKE2B_IV);
+    state[12] ^= input.t[0];
+    state[13] ^= input.t[1];
+    if input.f {
+        state[14] = !state[14];
+    }
+
+    state
+}
+
+fn mix(state: &mut Blake2fInnerState, a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(x);
+    state[d] = (state[d] ^ state[a]).rotate_right(32);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(24);
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(y);
+    state[d] = (state[d] ^ state[a]).rotate_right(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(63);
+}
+
+/// Applies a single round of the compression function.
+pub fn blake2f_round(state: &mut Blake2fInnerState, m: &[u64; 16], round: usize) {
+    let s = &SIGMA[round % 10];
+    mix(state, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+    mix(state, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+    mix(state, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+    mix(state, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+    mix(state, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+    mix(state, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+    mix(state, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+    mix(state, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+}
+
+/// Folds the local work vector into the resulting state.
+pub fn blake2f_finalize(h: &[u64; 8], state: &Blake2fInnerState) -> [u64; 8] {
+    std::array::from_fn(|i| h[i] ^ state[i] ^ state[i + 8])
+}
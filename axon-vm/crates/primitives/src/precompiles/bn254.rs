@@ -0,0 +1,94 @@
+//! Encoding of alt_bn128 (BN254) curve points shared by the ecAdd, ecMul and ecPairing
+//! precompiles. Points are encoded as in EIP-196 and EIP-197: field elements are big-endian
+//! words, the point at infinity is encoded as all zeros, and `Fq2` elements are encoded
+//! as the imaginary part followed by the real part.
+
+use alloy_primitives::U256;
+use bn::{AffineG1, AffineG2, Fq, Fq2, Fr, Group, G1, G2};
+
+use super::*;
+
+fn decode_fq(value: U256) -> Result<Fq, ()> {
+    Fq::from_slice(&value.to_be_bytes::<32>()).map_err(|_| ())
+}
+
+/// Decodes a G1 point checking that it's on the curve.
+#[allow(clippy::result_unit_err)]
+pub fn decode_g1(x: U256, y: U256) -> Result<G1, ()> {
+    if x.is_zero() && y.is_zero() {
+        return Ok(G1::zero());
+    }
+    let point = AffineG1::new(decode_fq(x)?, decode_fq(y)?).map_err(|_| ())?;
+    Ok(point.into())
+}
+
+/// Decodes a G2 point checking that it's on the curve and in the correct subgroup.
+#[allow(clippy::result_unit_err)]
+pub fn decode_g2(x_imag: U256, x_real: U256, y_imag: U256, y_real: U256) -> Result<G2, ()> {
+    let x = Fq2::new(decode_fq(x_real)?, decode_fq(x_imag)?);
+    let y = Fq2::new(decode_fq(y_real)?, decode_fq(y_imag)?);
+    if x.is_zero() && y.is_zero() {
+        return Ok(G2::zero());
+    }
+    let point = AffineG2::new(x, y).map_err(|_| ())?;
+    Ok(point.into())
+}
+
+/// Decodes a scalar. Scalars are arbitrary 256-bit values reduced modulo the group order.
+pub fn decode_scalar(value: U256) -> Fr {
+    Fr::from_slice(&value.to_be_bytes::<32>()).expect("slice has correct length")
+}
+
+/// Encodes a G1 point into `(x, y)` words.
+pub fn encode_g1(point: G1) -> (U256, U256) {
+    let Some(point) = AffineG1::from_jacobian(point) else {
+        return (U256::ZERO, U256::ZERO);
+    };
+    let mut x = [0u8; 32];
+    point
+        .x()
+        .to_big_endian(&mut x)
+        .expect("slice has correct length");
+    let mut y = [0u8; 32];
+    point
+        .y()
+        .to_big_endian(&mut y)
+        .expect("slice has correct length");
+    (U256::from_be_bytes(x), U256::from_be_bytes(y))
+}
+
+/// Reads `N` consecutive words starting from the specified location.
+pub(super) fn read_words<M: Memory, const N: usize>(
+    memory: &mut M,
+    monotonic_cycle_counter: u32,
+    timestamp: Timestamp,
+    location: &mut MemoryLocation,
+) -> [MemoryQuery; N] {
+    std::array::from_fn(|_| {
+        let query = MemoryQuery {
+            timestamp,
+            location: *location,
+            value: U256::ZERO,
+            value_is_pointer: false,
+            rw_flag: false,
+        };
+        location.index.0 += 1;
+        memory.execute_partial_query(monotonic_cycle_counter, query)
+    })
+}
+
+/// Writes consecutive words starting from the specified location.
+pub(super) fn write_words<M: Memory, const N: usize>(
+    memory: &mut M,
+    monotonic_cycle_counter: u32,
+    timestamp: Timestamp,
+    mut location: MemoryLocation,
+    values: [U256; N],
+) -> [MemoryQuery; N] {
+    values.map(|value| {
+        let query =
+            MemoryQuery { timestamp, location, value, value_is_pointer: false, rw_flag: true };
+        location.index.0 += 1;
+        memory.execute_partial_query(monotonic_cycle_counter, query)
+    })
+}
@@ -0,0 +1,85 @@
+use alloy_primitives::U256;
+
+use super::{bn254::*, *};
+
+// we need x, y, scalar
+pub const MEMORY_READS_PER_CYCLE: usize = 3;
+// ok or error marker, x, y
+pub const MEMORY_WRITES_PER_CYCLE: usize = 3;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ECMulRoundWitness {
+    pub new_request: LogQuery,
+    pub reads: [MemoryQuery; MEMORY_READS_PER_CYCLE],
+    pub writes: [MemoryQuery; MEMORY_WRITES_PER_CYCLE],
+}
+
+/// Scalar multiplication of an alt_bn128 G1 point as specified by EIP-196. If the point is not
+/// on the curve, the error marker and a zero point are written.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ECMulPrecompile<const B: bool>;
+
+impl<const B: bool> Precompile for ECMulPrecompile<B> {
+    type CycleWitness = ECMulRoundWitness;
+
+    fn execute_precompile<M: Memory>(
+        &mut self,
+        monotonic_cycle_counter: u32,
+        query: LogQuery,
+        memory: &mut M,
+    ) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, Vec<Self::CycleWitness>)> {
+        let precompile_call_params = query;
+        let params = precompile_abi_in_log(precompile_call_params);
+        let timestamp_to_read = precompile_call_params.timestamp;
+        let timestamp_to_write = Timestamp(timestamp_to_read.0 + 1); // our default timestamping agreement
+
+        let mut read_location = MemoryLocation {
+            memory_type: MemoryType::Heap, /* we default for some value, here it's not that
+                                            * important */
+            page: MemoryPage(params.memory_page_to_read),
+            index: MemoryIndex(params.input_memory_offset),
+        };
+        let reads: [MemoryQuery; MEMORY_READS_PER_CYCLE] =
+            read_words(memory, monotonic_cycle_counter, timestamp_to_read, &mut read_location);
+        let [x, y, scalar] = reads.map(|query| query.value);
+
+        let output = match decode_g1(x, y) {
+            Ok(point) => {
+                let (x, y) = encode_g1(point * decode_scalar(scalar));
+                [U256::from(1), x, y]
+            }
+            Err(()) => [U256::ZERO; MEMORY_WRITES_PER_CYCLE],
+        };
+
+        let write_location = MemoryLocation {
+            memory_type: MemoryType::Heap, /* we default for some value, here it's not that
+                                            * important */
+            page: MemoryPage(params.memory_page_to_write),
+            index: MemoryIndex(params.output_memory_offset),
+        };
+        let writes = write_words(
+            memory,
+            monotonic_cycle_counter,
+            timestamp_to_write,
+            write_location,
+            output,
+        );
+
+        if B {
+            let round_witness =
+                ECMulRoundWitness { new_request: precompile_call_params, reads, writes };
+            Some((reads.to_vec(), writes.to_vec(), vec![round_witness]))
+        } else {
+            None
+        }
+    }
+}
+
+pub fn ecmul_function<M: Memory, const B: bool>(
+    monotonic_cycle_counter: u32,
+    precompile_call_params: LogQuery,
+    memory: &mut M,
+) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, Vec<ECMulRoundWitness>)> {
+    let mut processor = ECMulPrecompile::<B>;
+    processor.execute_precompile(monotonic_cycle_counter, precompile_call_params, memory)
+}
@@ -0,0 +1,123 @@
+use alloy_primitives::U256;
+use bn::{pairing_batch, Gt};
+
+use super::{bn254::*, *};
+
+// we need G1 point x, y and G2 point x, y (imaginary and real parts) for every pair
+pub const MEMORY_READS_PER_CYCLE: usize = 6;
+// ok or error marker, result
+pub const MEMORY_WRITES_PER_CYCLE: usize = 2;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ECPairingRoundWitness {
+    pub new_request: Option<LogQuery>,
+    /// Reads of the processed pair; empty queries if the precompile is called without pairs.
+    pub reads: [MemoryQuery; MEMORY_READS_PER_CYCLE],
+    pub writes: Option<[MemoryQuery; MEMORY_WRITES_PER_CYCLE]>,
+}
+
+/// Pairing check on alt_bn128 as specified by EIP-197. Every round processes a single
+/// `(G1, G2)` pair; the number of pairs is passed in the interpreted data of the precompile ABI.
+///
+/// The precompile writes the marker whether all points are well-formed and the check result
+/// (`1` if the product of pairings is the identity, `0` otherwise). If called without pairs,
+/// the precompile reads nothing and reports success with the result `1`, as per EIP-197.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ECPairingPrecompile<const B: bool>;
+
+impl<const B: bool> Precompile for ECPairingPrecompile<B> {
+    type CycleWitness = ECPairingRoundWitness;
+
+    fn execute_precompile<M: Memory>(
+        &mut self,
+        monotonic_cycle_counter: u32,
+        query: LogQuery,
+        memory: &mut M,
+    ) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, Vec<Self::CycleWitness>)> {
+        let precompile_call_params = query;
+        let params = precompile_abi_in_log(precompile_call_params);
+        let timestamp_to_read = precompile_call_params.timestamp;
+        let timestamp_to_write = Timestamp(timestamp_to_read.0 + 1); // our default timestamping agreement
+
+        let num_rounds = params.precompile_interpreted_data as usize;
+
+        let mut read_location = MemoryLocation {
+            memory_type: MemoryType::Heap, /* we default for some value, here it's not that
+                                            * important */
+            page: MemoryPage(params.memory_page_to_read),
+            index: MemoryIndex(params.input_memory_offset),
+        };
+
+        let mut read_history =
+            if B { Vec::with_capacity(MEMORY_READS_PER_CYCLE * num_rounds) } else { vec![] };
+        let mut witness = if B { Vec::with_capacity(num_rounds) } else { vec![] };
+
+        // decoding errors are not short-circuited, since all pairs must be read anyway
+        let mut pairs = Ok(Vec::with_capacity(num_rounds));
+        for round in 0..num_rounds {
+            let reads: [MemoryQuery; MEMORY_READS_PER_CYCLE] =
+                read_words(memory, monotonic_cycle_counter, timestamp_to_read, &mut read_location);
+            let [x, y, x2_imag, x2_real, y2_imag, y2_real] = reads.map(|query| query.value);
+            let pair = decode_g1(x, y)
+                .and_then(|g1| Ok((g1, decode_g2(x2_imag, x2_real, y2_imag, y2_real)?)));
+            if let (Ok(pairs), Ok(pair)) = (&mut pairs, pair) {
+                pairs.push(pair);
+            } else {
+                pairs = Err(());
+            }
+
+            if B {
+                read_history.extend(reads);
+                witness.push(ECPairingRoundWitness {
+                    new_request: (round == 0).then_some(precompile_call_params),
+                    reads,
+                    writes: None,
+                });
+            }
+        }
+
+        let output = match pairs {
+            Ok(pairs) if pairs.is_empty() => [U256::from(1); MEMORY_WRITES_PER_CYCLE],
+            Ok(pairs) => [U256::from(1), U256::from((pairing_batch(&pairs) == Gt::one()) as u64)],
+            Err(()) => [U256::ZERO; MEMORY_WRITES_PER_CYCLE],
+        };
+
+        let write_location = MemoryLocation {
+            memory_type: MemoryType::Heap, /* we default for some value, here it's not that
+                                            * important */
+            page: MemoryPage(params.memory_page_to_write),
+            index: MemoryIndex(params.output_memory_offset),
+        };
+        let writes = write_words(
+            memory,
+            monotonic_cycle_counter,
+            timestamp_to_write,
+            write_location,
+            output,
+        );
+
+        if B {
+            if let Some(last_round) = witness.last_mut() {
+                last_round.writes = Some(writes);
+            } else {
+                witness.push(ECPairingRoundWitness {
+                    new_request: Some(precompile_call_params),
+                    reads: [MemoryQuery::empty(); MEMORY_READS_PER_CYCLE],
+                    writes: Some(writes),
+                });
+            }
+            Some((read_history, writes.to_vec(), witness))
+        } else {
+            None
+        }
+    }
+}
+
+pub fn ecpairing_function<M: Memory, const B: bool>(
+    monotonic_cycle_counter: u32,
+    precompile_call_params: LogQuery,
+    memory: &mut M,
+) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, Vec<ECPairingRoundWitness>)> {
+    let mut processor = ECPairingPrecompile::<B>;
+    processor.execute_precompile(monotonic_cycle_counter, precompile_call_params, memory)
+}
@@ -0,0 +1,98 @@
+use alloy_primitives::U256;
+
+use super::*;
+
+// identity copies a single word per round
+pub const MEMORY_READS_PER_CYCLE: usize = 1;
+pub const MEMORY_WRITES_PER_CYCLE: usize = 1;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IdentityRoundWitness {
+    pub new_request: Option<LogQuery>,
+    pub reads: [MemoryQuery; MEMORY_READS_PER_CYCLE],
+    pub writes: [MemoryQuery; MEMORY_WRITES_PER_CYCLE],
+}
+
+/// Copies `input_memory_length` words from the input location to the output location.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IdentityPrecompile<const B: bool>;
+
+impl<const B: bool> Precompile for IdentityPrecompile<B> {
+    type CycleWitness = IdentityRoundWitness;
+
+    fn execute_precompile<M: Memory>(
+        &mut self,
+        monotonic_cycle_counter: u32,
+        query: LogQuery,
+        memory: &mut M,
+    ) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, Vec<Self::CycleWitness>)> {
+        let precompile_call_params = query;
+        let params = precompile_abi_in_log(precompile_call_params);
+        let timestamp_to_read = precompile_call_params.timestamp;
+        let timestamp_to_write = Timestamp(timestamp_to_read.0 + 1); // our default timestamping agreement
+
+        let num_rounds = params.input_memory_length as usize;
+
+        let mut read_location = MemoryLocation {
+            memory_type: MemoryType::Heap, /* we default for some value, here it's not that
+                                            * important */
+            page: MemoryPage(params.memory_page_to_read),
+            index: MemoryIndex(params.input_memory_offset),
+        };
+        let mut write_location = MemoryLocation {
+            memory_type: MemoryType::Heap, /* we default for some value, here it's not that
+                                            * important */
+            page: MemoryPage(params.memory_page_to_write),
+            index: MemoryIndex(params.output_memory_offset),
+        };
+
+        let mut read_queries =
+            if B { Vec::with_capacity(MEMORY_READS_PER_CYCLE * num_rounds) } else { vec![] };
+        let mut write_queries =
+            if B { Vec::with_capacity(MEMORY_WRITES_PER_CYCLE * num_rounds) } else { vec![] };
+        let mut witness = if B { Vec::with_capacity(num_rounds) } else { vec![] };
+
+        for round in 0..num_rounds {
+            let read_query = MemoryQuery {
+                timestamp: timestamp_to_read,
+                location: read_location,
+                value: U256::ZERO,
+                value_is_pointer: false,
+                rw_flag: false,
+            };
+            let read_query = memory.execute_partial_query(monotonic_cycle_counter, read_query);
+            read_location.index.0 += 1;
+
+            let write_query = MemoryQuery {
+                timestamp: timestamp_to_write,
+                location: write_location,
+                value: read_query.value,
+                value_is_pointer: false,
+                rw_flag: true,
+            };
+            let write_query = memory.execute_partial_query(monotonic_cycle_counter, write_query);
+            write_location.index.0 += 1;
+
+            if B {
+                read_queries.push(read_query);
+                write_queries.push(write_query);
+                witness.push(IdentityRoundWitness {
+                    new_request: (round == 0).then_some(precompile_call_params),
+                    reads: [read_query],
+                    writes: [write_query],
+                });
+            }
+        }
+
+        if B { Some((read_queries, write_queries, witness)) } else { None }
+    }
+}
+
+pub fn identity_function<M: Memory, const B: bool>(
+    monotonic_cycle_counter: u32,
+    precompile_call_params: LogQuery,
+    memory: &mut M,
+) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, Vec<IdentityRoundWitness>)> {
+    let mut processor = IdentityPrecompile::<B>;
+    processor.execute_precompile(monotonic_cycle_counter, precompile_call_params, memory)
+}
@@ -1,13 +1,26 @@
-use crate::{aux::*, queries::*, vm::*};
+use crate::{aux::*, precompiles::registry::DynPrecompile, queries::*, vm::*};
 
+pub mod blake2f;
+pub mod bn254;
+pub mod ecadd;
+pub mod ecmul;
+pub mod ecpairing;
 pub mod ecrecover;
+pub mod identity;
 pub mod keccak256;
+pub mod modexp;
+pub mod registry;
+pub mod ripemd160;
+pub mod secp256r1_verify;
 pub mod sha256;
 
 use zkvm_opcodes::{
     system_params::{
-        ECRECOVER_INNER_FUNCTION_PRECOMPILE_ADDRESS, KECCAK256_ROUND_FUNCTION_PRECOMPILE_ADDRESS,
-        SHA256_ROUND_FUNCTION_PRECOMPILE_ADDRESS,
+        BLAKE2F_PRECOMPILE_ADDRESS, ECADD_PRECOMPILE_ADDRESS, ECMUL_PRECOMPILE_ADDRESS,
+        ECPAIRING_PRECOMPILE_ADDRESS, ECRECOVER_INNER_FUNCTION_PRECOMPILE_ADDRESS,
+        IDENTITY_PRECOMPILE_ADDRESS, KECCAK256_ROUND_FUNCTION_PRECOMPILE_ADDRESS,
+        MODEXP_PRECOMPILE_ADDRESS, RIPEMD160_ROUND_FUNCTION_PRECOMPILE_ADDRESS,
+        SECP256R1_VERIFY_PRECOMPILE_ADDRESS, SHA256_ROUND_FUNCTION_PRECOMPILE_ADDRESS,
     },
     PrecompileCallABI,
 };
@@ -16,6 +29,64 @@ pub const fn precompile_abi_in_log(query: LogQuery) -> PrecompileCallABI {
     PrecompileCallABI::from_u256(query.key)
 }
 
+/// Executes the precompile and converts its cycle witness. If `B` is set, the precompile must
+/// generate the witness.
+pub fn execute_pure_precompile<P, M, const B: bool>(
+    precompile: &mut P,
+    monotonic_cycle_counter: u32,
+    query: LogQuery,
+    memory: &mut M,
+) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, PrecompileCyclesWitness)>
+where
+    P: Precompile,
+    P::CycleWitness: IntoCyclesWitness,
+    M: Memory,
+{
+    let result = precompile.execute_precompile(monotonic_cycle_counter, query, memory);
+    if B {
+        let (reads, writes, round_witness) = result.expect("must generate intermediate witness");
+
+        Some((reads, writes, P::CycleWitness::into_cycles_witness(round_witness)))
+    } else {
+        None
+    }
+}
+
+/// Precompiles supported by default together with the lowest 16 bits of their addresses.
+/// All the precompiles are pure function calls, non-revertable.
+fn default_precompiles<const B: bool>() -> [(u16, Box<dyn DynPrecompile<B>>); 11] {
+    // precompiles are zero-sized, so boxing them doesn't allocate
+    [
+        (
+            KECCAK256_ROUND_FUNCTION_PRECOMPILE_ADDRESS,
+            Box::new(keccak256::Keccak256Precompile::<B>),
+        ),
+        (SHA256_ROUND_FUNCTION_PRECOMPILE_ADDRESS, Box::new(sha256::Sha256Precompile::<B>)),
+        (
+            ECRECOVER_INNER_FUNCTION_PRECOMPILE_ADDRESS,
+            Box::new(ecrecover::ECRecoverPrecompile::<B>),
+        ),
+        (
+            RIPEMD160_ROUND_FUNCTION_PRECOMPILE_ADDRESS,
+            Box::new(ripemd160::Ripemd160Precompile::<B>),
+        ),
+        (IDENTITY_PRECOMPILE_ADDRESS, Box::new(identity::IdentityPrecompile::<B>)),
+        (MODEXP_PRECOMPILE_ADDRESS, Box::new(modexp::ModexpPrecompile::<B>)),
+        (ECADD_PRECOMPILE_ADDRESS, Box::new(ecadd::ECAddPrecompile::<B>)),
+        (ECMUL_PRECOMPILE_ADDRESS, Box::new(ecmul::ECMulPrecompile::<B>)),
+        (ECPAIRING_PRECOMPILE_ADDRESS, Box::new(ecpairing::ECPairingPrecompile::<B>)),
+        (BLAKE2F_PRECOMPILE_ADDRESS, Box::new(blake2f::Blake2fPrecompile::<B>)),
+        (
+            SECP256R1_VERIFY_PRECOMPILE_ADDRESS,
+            Box::new(secp256r1_verify::Secp256r1VerifyPrecompile::<B>),
+        ),
+    ]
+}
+
+fn address_low(query: &LogQuery) -> u16 {
+    u16::from_le_bytes([query.address.0[19], query.address.0[18]])
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct DefaultPrecompilesProcessor<const B: bool>;
 
@@ -29,87 +100,16 @@ impl<const B: bool> PrecompilesProcessor for DefaultPrecompilesProcessor<B> {
         query: LogQuery,
         memory: &mut M,
     ) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, PrecompileCyclesWitness)> {
-        let address_low = u16::from_le_bytes([query.address.0[19], query.address.0[18]]);
-        match address_low {
-            KECCAK256_ROUND_FUNCTION_PRECOMPILE_ADDRESS => {
-                // pure function call, non-revertable
-                if B {
-                    let (reads, writes, round_witness) =
-                        keccak256::keccak256_rounds_function::<M, B>(
-                            monotonic_cycle_counter,
-                            query,
-                            memory,
-                        )
-                        .expect("must generate intermediate witness");
-
-                    Some((reads, writes, PrecompileCyclesWitness::Keccak256(round_witness)))
-                } else {
-                    let _ = keccak256::keccak256_rounds_function::<M, B>(
-                        monotonic_cycle_counter,
-                        query,
-                        memory,
-                    );
-
-                    None
-                }
-            }
-            SHA256_ROUND_FUNCTION_PRECOMPILE_ADDRESS => {
-                // pure function call, non-revertable
-                if B {
-                    let (reads, writes, round_witness) = sha256::sha256_rounds_function::<M, B>(
-                        monotonic_cycle_counter,
-                        query,
-                        memory,
-                    )
-                    .expect("must generate intermediate witness");
-
-                    Some((reads, writes, PrecompileCyclesWitness::Sha256(round_witness)))
-                } else {
-                    let _ = sha256::sha256_rounds_function::<M, B>(
-                        monotonic_cycle_counter,
-                        query,
-                        memory,
-                    );
-
-                    None
-                }
-            }
-            ECRECOVER_INNER_FUNCTION_PRECOMPILE_ADDRESS => {
-                // pure function call, non-revertable
-                if B {
-                    let (reads, writes, round_witness) = ecrecover::ecrecover_function::<M, B>(
-                        monotonic_cycle_counter,
-                        query,
-                        memory,
-                    )
-                    .expect("must generate intermediate witness");
-
-                    Some((reads, writes, PrecompileCyclesWitness::ECRecover(round_witness)))
-                } else {
-                    let _ = ecrecover::ecrecover_function::<M, B>(
-                        monotonic_cycle_counter,
-                        query,
-                        memory,
-                    );
-
-                    None
-                }
-            }
-            _ => {
-                // it's formally allowed for purposes of ergs-burning
-                // by special contracts
-
-                None
-            } /* _ => {
-               *     unreachable!(
-               *         "Tried to call a precompile from address {:?}",
-               *         query.address
-               *     );
-               * } */
-        }
+        let address_low = address_low(&query);
+        // it's formally allowed to call unknown addresses for purposes of ergs-burning
+        // by special contracts
+        let (_, mut precompile) = default_precompiles::<B>()
+            .into_iter()
+            .find(|(address, _)| *address == address_low)?;
+        precompile.execute_dyn(monotonic_cycle_counter, query, memory)
     }
 
     fn finish_frame(&mut self, _panicked: bool) {
-        // there are no revertable precompile yes, so we are ok
+        // there are no revertable precompiles here, see `PrecompileRegistry` for them
     }
 }
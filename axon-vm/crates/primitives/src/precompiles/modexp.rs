@@ -0,0 +1,184 @@
+use alloy_primitives::U256;
+use num_bigint::BigUint;
+
+use super::*;
+
+/// Exponent bits processed by a single round for a single-word modulus. Every round does
+/// the same amount of work, so for longer moduli a round processes quadratically fewer bits.
+pub const EXPONENT_BITS_PER_ROUND: u64 = 256;
+
+/// Maximum length of an operand in words, i.e., 1024 bytes as in EIP-7823. Longer inputs are
+/// rejected, which bounds the number of rounds.
+pub const MAX_OPERAND_WORDS: u32 = 32;
+
+/// A round of the precompile. The first round carries the request and the reads, and the last
+/// round carries the writes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ModexpRoundWitness {
+    pub new_request: Option<LogQuery>,
+    pub reads: Vec<MemoryQuery>,
+    pub writes: Vec<MemoryQuery>,
+}
+
+/// Lengths of the modexp operands in words.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ModexpLengths {
+    pub base: u32,
+    pub exponent: u32,
+    pub modulus: u32,
+}
+
+impl ModexpLengths {
+    /// Unpacks lengths of the operands from `precompile_interpreted_data`: the base length
+    /// goes to the lowest 16 bits, followed by the exponent and the modulus lengths.
+    pub const fn from_interpreted_data(data: u64) -> Self {
+        Self {
+            base: (data & 0xffff) as u32,
+            exponent: ((data >> 16) & 0xffff) as u32,
+            modulus: ((data >> 32) & 0xffff) as u32,
+        }
+    }
+
+    pub const fn to_interpreted_data(self) -> u64 {
+        (self.base as u64) | ((self.exponent as u64) << 16) | ((self.modulus as u64) << 32)
+    }
+
+    /// Checks that none of the operands is longer than [`MAX_OPERAND_WORDS`].
+    pub const fn is_supported(self) -> bool {
+        self.base <= MAX_OPERAND_WORDS
+            && self.exponent <= MAX_OPERAND_WORDS
+            && self.modulus <= MAX_OPERAND_WORDS
+    }
+}
+
+/// Modular exponentiation as specified by EIP-198.
+///
+/// Unlike in EIP-198, the operand lengths are passed in words via
+/// `precompile_interpreted_data` (see [`ModexpLengths`]) and every operand is a big-endian
+/// number occupying its words. The base, the exponent and the modulus are read as consecutive
+/// words and `base ** exponent % modulus` is written into the modulus length words. The
+/// result is zero if the modulus is zero, and nothing is written if the modulus length is zero.
+/// If any of the operands is longer than [`MAX_OPERAND_WORDS`], the input is rejected: nothing
+/// is read or written and the precompile takes a single round.
+///
+/// The number of rounds is proportional to the work needed for the exponentiation,
+/// see [`EXPONENT_BITS_PER_ROUND`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ModexpPrecompile<const B: bool>;
+
+impl<const B: bool> Precompile for ModexpPrecompile<B> {
+    type CycleWitness = ModexpRoundWitness;
+
+    fn execute_precompile<M: Memory>(
+        &mut self,
+        monotonic_cycle_counter: u32,
+        query: LogQuery,
+        memory: &mut M,
+    ) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, Vec<Self::CycleWitness>)> {
+        let precompile_call_params = query;
+        let params = precompile_abi_in_log(precompile_call_params);
+        let timestamp_to_read = precompile_call_params.timestamp;
+        let timestamp_to_write = Timestamp(timestamp_to_read.0 + 1); // our default timestamping agreement
+
+        let mut lengths = ModexpLengths::from_interpreted_data(params.precompile_interpreted_data);
+        if !lengths.is_supported() {
+            lengths = ModexpLengths { base: 0, exponent: 0, modulus: 0 };
+        }
+
+        let mut read_location = MemoryLocation {
+            memory_type: MemoryType::Heap, /* we default for some value, here it's not that
+                                            * important */
+            page: MemoryPage(params.memory_page_to_read),
+            index: MemoryIndex(params.input_memory_offset),
+        };
+
+        let num_reads = (lengths.base + lengths.exponent + lengths.modulus) as usize;
+        let mut reads = Vec::with_capacity(num_reads);
+        let mut read_number = |len: u32| {
+            let mut bytes = Vec::with_capacity(len as usize * 32);
+            for _ in 0..len {
+                let read_query = MemoryQuery {
+                    timestamp: timestamp_to_read,
+                    location: read_location,
+                    value: U256::ZERO,
+                    value_is_pointer: false,
+                    rw_flag: false,
+                };
+                let read_query = memory.execute_partial_query(monotonic_cycle_counter, read_query);
+                read_location.index.0 += 1;
+                bytes.extend_from_slice(&read_query.value.to_be_bytes::<32>());
+                reads.push(read_query);
+            }
+            BigUint::from_bytes_be(&bytes)
+        };
+        let base = read_number(lengths.base);
+        let exponent = read_number(lengths.exponent);
+        let modulus = read_number(lengths.modulus);
+
+        let output_len = lengths.modulus as usize * 32;
+        let mut output = vec![0u8; output_len];
+        // the output is empty for the zero modulus length, so there is nothing to compute
+        if lengths.modulus > 0 {
+            let result_bytes = modexp_inner(&base, &exponent, &modulus).to_bytes_be();
+            output[(output_len - result_bytes.len())..].copy_from_slice(&result_bytes);
+        }
+
+        let mut write_location = MemoryLocation {
+            memory_type: MemoryType::Heap, /* we default for some value, here it's not that
+                                            * important */
+            page: MemoryPage(params.memory_page_to_write),
+            index: MemoryIndex(params.output_memory_offset),
+        };
+        let mut writes = Vec::with_capacity(lengths.modulus as usize);
+        for word in output.chunks_exact(32) {
+            let write_query = MemoryQuery {
+                timestamp: timestamp_to_write,
+                location: write_location,
+                value: U256::from_be_slice(word),
+                value_is_pointer: false,
+                rw_flag: true,
+            };
+            let write_query = memory.execute_partial_query(monotonic_cycle_counter, write_query);
+            write_location.index.0 += 1;
+            writes.push(write_query);
+        }
+
+        if B {
+            let num_rounds = num_rounds(&exponent, lengths);
+            let empty_round =
+                ModexpRoundWitness { new_request: None, reads: vec![], writes: vec![] };
+            let mut witness = vec![empty_round; num_rounds];
+            witness[0].new_request = Some(precompile_call_params);
+            witness[0].reads = reads.clone();
+            witness[num_rounds - 1].writes = writes.clone();
+            Some((reads, writes, witness))
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the number of rounds needed to raise to the `exponent` modulo a number
+/// of `lengths.modulus` words. There is at least one round, which reads the input
+/// and writes the output. The lengths must be [supported](ModexpLengths::is_supported).
+pub fn num_rounds(exponent: &BigUint, lengths: ModexpLengths) -> usize {
+    let work = exponent.bits() * u64::from(lengths.modulus).pow(2);
+    work.div_ceil(EXPONENT_BITS_PER_ROUND).max(1) as usize
+}
+
+/// Computes `base ** exponent % modulus`, returning zero for the zero modulus.
+pub fn modexp_inner(base: &BigUint, exponent: &BigUint, modulus: &BigUint) -> BigUint {
+    if modulus.bits() == 0 {
+        return BigUint::default();
+    }
+    base.modpow(exponent, modulus)
+}
+
+pub fn modexp_function<M: Memory, const B: bool>(
+    monotonic_cycle_counter: u32,
+    precompile_call_params: LogQuery,
+    memory: &mut M,
+) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, Vec<ModexpRoundWitness>)> {
+    let mut processor = ModexpPrecompile::<B>;
+    processor.execute_precompile(monotonic_cycle_counter, precompile_call_params, memory)
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use super::*;
+
+/// Object-safe counterpart of [`Precompile`] that is implemented for every precompile with
+/// a witness convertible into [`PrecompileCyclesWitness`]. If `B` is set, the precompile must
+/// generate the witness.
+pub trait DynPrecompile<const B: bool>: std::fmt::Debug {
+    fn execute_dyn(
+        &mut self,
+        monotonic_cycle_counter: u32,
+        query: LogQuery,
+        memory: &mut dyn Memory,
+    ) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, PrecompileCyclesWitness)>;
+
+    fn is_revertable(&self) -> bool {
+        false
+    }
+
+    fn rollback_dyn(&mut self, _query: LogQuery) {
+        unreachable!("pure precompiles have nothing to rollback")
+    }
+}
+
+impl<P, const B: bool> DynPrecompile<B> for P
+where
+    P: Precompile,
+    P::CycleWitness: IntoCyclesWitness,
+{
+    fn execute_dyn(
+        &mut self,
+        monotonic_cycle_counter: u32,
+        query: LogQuery,
+        mut memory: &mut dyn Memory,
+    ) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, PrecompileCyclesWitness)> {
+        execute_pure_precompile::<_, _, B>(self, monotonic_cycle_counter, query, &mut memory)
+    }
+}
+
+#[derive(Debug)]
+struct RevertableWrapper<P>(P);
+
+impl<P, const B: bool> DynPrecompile<B> for RevertableWrapper<P>
+where
+    P: RevertablePrecompile,
+    P::CycleWitness: IntoCyclesWitness,
+{
+    fn execute_dyn(
+        &mut self,
+        monotonic_cycle_counter: u32,
+        query: LogQuery,
+        memory: &mut dyn Memory,
+    ) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, PrecompileCyclesWitness)> {
+        DynPrecompile::<B>::execute_dyn(&mut self.0, monotonic_cycle_counter, query, memory)
+    }
+
+    fn is_revertable(&self) -> bool {
+        true
+    }
+
+    fn rollback_dyn(&mut self, query: LogQuery) {
+        self.0.rollback(query)
+    }
+}
+
+/// Calls to the revertable precompiles made in the frame.
+#[derive(Clone, Debug, Default)]
+struct FrameHistory {
+    forward: Vec<LogQuery>,
+    rollbacks: Vec<LogQuery>,
+}
+
+/// Precompiles processor dispatching calls to the precompiles registered by their addresses.
+/// Calls to unregistered addresses are no-op, as in [`DefaultPrecompilesProcessor`]. If `B`
+/// is set, the registered precompiles must generate the witness.
+#[derive(Debug)]
+pub struct PrecompileRegistry<const B: bool> {
+    precompiles: HashMap<u16, Box<dyn DynPrecompile<B>>>,
+    frames_stack: Vec<FrameHistory>,
+}
+
+// as in the event sink, if we rollback the current frame then we apply rollbacks to the
+// precompiles immediately, otherwise we carry them to the parent's frame
+
+impl<const B: bool> Default for PrecompileRegistry<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const B: bool> PrecompileRegistry<B> {
+    pub fn new() -> Self {
+        Self {
+            precompiles: HashMap::new(),
+            // we add single frame that will serve as a last one
+            frames_stack: vec![FrameHistory::default()],
+        }
+    }
+
+    /// Creates a registry with all the precompiles supported by [`DefaultPrecompilesProcessor`].
+    pub fn with_default_precompiles() -> Self {
+        let mut registry = Self::new();
+        registry.precompiles.extend(default_precompiles::<B>());
+        registry
+    }
+
+    /// Registers the precompile at the lowest 16 bits of the address, returning the previously
+    /// registered one if any.
+    pub fn register<P: DynPrecompile<B> + 'static>(
+        &mut self,
+        address: u16,
+        precompile: P,
+    ) -> Option<Box<dyn DynPrecompile<B>>> {
+        self.precompiles.insert(address, Box::new(precompile))
+    }
+
+    /// Registers the precompile which calls are rolled back if the calling frame panics.
+    pub fn register_revertable<P>(
+        &mut self,
+        address: u16,
+        precompile: P,
+    ) -> Option<Box<dyn DynPrecompile<B>>>
+    where
+        P: RevertablePrecompile + 'static,
+        P::CycleWitness: IntoCyclesWitness,
+    {
+        self.register(address, RevertableWrapper(precompile))
+    }
+
+    pub fn unregister(&mut self, address: u16) -> Option<Box<dyn DynPrecompile<B>>> {
+        self.precompiles.remove(&address)
+    }
+
+    pub fn is_registered(&self, address: u16) -> bool {
+        self.precompiles.contains_key(&address)
+    }
+
+    /// Full history of the calls to the revertable precompiles, including rollbacks, that
+    /// is final once all the frames are finished.
+    pub fn revertable_calls_history(&self) -> &[LogQuery] {
+        &self.frames_stack[0].forward
+    }
+}
+
+impl<const B: bool> PrecompilesProcessor for PrecompileRegistry<B> {
+    fn start_frame(&mut self) {
+        self.frames_stack.push(FrameHistory::default());
+    }
+
+    fn execute_precompile<M: Memory>(
+        &mut self,
+        monotonic_cycle_counter: u32,
+        query: LogQuery,
+        memory: &mut M,
+    ) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, PrecompileCyclesWitness)> {
+        // it's formally allowed to call unknown addresses for purposes of ergs-burning
+        // by special contracts
+        let precompile = self.precompiles.get_mut(&address_low(&query))?;
+        if precompile.is_revertable() {
+            let frame = self.frames_stack.last_mut().expect("frame must be started");
+            frame.forward.push(query);
+            frame.rollbacks.push(LogQuery { rollback: true, ..query });
+        }
+
+        precompile.execute_dyn(monotonic_cycle_counter, query, memory)
+    }
+
+    fn finish_frame(&mut self, panicked: bool) {
+        // if we panic then we rollback the calls and append forward and rollbacks to the
+        // forward of parent, otherwise we carry rollbacks to the parent
+        let FrameHistory { forward, rollbacks } = self
+            .frames_stack
+            .pop()
+            .expect("frame must be started before finishing");
+        let parent = self
+            .frames_stack
+            .last_mut()
+            .expect("parent frame must exist");
+        parent.forward.extend(forward);
+        if panicked {
+            for query in rollbacks.into_iter().rev() {
+                self.precompiles
+                    .get_mut(&address_low(&query))
+                    .expect("revertable precompile must not be unregistered")
+                    .rollback_dyn(query);
+                parent.forward.push(query);
+            }
+        } else {
+            parent.rollbacks.extend(rollbacks);
+        }
+    }
+
+    fn is_revertable_precompile_call(&self, query: &LogQuery) -> bool {
+        self.precompiles
+            .get(&address_low(query))
+            .is_some_and(|precompile| precompile.is_revertable())
+    }
+}
@@ -0,0 +1,232 @@
+use alloy_primitives::U256;
+
+use super::*;
+use crate::{
+    queries::MemoryQuery,
+    vm::{Memory, MemoryType, Precompile},
+};
+
+// like sha256, ripemd160 consumes 64 bytes per round, and this is divisible by our 32 byte
+// per query. The input is expected to be already padded by the caller
+
+pub const MEMORY_READS_PER_CYCLE: usize = 2;
+pub const MEMORY_WRITES_PER_CYCLE: usize = 1;
+
+pub type Ripemd160InnerState = [u32; 5];
+
+pub const RIPEMD160_INITIAL_STATE: Ripemd160InnerState =
+    [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476, 0xc3d2_e1f0];
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Ripemd160RoundWitness {
+    pub new_request: Option<LogQuery>,
+    pub reads: [MemoryQuery; MEMORY_READS_PER_CYCLE],
+    pub writes: Option<[MemoryQuery; MEMORY_WRITES_PER_CYCLE]>,
+}
+
+/// RIPEMD-160 round function over the input that is already padded by the caller. The number
+/// of 64 byte blocks is passed via `precompile_interpreted_data`, and the resulting 20 byte
+/// digest is written right-aligned into a single word.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Ripemd160Precompile<const B: bool>;
+
+impl<const B: bool> Precompile for Ripemd160Precompile<B> {
+    type CycleWitness = Ripemd160RoundWitness;
+
+    fn execute_precompile<M: Memory>(
+        &mut self,
+        monotonic_cycle_counter: u32,
+        query: LogQuery,
+        memory: &mut M,
+    ) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, Vec<Self::CycleWitness>)> {
+        let precompile_call_params = query;
+        let params = precompile_abi_in_log(precompile_call_params);
+        let timestamp_to_read = precompile_call_params.timestamp;
+        let timestamp_to_write = Timestamp(timestamp_to_read.0 + 1); // our default timestamping agreement
+
+        let num_rounds = params.precompile_interpreted_data as usize;
+        let source_memory_page = params.memory_page_to_read;
+        let destination_memory_page = params.memory_page_to_write;
+        let mut current_read_offset = params.input_memory_offset;
+        let write_offset = params.output_memory_offset;
+
+        let mut read_queries =
+            if B { Vec::with_capacity(MEMORY_READS_PER_CYCLE * num_rounds) } else { vec![] };
+
+        let mut write_queries =
+            if B { Vec::with_capacity(MEMORY_WRITES_PER_CYCLE) } else { vec![] };
+
+        let mut witness = if B { Vec::with_capacity(num_rounds) } else { vec![] };
+
+        let mut internal_state = RIPEMD160_INITIAL_STATE;
+        for round in 0..num_rounds {
+            let mut block = [0u8; 64];
+
+            let mut reads = [MemoryQuery::empty(); MEMORY_READS_PER_CYCLE];
+            for (query_index, read) in reads.iter_mut().enumerate() {
+                let query = MemoryQuery {
+                    timestamp: timestamp_to_read,
+                    location: MemoryLocation {
+                        memory_type: MemoryType::Heap,
+                        page: MemoryPage(source_memory_page),
+                        index: MemoryIndex(current_read_offset),
+                    },
+                    value: U256::ZERO,
+                    value_is_pointer: false,
+                    rw_flag: false,
+                };
+
+                let query = memory.execute_partial_query(monotonic_cycle_counter, query);
+                current_read_offset += 1;
+                if B {
+                    read_queries.push(query);
+                }
+
+                *read = query;
+                block[(query_index * 32)..(query_index * 32 + 32)]
+                    .copy_from_slice(&query.value.to_be_bytes::<32>());
+            }
+
+            // run round function
+            ripemd160_compress(&mut internal_state, &block);
+
+            let is_last = round == num_rounds - 1;
+
+            let mut round_witness =
+                Ripemd160RoundWitness { new_request: None, reads, writes: None };
+
+            if round == 0 {
+                round_witness.new_request = Some(precompile_call_params);
+            }
+
+            if is_last {
+                // the 20 byte hash is the state in little endian, and is right-aligned in
+                // the output word as in Cortex
+                let mut hash_as_bytes32 = [0u8; 32];
+                for (chunk, state_word) in hash_as_bytes32[12..].chunks_mut(4).zip(internal_state) {
+                    chunk.copy_from_slice(&state_word.to_le_bytes());
+                }
+                let as_u256 = U256::from_be_bytes(hash_as_bytes32);
+
+                let write_location = MemoryLocation {
+                    memory_type: MemoryType::Heap, /* we default for some value, here it's not
+                                                    * that important */
+                    page: MemoryPage(destination_memory_page),
+                    index: MemoryIndex(write_offset),
+                };
+
+                let result_query = MemoryQuery {
+                    timestamp: timestamp_to_write,
+                    location: write_location,
+                    value: as_u256,
+                    value_is_pointer: false,
+                    rw_flag: true,
+                };
+                let result_query =
+                    memory.execute_partial_query(monotonic_cycle_counter, result_query);
+                round_witness.writes = Some([result_query]);
+
+                if B {
+                    write_queries.push(result_query);
+                }
+            }
+
+            if B {
+                witness.push(round_witness);
+            }
+        }
+
+        if B { Some((read_queries, write_queries, witness)) } else { None }
+    }
+}
+
+pub fn ripemd160_rounds_function<M: Memory, const B: bool>(
+    monotonic_cycle_counter: u32,
+    precompile_call_params: LogQuery,
+    memory: &mut M,
+) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, Vec<Ripemd160RoundWitness>)> {
+    let mut processor = Ripemd160Precompile::<B>;
+    processor.execute_precompile(monotonic_cycle_counter, precompile_call_params, memory)
+}
+
+// message word selection for the left and right lines
+const R_LEFT: [usize; 80] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, //
+    7, 4, 13, 1, 10, 6, 15, 3, 12, 0, 9, 5, 2, 14, 11, 8, //
+    3, 10, 14, 4, 9, 15, 8, 1, 2, 7, 0, 6, 13, 11, 5, 12, //
+    1, 9, 11, 10, 0, 8, 12, 4, 13, 3, 7, 15, 14, 5, 6, 2, //
+    4, 0, 5, 9, 7, 12, 2, 10, 14, 1, 3, 8, 11, 6, 15, 13,
+];
+const R_RIGHT: [usize; 80] = [
+    5, 14, 7, 0, 9, 2, 11, 4, 13, 6, 15, 8, 1, 10, 3, 12, //
+    6, 11, 3, 7, 0, 13, 5, 10, 14, 15, 8, 12, 4, 9, 1, 2, //
+    15, 5, 1, 3, 7, 14, 6, 9, 11, 8, 12, 2, 10, 0, 4, 13, //
+    8, 6, 4, 1, 3, 11, 15, 0, 5, 12, 2, 13, 9, 7, 10, 14, //
+    12, 15, 10, 4, 1, 5, 8, 7, 6, 2, 13, 14, 0, 3, 9, 11,
+];
+// rotation amounts for the left and right lines
+const S_LEFT: [u32; 80] = [
+    11, 14, 15, 12, 5, 8, 7, 9, 11, 13, 14, 15, 6, 7, 9, 8, //
+    7, 6, 8, 13, 11, 9, 7, 15, 7, 12, 15, 9, 11, 7, 13, 12, //
+    11, 13, 6, 7, 14, 9, 13, 15, 14, 8, 13, 6, 5, 12, 7, 5, //
+    11, 12, 14, 15, 14, 15, 9, 8, 9, 14, 5, 6, 8, 6, 5, 12, //
+    9, 15, 5, 11, 6, 8, 13, 12, 5, 12, 13, 14, 11, 8, 5, 6,
+];
+const S_RIGHT: [u32; 80] = [
+    8, 9, 9, 11, 13, 15, 15, 5, 7, 7, 8, 11, 14, 14, 12, 6, //
+    9, 13, 15, 7, 12, 8, 9, 11, 7, 7, 12, 7, 6, 15, 13, 11, //
+    9, 7, 15, 11, 8, 6, 6, 14, 12, 13, 5, 14, 13, 13, 7, 5, //
+    15, 5, 8, 11, 14, 14, 6, 14, 6, 9, 12, 9, 12, 5, 15, 8, //
+    8, 5, 12, 9, 12, 5, 14, 6, 8, 13, 6, 5, 15, 13, 11, 11,
+];
+const K_LEFT: [u32; 5] = [0x0000_0000, 0x5a82_7999, 0x6ed9_eba1, 0x8f1b_bcdc, 0xa953_fd4e];
+const K_RIGHT: [u32; 5] = [0x50a2_8be6, 0x5c4d_d124, 0x6d70_3ef3, 0x7a6d_76e9, 0x0000_0000];
+
+fn boolean_function(round: usize, x: u32, y: u32, z: u32) -> u32 {
+    match round {
+        0 => x ^ y ^ z,
+        1 => (x & y) | (!x & z),
+        2 => (x | !y) ^ z,
+        3 => (x & z) | (y & !z),
+        4 => x ^ (y | !z),
+        _ => unreachable!(),
+    }
+}
+
+/// RIPEMD-160 compression function applied to a single 64 byte block.
+pub fn ripemd160_compress(state: &mut Ripemd160InnerState, block: &[u8; 64]) {
+    let mut words = [0u32; 16];
+    for (word, chunk) in words.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_le_bytes(chunk.try_into().unwrap());
+    }
+
+    let [mut al, mut bl, mut cl, mut dl, mut el] = *state;
+    let [mut ar, mut br, mut cr, mut dr, mut er] = *state;
+    for step in 0..80 {
+        let round = step / 16;
+
+        let t = al
+            .wrapping_add(boolean_function(round, bl, cl, dl))
+            .wrapping_add(words[R_LEFT[step]])
+            .wrapping_add(K_LEFT[round])
+            .rotate_left(S_LEFT[step])
+            .wrapping_add(el);
+        (al, el, dl, cl, bl) = (el, dl, cl.rotate_left(10), bl, t);
+
+        // the right line applies boolean functions in the reverse order
+        let t = ar
+            .wrapping_add(boolean_function(4 - round, br, cr, dr))
+            .wrapping_add(words[R_RIGHT[step]])
+            .wrapping_add(K_RIGHT[round])
+            .rotate_left(S_RIGHT[step])
+            .wrapping_add(er);
+        (ar, er, dr, cr, br) = (er, dr, cr.rotate_left(10), br, t);
+    }
+
+    let t = state[1].wrapping_add(cl).wrapping_add(dr);
+    state[1] = state[2].wrapping_add(dl).wrapping_add(er);
+    state[2] = state[3].wrapping_add(el).wrapping_add(ar);
+    state[3] = state[4].wrapping_add(al).wrapping_add(br);
+    state[4] = state[0].wrapping_add(bl).wrapping_add(cr);
+    state[0] = t;
+}
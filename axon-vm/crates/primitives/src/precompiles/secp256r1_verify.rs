@@ -0,0 +1,150 @@
+use alloy_primitives::U256;
+use p256::{
+    ecdsa::{signature::hazmat::PrehashVerifier, Signature, VerifyingKey},
+    EncodedPoint,
+};
+
+use super::*;
+
+// we need hash, r, s, x, y
+pub const MEMORY_READS_PER_CYCLE: usize = 5;
+pub const MEMORY_WRITES_PER_CYCLE: usize = 2;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Secp256r1VerifyRoundWitness {
+    pub new_request: LogQuery,
+    pub reads: [MemoryQuery; MEMORY_READS_PER_CYCLE],
+    pub writes: [MemoryQuery; MEMORY_WRITES_PER_CYCLE],
+}
+
+/// Verification of secp256r1 (P-256) signatures over a prehashed message as specified
+/// by RIP-7212.
+///
+/// The precompile reads the message hash, `r`, `s` and the public key coordinates `x`, `y`
+/// as consecutive words and writes two words: the marker whether the input is well-formed
+/// (i.e., `r` and `s` are non-zero scalars and the public key is a point on the curve)
+/// and the verification result (`1` for a valid signature, `0` otherwise).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Secp256r1VerifyPrecompile<const B: bool>;
+
+impl<const B: bool> Precompile for Secp256r1VerifyPrecompile<B> {
+    type CycleWitness = Secp256r1VerifyRoundWitness;
+
+    fn execute_precompile<M: Memory>(
+        &mut self,
+        monotonic_cycle_counter: u32,
+        query: LogQuery,
+        memory: &mut M,
+    ) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, Vec<Self::CycleWitness>)> {
+        // read the parameters
+        let precompile_call_params = query;
+        let params = precompile_abi_in_log(precompile_call_params);
+        let timestamp_to_read = precompile_call_params.timestamp;
+        let timestamp_to_write = Timestamp(timestamp_to_read.0 + 1); // our default timestamping agreement
+
+        let mut current_read_location = MemoryLocation {
+            memory_type: MemoryType::Heap, /* we default for some value, here it's not that
+                                            * important */
+            page: MemoryPage(params.memory_page_to_read),
+            index: MemoryIndex(params.input_memory_offset),
+        };
+
+        // we do 7 queries per precompile
+        let mut read_history = if B { Vec::with_capacity(MEMORY_READS_PER_CYCLE) } else { vec![] };
+        let mut write_history =
+            if B { Vec::with_capacity(MEMORY_WRITES_PER_CYCLE) } else { vec![] };
+
+        let mut round_witness = Secp256r1VerifyRoundWitness {
+            new_request: precompile_call_params,
+            reads: [MemoryQuery::empty(); MEMORY_READS_PER_CYCLE],
+            writes: [MemoryQuery::empty(); MEMORY_WRITES_PER_CYCLE],
+        };
+
+        // hash, r, s, x, y
+        let mut values = [U256::ZERO; MEMORY_READS_PER_CYCLE];
+        for (read_idx, value) in values.iter_mut().enumerate() {
+            let read_query = MemoryQuery {
+                timestamp: timestamp_to_read,
+                location: current_read_location,
+                value: U256::ZERO,
+                value_is_pointer: false,
+                rw_flag: false,
+            };
+            let read_query = memory.execute_partial_query(monotonic_cycle_counter, read_query);
+            *value = read_query.value;
+            if B {
+                round_witness.reads[read_idx] = read_query;
+                read_history.push(read_query);
+            }
+            current_read_location.index.0 += 1;
+        }
+
+        let [hash, r, s, x, y] = values.map(|value| value.to_be_bytes::<32>());
+        let (ok_marker, result) = match secp256r1_verify_inner(&hash, &r, &s, &x, &y) {
+            Ok(is_valid) => (U256::from(1), U256::from(is_valid as u64)),
+            Err(()) => (U256::ZERO, U256::ZERO),
+        };
+
+        let mut write_location = MemoryLocation {
+            memory_type: MemoryType::Heap, /* we default for some value, here it's not that
+                                            * important */
+            page: MemoryPage(params.memory_page_to_write),
+            index: MemoryIndex(params.output_memory_offset),
+        };
+
+        let ok_or_err_query = MemoryQuery {
+            timestamp: timestamp_to_write,
+            location: write_location,
+            value: ok_marker,
+            value_is_pointer: false,
+            rw_flag: true,
+        };
+        let ok_or_err_query =
+            memory.execute_partial_query(monotonic_cycle_counter, ok_or_err_query);
+
+        write_location.index.0 += 1;
+        let result_query = MemoryQuery {
+            timestamp: timestamp_to_write,
+            location: write_location,
+            value: result,
+            value_is_pointer: false,
+            rw_flag: true,
+        };
+        let result_query = memory.execute_partial_query(monotonic_cycle_counter, result_query);
+
+        if B {
+            round_witness.writes[0] = ok_or_err_query;
+            round_witness.writes[1] = result_query;
+            write_history.push(ok_or_err_query);
+            write_history.push(result_query);
+        }
+
+        if B { Some((read_history, write_history, vec![round_witness])) } else { None }
+    }
+}
+
+/// Verifies the signature `(r, s)` of the message `digest` against the public key `(x, y)`.
+/// Returns an error if the signature or the public key are malformed.
+#[allow(clippy::result_unit_err)]
+pub fn secp256r1_verify_inner(
+    digest: &[u8; 32],
+    r: &[u8; 32],
+    s: &[u8; 32],
+    x: &[u8; 32],
+    y: &[u8; 32],
+) -> Result<bool, ()> {
+    let signature = Signature::from_scalars(*r, *s).map_err(|_| ())?;
+    let encoded_point = EncodedPoint::from_affine_coordinates(x.into(), y.into(), false);
+    let verifying_key = VerifyingKey::from_encoded_point(&encoded_point).map_err(|_| ())?;
+
+    Ok(verifying_key.verify_prehash(digest, &signature).is_ok())
+}
+
+pub fn secp256r1_verify_function<M: Memory, const B: bool>(
+    monotonic_cycle_counter: u32,
+    precompile_call_params: LogQuery,
+    memory: &mut M,
+) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, Vec<Secp256r1VerifyRoundWitness>)> {
+    let mut processor = Secp256r1VerifyPrecompile::<B>;
+    processor.execute_precompile(monotonic_cycle_counter, precompile_call_params, memory)
+}
@@ -1,10 +1,22 @@
+use std::any::Any;
+
 use alloy_primitives::U256;
 use zkvm_opcodes::FatPointer;
 
 use crate::{
-    aux::{MemoryPage, Timestamp},
+    aux::{MemoryPage, SnapshotId, Timestamp},
     precompiles::{
-        ecrecover::ECRecoverPrecompile, keccak256::Keccak256Precompile, sha256::Sha256Precompile,
+        blake2f::{Blake2fPrecompile, Blake2fRoundWitness},
+        ecadd::{ECAddPrecompile, ECAddRoundWitness},
+        ecmul::{ECMulPrecompile, ECMulRoundWitness},
+        ecpairing::{ECPairingPrecompile, ECPairingRoundWitness},
+        ecrecover::{ECRecoverPrecompile, ECRecoverRoundWitness},
+        identity::{IdentityPrecompile, IdentityRoundWitness},
+        keccak256::{Keccak256Precompile, Keccak256RoundWitness},
+        modexp::{ModexpPrecompile, ModexpRoundWitness},
+        ripemd160::{Ripemd160Precompile, Ripemd160RoundWitness},
+        secp256r1_verify::{Secp256r1VerifyPrecompile, Secp256r1VerifyRoundWitness},
+        sha256::{Sha256Precompile, Sha256RoundWitness},
     },
     queries::{DecommittmentQuery, LogQuery, MemoryQuery},
 };
@@ -61,14 +73,120 @@ pub enum PrecompileCyclesWitness {
     Sha256(Vec<<Sha256Precompile<true> as Precompile>::CycleWitness>),
     Keccak256(Vec<<Keccak256Precompile<true> as Precompile>::CycleWitness>),
     ECRecover(Vec<<ECRecoverPrecompile<true> as Precompile>::CycleWitness>),
+    Ripemd160(Vec<<Ripemd160Precompile<true> as Precompile>::CycleWitness>),
+    Identity(Vec<<IdentityPrecompile<true> as Precompile>::CycleWitness>),
+    Modexp(Vec<<ModexpPrecompile<true> as Precompile>::CycleWitness>),
+    ECAdd(Vec<<ECAddPrecompile<true> as Precompile>::CycleWitness>),
+    ECMul(Vec<<ECMulPrecompile<true> as Precompile>::CycleWitness>),
+    ECPairing(Vec<<ECPairingPrecompile<true> as Precompile>::CycleWitness>),
+    Blake2f(Vec<<Blake2fPrecompile<true> as Precompile>::CycleWitness>),
+    Secp256r1Verify(Vec<<Secp256r1VerifyPrecompile<true> as Precompile>::CycleWitness>),
+    // precompiles registered outside of this crate
+    Erased(ErasedCyclesWitness),
+}
+
+/// Type-erased cycle witness of a precompile that is not enumerated in
+/// [`PrecompileCyclesWitness`]. Holds the `Vec` of cycle witnesses produced by the precompile.
+pub struct ErasedCyclesWitness(Box<dyn Any + Send>);
+
+impl ErasedCyclesWitness {
+    pub fn new<W: Any + Send>(witness: Vec<W>) -> Self {
+        Self(Box::new(witness))
+    }
+
+    pub fn is<W: Any>(&self) -> bool {
+        self.0.is::<Vec<W>>()
+    }
+
+    pub fn downcast_ref<W: Any>(&self) -> Option<&Vec<W>> {
+        self.0.downcast_ref()
+    }
+
+    /// Returns the witness back if it has a different type.
+    pub fn downcast<W: Any>(self) -> Result<Vec<W>, Self> {
+        self.0.downcast().map(|witness| *witness).map_err(Self)
+    }
+}
+
+/// Conversion of the precompile cycle witnesses into [`PrecompileCyclesWitness`]. By default
+/// the witness is type-erased, so implementing this trait for a custom witness is a one-liner.
+pub trait IntoCyclesWitness: Any + Send + Sized {
+    fn into_cycles_witness(witness: Vec<Self>) -> PrecompileCyclesWitness {
+        PrecompileCyclesWitness::Erased(ErasedCyclesWitness::new(witness))
+    }
+}
+
+impl IntoCyclesWitness for Sha256RoundWitness {
+    fn into_cycles_witness(witness: Vec<Self>) -> PrecompileCyclesWitness {
+        PrecompileCyclesWitness::Sha256(witness)
+    }
+}
+
+impl IntoCyclesWitness for Keccak256RoundWitness {
+    fn into_cycles_witness(witness: Vec<Self>) -> PrecompileCyclesWitness {
+        PrecompileCyclesWitness::Keccak256(witness)
+    }
+}
+
+impl IntoCyclesWitness for ECRecoverRoundWitness {
+    fn into_cycles_witness(witness: Vec<Self>) -> PrecompileCyclesWitness {
+        PrecompileCyclesWitness::ECRecover(witness)
+    }
+}
+
+impl IntoCyclesWitness for Ripemd160RoundWitness {
+    fn into_cycles_witness(witness: Vec<Self>) -> PrecompileCyclesWitness {
+        PrecompileCyclesWitness::Ripemd160(witness)
+    }
+}
+
+impl IntoCyclesWitness for IdentityRoundWitness {
+    fn into_cycles_witness(witness: Vec<Self>) -> PrecompileCyclesWitness {
+        PrecompileCyclesWitness::Identity(witness)
+    }
+}
+
+impl IntoCyclesWitness for ModexpRoundWitness {
+    fn into_cycles_witness(witness: Vec<Self>) -> PrecompileCyclesWitness {
+        PrecompileCyclesWitness::Modexp(witness)
+    }
+}
+
+impl IntoCyclesWitness for ECAddRoundWitness {
+    fn into_cycles_witness(witness: Vec<Self>) -> PrecompileCyclesWitness {
+        PrecompileCyclesWitness::ECAdd(witness)
+    }
+}
+
+impl IntoCyclesWitness for ECMulRoundWitness {
+    fn into_cycles_witness(witness: Vec<Self>) -> PrecompileCyclesWitness {
+        PrecompileCyclesWitness::ECMul(witness)
+    }
+}
+
+impl IntoCyclesWitness for ECPairingRoundWitness {
+    fn into_cycles_witness(witness: Vec<Self>) -> PrecompileCyclesWitness {
+        PrecompileCyclesWitness::ECPairing(witness)
+    }
+}
+
+impl IntoCyclesWitness for Blake2fRoundWitness {
+    fn into_cycles_witness(witness: Vec<Self>) -> PrecompileCyclesWitness {
+        PrecompileCyclesWitness::Blake2f(witness)
+    }
+}
+
+impl IntoCyclesWitness for Secp256r1VerifyRoundWitness {
+    fn into_cycles_witness(witness: Vec<Self>) -> PrecompileCyclesWitness {
+        PrecompileCyclesWitness::Secp256r1Verify(witness)
+    }
 }
 
 // ALL traits here are for execution and NOT for witness generation. They can depend on one another,
 // but should not have large interconnections.
 
-// Note: We may need to extend them to allow sequencer to easily perform decisions on whether or
-// not to accept a transaction (or revert to the previous state) and actually perform "huge"
-// rollbacks on on all the corresponding implementors
+// Note: sequencer can decide whether or not to accept a transaction after executing it, and
+// perform "huge" rollbacks of the storage and events using `make_snapshot` and `rollback_to`
 
 pub trait Storage: std::fmt::Debug {
     // We can evaluate a query cost (or more precisely - get expected refunds)
@@ -87,10 +205,15 @@ pub trait Storage: std::fmt::Debug {
     // Indicate that execution frame went out from the scope, so we can
     // log the history and either rollback immediately or keep records to rollback later
     fn finish_frame(&mut self, timestamp: Timestamp, panicked: bool);
-
-    // N.B. We may need to extend frame markers for e.g. special bootloader execution frame
-    // such that we can flush all the changes of particular transaction since bootloader doesn't
-    // rollback further (at least now)
+    // Remember the current state, so that all the changes made after this point (including the
+    // ones of successfully finished child frames) can be reverted, e.g. the ones of a particular
+    // transaction executed by bootloader. Snapshot is invalidated when the frame it was made
+    // in is finished
+    fn make_snapshot(&mut self) -> SnapshotId;
+    // Revert the changes made after the snapshot and erase them from the history, as if they never
+    // happened. Must be called in the same frame as the snapshot was made, and invalidates the
+    // snapshots that were made after it
+    fn rollback_to(&mut self, snapshot: SnapshotId);
 }
 
 pub trait Memory: std::fmt::Debug {
@@ -127,6 +250,53 @@ pub trait Memory: std::fmt::Debug {
     }
 }
 
+// allows to pass `&mut dyn Memory` where a generic memory is expected
+impl<M: Memory + ?Sized> Memory for &mut M {
+    fn execute_partial_query(
+        &mut self,
+        monotonic_cycle_counter: u32,
+        query: MemoryQuery,
+    ) -> MemoryQuery {
+        (**self).execute_partial_query(monotonic_cycle_counter, query)
+    }
+
+    fn specialized_code_query(
+        &mut self,
+        monotonic_cycle_counter: u32,
+        query: MemoryQuery,
+    ) -> MemoryQuery {
+        (**self).specialized_code_query(monotonic_cycle_counter, query)
+    }
+
+    fn read_code_query(&self, monotonic_cycle_counter: u32, query: MemoryQuery) -> MemoryQuery {
+        (**self).read_code_query(monotonic_cycle_counter, query)
+    }
+
+    fn start_global_frame(
+        &mut self,
+        current_base_page: MemoryPage,
+        new_base_page: MemoryPage,
+        calldata_fat_pointer: FatPointer,
+        timestamp: Timestamp,
+    ) {
+        (**self).start_global_frame(
+            current_base_page,
+            new_base_page,
+            calldata_fat_pointer,
+            timestamp,
+        )
+    }
+
+    fn finish_global_frame(
+        &mut self,
+        base_page: MemoryPage,
+        returndata_fat_pointer: FatPointer,
+        timestamp: Timestamp,
+    ) {
+        (**self).finish_global_frame(base_page, returndata_fat_pointer, timestamp)
+    }
+}
+
 impl Memory for () {
     fn execute_partial_query(
         &mut self,
@@ -155,6 +325,9 @@ pub trait EventSink: std::fmt::Debug {
     fn add_partial_query(&mut self, monotonic_cycle_counter: u32, query: LogQuery);
     fn start_frame(&mut self, timestamp: Timestamp);
     fn finish_frame(&mut self, panicked: bool, timestamp: Timestamp);
+    // Same as for the storage
+    fn make_snapshot(&mut self) -> SnapshotId;
+    fn rollback_to(&mut self, snapshot: SnapshotId);
 }
 
 pub trait PrecompilesProcessor: std::fmt::Debug {
@@ -170,6 +343,12 @@ pub trait PrecompilesProcessor: std::fmt::Debug {
     ) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, PrecompileCyclesWitness)>;
     fn start_frame(&mut self);
     fn finish_frame(&mut self, panicked: bool);
+
+    // Whether the effects of the call are rolled back if the current frame panics. Such calls
+    // are reported to the witness tracer
+    fn is_revertable_precompile_call(&self, _query: &LogQuery) -> bool {
+        false
+    }
 }
 
 pub trait DecommittmentProcessor: std::fmt::Debug {
@@ -204,6 +383,14 @@ pub trait Precompile: std::fmt::Debug {
     ) -> Option<(Vec<MemoryQuery>, Vec<MemoryQuery>, Vec<Self::CycleWitness>)>;
 }
 
+/// Precompile with an internal state, e.g. a randomness beacon. Effects of the calls made in
+/// a frame that panicked are reverted by calling [`RevertablePrecompile::rollback`] for them
+/// in the reverse order.
+pub trait RevertablePrecompile: Precompile {
+    /// Reverts the effects of the call. `query` is the original call query marked as rollback
+    fn rollback(&mut self, query: LogQuery);
+}
+
 pub enum SpongeExecutionMarker {
     MemoryQuery,
     DecommittmentQuery,
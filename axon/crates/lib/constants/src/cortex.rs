@@ -25,3 +25,26 @@ pub const MAX_GAS_PER_PUBDATA_BYTE: u64 = MAX_L2_TX_GAS_LIMIT / GUARANTEED_PUBDA
 
 // The L1->L2 are required to have the following gas per pubdata byte.
 pub const REQUIRED_L1_TO_L2_GAS_PER_PUBDATA_BYTE: u64 = 800;
+
+#[cfg(test)]
+mod tests {
+    use axon_primitives::chain_spec::ChainLimits;
+
+    use super::*;
+    use crate::{MAX_NEW_FACTORY_DEPS, MAX_TXS_IN_BLOCK};
+
+    #[test]
+    fn default_chain_limits_match_constants() {
+        let limits = ChainLimits::default();
+        assert_eq!(limits.max_l2_tx_gas_limit, MAX_L2_TX_GAS_LIMIT);
+        assert_eq!(limits.max_l1_tx_gas_limit, MAX_L1_TRANSACTION_GAS_LIMIT);
+        assert_eq!(
+            limits.guaranteed_pubdata_per_l1_batch,
+            GUARANTEED_PUBDATA_PER_L1_BATCH
+        );
+        assert_eq!(limits.max_pubdata_per_l1_batch, MAX_PUBDATA_PER_L1_BATCH);
+        assert_eq!(limits.max_txs_in_l1_batch, MAX_TXS_IN_BLOCK);
+        assert_eq!(limits.max_new_factory_deps, MAX_NEW_FACTORY_DEPS);
+        assert_eq!(limits.max_gas_per_pubdata_byte(), MAX_GAS_PER_PUBDATA_BYTE);
+    }
+}
@@ -0,0 +1,347 @@
+//! Fee calculator deriving transaction fee parameters from [`IntrinsicSystemGasConstants`].
+
+use axon_utils::bytecode::{compress_bytecode_if_profitable, InvalidBytecodeError};
+
+use super::{
+    get_intrinsic_constants, IntrinsicSystemGasConstants, BYTECODE_PUBLISHING_COSTS,
+    L1_GAS_PER_PUBDATA_BYTE, PUBLISH_BYTECODE_OVERHEAD,
+};
+use crate::{
+    MAX_GAS_PER_PUBDATA_BYTE, MAX_L2_TX_GAS_LIMIT, MAX_TXS_IN_BLOCK,
+    REQUIRED_L1_TO_L2_GAS_PER_PUBDATA_BYTE,
+};
+
+/// Number of transaction encoding bytes for which an L1 transaction is charged
+/// `l1_tx_delta_544_encoding_bytes` gas.
+const L1_TX_ENCODING_CHUNK_BYTES: u64 = 544;
+
+/// Origin of a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionKind {
+    /// Priority transaction submitted on L1.
+    L1,
+    /// Transaction submitted directly on L2.
+    L2,
+}
+
+/// Properties of a transaction affecting its fee.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionShape {
+    pub kind: TransactionKind,
+    /// Length of the transaction encoding in the bootloader memory, in bytes.
+    pub encoding_len: usize,
+    /// Number of factory dependencies (i.e., bytecodes) supplied with the transaction.
+    pub factory_deps: usize,
+    /// Pubdata published by the transaction on top of its intrinsic pubdata, in bytes.
+    pub pubdata_bytes: u64,
+}
+
+impl TransactionShape {
+    /// Creates a shape of an L2 transaction without factory deps or published pubdata.
+    pub fn l2(encoding_len: usize) -> Self {
+        Self {
+            kind: TransactionKind::L2,
+            encoding_len,
+            factory_deps: 0,
+            pubdata_bytes: 0,
+        }
+    }
+
+    /// Creates a shape of an L1 transaction without factory deps or published pubdata.
+    pub fn l1(encoding_len: usize) -> Self {
+        Self {
+            kind: TransactionKind::L1,
+            ..Self::l2(encoding_len)
+        }
+    }
+
+    /// Adds factory deps supplied with the transaction.
+    ///
+    /// Bytecodes of L2 transactions are published as pubdata, compressed if it's cheaper
+    /// according to [`BYTECODE_PUBLISHING_COSTS`], and each of them costs
+    /// [`PUBLISH_BYTECODE_OVERHEAD`] bytes on top of its published length. Bytecodes of L1
+    /// transactions are already available on L1, so they are only counted.
+    pub fn with_factory_deps(
+        mut self,
+        bytecodes: &[Vec<u8>],
+    ) -> Result<Self, InvalidBytecodeError> {
+        self.factory_deps += bytecodes.len();
+        if self.kind == TransactionKind::L1 {
+            return Ok(self);
+        }
+        for bytecode in bytecodes {
+            let compressed = compress_bytecode_if_profitable(bytecode, &BYTECODE_PUBLISHING_COSTS)?;
+            let published_len = compressed.map_or(bytecode.len(), |compressed| compressed.len());
+            self.pubdata_bytes = self
+                .pubdata_bytes
+                .saturating_add(u64::from(PUBLISH_BYTECODE_OVERHEAD) + published_len as u64);
+        }
+        Ok(self)
+    }
+}
+
+/// Calculator of transaction fees for the specified gas prices.
+///
+/// The batch overhead (i.e., the cost of running the bootloader and publishing its pubdata)
+/// is shared among transactions in the batch. Each transaction pays for the largest share
+/// of batch resources it can occupy: the gas limit relative to [`MAX_L2_TX_GAS_LIMIT`],
+/// the encoding length relative to the bootloader transaction memory, or a single transaction
+/// slot out of [`MAX_TXS_IN_BLOCK`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeCalculator {
+    constants: IntrinsicSystemGasConstants,
+    base_fee: u64,
+    gas_per_pubdata: u64,
+}
+
+impl FeeCalculator {
+    /// Creates a calculator using the intrinsic constants of the current protocol version.
+    /// Prices are specified in wei.
+    pub fn new(l1_gas_price: u64, fair_l2_gas_price: u64) -> Self {
+        Self::with_constants(get_intrinsic_constants(), l1_gas_price, fair_l2_gas_price)
+    }
+
+    /// Creates a calculator using the provided intrinsic constants.
+    ///
+    /// # Panics
+    ///
+    /// Panics if both gas prices are zero.
+    pub fn with_constants(
+        constants: IntrinsicSystemGasConstants,
+        l1_gas_price: u64,
+        fair_l2_gas_price: u64,
+    ) -> Self {
+        let price_per_pubdata_byte =
+            l1_gas_price.saturating_mul(u64::from(L1_GAS_PER_PUBDATA_BYTE));
+        // The base fee is raised if necessary so that gas per pubdata doesn't exceed the maximum
+        // value users can provide.
+        let base_fee =
+            fair_l2_gas_price.max(price_per_pubdata_byte.div_ceil(MAX_GAS_PER_PUBDATA_BYTE));
+        assert!(base_fee > 0, "gas prices must not be zero");
+        let gas_per_pubdata = price_per_pubdata_byte.div_ceil(base_fee);
+        Self {
+            constants,
+            base_fee,
+            gas_per_pubdata,
+        }
+    }
+
+    /// Returns the L2 base fee in wei.
+    pub fn base_fee(&self) -> u64 {
+        self.base_fee
+    }
+
+    /// Returns the amount of L2 gas charged for each published pubdata byte of L2 transactions.
+    pub fn gas_per_pubdata(&self) -> u64 {
+        self.gas_per_pubdata
+    }
+
+    fn gas_per_pubdata_for(&self, kind: TransactionKind) -> u64 {
+        match kind {
+            TransactionKind::L1 => REQUIRED_L1_TO_L2_GAS_PER_PUBDATA_BYTE,
+            TransactionKind::L2 => self.gas_per_pubdata,
+        }
+    }
+
+    /// Returns the gas overhead of processing a batch, which is shared among its transactions.
+    pub fn batch_overhead_gas(&self, kind: TransactionKind) -> u64 {
+        u64::from(self.constants.bootloader_intrinsic_gas)
+            + u64::from(self.constants.bootloader_intrinsic_pubdata)
+                * self.gas_per_pubdata_for(kind)
+    }
+
+    /// Returns the share of the batch overhead paid by a transaction with the specified shape
+    /// and gas limit. The share never exceeds the whole batch overhead.
+    pub fn batch_overhead_share(&self, tx: &TransactionShape, gas_limit: u64) -> u64 {
+        let batch_overhead = self.batch_overhead_gas(tx.kind);
+        // Gas limit beyond the maximum is assumed to be spent on publishing pubdata,
+        // so it doesn't occupy more of the batch.
+        let gas_limit = gas_limit.min(MAX_L2_TX_GAS_LIMIT);
+        let bootloader_tx_memory_bytes =
+            u64::from(self.constants.bootloader_tx_memory_size_slots) * 32;
+
+        let overhead_for_gas = gas_limit
+            .saturating_mul(batch_overhead)
+            .div_ceil(MAX_L2_TX_GAS_LIMIT);
+        let overhead_for_len = (tx.encoding_len as u64)
+            .saturating_mul(batch_overhead)
+            .div_ceil(bootloader_tx_memory_bytes);
+        let overhead_for_slot = batch_overhead.div_ceil(MAX_TXS_IN_BLOCK as u64);
+        // Transactions that don't fit into the bootloader memory are rejected anyway,
+        // so their share is capped as well.
+        overhead_for_gas
+            .max(overhead_for_len)
+            .max(overhead_for_slot)
+            .min(batch_overhead)
+    }
+
+    /// Returns the minimal gas needed to execute the transaction body, excluding the batch
+    /// overhead share.
+    pub fn min_body_gas(&self, tx: &TransactionShape) -> u64 {
+        let constants = &self.constants;
+        let gas_per_pubdata = self.gas_per_pubdata_for(tx.kind);
+        let factory_deps = tx.factory_deps as u64;
+        match tx.kind {
+            TransactionKind::L1 => {
+                let encoding_chunks = (tx.encoding_len as u64).div_ceil(L1_TX_ENCODING_CHUNK_BYTES);
+                let computation_gas = u64::from(constants.l1_tx_intrinsic_gas)
+                    + encoding_chunks * u64::from(constants.l1_tx_delta_544_encoding_bytes)
+                    + factory_deps * u64::from(constants.l1_tx_delta_factory_dep_gas);
+                let computation_gas = computation_gas.max(constants.l1_tx_min_gas_base.into());
+                let pubdata = u64::from(constants.l1_tx_intrinsic_pubdata)
+                    + factory_deps * u64::from(constants.l1_tx_delta_factory_dep_pubdata);
+                let pubdata = pubdata.saturating_add(tx.pubdata_bytes);
+                computation_gas.saturating_add(pubdata.saturating_mul(gas_per_pubdata))
+            }
+            TransactionKind::L2 => {
+                let pubdata =
+                    u64::from(constants.l2_tx_intrinsic_pubdata).saturating_add(tx.pubdata_bytes);
+                u64::from(constants.l2_tx_intrinsic_gas)
+                    .saturating_add(pubdata.saturating_mul(gas_per_pubdata))
+            }
+        }
+    }
+
+    /// Returns the minimal gas limit for the transaction, i.e. the minimal gas needed
+    /// to execute its body together with its share of the batch overhead.
+    pub fn min_gas_limit(&self, tx: &TransactionShape) -> u64 {
+        let body_gas = self.min_body_gas(tx);
+        // The overhead share grows with the gas limit, but slower than the gas limit itself,
+        // so the fixed point is reached in a few iterations.
+        let mut gas_limit = body_gas;
+        loop {
+            let next_gas_limit = body_gas.saturating_add(self.batch_overhead_share(tx, gas_limit));
+            if next_gas_limit == gas_limit {
+                return gas_limit;
+            }
+            gas_limit = next_gas_limit;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GWEI: u64 = 1_000_000_000;
+
+    #[test]
+    fn deriving_base_fee_and_gas_per_pubdata() {
+        let calculator = FeeCalculator::new(10 * GWEI, GWEI / 4);
+        assert_eq!(calculator.base_fee(), GWEI / 4);
+        assert_eq!(calculator.gas_per_pubdata(), 680);
+
+        // Base fee should be raised so that gas per pubdata is within bounds.
+        let calculator = FeeCalculator::new(100_000 * GWEI, GWEI / 4);
+        assert_eq!(calculator.gas_per_pubdata(), MAX_GAS_PER_PUBDATA_BYTE);
+        assert_eq!(calculator.base_fee(), 85 * GWEI);
+
+        let calculator = FeeCalculator::new(0, GWEI / 4);
+        assert_eq!(calculator.base_fee(), GWEI / 4);
+        assert_eq!(calculator.gas_per_pubdata(), 0);
+    }
+
+    #[test]
+    fn batch_overhead_share_is_bounded_by_batch_overhead() {
+        let calculator = FeeCalculator::new(10 * GWEI, GWEI / 4);
+        let batch_overhead = calculator.batch_overhead_gas(TransactionKind::L2);
+        assert_eq!(batch_overhead, 182_918 + 472 * 680);
+
+        let tx = TransactionShape::l2(200);
+        let slot_overhead = batch_overhead.div_ceil(MAX_TXS_IN_BLOCK as u64);
+        assert_eq!(calculator.batch_overhead_share(&tx, 50_000), slot_overhead);
+        let share = calculator.batch_overhead_share(&tx, MAX_L2_TX_GAS_LIMIT / 2);
+        assert_eq!(share, batch_overhead / 2);
+        let share = calculator.batch_overhead_share(&tx, u64::MAX / MAX_L2_TX_GAS_LIMIT);
+        assert_eq!(share, batch_overhead);
+
+        let large_tx = TransactionShape::l2(519_017 * 32);
+        assert_eq!(
+            calculator.batch_overhead_share(&large_tx, 100_000),
+            batch_overhead
+        );
+    }
+
+    #[test]
+    fn min_gas_limit_for_l2_transaction() {
+        let calculator = FeeCalculator::new(10 * GWEI, GWEI / 4);
+        let tx = TransactionShape {
+            pubdata_bytes: 100,
+            ..TransactionShape::l2(200)
+        };
+        let body_gas = calculator.min_body_gas(&tx);
+        assert_eq!(body_gas, 14_070 + 100 * 680);
+
+        let gas_limit = calculator.min_gas_limit(&tx);
+        assert_eq!(
+            gas_limit,
+            body_gas + calculator.batch_overhead_share(&tx, gas_limit)
+        );
+    }
+
+    #[test]
+    fn min_gas_limit_for_l1_transaction() {
+        let calculator = FeeCalculator::new(10 * GWEI, GWEI / 4);
+        let tx = TransactionShape::l1(200);
+        // The minimal computation gas applies.
+        assert_eq!(calculator.min_body_gas(&tx), 173_484 + 88 * 800);
+
+        let tx = TransactionShape {
+            factory_deps: 2,
+            ..TransactionShape::l1(1_000)
+        };
+        let expected_body_gas = 167_157 + 2 * 1_656 + 2 * 2_473 + (88 + 2 * 64) * 800;
+        assert_eq!(calculator.min_body_gas(&tx), expected_body_gas);
+        let gas_limit = calculator.min_gas_limit(&tx);
+        assert_eq!(
+            gas_limit,
+            expected_body_gas + calculator.batch_overhead_share(&tx, gas_limit)
+        );
+    }
+
+    #[test]
+    fn factory_deps_are_published_compressed_if_profitable() {
+        // 12 chunks, 3 of which are distinct: 50 bytes compressed.
+        let repetitive_code = [[1_u8; 8], [2; 8], [1; 8], [3; 8]].repeat(3).concat();
+        let unique_code: Vec<_> = (1_u64..=12).flat_map(u64::to_be_bytes).collect();
+        let bytecodes = [repetitive_code, unique_code];
+
+        let tx = TransactionShape::l2(200)
+            .with_factory_deps(&bytecodes)
+            .unwrap();
+        assert_eq!(tx.factory_deps, 2);
+        assert_eq!(
+            tx.pubdata_bytes,
+            2 * u64::from(PUBLISH_BYTECODE_OVERHEAD) + 50 + 96
+        );
+
+        let tx = TransactionShape::l1(200)
+            .with_factory_deps(&bytecodes)
+            .unwrap();
+        assert_eq!(tx.factory_deps, 2);
+        assert_eq!(tx.pubdata_bytes, 0);
+
+        let err = TransactionShape::l2(200)
+            .with_factory_deps(&[vec![0; 64]])
+            .unwrap_err();
+        assert_eq!(err, InvalidBytecodeError::BytecodeLengthInWordsIsEven);
+    }
+
+    #[test]
+    fn extreme_inputs_saturate() {
+        let calculator = FeeCalculator::new(u64::MAX, GWEI / 4);
+        assert_eq!(calculator.gas_per_pubdata(), MAX_GAS_PER_PUBDATA_BYTE);
+
+        let tx = TransactionShape {
+            pubdata_bytes: u64::MAX,
+            ..TransactionShape::l2(usize::MAX)
+        };
+        let batch_overhead = calculator.batch_overhead_gas(TransactionKind::L2);
+        assert_eq!(
+            calculator.batch_overhead_share(&tx, u64::MAX),
+            batch_overhead
+        );
+        assert_eq!(calculator.min_body_gas(&tx), u64::MAX);
+        assert_eq!(calculator.min_gas_limit(&tx), u64::MAX);
+    }
+}
@@ -1,7 +1,12 @@
+use axon_utils::bytecode::BytecodePublishingCosts;
+
+mod calculator;
 mod intrinsic;
 
+pub use calculator::*;
 pub use intrinsic::*;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct IntrinsicSystemGasConstants {
     // The overhead for each L2 transaction in computation (it is assumed that it is roughly independent of its structure)
     pub l2_tx_intrinsic_gas: u32,
@@ -37,6 +42,9 @@ pub struct IntrinsicSystemGasConstants {
 /// Note that it is bigger than 16 to account for potential overhead
 pub const L1_GAS_PER_PUBDATA_BYTE: u32 = 17;
 
+/// The amount of gas we need to pay for each zero pubdata byte, as for zero bytes of L1 calldata.
+pub const L1_GAS_PER_ZERO_PUBDATA_BYTE: u32 = 4;
+
 /// The amount of pubdata that is strictly guaranteed to be available for a block
 pub const GUARANTEED_PUBDATA_IN_TX: u32 = 100000;
 
@@ -45,3 +53,10 @@ pub const GUARANTEED_PUBDATA_IN_TX: u32 = 100000;
 /// of the commitment. The other "36" bytes are mostly an approximation of the amount of gas it takes
 /// to properly hash it and compare with the corresponding L2->L1 message.
 pub const PUBLISH_BYTECODE_OVERHEAD: u32 = 100;
+
+/// Pubdata pricing used to decide whether bytecodes should be published compressed.
+pub const BYTECODE_PUBLISHING_COSTS: BytecodePublishingCosts = BytecodePublishingCosts {
+    gas_per_pubdata_byte: L1_GAS_PER_PUBDATA_BYTE,
+    gas_per_zero_pubdata_byte: L1_GAS_PER_ZERO_PUBDATA_BYTE,
+    publish_bytecode_overhead: PUBLISH_BYTECODE_OVERHEAD,
+};
@@ -2,7 +2,12 @@
 
 use std::path::Path;
 
-use axon_storage::{db::NamedColumnFamily, rocksdb::DBPinnableSlice, RocksDB};
+use axon_storage::{
+    db::NamedColumnFamily,
+    rocksdb::DBPinnableSlice,
+    typed::{Codec, CodecError, TypedColumnFamily},
+    RocksDB,
+};
 use rayon::prelude::*;
 
 use crate::{
@@ -12,7 +17,9 @@ use crate::{
         database::{PruneDatabase, PrunePatchSet},
         Database, NodeKeys, PatchSet,
     },
-    types::{InternalNode, LeafNode, Manifest, Nibbles, Node, NodeKey, Root, StaleNodeKey},
+    types::{
+        InternalNode, LeafNode, Manifest, Nibbles, Node, NodeKey, Root, StaleNodeKey, KEY_SIZE,
+    },
 };
 
 /// RocksDB column families used by the tree.
@@ -41,6 +48,45 @@ impl NamedColumnFamily for MerkleTreeColumnFamily {
     }
 }
 
+/// Typed view of [`MerkleTreeColumnFamily::StaleKeys`]. Stale keys are ordered by the version
+/// they were replaced in, and have empty values.
+#[derive(Debug)]
+struct StaleKeysColumn;
+
+impl TypedColumnFamily for StaleKeysColumn {
+    type Family = MerkleTreeColumnFamily;
+    const FAMILY: Self::Family = MerkleTreeColumnFamily::StaleKeys;
+    type Key = StaleNodeKey;
+    type Value = ();
+}
+
+impl Codec for StaleNodeKey {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        buffer.extend_from_slice(&self.to_db_key());
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        if bytes.len() < 17 {
+            return Err(CodecError::new("stale node key is too short"));
+        }
+        let (version, key) = bytes.split_at(8);
+        let version = u64::decode(version)?;
+
+        // `NodeKey::from_db_key()` panics on malformed keys, so they are validated beforehand.
+        let nibble_count = usize::from(key[8]);
+        if nibble_count > 2 * KEY_SIZE {
+            return Err(CodecError::new(format!(
+                "invalid nibble count in stale node key: {nibble_count}"
+            )));
+        }
+        let expected_len = 17 + nibble_count.div_ceil(2);
+        if bytes.len() != expected_len {
+            return Err(CodecError::unexpected_len(expected_len, bytes.len()));
+        }
+        Ok(Self::new(NodeKey::from_db_key(key), version))
+    }
+}
+
 /// Main [`Database`] implementation wrapping a [`RocksDB`] reference.
 ///
 /// # Cloning
@@ -223,7 +269,6 @@ impl Database for RocksDBWrapper {
             }
         }
 
-        let stale_keys_cf = MerkleTreeColumnFamily::StaleKeys;
         let all_stale_keys = patch
             .stale_keys_by_version
             .into_iter()
@@ -232,7 +277,7 @@ impl Database for RocksDBWrapper {
                     .map(move |key| StaleNodeKey::new(key, version))
             });
         for replaced_key in all_stale_keys {
-            write_batch.put_cf(stale_keys_cf, &replaced_key.to_db_key(), &[]);
+            write_batch.put_typed::<StaleKeysColumn>(&replaced_key, &());
         }
 
         self.db
@@ -244,22 +289,17 @@ impl Database for RocksDBWrapper {
 
 impl PruneDatabase for RocksDBWrapper {
     fn min_stale_key_version(&self) -> Option<u64> {
-        let stale_keys_cf = MerkleTreeColumnFamily::StaleKeys;
-        let kv_bytes = self.db.prefix_iterator_cf(stale_keys_cf, &[]).next()?;
-        let version_prefix: [u8; 8] = kv_bytes.0[..8].try_into().unwrap();
-        Some(u64::from_be_bytes(version_prefix))
+        let (stale_key, ()) = self.db.iter_typed::<StaleKeysColumn>(..).next()?;
+        Some(stale_key.replaced_in_version)
     }
 
     fn stale_keys(&self, version: u64) -> Vec<NodeKey> {
-        let stale_keys_cf = MerkleTreeColumnFamily::StaleKeys;
-        let version_prefix = version.to_be_bytes();
         let keys = self
             .db
-            .prefix_iterator_cf(stale_keys_cf, &version_prefix)
-            .map(|entry| {
-                let key_bytes = entry.0;
-                debug_assert_eq!(&key_bytes[..8], version_prefix);
-                NodeKey::from_db_key(&key_bytes[8..])
+            .prefix_iter_typed::<StaleKeysColumn, _>(&version)
+            .map(|(stale_key, ())| {
+                debug_assert_eq!(stale_key.replaced_in_version, version);
+                stale_key.key
             });
         keys.collect()
     }
@@ -287,6 +327,7 @@ impl PruneDatabase for RocksDBWrapper {
 mod tests {
     use std::collections::{HashMap, HashSet};
 
+    use axon_types::U256;
     use tempfile::TempDir;
 
     use super::*;
@@ -336,6 +377,28 @@ mod tests {
         assert_contains_exactly_keys(&db, &expected_keys);
     }
 
+    #[test]
+    fn stale_node_key_codec() {
+        let nibbles = Nibbles::new(&U256::from(0xdead_beef_u64), 5);
+        let key = StaleNodeKey::new(nibbles.with_version(3), 5);
+        let bytes = key.encode_to_vec();
+        let decoded = StaleNodeKey::decode(&bytes).unwrap();
+        assert_eq!(decoded.key, key.key);
+        assert_eq!(decoded.replaced_in_version, key.replaced_in_version);
+
+        for len in [0, 16, bytes.len() - 1] {
+            StaleNodeKey::decode(&bytes[..len]).unwrap_err();
+        }
+        let mut padded_bytes = bytes.clone();
+        padded_bytes.push(0);
+        StaleNodeKey::decode(&padded_bytes).unwrap_err();
+
+        let mut bytes = bytes;
+        bytes[16] = 0xff;
+        let err = StaleNodeKey::decode(&bytes).unwrap_err();
+        assert!(err.to_string().contains("invalid nibble count"), "{err}");
+    }
+
     fn assert_contains_exactly_keys(db: &RocksDBWrapper, expected_keys: &HashSet<NodeKey>) {
         let cf = MerkleTreeColumnFamily::Tree;
         let actual_keys: HashSet<_> = db
@@ -1,6 +1,6 @@
 //! Crate allowing to calculate root hashes and Merkle proofs for small in-memory Merkle trees.
 
-use std::{iter, str::FromStr};
+use std::{collections::HashMap, iter, str::FromStr, sync::RwLock};
 
 use once_cell::sync::Lazy;
 
@@ -8,7 +8,7 @@ use once_cell::sync::Lazy;
 mod tests;
 
 use axon_primitives::{
-    hasher::{keccak::KeccakHasher, Hasher},
+    hasher::{blake2::Blake2Hasher, keccak::KeccakHasher, sha256::Sha256Hasher, Hasher},
     B256,
 };
 
@@ -78,7 +78,7 @@ where
         assert!(
             tree_depth_by_size(binary_tree_size) <= MAX_TREE_DEPTH,
             "Tree contains more than {} items; this is not supported",
-            1 << MAX_TREE_DEPTH
+            1_u64 << MAX_TREE_DEPTH
         );
 
         Self {
@@ -118,10 +118,11 @@ where
             merkle_path.reserve(depth);
         }
 
+        let empty_hashes = self.hasher.empty_subtree_hashes();
         let mut hashes = self.hashes;
         let mut level_len = hashes.len();
         for level in 0..depth {
-            let empty_hash_at_level = self.hasher.empty_subtree_hash(level);
+            let empty_hash_at_level = empty_hashes[level];
 
             if let Some(merkle_path) = merkle_path.as_deref_mut() {
                 let adjacent_idx = index ^ 1;
@@ -158,23 +159,55 @@ fn tree_depth_by_size(tree_size: usize) -> usize {
 pub trait HashEmptySubtree<const LEAF_SIZE: usize>:
     'static + Send + Sync + Hasher<Hash = B256>
 {
-    /// Returns the hash of an empty subtree with the given depth. Implementations
-    /// are encouraged to cache the returned values.
-    fn empty_subtree_hash(&self, depth: usize) -> B256;
-}
+    /// Returns hashes of empty subtrees indexed by depth, from a single empty leaf up to
+    /// the maximum supported tree depth. The returned slice is computed once and cached.
+    fn empty_subtree_hashes(&self) -> &'static [B256];
 
-impl HashEmptySubtree<88> for KeccakHasher {
+    /// Returns the hash of an empty subtree with the given depth.
     fn empty_subtree_hash(&self, depth: usize) -> B256 {
-        static EMPTY_TREE_HASHES: Lazy<Vec<B256>> = Lazy::new(compute_empty_tree_hashes::<88>);
-        EMPTY_TREE_HASHES[depth]
+        self.empty_subtree_hashes()[depth]
     }
 }
 
-fn compute_empty_tree_hashes<const LEAF_SIZE: usize>() -> Vec<B256> {
-    let empty_leaf_hash = KeccakHasher.hash_bytes(&[0_u8; LEAF_SIZE]);
-    iter::successors(Some(empty_leaf_hash), |hash| {
-        Some(KeccakHasher.compress(hash, hash))
-    })
-    .take(MAX_TREE_DEPTH + 1)
-    .collect()
+/// Cache of empty subtree hashes for a single hasher keyed by the leaf size. Hashes are leaked
+/// so that callers can hold onto them without keeping the lock; the number of entries is bounded
+/// by the number of distinct `LEAF_SIZE`s used with the hasher.
+struct EmptyTreeHashes(Lazy<RwLock<HashMap<usize, &'static [B256]>>>);
+
+impl EmptyTreeHashes {
+    const fn new() -> Self {
+        Self(Lazy::new(RwLock::default))
+    }
+
+    fn get<H: Hasher<Hash = B256>>(&self, hasher: &H, leaf_size: usize) -> &'static [B256] {
+        if let Some(hashes) = self.0.read().unwrap().get(&leaf_size) {
+            return hashes;
+        }
+        let mut cache = self.0.write().unwrap();
+        cache.entry(leaf_size).or_insert_with(|| {
+            Box::leak(compute_empty_tree_hashes(hasher, leaf_size).into_boxed_slice())
+        })
+    }
 }
+
+fn compute_empty_tree_hashes<H: Hasher<Hash = B256>>(hasher: &H, leaf_size: usize) -> Vec<B256> {
+    let empty_leaf_hash = hasher.hash_bytes(&vec![0_u8; leaf_size]);
+    iter::successors(Some(empty_leaf_hash), |hash| Some(hasher.compress(hash, hash)))
+        .take(MAX_TREE_DEPTH + 1)
+        .collect()
+}
+
+macro_rules! impl_hash_empty_subtree {
+    ($hasher:ty) => {
+        impl<const LEAF_SIZE: usize> HashEmptySubtree<LEAF_SIZE> for $hasher {
+            fn empty_subtree_hashes(&self) -> &'static [B256] {
+                static EMPTY_TREE_HASHES: EmptyTreeHashes = EmptyTreeHashes::new();
+                EMPTY_TREE_HASHES.get(self, LEAF_SIZE)
+            }
+        }
+    };
+}
+
+impl_hash_empty_subtree!(KeccakHasher);
+impl_hash_empty_subtree!(Sha256Hasher);
+impl_hash_empty_subtree!(Blake2Hasher);
@@ -27,7 +27,10 @@ fn hash_of_empty_tree_with_single_item() {
         let len = 1 << depth;
         println!("checking tree with {len} items");
         let tree = MiniMerkleTree::new(iter::once([0_u8; 88]), Some(len));
-        assert_eq!(tree.merkle_root(), KeccakHasher.empty_subtree_hash(depth));
+        assert_eq!(
+            tree.merkle_root(),
+            HashEmptySubtree::<88>::empty_subtree_hash(&KeccakHasher, depth)
+        );
     }
 }
 
@@ -40,13 +43,25 @@ fn hash_of_large_empty_tree_with_multiple_items() {
 
         let tree = MiniMerkleTree::new(leaves.clone(), Some(tree_size));
         let depth = tree_depth_by_size(tree_size);
-        assert_eq!(tree.merkle_root(), KeccakHasher.empty_subtree_hash(depth));
+        assert_eq!(
+            tree.merkle_root(),
+            HashEmptySubtree::<88>::empty_subtree_hash(&KeccakHasher, depth)
+        );
         let tree = MiniMerkleTree::new(leaves, None);
         let depth = tree_depth_by_size(tree_size);
-        assert_eq!(tree.merkle_root(), KeccakHasher.empty_subtree_hash(depth));
+        assert_eq!(
+            tree.merkle_root(),
+            HashEmptySubtree::<88>::empty_subtree_hash(&KeccakHasher, depth)
+        );
     }
 }
 
+#[test]
+#[should_panic(expected = "Tree contains more than 4294967296 items")]
+fn tree_larger_than_max_depth_is_rejected() {
+    MiniMerkleTree::new(iter::once([0_u8; 88]), Some(1 << (MAX_TREE_DEPTH + 1)));
+}
+
 #[test]
 fn single_item_tree_snapshot() {
     let tree = MiniMerkleTree::new(iter::once([1_u8; 88]), Some(32));
@@ -217,3 +232,69 @@ fn merkle_proofs_are_valid_in_very_small_trees() {
         }
     }
 }
+
+fn assert_empty_subtree_hashes<H, const LEAF_SIZE: usize>(hasher: H)
+where
+    H: HashEmptySubtree<LEAF_SIZE> + Clone,
+{
+    let mut expected = hasher.hash_bytes(&[0_u8; LEAF_SIZE]);
+    for depth in 0..=MAX_TREE_DEPTH {
+        assert_eq!(hasher.empty_subtree_hash(depth), expected, "depth={depth}");
+        expected = hasher.compress(&expected, &expected);
+    }
+
+    for depth in 0..=5 {
+        let leaves = iter::repeat_n([0_u8; LEAF_SIZE], 3);
+        let tree = MiniMerkleTree::with_hasher(hasher.clone(), leaves, Some(1 << (depth + 2)));
+        assert_eq!(tree.merkle_root(), hasher.empty_subtree_hash(depth + 2));
+    }
+}
+
+#[test]
+fn empty_subtree_hashes_for_all_hashers() {
+    assert_empty_subtree_hashes::<_, 88>(KeccakHasher);
+    assert_empty_subtree_hashes::<_, 32>(KeccakHasher);
+    assert_empty_subtree_hashes::<_, 88>(Sha256Hasher);
+    assert_empty_subtree_hashes::<_, 64>(Sha256Hasher);
+    assert_empty_subtree_hashes::<_, 32>(Blake2Hasher);
+    assert_empty_subtree_hashes::<_, 1>(Blake2Hasher);
+}
+
+#[test]
+fn empty_subtree_hashes_are_cached_per_leaf_size() {
+    // Interleave leaf sizes to check that cached hashes are not mixed up.
+    for _ in 0..2 {
+        for depth in [0, 3] {
+            let hash_for_32_bytes =
+                HashEmptySubtree::<32>::empty_subtree_hash(&Sha256Hasher, depth);
+            let hash_for_64_bytes =
+                HashEmptySubtree::<64>::empty_subtree_hash(&Sha256Hasher, depth);
+            assert_ne!(hash_for_32_bytes, hash_for_64_bytes);
+        }
+    }
+    assert_eq!(
+        HashEmptySubtree::<64>::empty_subtree_hash(&Sha256Hasher, 0),
+        Sha256Hasher.hash_bytes(&[0; 64])
+    );
+
+    let hashes = HashEmptySubtree::<64>::empty_subtree_hashes(&Sha256Hasher);
+    assert_eq!(hashes.len(), MAX_TREE_DEPTH + 1);
+    assert!(std::ptr::eq(
+        hashes,
+        HashEmptySubtree::<64>::empty_subtree_hashes(&Sha256Hasher)
+    ));
+}
+
+#[test]
+fn merkle_root_with_sha256_hasher() {
+    let leaves = (1_u8..=3).map(|byte| [byte; 32]);
+    let tree = MiniMerkleTree::with_hasher(Sha256Hasher, leaves.clone(), Some(4));
+
+    let leaf_hashes: Vec<_> = leaves.map(|leaf| Sha256Hasher.hash_bytes(&leaf)).collect();
+    let empty_leaf_hash = Sha256Hasher.hash_bytes(&[0; 32]);
+    let expected_root = Sha256Hasher.compress(
+        &Sha256Hasher.compress(&leaf_hashes[0], &leaf_hashes[1]),
+        &Sha256Hasher.compress(&leaf_hashes[2], &empty_leaf_hash),
+    );
+    assert_eq!(tree.merkle_root(), expected_root);
+}
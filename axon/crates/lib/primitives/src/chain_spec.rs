@@ -0,0 +1,275 @@
+//! Chain parameters bundled per network.
+
+use std::{collections::HashMap, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{network::Network, L1ChainId, L2ChainId, B256};
+
+/// Errors that can occur when loading chain specs.
+#[derive(Debug, thiserror::Error)]
+pub enum ChainSpecError {
+    #[error("failed reading chain specs: {0}")]
+    Io(#[from] io::Error),
+    #[error("failed parsing chain specs: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("L1 chain ID {actual} does not match network `{network}` (expected {expected})")]
+    L1ChainIdMismatch {
+        network: Network,
+        expected: L1ChainId,
+        actual: L1ChainId,
+    },
+    #[error("chain spec for network `{0}` is specified multiple times")]
+    DuplicateNetwork(Network),
+}
+
+/// Hashes of the system contracts which are not deployed at a fixed address, but are
+/// supplied to each L1 batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaseSystemContractsHashes {
+    /// Bytecode hash of the bootloader.
+    pub bootloader: B256,
+    /// Bytecode hash of the default account abstraction, which is used for accounts
+    /// without deployed code.
+    pub default_aa: B256,
+}
+
+/// Limits imposed on transactions and L1 batches. Default values correspond to the constants
+/// in `axon_constants`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChainLimits {
+    /// Maximum gas limit of an L2 transaction.
+    pub max_l2_tx_gas_limit: u64,
+    /// Maximum gas limit of an L1 (priority) transaction on the L1 side.
+    pub max_l1_tx_gas_limit: u64,
+    /// Amount of pubdata which can always be published by a single transaction.
+    pub guaranteed_pubdata_per_l1_batch: u64,
+    /// Maximum amount of pubdata published by a single L1 batch.
+    pub max_pubdata_per_l1_batch: u64,
+    /// Maximum number of transactions in an L1 batch.
+    pub max_txs_in_l1_batch: usize,
+    /// Maximum number of factory deps supplied with a single transaction.
+    pub max_new_factory_deps: usize,
+}
+
+impl Default for ChainLimits {
+    fn default() -> Self {
+        Self {
+            max_l2_tx_gas_limit: 80_000_000,
+            max_l1_tx_gas_limit: 300_000,
+            guaranteed_pubdata_per_l1_batch: 4_000,
+            max_pubdata_per_l1_batch: 110_000,
+            max_txs_in_l1_batch: 1_024,
+            max_new_factory_deps: 32,
+        }
+    }
+}
+
+impl ChainLimits {
+    /// Returns the maximum gas per pubdata byte users can provide in their transactions,
+    /// so that they are always able to publish `guaranteed_pubdata_per_l1_batch` bytes.
+    pub fn max_gas_per_pubdata_byte(&self) -> u64 {
+        self.max_l2_tx_gas_limit / self.guaranteed_pubdata_per_l1_batch
+    }
+}
+
+/// Parameters of a chain, which components can take instead of relying on compiled-in constants.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainSpec {
+    pub network: Network,
+    pub l1_chain_id: L1ChainId,
+    pub l2_chain_id: L2ChainId,
+    pub base_system_contracts_hashes: BaseSystemContractsHashes,
+    #[serde(default)]
+    pub limits: ChainLimits,
+}
+
+impl ChainSpec {
+    /// Creates a spec for a known network with default limits. The L1 chain ID is derived
+    /// from the network.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the network doesn't have a well-known L1 chain ID (i.e., it's
+    /// [`Network::Unknown`] or [`Network::Test`]).
+    pub fn new(
+        network: Network,
+        l2_chain_id: L2ChainId,
+        base_system_contracts_hashes: BaseSystemContractsHashes,
+    ) -> Self {
+        Self {
+            network,
+            l1_chain_id: network.chain_id(),
+            l2_chain_id,
+            base_system_contracts_hashes,
+            limits: ChainLimits::default(),
+        }
+    }
+
+    /// Sets limits for the spec.
+    #[must_use]
+    pub fn with_limits(mut self, limits: ChainLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Checks that the L1 chain ID corresponds to the network.
+    pub fn validate(&self) -> Result<(), ChainSpecError> {
+        let expected = match self.network {
+            Network::Mainnet | Network::Localhost => self.network.chain_id(),
+            Network::Unknown | Network::Test => return Ok(()),
+        };
+        if self.l1_chain_id != expected {
+            return Err(ChainSpecError::L1ChainIdMismatch {
+                network: self.network,
+                expected,
+                actual: self.l1_chain_id,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Registry of chain specs keyed by [`Network`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChainSpecRegistry {
+    specs: HashMap<Network, ChainSpec>,
+}
+
+impl ChainSpecRegistry {
+    /// Parses specs from a JSON array, e.g.
+    ///
+    /// ```json
+    /// [{
+    ///     "network": "localhost",
+    ///     "l1_chain_id": 9,
+    ///     "l2_chain_id": "270",
+    ///     "base_system_contracts_hashes": { "bootloader": "0x01..", "default_aa": "0x01.." },
+    ///     "limits": { "max_txs_in_l1_batch": 512 }
+    /// }]
+    /// ```
+    ///
+    /// Omitted limits take default values.
+    pub fn from_json(json: &str) -> Result<Self, ChainSpecError> {
+        let specs: Vec<ChainSpec> = serde_json::from_str(json)?;
+        let mut this = Self::default();
+        for spec in specs {
+            this.insert(spec)?;
+        }
+        Ok(this)
+    }
+
+    /// Loads specs from a JSON file. See [`Self::from_json()`] for the file format.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ChainSpecError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    /// Adds a spec to the registry.
+    pub fn insert(&mut self, spec: ChainSpec) -> Result<(), ChainSpecError> {
+        spec.validate()?;
+        if self.specs.contains_key(&spec.network) {
+            return Err(ChainSpecError::DuplicateNetwork(spec.network));
+        }
+        self.specs.insert(spec.network, spec);
+        Ok(())
+    }
+
+    /// Returns the spec for the specified network, if any.
+    pub fn get(&self, network: Network) -> Option<&ChainSpec> {
+        self.specs.get(&network)
+    }
+
+    /// Iterates over all specs in the registry in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &ChainSpec> + '_ {
+        self.specs.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASHES: BaseSystemContractsHashes = BaseSystemContractsHashes {
+        bootloader: B256::repeat_byte(1),
+        default_aa: B256::repeat_byte(2),
+    };
+
+    #[test]
+    fn creating_spec_for_known_network() {
+        let spec = ChainSpec::new(Network::Localhost, L2ChainId::default(), HASHES);
+        assert_eq!(spec.l1_chain_id, L1ChainId(9));
+        assert_eq!(spec.limits.max_gas_per_pubdata_byte(), 20_000);
+        spec.validate().unwrap();
+
+        let mut registry = ChainSpecRegistry::default();
+        registry.insert(spec.clone()).unwrap();
+        assert_eq!(registry.get(Network::Localhost), Some(&spec));
+        assert_eq!(registry.get(Network::Mainnet), None);
+        let err = registry.insert(spec).unwrap_err();
+        assert!(
+            matches!(err, ChainSpecError::DuplicateNetwork(Network::Localhost)),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn loading_specs_from_json() {
+        let json = format!(
+            r#"[{{
+                "network": "test",
+                "l1_chain_id": 1337,
+                "l2_chain_id": "0x10e",
+                "base_system_contracts_hashes": {{ "bootloader": "{}", "default_aa": "{}" }},
+                "limits": {{ "max_txs_in_l1_batch": 512 }}
+            }}, {{
+                "network": "mainnet",
+                "l1_chain_id": 21,
+                "l2_chain_id": "300",
+                "base_system_contracts_hashes": {{ "bootloader": "{}", "default_aa": "{}" }}
+            }}]"#,
+            HASHES.bootloader, HASHES.default_aa, HASHES.bootloader, HASHES.default_aa
+        );
+        let registry = ChainSpecRegistry::from_json(&json).unwrap();
+        assert_eq!(registry.iter().count(), 2);
+
+        let spec = registry.get(Network::Test).unwrap();
+        assert_eq!(spec.l1_chain_id, L1ChainId(1337));
+        assert_eq!(spec.l2_chain_id, L2ChainId::default());
+        assert_eq!(spec.base_system_contracts_hashes, HASHES);
+        let expected_limits = ChainLimits {
+            max_txs_in_l1_batch: 512,
+            ..ChainLimits::default()
+        };
+        assert_eq!(spec.limits, expected_limits);
+
+        let spec = registry.get(Network::Mainnet).unwrap();
+        assert_eq!(spec.l2_chain_id.as_u64(), 300);
+        assert_eq!(spec.limits, ChainLimits::default());
+    }
+
+    #[test]
+    fn mismatched_l1_chain_id_is_rejected() {
+        let json = format!(
+            r#"[{{
+                "network": "mainnet",
+                "l1_chain_id": 9,
+                "l2_chain_id": "270",
+                "base_system_contracts_hashes": {{ "bootloader": "{}", "default_aa": "{}" }}
+            }}]"#,
+            HASHES.bootloader, HASHES.default_aa
+        );
+        let err = ChainSpecRegistry::from_json(&json).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                ChainSpecError::L1ChainIdMismatch {
+                    expected: L1ChainId(21),
+                    actual: L1ChainId(9),
+                    ..
+                }
+            ),
+            "{err}"
+        );
+    }
+}
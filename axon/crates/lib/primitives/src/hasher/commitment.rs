@@ -0,0 +1,256 @@
+//! Commitments to sequences of items built on top of a [`Hasher`], and to field element encodings
+//! built on top of an algebraic sponge.
+
+use crate::hasher::Hasher;
+
+/// Computes a linear hash of the items, i.e. the hash of their concatenation. This is how
+/// the linear hasher circuit commits to the serialized storage logs; an empty sequence of items
+/// is committed to as the hash of an empty byte slice.
+pub fn linear_hash<H, I>(hasher: &H, items: I) -> H::Hash
+where
+    H: Hasher,
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    let mut buffer = vec![];
+    for item in items {
+        buffer.extend_from_slice(item.as_ref());
+    }
+    hasher.hash_bytes(&buffer)
+}
+
+/// Rolling hash commitment to a sequence of items. Each added item is hashed, and the hash is
+/// merged with the current commitment: `commitment = compress(commitment, hash_bytes(item))`.
+#[derive(Debug, Clone)]
+pub struct RollingHash<H: Hasher> {
+    hasher: H,
+    hash: H::Hash,
+    len: usize,
+}
+
+impl<H: Hasher> RollingHash<H> {
+    /// Creates a commitment to an empty sequence with the specified initial hash.
+    pub fn new(hasher: H, initial_hash: H::Hash) -> Self {
+        Self {
+            hasher,
+            hash: initial_hash,
+            len: 0,
+        }
+    }
+
+    /// Creates a commitment to an empty sequence with the initial hash equal to the hash
+    /// of an empty byte slice.
+    pub fn with_empty_hash(hasher: H) -> Self {
+        let initial_hash = hasher.hash_bytes(&[]);
+        Self::new(hasher, initial_hash)
+    }
+
+    /// Adds an item to the committed sequence.
+    pub fn push(&mut self, item: &[u8]) {
+        let item_hash = self.hasher.hash_bytes(item);
+        self.push_hash(&item_hash);
+    }
+
+    /// Adds an already hashed item to the committed sequence.
+    pub fn push_hash(&mut self, item_hash: &H::Hash) {
+        self.hash = self.hasher.compress(&self.hash, item_hash);
+        self.len += 1;
+    }
+
+    /// Returns the current commitment.
+    pub fn hash(&self) -> &H::Hash {
+        &self.hash
+    }
+
+    /// Returns the number of items added to the commitment.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks whether no items were added to the commitment.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<H: Hasher, T: AsRef<[u8]>> Extend<T> for RollingHash<H> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item.as_ref());
+        }
+    }
+}
+
+/// Round function of an algebraic sponge with `AW` rate elements out of `SW` state elements.
+/// Circuits use such a sponge (e.g., Poseidon2 over Goldilocks) to commit to their inputs
+/// and outputs, such as the log sorter queue states.
+pub trait SpongeRoundFunction<const AW: usize, const SW: usize> {
+    /// Field element; its default value must be zero.
+    type Element: Copy + Default;
+
+    /// Prepares the empty state for absorbing an input of the specified length (in elements).
+    fn apply_length_specialization(&self, state: &mut [Self::Element; SW], length: u32);
+
+    /// Applies the round function to the state in place.
+    fn compute_round_function(&self, state: &mut [Self::Element; SW]);
+}
+
+/// Computes an `N`-element commitment to the encoding, the same way as `commit_encoding`
+/// in circuits. The encoding is zero-padded to a multiple of `AW` and absorbed with replacement
+/// in `AW`-element chunks into a length-specialized empty state; the commitment is the first
+/// `N` elements of the resulting state.
+///
+/// # Panics
+///
+/// Panics if `N > AW`, or if the encoding length doesn't fit into `u32`.
+pub fn sponge_commitment<R, const AW: usize, const SW: usize, const N: usize>(
+    round_function: &R,
+    encoding: &[R::Element],
+) -> [R::Element; N]
+where
+    R: SpongeRoundFunction<AW, SW>,
+{
+    assert!(N <= AW, "commitment cannot be wider than the sponge rate");
+    let length = u32::try_from(encoding.len()).expect("encoding is too long");
+
+    let mut state = [R::Element::default(); SW];
+    round_function.apply_length_specialization(&mut state, length);
+    for chunk in encoding.chunks(AW) {
+        state[..chunk.len()].copy_from_slice(chunk);
+        state[chunk.len()..AW].fill(R::Element::default());
+        round_function.compute_round_function(&mut state);
+    }
+    std::array::from_fn(|i| state[i])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use alloy_primitives::{utils::keccak256, B256};
+
+    use super::*;
+    use crate::hasher::{blake2::Blake2Hasher, keccak::KeccakHasher, sha256::Sha256Hasher};
+
+    #[test]
+    fn linear_hash_of_empty_sequence() {
+        let empty_keccak =
+            B256::from_str("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+                .unwrap();
+        assert_eq!(linear_hash(&KeccakHasher, [[0_u8; 0]; 0]), empty_keccak);
+        let empty_sha256 =
+            B256::from_str("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+                .unwrap();
+        assert_eq!(linear_hash(&Sha256Hasher, [[0_u8; 0]; 0]), empty_sha256);
+    }
+
+    fn assert_linear_hash<H>(hasher: &H)
+    where
+        H: Hasher<Hash = B256>,
+    {
+        let items = [b"first".as_slice(), b"", b"third item"];
+        let expected = hasher.hash_bytes(b"firstthird item");
+        assert_eq!(linear_hash(hasher, items), expected);
+        assert_eq!(linear_hash(hasher, items.map(<[u8]>::to_vec)), expected);
+    }
+
+    #[test]
+    fn linear_hash_of_items() {
+        assert_linear_hash(&KeccakHasher);
+        assert_linear_hash(&Sha256Hasher);
+        assert_linear_hash(&Blake2Hasher);
+    }
+
+    fn assert_rolling_hash<H>(hasher: H)
+    where
+        H: Hasher<Hash = B256> + Clone,
+    {
+        let mut rolling_hash = RollingHash::with_empty_hash(hasher.clone());
+        assert!(rolling_hash.is_empty());
+        assert_eq!(*rolling_hash.hash(), hasher.hash_bytes(&[]));
+
+        rolling_hash.extend([b"first".as_slice(), b"second"]);
+        assert_eq!(rolling_hash.len(), 2);
+        let expected = hasher.compress(&hasher.hash_bytes(&[]), &hasher.hash_bytes(b"first"));
+        let expected = hasher.compress(&expected, &hasher.hash_bytes(b"second"));
+        assert_eq!(*rolling_hash.hash(), expected);
+
+        let mut hash_from_hashes = RollingHash::with_empty_hash(hasher.clone());
+        hash_from_hashes.push_hash(&hasher.hash_bytes(b"first"));
+        hash_from_hashes.push_hash(&hasher.hash_bytes(b"second"));
+        assert_eq!(hash_from_hashes.hash(), rolling_hash.hash());
+    }
+
+    #[test]
+    fn rolling_hash_of_items() {
+        assert_rolling_hash(KeccakHasher);
+        assert_rolling_hash(Sha256Hasher);
+        assert_rolling_hash(Blake2Hasher);
+    }
+
+    #[test]
+    fn rolling_keccak_hash_matches_reference() {
+        // Rolling hash of priority operations as computed by L1 contracts:
+        // `rolling_hash = keccak256(abi.encode(rolling_hash, keccak256(tx)))`.
+        let tx_hashes = [keccak256(b"tx #0"), keccak256(b"tx #1")];
+        let mut expected = keccak256([]);
+        for tx_hash in &tx_hashes {
+            expected = keccak256([expected.as_slice(), tx_hash.as_slice()].concat());
+        }
+
+        let mut rolling_hash = RollingHash::with_empty_hash(KeccakHasher);
+        rolling_hash.extend([b"tx #0", b"tx #1"]);
+        assert_eq!(*rolling_hash.hash(), expected);
+    }
+
+    /// Toy sponge with a 2-element rate and 4-element state, which stores the length
+    /// in the last capacity element (like Poseidon2 in circuits).
+    #[derive(Debug)]
+    struct ToySponge;
+
+    impl ToySponge {
+        fn mix(state: [u64; 4]) -> [u64; 4] {
+            std::array::from_fn(|i| {
+                state[i]
+                    .wrapping_mul(0x9e37_79b9_7f4a_7c15)
+                    .wrapping_add(state[(i + 1) % 4].rotate_left(17))
+            })
+        }
+    }
+
+    impl SpongeRoundFunction<2, 4> for ToySponge {
+        type Element = u64;
+
+        fn apply_length_specialization(&self, state: &mut [u64; 4], length: u32) {
+            state[3] = length.into();
+        }
+
+        fn compute_round_function(&self, state: &mut [u64; 4]) {
+            *state = Self::mix(*state);
+        }
+    }
+
+    #[test]
+    fn sponge_commitment_of_empty_encoding() {
+        let commitment: [u64; 2] = sponge_commitment(&ToySponge, &[]);
+        assert_eq!(commitment, [0; 2]);
+    }
+
+    #[test]
+    fn sponge_commitment_absorbs_padded_chunks() {
+        let state = ToySponge::mix([1, 2, 0, 3]);
+        let state = ToySponge::mix([3, 0, state[2], state[3]]);
+        let commitment: [u64; 2] = sponge_commitment(&ToySponge, &[1, 2, 3]);
+        assert_eq!(commitment, [state[0], state[1]]);
+
+        // Explicit padding changes the length specialization, and thus the commitment.
+        let padded_commitment: [u64; 2] = sponge_commitment(&ToySponge, &[1, 2, 3, 0]);
+        assert_ne!(padded_commitment, commitment);
+    }
+
+    #[test]
+    #[should_panic(expected = "commitment cannot be wider than the sponge rate")]
+    fn sponge_commitment_wider_than_rate() {
+        let _: [u64; 3] = sponge_commitment(&ToySponge, &[1]);
+    }
+}
@@ -1,4 +1,5 @@
 pub mod blake2;
+pub mod commitment;
 pub mod keccak;
 pub mod sha256;
 
@@ -5,6 +5,7 @@
 #[macro_use]
 mod macros;
 
+pub mod chain_spec;
 pub mod hasher;
 pub mod network;
 
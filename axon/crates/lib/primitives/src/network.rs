@@ -7,7 +7,7 @@ use serde::{Deserialize, Serialize};
 use crate::L1ChainId;
 
 /// Network to be used for the axon client.
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "camelCase")]
 pub enum Network {
     /// Cortex Mainnet.
@@ -2,11 +2,12 @@
 //!
 //! ## Storage layout
 //!
-//! This database has 3 column families:
+//! This database has 4 column families:
 //!
 //! - State
 //! - Contracts
 //! - Factory dependencies
+//! - Undo log
 //!
 //! | Column       | Key                             | Value                           | Description                               |
 //! | ------------ | ------------------------------- | ------------------------------- | ----------------------------------------- |
@@ -18,19 +19,29 @@
 //! |              |                                 |                    (big-endian) |                                           |
 //! | Contracts    | address (20 bytes)              | `Vec<u8>`                       | Contract contents                         |
 //! | Factory deps | hash (32 bytes)                 | `Vec<u8>`                       | Bytecodes for new contracts that a certain contract may deploy. |
+//! | Undo log     | L1 batch number (u32,           | serialized undo record          | Previous state values and added factory deps for the batch; |
+//! |              | big-endian)                     |                                 | kept only for the latest batches.                           |
 
 use std::{collections::HashMap, mem, path::Path, time::Instant};
 
 use axon_dal::StorageProcessor;
-use axon_storage::{db::NamedColumnFamily, RocksDB};
+use axon_storage::{
+    db::{NamedColumnFamily, WriteBatch},
+    typed::{Codec, CodecError, TypedColumnFamily},
+    RocksDB,
+};
 use axon_types::{L1BatchNumber, StorageKey, StorageValue, B256};
 use axon_utils::{b256_to_u256, u256_to_b256, U256ONE};
 use itertools::{Either, Itertools};
 
-use self::metrics::METRICS;
+use self::{
+    metrics::METRICS,
+    undo_log::{BatchUndo, UndoLogColumn},
+};
 use crate::{InMemoryStorage, ReadStorage};
 
 mod metrics;
+mod undo_log;
 
 fn serialize_block_number(block_number: u32) -> [u8; 4] {
     block_number.to_le_bytes()
@@ -46,22 +57,42 @@ enum StateKeeperColumnFamily {
     State,
     Contracts,
     FactoryDeps,
+    UndoLog,
 }
 
 impl NamedColumnFamily for StateKeeperColumnFamily {
     const DB_NAME: &'static str = "state_keeper";
-    const ALL: &'static [Self] = &[Self::State, Self::Contracts, Self::FactoryDeps];
+    const ALL: &'static [Self] = &[
+        Self::State,
+        Self::Contracts,
+        Self::FactoryDeps,
+        Self::UndoLog,
+    ];
 
     fn name(&self) -> &'static str {
         match self {
             Self::State => "state",
             Self::Contracts => "contracts",
             Self::FactoryDeps => "factory_deps",
+            Self::UndoLog => "undo_log",
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Typed view of state values in [`StateKeeperColumnFamily::State`] keyed by hashed storage keys.
+/// The column family also contains special keys (see [`RocksdbStorage::is_special_key()`]),
+/// so it must not be iterated over using this view.
+#[derive(Debug)]
+struct StateColumn;
+
+impl TypedColumnFamily for StateColumn {
+    type Family = StateKeeperColumnFamily;
+    const FAMILY: Self::Family = StateKeeperColumnFamily::State;
+    type Key = [u8; 32];
+    type Value = StateValue;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct StateValue {
     pub value: B256,
     pub enum_index: Option<u64>,
@@ -71,28 +102,32 @@ impl StateValue {
     pub fn new(value: B256, enum_index: Option<u64>) -> Self {
         Self { value, enum_index }
     }
+}
 
-    pub fn deserialize(bytes: &[u8]) -> Self {
-        if bytes.len() == 32 {
-            Self {
-                value: B256::from_slice(bytes),
-                enum_index: None,
-            }
-        } else {
-            Self {
-                value: B256::from_slice(&bytes[..32]),
-                enum_index: Some(u64::from_be_bytes(bytes[32..40].try_into().unwrap())),
-            }
-        }
-    }
-
-    pub fn serialize(&self) -> Vec<u8> {
-        let mut buffer = Vec::with_capacity(40);
+/// The value is serialized as 32 bytes, followed by the big-endian enumeration index if it is
+/// known.
+impl Codec for StateValue {
+    fn encode(&self, buffer: &mut Vec<u8>) {
         buffer.extend_from_slice(self.value.as_slice());
         if let Some(index) = self.enum_index {
             buffer.extend_from_slice(&index.to_be_bytes());
         }
-        buffer
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        match bytes.len() {
+            32 => Ok(Self::new(B256::from_slice(bytes), None)),
+            40 => {
+                let (value, enum_index) = bytes.split_at(32);
+                Ok(Self::new(
+                    B256::from_slice(value),
+                    Some(u64::decode(enum_index)?),
+                ))
+            }
+            len => Err(CodecError::new(format!(
+                "unexpected state value length: expected 32 or 40, got {len}"
+            ))),
+        }
     }
 }
 
@@ -102,11 +137,14 @@ pub struct RocksdbStorage {
     db: RocksDB<StateKeeperColumnFamily>,
     pending_patch: InMemoryStorage,
     enum_index_migration_chunk_size: usize,
+    undo_log_capacity: u32,
 }
 
 impl RocksdbStorage {
     const BLOCK_NUMBER_KEY: &'static [u8] = b"block_number";
     const ENUM_INDEX_MIGRATION_CURSOR: &'static [u8] = b"enum_index_migration_cursor";
+    /// Default number of latest L1 batches that can be rolled back without Postgres.
+    pub const DEFAULT_UNDO_LOG_CAPACITY: u32 = 16;
 
     fn is_special_key(key: &[u8]) -> bool {
         key == Self::BLOCK_NUMBER_KEY || key == Self::ENUM_INDEX_MIGRATION_CURSOR
@@ -119,9 +157,17 @@ impl RocksdbStorage {
             db,
             pending_patch: InMemoryStorage::default(),
             enum_index_migration_chunk_size: 100,
+            undo_log_capacity: Self::DEFAULT_UNDO_LOG_CAPACITY,
         }
     }
 
+    /// Sets the number of latest L1 batches for which undo records are kept, so that they
+    /// can be rolled back without Postgres. Older records are trimmed on each save.
+    /// Setting the capacity to 0 disables the undo log.
+    pub fn set_undo_log_capacity(&mut self, capacity: u32) {
+        self.undo_log_capacity = capacity;
+    }
+
     /// Enables enum indices migration.
     pub fn enable_enum_index_migration(&mut self, chunk_size: usize) {
         self.enum_index_migration_chunk_size = chunk_size;
@@ -246,7 +292,8 @@ impl RocksdbStorage {
                 if Self::is_special_key(&key) {
                     return None;
                 }
-                let state_value = StateValue::deserialize(&value);
+                let state_value =
+                    StateValue::decode(&value).expect("failed decoding RocksDB state value");
                 (state_value.enum_index.is_none())
                     .then(|| (B256::from_slice(&key), state_value.value))
             })
@@ -260,11 +307,7 @@ impl RocksdbStorage {
 
         for (key, value) in keys.iter().zip(values) {
             let index = enum_indices_and_batches[key].1;
-            write_batch.put_cf(
-                StateKeeperColumnFamily::State,
-                key.as_slice(),
-                &StateValue::new(value, Some(index)).serialize(),
-            );
+            write_batch.put_typed::<StateColumn>(&key.0, &StateValue::new(value, Some(index)));
         }
 
         let next_key = keys
@@ -304,11 +347,9 @@ impl RocksdbStorage {
     }
 
     fn read_state_value(&self, key: &StorageKey) -> Option<StateValue> {
-        let cf = StateKeeperColumnFamily::State;
         self.db
-            .get_cf(cf, &Self::serialize_state_key(key))
+            .get_typed::<StateColumn>(&Self::serialize_state_key(key))
             .expect("failed to read rocksdb state value")
-            .map(|value| StateValue::deserialize(&value))
     }
 
     /// Returns storage logs to apply.
@@ -330,7 +371,8 @@ impl RocksdbStorage {
         self.pending_patch.factory_deps.insert(hash, bytecode);
     }
 
-    /// Rolls back the state to a previous L1 batch number.
+    /// Rolls back the state to a previous L1 batch number. If the local undo log covers
+    /// all reverted L1 batches, Postgres is not queried.
     ///
     /// # Panics
     ///
@@ -340,6 +382,9 @@ impl RocksdbStorage {
         connection: &mut StorageProcessor<'_>,
         last_l1_batch_to_keep: L1BatchNumber,
     ) {
+        if self.rollback_locally(last_l1_batch_to_keep).await {
+            return;
+        }
         tracing::info!("Rolling back state keeper storage to L1 batch #{last_l1_batch_to_keep}...");
 
         tracing::info!("Getting logs that should be applied to rollback state...");
@@ -379,20 +424,16 @@ impl RocksdbStorage {
         tokio::task::spawn_blocking(move || {
             let mut batch = db.new_write_batch();
 
-            let cf = StateKeeperColumnFamily::State;
             for (key, maybe_value) in logs {
                 if let Some((prev_value, prev_index)) = maybe_value {
-                    batch.put_cf(
-                        cf,
-                        key.as_slice(),
-                        &StateValue::new(prev_value, Some(prev_index)).serialize(),
-                    );
+                    let prev_value = StateValue::new(prev_value, Some(prev_index));
+                    batch.put_typed::<StateColumn>(&key.0, &prev_value);
                 } else {
-                    batch.delete_cf(cf, key.as_slice());
+                    batch.delete_typed::<StateColumn>(&key.0);
                 }
             }
             batch.put_cf(
-                cf,
+                StateKeeperColumnFamily::State,
                 Self::BLOCK_NUMBER_KEY,
                 &serialize_block_number(last_l1_batch_to_keep.0 + 1),
             );
@@ -401,32 +442,178 @@ impl RocksdbStorage {
             for factory_dep_hash in &factory_deps {
                 batch.delete_cf(cf, factory_dep_hash.as_slice());
             }
+            Self::delete_undo_records_after(&mut batch, last_l1_batch_to_keep);
+
+            db.write(batch)
+                .expect("failed to save state data into RocksDB");
+        })
+        .await
+        .unwrap();
+    }
+
+    /// Rolls back the state to a previous L1 batch number using only the local undo log.
+    ///
+    /// Returns `false` and leaves the storage intact if the undo log doesn't contain records
+    /// for all L1 batches after `last_l1_batch_to_keep`, e.g. because they were trimmed.
+    /// If there are no L1 batches after `last_l1_batch_to_keep`, there is nothing to revert,
+    /// and `true` is returned without touching the storage.
+    ///
+    /// # Panics
+    ///
+    /// Panics on RocksDB errors.
+    pub async fn rollback_locally(&mut self, last_l1_batch_to_keep: L1BatchNumber) -> bool {
+        let next_l1_batch_number = self.l1_batch_number().0;
+        if last_l1_batch_to_keep.0 >= next_l1_batch_number.saturating_sub(1) {
+            tracing::info!(
+                "State keeper storage has no L1 batches after #{last_l1_batch_to_keep}; nothing to roll back"
+            );
+            return true;
+        }
+        let first_l1_batch_to_revert = last_l1_batch_to_keep.0 + 1;
+        let stage_start = Instant::now();
+
+        let db = self.db.clone();
+        let rolled_back = tokio::task::spawn_blocking(move || {
+            let mut records = Vec::new();
+            for l1_batch_number in first_l1_batch_to_revert..next_l1_batch_number {
+                let record = db
+                    .get_typed::<UndoLogColumn>(&l1_batch_number)
+                    .expect("failed to read RocksDB undo log");
+                let Some(record) = record else {
+                    tracing::info!(
+                        "Undo log doesn't contain L1 batch #{l1_batch_number}; cannot roll back locally"
+                    );
+                    return false;
+                };
+                records.push(record);
+            }
+            tracing::info!(
+                "Rolling back state keeper storage to L1 batch #{last_l1_batch_to_keep} using undo log..."
+            );
+
+            let mut batch = db.new_write_batch();
+            // Records are applied from the newest batch to the oldest one, so that the final
+            // write for each key is its value before `first_l1_batch_to_revert`.
+            let mut reverted_state = HashMap::new();
+            let mut reverted_factory_deps = Vec::new();
+            for record in records.into_iter().rev() {
+                reverted_state.extend(record.state);
+                reverted_factory_deps.extend(record.factory_deps);
+            }
+
+            for (hashed_key, prev_value) in reverted_state {
+                if let Some(prev_value) = prev_value {
+                    batch.put_typed::<StateColumn>(&hashed_key.0, &prev_value);
+                } else {
+                    batch.delete_typed::<StateColumn>(&hashed_key.0);
+                }
+            }
+            batch.put_cf(
+                StateKeeperColumnFamily::State,
+                Self::BLOCK_NUMBER_KEY,
+                &serialize_block_number(first_l1_batch_to_revert),
+            );
+
+            let cf = StateKeeperColumnFamily::FactoryDeps;
+            for factory_dep_hash in &reverted_factory_deps {
+                batch.delete_cf(cf, factory_dep_hash.as_slice());
+            }
+            Self::delete_undo_records_after(&mut batch, last_l1_batch_to_keep);
 
             db.write(batch)
                 .expect("failed to save state data into RocksDB");
+            true
         })
         .await
         .unwrap();
+
+        if rolled_back {
+            tracing::info!(
+                "Rolled back state keeper storage using undo log, took {:?}",
+                stage_start.elapsed()
+            );
+        }
+        rolled_back
+    }
+
+    fn delete_undo_records_after(
+        batch: &mut WriteBatch<'_, StateKeeperColumnFamily>,
+        last_l1_batch_to_keep: L1BatchNumber,
+    ) {
+        let start = last_l1_batch_to_keep.0 + 1;
+        // The range end is exclusive, so the last possible record is deleted separately.
+        batch.delete_range_typed::<UndoLogColumn>(&start..&u32::MAX);
+        batch.delete_typed::<UndoLogColumn>(&u32::MAX);
+    }
+
+    /// Collects the undo record for the pending patch, i.e., values that will be overwritten
+    /// by it and factory deps that it will introduce. Reads from RocksDB, so it must be executed
+    /// on a blocking thread.
+    fn undo_record_for_patch(
+        db: &RocksDB<StateKeeperColumnFamily>,
+        pending_patch: &InMemoryStorage,
+    ) -> BatchUndo {
+        let state = pending_patch
+            .state
+            .keys()
+            .map(|key| {
+                let prev_value = db
+                    .get_typed::<StateColumn>(&Self::serialize_state_key(key))
+                    .expect("failed to read rocksdb state value");
+                (key.hashed_key(), prev_value)
+            })
+            .collect();
+        let factory_deps = pending_patch
+            .factory_deps
+            .keys()
+            .filter(|hash| {
+                let cf = StateKeeperColumnFamily::FactoryDeps;
+                let existing = db
+                    .get_cf(cf, hash.as_slice())
+                    .expect("failed to read RocksDB factory dep");
+                existing.is_none()
+            })
+            .copied()
+            .collect();
+        BatchUndo {
+            state,
+            factory_deps,
+        }
     }
 
     /// Saves the pending changes to RocksDB. Must be executed on a Tokio thread.
+    ///
+    /// `l1_batch_number` is the number of the next L1 batch to be processed; the pending changes
+    /// are recorded in the undo log as belonging to the preceding batch.
     async fn save(&mut self, l1_batch_number: L1BatchNumber) {
+        let undo_log_capacity = self.undo_log_capacity;
+        // Changes saved before the first L1 batch don't belong to any batch and cannot be undone.
+        let saved_l1_batch_number = l1_batch_number
+            .0
+            .checked_sub(1)
+            .filter(|_| undo_log_capacity > 0);
         let pending_patch = mem::take(&mut self.pending_patch);
 
         let db = self.db.clone();
         let save_task = tokio::task::spawn_blocking(move || {
             let mut batch = db.new_write_batch();
-            let cf = StateKeeperColumnFamily::State;
+            if let Some(saved_l1_batch_number) = saved_l1_batch_number {
+                let undo_record = Self::undo_record_for_patch(&db, &pending_patch);
+                batch.put_typed::<UndoLogColumn>(&saved_l1_batch_number, &undo_record);
+                if let Some(first_kept) = saved_l1_batch_number.checked_sub(undo_log_capacity - 1) {
+                    batch.delete_range_typed::<UndoLogColumn>(&0..&first_kept);
+                }
+            }
+
             batch.put_cf(
-                cf,
+                StateKeeperColumnFamily::State,
                 Self::BLOCK_NUMBER_KEY,
                 &serialize_block_number(l1_batch_number.0),
             );
             for (key, (value, enum_index)) in pending_patch.state {
-                batch.put_cf(
-                    cf,
+                batch.put_typed::<StateColumn>(
                     &Self::serialize_state_key(&key),
-                    &StateValue::new(value, Some(enum_index)).serialize(),
+                    &StateValue::new(value, Some(enum_index)),
                 );
             }
 
@@ -502,3 +689,208 @@ impl ReadStorage for RocksdbStorage {
             .map(|state_value| state_value.enum_index.unwrap())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use axon_types::{AccountTreeId, Address};
+    use axon_utils::b256_from_low_u64_be;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    type Contents = (
+        BTreeMap<Box<[u8]>, Box<[u8]>>,
+        BTreeMap<Box<[u8]>, Box<[u8]>>,
+    );
+
+    fn storage_key(index: u64) -> StorageKey {
+        let account = AccountTreeId::new(Address::from([0xfe; 20]));
+        StorageKey::new(account, b256_from_low_u64_be(index))
+    }
+
+    fn contents(storage: &RocksdbStorage) -> Contents {
+        let state = storage
+            .db
+            .from_iterator_cf(StateKeeperColumnFamily::State, &[])
+            .collect();
+        let factory_deps = storage
+            .db
+            .from_iterator_cf(StateKeeperColumnFamily::FactoryDeps, &[])
+            .collect();
+        (state, factory_deps)
+    }
+
+    /// Applies `changes` as the next L1 batch and returns the storage contents after the batch.
+    async fn apply_batch(
+        storage: &mut RocksdbStorage,
+        changes: &[(u64, u64, u64)],
+        factory_deps: &[u64],
+    ) -> Contents {
+        for &(key, value, enum_index) in changes {
+            storage
+                .pending_patch
+                .state
+                .insert(storage_key(key), (b256_from_low_u64_be(value), enum_index));
+        }
+        for &hash in factory_deps {
+            storage.store_factory_dep(b256_from_low_u64_be(hash), vec![hash as u8; 32]);
+        }
+        let next_l1_batch_number = storage.l1_batch_number().0 + 1;
+        storage.save(L1BatchNumber(next_l1_batch_number)).await;
+        contents(storage)
+    }
+
+    async fn apply_test_batches(storage: &mut RocksdbStorage) -> Vec<Contents> {
+        vec![
+            contents(storage),
+            apply_batch(storage, &[(1, 10, 1), (2, 20, 2), (3, 30, 3)], &[100]).await,
+            apply_batch(storage, &[(1, 11, 1), (4, 40, 4)], &[100, 101]).await,
+            apply_batch(storage, &[(2, 0, 2), (5, 50, 5)], &[]).await,
+            apply_batch(storage, &[(4, 41, 4), (6, 60, 6)], &[102]).await,
+        ]
+    }
+
+    #[test]
+    fn state_value_codec() {
+        let value = StateValue::new(b256_from_low_u64_be(1), None);
+        let bytes = value.encode_to_vec();
+        assert_eq!(bytes.len(), 32);
+        assert_eq!(StateValue::decode(&bytes).unwrap(), value);
+
+        let value = StateValue::new(b256_from_low_u64_be(1), Some(42));
+        let bytes = value.encode_to_vec();
+        assert_eq!(bytes.len(), 40);
+        assert_eq!(StateValue::decode(&bytes).unwrap(), value);
+
+        for len in [0, 31, 33, 39] {
+            StateValue::decode(&bytes[..len]).unwrap_err();
+        }
+    }
+
+    #[tokio::test]
+    async fn rolling_back_using_undo_log() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = RocksdbStorage::new(temp_dir.path());
+        let snapshots = apply_test_batches(&mut storage).await;
+        assert_eq!(storage.l1_batch_number(), L1BatchNumber(4));
+
+        assert!(storage.rollback_locally(L1BatchNumber(2)).await);
+        assert_eq!(storage.l1_batch_number(), L1BatchNumber(3));
+        assert_eq!(contents(&storage), snapshots[3]);
+
+        assert!(storage.rollback_locally(L1BatchNumber(0)).await);
+        assert_eq!(storage.l1_batch_number(), L1BatchNumber(1));
+        assert_eq!(contents(&storage), snapshots[1]);
+    }
+
+    #[tokio::test]
+    async fn replaying_batches_after_rollback() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = RocksdbStorage::new(temp_dir.path());
+        let snapshots = apply_test_batches(&mut storage).await;
+
+        assert!(storage.rollback_locally(L1BatchNumber(1)).await);
+        assert_eq!(contents(&storage), snapshots[2]);
+        let replayed = apply_batch(&mut storage, &[(2, 0, 2), (5, 50, 5)], &[]).await;
+        assert_eq!(replayed, snapshots[3]);
+        let replayed = apply_batch(&mut storage, &[(4, 41, 4), (6, 60, 6)], &[102]).await;
+        assert_eq!(replayed, snapshots[4]);
+
+        // Undo records of replayed batches must be usable as well.
+        assert!(storage.rollback_locally(L1BatchNumber(0)).await);
+        assert_eq!(contents(&storage), snapshots[1]);
+    }
+
+    #[tokio::test]
+    async fn undo_log_is_trimmed() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = RocksdbStorage::new(temp_dir.path());
+        storage.set_undo_log_capacity(2);
+        let snapshots = apply_test_batches(&mut storage).await;
+
+        let undo_keys: Vec<_> = storage
+            .db
+            .iter_typed::<UndoLogColumn>(..)
+            .map(|(l1_batch_number, _)| l1_batch_number)
+            .collect();
+        assert_eq!(undo_keys, [2, 3]);
+
+        assert!(!storage.rollback_locally(L1BatchNumber(0)).await);
+        assert_eq!(storage.l1_batch_number(), L1BatchNumber(4));
+        assert_eq!(contents(&storage), snapshots[4]);
+
+        assert!(storage.rollback_locally(L1BatchNumber(1)).await);
+        assert_eq!(contents(&storage), snapshots[2]);
+    }
+
+    #[tokio::test]
+    async fn saving_before_first_l1_batch() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = RocksdbStorage::new(temp_dir.path());
+        storage
+            .pending_patch
+            .state
+            .insert(storage_key(1), (b256_from_low_u64_be(10), 1));
+        storage.save(L1BatchNumber(0)).await;
+
+        assert_eq!(storage.l1_batch_number(), L1BatchNumber(0));
+        assert_eq!(
+            storage.read_value_inner(&storage_key(1)),
+            Some(b256_from_low_u64_be(10))
+        );
+        let undo_records = storage.db.iter_typed::<UndoLogColumn>(..).count();
+        assert_eq!(undo_records, 0);
+    }
+
+    #[tokio::test]
+    async fn rollback_removes_all_later_undo_records() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = RocksdbStorage::new(temp_dir.path());
+        apply_test_batches(&mut storage).await;
+        let mut batch = storage.db.new_write_batch();
+        batch.put_typed::<UndoLogColumn>(&u32::MAX, &BatchUndo::default());
+        storage.db.write(batch).unwrap();
+
+        assert!(storage.rollback_locally(L1BatchNumber(1)).await);
+        let undo_keys: Vec<_> = storage
+            .db
+            .iter_typed::<UndoLogColumn>(..)
+            .map(|(l1_batch_number, _)| l1_batch_number)
+            .collect();
+        assert_eq!(undo_keys, [0, 1]);
+    }
+
+    #[tokio::test]
+    async fn rollback_without_batches_to_revert() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = RocksdbStorage::new(temp_dir.path());
+        assert!(storage.rollback_locally(L1BatchNumber(0)).await);
+        assert_eq!(storage.l1_batch_number(), L1BatchNumber(0));
+
+        let snapshots = apply_test_batches(&mut storage).await;
+        for last_l1_batch_to_keep in [3, 10, u32::MAX] {
+            assert!(
+                storage
+                    .rollback_locally(L1BatchNumber(last_l1_batch_to_keep))
+                    .await
+            );
+            assert_eq!(storage.l1_batch_number(), L1BatchNumber(4));
+            assert_eq!(contents(&storage), snapshots[4]);
+        }
+        let undo_records = storage.db.iter_typed::<UndoLogColumn>(..).count();
+        assert_eq!(undo_records, 4);
+    }
+
+    #[tokio::test]
+    async fn disabled_undo_log() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = RocksdbStorage::new(temp_dir.path());
+        storage.set_undo_log_capacity(0);
+        apply_test_batches(&mut storage).await;
+
+        assert!(!storage.rollback_locally(L1BatchNumber(3)).await);
+        assert_eq!(storage.l1_batch_number(), L1BatchNumber(4));
+    }
+}
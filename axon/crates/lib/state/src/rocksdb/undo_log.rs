@@ -0,0 +1,161 @@
+//! Per-batch undo log for `RocksdbStorage`.
+//!
+//! Each record holds everything necessary to revert a single L1 batch applied to the storage
+//! without consulting Postgres: previous state values together with their enumeration indices,
+//! and hashes of factory deps first introduced in the batch.
+
+use axon_storage::typed::{Codec, CodecError, TypedColumnFamily};
+use axon_types::B256;
+
+use super::{StateKeeperColumnFamily, StateValue};
+
+/// Tag for a state key that was absent before the batch.
+const ABSENT_TAG: u8 = 0;
+/// Tag for a state value without an enumeration index (i.e., not yet migrated).
+const VALUE_TAG: u8 = 1;
+/// Tag for a state value with an enumeration index.
+const VALUE_WITH_INDEX_TAG: u8 = 2;
+
+/// Typed view of the undo log column family keyed by L1 batch number.
+#[derive(Debug)]
+pub(super) struct UndoLogColumn;
+
+impl TypedColumnFamily for UndoLogColumn {
+    type Family = StateKeeperColumnFamily;
+    const FAMILY: Self::Family = StateKeeperColumnFamily::UndoLog;
+    type Key = u32;
+    type Value = BatchUndo;
+}
+
+/// Changes necessary to revert a single L1 batch.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(super) struct BatchUndo {
+    /// Hashed storage keys touched in the batch together with their values before the batch.
+    /// `None` means that the key was absent.
+    pub state: Vec<(B256, Option<StateValue>)>,
+    /// Hashes of factory deps added in the batch.
+    pub factory_deps: Vec<B256>,
+}
+
+/// The record is serialized as follows:
+///
+/// - Number of state entries (u32, big-endian)
+/// - For each entry: 32-byte hashed key, 1-byte tag, and 0, 32 or 40 bytes of the previous
+///   [`StateValue`] depending on the tag
+/// - Concatenated 32-byte factory dep hashes
+impl Codec for BatchUndo {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        let state_len = u32::try_from(self.state.len()).expect("too many state entries");
+        buffer.reserve(4 + self.state.len() * 73 + self.factory_deps.len() * 32);
+        buffer.extend_from_slice(&state_len.to_be_bytes());
+        for (hashed_key, prev_value) in &self.state {
+            buffer.extend_from_slice(hashed_key.as_slice());
+            match prev_value {
+                None => buffer.push(ABSENT_TAG),
+                Some(value) => {
+                    let tag = if value.enum_index.is_some() {
+                        VALUE_WITH_INDEX_TAG
+                    } else {
+                        VALUE_TAG
+                    };
+                    buffer.push(tag);
+                    value.encode(buffer);
+                }
+            }
+        }
+        for hash in &self.factory_deps {
+            buffer.extend_from_slice(hash.as_slice());
+        }
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        const TRUNCATED_MSG: &str = "truncated undo log record";
+
+        if bytes.len() < 4 {
+            return Err(CodecError::new(TRUNCATED_MSG));
+        }
+        let (state_len, mut bytes) = bytes.split_at(4);
+        let state_len = u32::decode(state_len)?;
+        let mut state = Vec::with_capacity(state_len as usize);
+        for _ in 0..state_len {
+            if bytes.len() < 33 {
+                return Err(CodecError::new(TRUNCATED_MSG));
+            }
+            let hashed_key = B256::from_slice(&bytes[..32]);
+            let value_len = match bytes[32] {
+                ABSENT_TAG => 0,
+                VALUE_TAG => 32,
+                VALUE_WITH_INDEX_TAG => 40,
+                tag => return Err(CodecError::new(format!("unknown undo log tag: {tag}"))),
+            };
+            bytes = &bytes[33..];
+            if bytes.len() < value_len {
+                return Err(CodecError::new(TRUNCATED_MSG));
+            }
+            let prev_value = if value_len > 0 {
+                Some(StateValue::decode(&bytes[..value_len])?)
+            } else {
+                None
+            };
+            state.push((hashed_key, prev_value));
+            bytes = &bytes[value_len..];
+        }
+
+        if !bytes.len().is_multiple_of(32) {
+            return Err(CodecError::new(TRUNCATED_MSG));
+        }
+        let factory_deps = bytes.chunks(32).map(B256::from_slice).collect();
+        Ok(Self {
+            state,
+            factory_deps,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axon_utils::b256_from_low_u64_be;
+
+    use super::*;
+
+    #[test]
+    fn undo_record_serialization_roundtrip() {
+        let record = BatchUndo {
+            state: vec![
+                (b256_from_low_u64_be(1), None),
+                (
+                    b256_from_low_u64_be(2),
+                    Some(StateValue::new(b256_from_low_u64_be(3), None)),
+                ),
+                (
+                    b256_from_low_u64_be(4),
+                    Some(StateValue::new(b256_from_low_u64_be(5), Some(42))),
+                ),
+            ],
+            factory_deps: vec![b256_from_low_u64_be(6), b256_from_low_u64_be(7)],
+        };
+        let bytes = record.encode_to_vec();
+        assert_eq!(BatchUndo::decode(&bytes).unwrap(), record);
+
+        let empty_record = BatchUndo::default();
+        assert_eq!(empty_record.encode_to_vec(), [0; 4]);
+        assert_eq!(BatchUndo::decode(&[0; 4]).unwrap(), empty_record);
+    }
+
+    #[test]
+    fn decoding_malformed_undo_records() {
+        let record = BatchUndo {
+            state: vec![(b256_from_low_u64_be(1), None)],
+            factory_deps: vec![b256_from_low_u64_be(2)],
+        };
+        let bytes = record.encode_to_vec();
+        for len in [0, 3, 20, 36, 37 + 31] {
+            BatchUndo::decode(&bytes[..len]).unwrap_err();
+        }
+
+        let mut bytes = bytes;
+        bytes[36] = 0xff;
+        let err = BatchUndo::decode(&bytes).unwrap_err();
+        assert!(err.to_string().contains("unknown undo log tag"), "{err}");
+    }
+}
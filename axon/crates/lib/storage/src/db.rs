@@ -3,7 +3,7 @@ use std::{
     ffi::CStr,
     fmt, iter,
     marker::PhantomData,
-    ops,
+    ops::{self, Bound},
     path::Path,
     sync::{Arc, Condvar, Mutex},
     thread,
@@ -550,6 +550,42 @@ impl<CF: NamedColumnFamily> RocksDB<CF> {
             .fuse()
         // ^ unwrap() is safe for the same reasons as in `prefix_iterator_cf()`.
     }
+
+    /// Iterates over key-value pairs in the specified column family `cf` with keys restricted
+    /// to the specified `range`. Depending on `direction`, keys are iterated in the lexical order
+    /// or in the reverse lexical order.
+    pub fn range_iterator_cf(
+        &self,
+        cf: CF,
+        range: (Bound<&[u8]>, Bound<&[u8]>),
+        direction: Direction,
+    ) -> impl Iterator<Item = (Box<[u8]>, Box<[u8]>)> + '_ {
+        let cf = self.column_family(cf);
+        let mut options = ReadOptions::default();
+        // RocksDB bounds are inclusive for the lower bound and exclusive for the upper one.
+        // Appending a zero byte to a key produces the lexically smallest key that is greater
+        // than it, which allows to express the remaining kinds of bounds.
+        match range.0 {
+            Bound::Included(start) => options.set_iterate_lower_bound(start),
+            Bound::Excluded(start) => options.set_iterate_lower_bound([start, &[0]].concat()),
+            Bound::Unbounded => { /* do nothing */ }
+        }
+        match range.1 {
+            Bound::Included(end) => options.set_iterate_upper_bound([end, &[0]].concat()),
+            Bound::Excluded(end) => options.set_iterate_upper_bound(end),
+            Bound::Unbounded => { /* do nothing */ }
+        }
+        let mode = match direction {
+            Direction::Forward => IteratorMode::Start,
+            Direction::Reverse => IteratorMode::End,
+        };
+        self.inner
+            .db
+            .iterator_cf_opt(cf, options, mode)
+            .map(Result::unwrap)
+            .fuse()
+        // ^ unwrap() is safe for the same reasons as in `prefix_iterator_cf()`.
+    }
 }
 
 impl RocksDB<()> {
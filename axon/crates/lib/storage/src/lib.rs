@@ -1,5 +1,6 @@
 pub mod db;
 mod metrics;
+pub mod typed;
 
 pub use db::{RocksDB, RocksDBOptions, StalledWritesRetries};
 pub use rocksdb;
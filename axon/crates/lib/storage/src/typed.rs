@@ -0,0 +1,434 @@
+//! Typed access to RocksDB column families.
+//!
+//! A [`TypedColumnFamily`] binds a column family from a [`NamedColumnFamily`] enumeration
+//! to key and value types implementing [`Codec`]. Typed reads are provided by [`RocksDB`]
+//! methods with the `_typed` suffix, and typed writes by the similarly named [`WriteBatch`]
+//! methods.
+
+use std::{
+    borrow::Cow,
+    error, fmt,
+    ops::{self, Bound},
+};
+
+use rocksdb::Direction;
+
+use crate::db::{NamedColumnFamily, RocksDB, WriteBatch};
+
+/// Error decoding a key or value from the raw bytes stored in RocksDB.
+#[derive(Debug, Clone)]
+pub struct CodecError {
+    message: Cow<'static, str>,
+}
+
+impl CodecError {
+    /// Creates an error with the specified message.
+    pub fn new(message: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+
+    /// Creates an error signalling that the encoded data has an unexpected byte length.
+    pub fn unexpected_len(expected: usize, actual: usize) -> Self {
+        Self::new(format!(
+            "unexpected byte length: expected {expected}, got {actual}"
+        ))
+    }
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(&self.message)
+    }
+}
+
+impl error::Error for CodecError {}
+
+/// Encoding of a type as raw RocksDB keys or values.
+///
+/// For types used as keys, the lexical order of encoded keys should coincide with the logical
+/// order of keys; otherwise, range scans and range deletions will produce unexpected results.
+/// This is the case for the provided implementations for unsigned integers, which use
+/// big-endian encoding.
+pub trait Codec: Sized {
+    /// Appends the encoded representation of this value to `buffer`.
+    fn encode(&self, buffer: &mut Vec<u8>);
+
+    /// Decodes a value from the provided bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bytes` do not represent a valid value.
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError>;
+
+    /// Encodes this value into a newly allocated buffer.
+    fn encode_to_vec(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.encode(&mut buffer);
+        buffer
+    }
+}
+
+macro_rules! impl_codec_for_uint {
+    ($($uint:ty),+) => {
+        $(
+        impl Codec for $uint {
+            fn encode(&self, buffer: &mut Vec<u8>) {
+                buffer.extend_from_slice(&self.to_be_bytes());
+            }
+
+            fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+                let bytes = bytes.try_into().map_err(|_| {
+                    CodecError::unexpected_len(std::mem::size_of::<Self>(), bytes.len())
+                })?;
+                Ok(Self::from_be_bytes(bytes))
+            }
+        }
+        )+
+    };
+}
+
+impl_codec_for_uint!(u8, u16, u32, u64, u128);
+
+impl<const N: usize> Codec for [u8; N] {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        buffer.extend_from_slice(self);
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        bytes
+            .try_into()
+            .map_err(|_| CodecError::unexpected_len(N, bytes.len()))
+    }
+}
+
+impl Codec for Vec<u8> {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        buffer.extend_from_slice(self);
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        Ok(bytes.to_vec())
+    }
+}
+
+/// Empty values are useful for column families used as sets of keys.
+impl Codec for () {
+    fn encode(&self, _buffer: &mut Vec<u8>) {
+        // Nothing to encode
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        if bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(CodecError::unexpected_len(0, bytes.len()))
+        }
+    }
+}
+
+/// Column family with typed keys and values.
+///
+/// Implementations are usually zero-sized marker types, one per column family
+/// in a [`NamedColumnFamily`] enumeration.
+pub trait TypedColumnFamily: 'static {
+    /// Enumeration of column families in the database.
+    type Family: NamedColumnFamily;
+    /// Column family described by this type.
+    const FAMILY: Self::Family;
+    /// Type of keys in the column family.
+    type Key: Codec;
+    /// Type of values in the column family.
+    type Value: Codec;
+}
+
+fn decode_or_panic<T: Codec>(bytes: &[u8], cf_name: &str, kind: &str) -> T {
+    T::decode(bytes)
+        .unwrap_or_else(|err| panic!("failed decoding {kind} in column family `{cf_name}`: {err}"))
+}
+
+fn decode_entry<T: TypedColumnFamily>((key, value): (Box<[u8]>, Box<[u8]>)) -> (T::Key, T::Value) {
+    let cf_name = T::FAMILY.name();
+    (
+        decode_or_panic(&key, cf_name, "key"),
+        decode_or_panic(&value, cf_name, "value"),
+    )
+}
+
+fn encode_bound<K: Codec>(bound: Bound<&K>) -> Bound<Vec<u8>> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key.encode_to_vec()),
+        Bound::Excluded(key) => Bound::Excluded(key.encode_to_vec()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+impl<CF: NamedColumnFamily> RocksDB<CF> {
+    /// Reads a typed value for the specified `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored value cannot be decoded.
+    pub fn get_typed<T>(&self, key: &T::Key) -> Result<Option<T::Value>, rocksdb::Error>
+    where
+        T: TypedColumnFamily<Family = CF>,
+    {
+        let raw_value = self.get_cf(T::FAMILY, &key.encode_to_vec())?;
+        Ok(raw_value.map(|value| decode_or_panic(&value, T::FAMILY.name(), "value")))
+    }
+
+    /// Iterates over typed entries with keys in the specified `range` in the ascending key order.
+    ///
+    /// # Panics
+    ///
+    /// Panics on RocksDB errors and if an entry cannot be decoded.
+    pub fn iter_typed<T>(
+        &self,
+        range: impl ops::RangeBounds<T::Key>,
+    ) -> impl Iterator<Item = (T::Key, T::Value)> + '_
+    where
+        T: TypedColumnFamily<Family = CF>,
+    {
+        self.range_iter_typed::<T>(range, Direction::Forward)
+    }
+
+    /// Iterates over typed entries with keys in the specified `range` in the descending key order.
+    ///
+    /// # Panics
+    ///
+    /// Panics on RocksDB errors and if an entry cannot be decoded.
+    pub fn iter_typed_rev<T>(
+        &self,
+        range: impl ops::RangeBounds<T::Key>,
+    ) -> impl Iterator<Item = (T::Key, T::Value)> + '_
+    where
+        T: TypedColumnFamily<Family = CF>,
+    {
+        self.range_iter_typed::<T>(range, Direction::Reverse)
+    }
+
+    fn range_iter_typed<T>(
+        &self,
+        range: impl ops::RangeBounds<T::Key>,
+        direction: Direction,
+    ) -> impl Iterator<Item = (T::Key, T::Value)> + '_
+    where
+        T: TypedColumnFamily<Family = CF>,
+    {
+        let start = encode_bound(range.start_bound());
+        let end = encode_bound(range.end_bound());
+        let range = (
+            start.as_ref().map(Vec::as_slice),
+            end.as_ref().map(Vec::as_slice),
+        );
+        self.range_iterator_cf(T::FAMILY, range, direction)
+            .map(decode_entry::<T>)
+    }
+
+    /// Iterates over typed entries in the ascending key order. Keys are filtered so that
+    /// their encoding starts with the encoding of `prefix`.
+    ///
+    /// # Panics
+    ///
+    /// Panics on RocksDB errors and if an entry cannot be decoded.
+    pub fn prefix_iter_typed<T, P>(
+        &self,
+        prefix: &P,
+    ) -> impl Iterator<Item = (T::Key, T::Value)> + '_
+    where
+        T: TypedColumnFamily<Family = CF>,
+        P: Codec,
+    {
+        self.prefix_iterator_cf(T::FAMILY, &prefix.encode_to_vec())
+            .map(decode_entry::<T>)
+    }
+}
+
+impl<CF: NamedColumnFamily> WriteBatch<'_, CF> {
+    /// Puts a typed key–value entry into this batch.
+    pub fn put_typed<T>(&mut self, key: &T::Key, value: &T::Value)
+    where
+        T: TypedColumnFamily<Family = CF>,
+    {
+        self.put_cf(T::FAMILY, &key.encode_to_vec(), &value.encode_to_vec());
+    }
+
+    /// Deletes a typed key.
+    pub fn delete_typed<T>(&mut self, key: &T::Key)
+    where
+        T: TypedColumnFamily<Family = CF>,
+    {
+        self.delete_cf(T::FAMILY, &key.encode_to_vec());
+    }
+
+    /// Deletes all keys in the specified half-open range.
+    pub fn delete_range_typed<T>(&mut self, keys: ops::Range<&T::Key>)
+    where
+        T: TypedColumnFamily<Family = CF>,
+    {
+        let start = keys.start.encode_to_vec();
+        let end = keys.end.encode_to_vec();
+        self.delete_range_cf(T::FAMILY, &start[..]..&end[..]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy)]
+    enum TestColumnFamilies {
+        Numbers,
+        Hashes,
+    }
+
+    impl NamedColumnFamily for TestColumnFamilies {
+        const DB_NAME: &'static str = "test";
+        const ALL: &'static [Self] = &[Self::Numbers, Self::Hashes];
+
+        fn name(&self) -> &'static str {
+            match self {
+                Self::Numbers => "numbers",
+                Self::Hashes => "hashes",
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct Numbers;
+
+    impl TypedColumnFamily for Numbers {
+        type Family = TestColumnFamilies;
+        const FAMILY: Self::Family = TestColumnFamilies::Numbers;
+        type Key = u32;
+        type Value = Vec<u8>;
+    }
+
+    #[derive(Debug)]
+    struct Hashes;
+
+    impl TypedColumnFamily for Hashes {
+        type Family = TestColumnFamilies;
+        const FAMILY: Self::Family = TestColumnFamilies::Hashes;
+        type Key = [u8; 4];
+        type Value = ();
+    }
+
+    fn create_db(temp_dir: &TempDir) -> RocksDB<TestColumnFamilies> {
+        let db = RocksDB::new(temp_dir.path()).with_sync_writes();
+        let mut batch = db.new_write_batch();
+        for number in [1_u32, 2, 255, 256, 1_000] {
+            batch.put_typed::<Numbers>(&number, &number.to_string().into_bytes());
+        }
+        for hash in [[0, 0, 1, 2], [0, 1, 0, 0], [0, 1, 2, 3], [1, 0, 0, 0]] {
+            batch.put_typed::<Hashes>(&hash, &());
+        }
+        db.write(batch).unwrap();
+        db
+    }
+
+    fn keys<K, V>(iter: impl Iterator<Item = (K, V)>) -> Vec<K> {
+        iter.map(|(key, _)| key).collect()
+    }
+
+    #[test]
+    fn uint_codec_preserves_order() {
+        let numbers = [0_u64, 1, 255, 256, 65_535, 1 << 40, u64::MAX];
+        for window in numbers.windows(2) {
+            assert!(window[0].encode_to_vec() < window[1].encode_to_vec());
+        }
+        for number in numbers {
+            assert_eq!(u64::decode(&number.encode_to_vec()).unwrap(), number);
+        }
+
+        let err = u32::decode(&[1, 2, 3]).unwrap_err();
+        assert!(err.to_string().contains("expected 4, got 3"), "{err}");
+        <()>::decode(&[1]).unwrap_err();
+    }
+
+    #[test]
+    fn typed_reads() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = create_db(&temp_dir);
+
+        let value = db.get_typed::<Numbers>(&255).unwrap();
+        assert_eq!(value.unwrap(), b"255");
+        let value = db.get_typed::<Numbers>(&3).unwrap();
+        assert_eq!(value, None);
+        let value = db.get_typed::<Hashes>(&[0, 1, 2, 3]).unwrap();
+        assert_eq!(value, Some(()));
+    }
+
+    #[test]
+    fn range_scans() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = create_db(&temp_dir);
+
+        assert_eq!(keys(db.iter_typed::<Numbers>(..)), [1, 2, 255, 256, 1_000]);
+        assert_eq!(keys(db.iter_typed::<Numbers>(2..256)), [2, 255]);
+        assert_eq!(keys(db.iter_typed::<Numbers>(2..=256)), [2, 255, 256]);
+        assert_eq!(keys(db.iter_typed::<Numbers>(3..)), [255, 256, 1_000]);
+        let range = (Bound::Excluded(2), Bound::Included(1_000));
+        assert_eq!(keys(db.iter_typed::<Numbers>(range)), [255, 256, 1_000]);
+
+        assert_eq!(
+            keys(db.iter_typed_rev::<Numbers>(..)),
+            [1_000, 256, 255, 2, 1]
+        );
+        assert_eq!(keys(db.iter_typed_rev::<Numbers>(..256)), [255, 2, 1]);
+        assert_eq!(keys(db.iter_typed_rev::<Numbers>(2..=256)), [256, 255, 2]);
+        assert_eq!(
+            keys(db.iter_typed_rev::<Numbers>(257..1_000)),
+            [] as [u32; 0]
+        );
+    }
+
+    #[test]
+    fn prefix_scans() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = create_db(&temp_dir);
+
+        let hashes = keys(db.prefix_iter_typed::<Hashes, _>(&[0_u8, 1]));
+        assert_eq!(hashes, [[0, 1, 0, 0], [0, 1, 2, 3]]);
+        let hashes = keys(db.prefix_iter_typed::<Hashes, _>(&0_u8));
+        assert_eq!(hashes, [[0, 0, 1, 2], [0, 1, 0, 0], [0, 1, 2, 3]]);
+        // Numbers with the most significant bytes equal to 0
+        let numbers = keys(db.prefix_iter_typed::<Numbers, _>(&0_u16));
+        assert_eq!(numbers, [1, 2, 255, 256, 1_000]);
+        let numbers = keys(db.prefix_iter_typed::<Numbers, _>(&[0_u8, 0, 1]));
+        assert_eq!(numbers, [256]);
+    }
+
+    #[test]
+    fn typed_deletions() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = create_db(&temp_dir);
+
+        let mut batch = db.new_write_batch();
+        batch.delete_typed::<Numbers>(&1);
+        batch.delete_range_typed::<Numbers>(&255..&1_000);
+        batch.delete_range_typed::<Hashes>(&[0, 1, 0, 0]..&[1, 0, 0, 0]);
+        db.write(batch).unwrap();
+
+        assert_eq!(keys(db.iter_typed::<Numbers>(..)), [2, 1_000]);
+        assert_eq!(
+            keys(db.iter_typed::<Hashes>(..)),
+            [[0, 0, 1, 2], [1, 0, 0, 0]]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "failed decoding value in column family `hashes`")]
+    fn decoding_error_is_reported() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = RocksDB::<TestColumnFamilies>::new(temp_dir.path()).with_sync_writes();
+        let mut batch = db.new_write_batch();
+        batch.put_cf(TestColumnFamilies::Hashes, &[0, 0, 0, 0], b"junk");
+        db.write(batch).unwrap();
+
+        db.iter_typed::<Hashes>(..).for_each(drop);
+    }
+}
@@ -27,6 +27,32 @@ pub enum FailedToCompressBytecodeError {
     InvalidBytecode(#[from] InvalidBytecodeError),
 }
 
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum FailedToDecompressBytecodeError {
+    #[error("Compressed bytecode is too short to contain the dictionary length")]
+    MissingDictionaryLength,
+    #[error("Dictionary is truncated: expected {0} bytes, got {1}")]
+    TruncatedDictionary(usize, usize),
+    #[error("Encoded data length {0} is not divisible by 2")]
+    OddEncodedDataLength(usize),
+    #[error("Encoded chunk index {0} is out of bounds of the dictionary with {1} chunks")]
+    ChunkIndexOutOfBounds(u16, usize),
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum InvalidCompressedBytecodeError {
+    #[error("Original bytecode is invalid: {0}")]
+    InvalidBytecode(#[from] InvalidBytecodeError),
+    #[error("Bytecode hash mismatch: expected {expected}, got {actual}")]
+    HashMismatch { expected: B256, actual: B256 },
+    #[error("Failed to decompress bytecode: {0}")]
+    Decompression(#[from] FailedToDecompressBytecodeError),
+    #[error("Encoded data length {0} does not match the original bytecode length {1}")]
+    EncodedDataLengthMismatch(usize, usize),
+    #[error("Encoded chunk #{0} does not match the original bytecode")]
+    ChunkMismatch(usize),
+}
+
 /// Implements, a simple compression algorithm for the bytecode.
 pub fn compress_bytecode(code: &[u8]) -> Result<Vec<u8>, FailedToCompressBytecodeError> {
     validate_bytecode(code)?;
@@ -95,6 +121,51 @@ pub fn compress_bytecode(code: &[u8]) -> Result<Vec<u8>, FailedToCompressBytecod
     Ok(compressed)
 }
 
+/// Pubdata pricing used to decide whether a bytecode should be published compressed.
+/// Production values are defined in `axon_constants::fees`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BytecodePublishingCosts {
+    /// L1 gas paid for each non-zero byte of pubdata.
+    pub gas_per_pubdata_byte: u32,
+    /// L1 gas paid for each zero byte of pubdata.
+    pub gas_per_zero_pubdata_byte: u32,
+    /// Fixed overhead in pubdata bytes paid for each published bytecode, regardless of its form.
+    pub publish_bytecode_overhead: u32,
+}
+
+impl BytecodePublishingCosts {
+    /// Returns the L1 gas needed to publish `bytes` of a bytecode, including the overhead.
+    pub fn publishing_gas(&self, bytes: &[u8]) -> u64 {
+        let zero_bytes = bytes.iter().filter(|&&byte| byte == 0).count() as u64;
+        let nonzero_bytes = bytes.len() as u64 - zero_bytes;
+        (u64::from(self.publish_bytecode_overhead) + nonzero_bytes)
+            * u64::from(self.gas_per_pubdata_byte)
+            + zero_bytes * u64::from(self.gas_per_zero_pubdata_byte)
+    }
+}
+
+/// Compresses the bytecode if it reduces the L1 gas of publishing it according to `costs`.
+/// Returns `None` if the bytecode should be published raw.
+///
+/// The compressed format used by the `Compressor` system contract encodes every 8-byte chunk
+/// as a 2-byte dictionary index, so each distinct chunk has to be included in the dictionary,
+/// and the decision is made for the bytecode as a whole. Zero bytes are cheaper to publish,
+/// so the decision may differ from comparing lengths: e.g., chunks consisting mostly of zeros
+/// are cheap to publish raw, while 2-byte indices of the less popular chunks are not. Bytecodes
+/// that overflow the dictionary are published raw as well.
+pub fn compress_bytecode_if_profitable(
+    code: &[u8],
+    costs: &BytecodePublishingCosts,
+) -> Result<Option<Vec<u8>>, InvalidBytecodeError> {
+    let compressed = match compress_bytecode(code) {
+        Ok(compressed) => compressed,
+        Err(FailedToCompressBytecodeError::InvalidBytecode(err)) => return Err(err),
+        Err(FailedToCompressBytecodeError::DictionaryOverflow) => return Ok(None),
+    };
+    let is_profitable = costs.publishing_gas(&compressed) < costs.publishing_gas(code);
+    Ok(is_profitable.then_some(compressed))
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CompressedBytecodeInfo {
     pub original: Vec<u8>,
@@ -113,6 +184,34 @@ impl CompressedBytecodeInfo {
         Ok(result)
     }
 
+    /// Same as [`Self::from_original()`], but returns `None` if publishing the bytecode raw
+    /// is cheaper than publishing it compressed. See [`compress_bytecode_if_profitable()`]
+    /// for details.
+    pub fn from_original_if_profitable(
+        bytecode: Vec<u8>,
+        costs: &BytecodePublishingCosts,
+    ) -> Result<Option<Self>, InvalidBytecodeError> {
+        let compressed = compress_bytecode_if_profitable(&bytecode, costs)?;
+        Ok(compressed.map(|compressed| Self {
+            original: bytecode,
+            compressed,
+        }))
+    }
+
+    /// Checks that the compressed bytecode expands to the original one, which has
+    /// the `expected_hash`.
+    pub fn verify(&self, expected_hash: B256) -> Result<(), InvalidCompressedBytecodeError> {
+        verify_compressed(&self.original, &self.compressed)?;
+        let actual = hash_bytecode(&self.original);
+        if actual != expected_hash {
+            return Err(InvalidCompressedBytecodeError::HashMismatch {
+                expected: expected_hash,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
     pub fn encode_call(&self) -> Vec<u8> {
         let bytecode_hash = hash_bytecode(&self.original).to_vec();
         let empty_cell = vec![0u8; 32];
@@ -127,6 +226,78 @@ impl CompressedBytecodeInfo {
     }
 }
 
+/// Splits the compressed bytecode into the dictionary and encoded data.
+fn split_compressed_bytecode(
+    compressed: &[u8],
+) -> Result<(&[u8], &[u8]), FailedToDecompressBytecodeError> {
+    if compressed.len() < 2 {
+        return Err(FailedToDecompressBytecodeError::MissingDictionaryLength);
+    }
+    let dictionary_len = u16::from_be_bytes([compressed[0], compressed[1]]) as usize;
+    let rest = &compressed[2..];
+    let dictionary_len_in_bytes = dictionary_len * 8;
+    if rest.len() < dictionary_len_in_bytes {
+        return Err(FailedToDecompressBytecodeError::TruncatedDictionary(
+            dictionary_len_in_bytes,
+            rest.len(),
+        ));
+    }
+    let (dictionary, encoded_data) = rest.split_at(dictionary_len_in_bytes);
+    if !encoded_data.len().is_multiple_of(2) {
+        return Err(FailedToDecompressBytecodeError::OddEncodedDataLength(
+            encoded_data.len(),
+        ));
+    }
+    Ok((dictionary, encoded_data))
+}
+
+/// Decompresses the bytecode produced by [`compress_bytecode()`].
+pub fn decompress_bytecode(compressed: &[u8]) -> Result<Vec<u8>, FailedToDecompressBytecodeError> {
+    let (dictionary, encoded_data) = split_compressed_bytecode(compressed)?;
+    let dictionary: Vec<_> = dictionary.chunks(8).collect();
+
+    let mut decompressed = Vec::with_capacity(encoded_data.len() * 4);
+    for index_bytes in encoded_data.chunks(2) {
+        let index = u16::from_be_bytes([index_bytes[0], index_bytes[1]]);
+        let chunk = dictionary.get(index as usize).ok_or(
+            FailedToDecompressBytecodeError::ChunkIndexOutOfBounds(index, dictionary.len()),
+        )?;
+        decompressed.extend_from_slice(chunk);
+    }
+    Ok(decompressed)
+}
+
+/// Verifies that `compressed` is a valid compression of the `original` bytecode. Mirrors the checks
+/// performed by the `Compressor` system contract when publishing a compressed bytecode, so that
+/// a compressed bytecode passing this check is accepted on-chain.
+pub fn verify_compressed(
+    original: &[u8],
+    compressed: &[u8],
+) -> Result<(), InvalidCompressedBytecodeError> {
+    validate_bytecode(original)?;
+    let (dictionary, encoded_data) = split_compressed_bytecode(compressed)?;
+    if encoded_data.len() * 4 != original.len() {
+        return Err(InvalidCompressedBytecodeError::EncodedDataLengthMismatch(
+            encoded_data.len(),
+            original.len(),
+        ));
+    }
+
+    let dictionary: Vec<_> = dictionary.chunks(8).collect();
+    for (position, (index_bytes, original_chunk)) in
+        encoded_data.chunks(2).zip(original.chunks(8)).enumerate()
+    {
+        let index = u16::from_be_bytes([index_bytes[0], index_bytes[1]]);
+        let chunk = dictionary.get(index as usize).ok_or(
+            FailedToDecompressBytecodeError::ChunkIndexOutOfBounds(index, dictionary.len()),
+        )?;
+        if *chunk != original_chunk {
+            return Err(InvalidCompressedBytecodeError::ChunkMismatch(position));
+        }
+    }
+    Ok(())
+}
+
 pub fn validate_bytecode(code: &[u8]) -> Result<(), InvalidBytecodeError> {
     let bytecode_len = code.len();
 
@@ -168,41 +339,18 @@ pub fn bytecode_len_in_bytes(bytecodehash: B256) -> usize {
 #[cfg(test)]
 mod test {
     use axon_primitives::web3::hex;
+    use proptest::prelude::*;
 
     use super::*;
 
-    fn decompress_bytecode(raw_compressed_bytecode: &[u8]) -> Vec<u8> {
-        let mut decompressed: Vec<u8> = Vec::new();
-        let mut dictionary: Vec<u64> = Vec::new();
-
-        let dictionary_len = u16::from_be_bytes(raw_compressed_bytecode[0..2].try_into().unwrap());
-        for index in 0..dictionary_len {
-            let chunk = u64::from_be_bytes(
-                raw_compressed_bytecode[2 + index as usize * 8..10 + index as usize * 8]
-                    .try_into()
-                    .unwrap(),
-            );
-            dictionary.push(chunk);
-        }
-
-        let encoded_data = &raw_compressed_bytecode[2 + dictionary_len as usize * 8..];
-        for index_bytes in encoded_data.chunks(2) {
-            let index = u16::from_be_bytes(index_bytes.try_into().unwrap());
-
-            let chunk = dictionary[index as usize];
-            decompressed.extend(chunk.to_be_bytes());
-        }
-
-        decompressed
-    }
-
     #[test]
     fn bytecode_compression_test() {
         let example_code = hex::decode("000200000000000200010000000103550000006001100270000000150010019d0000000101200190000000080000c13d0000000001000019004e00160000040f0000000101000039004e00160000040f0000001504000041000000150510009c000000000104801900000040011002100000000001310019000000150320009c0000000002048019000000600220021000000000012100190000004f0001042e000000000100001900000050000104300000008002000039000000400020043f0000000002000416000000000110004c000000240000613d000000000120004c0000004d0000c13d000000200100003900000100001004430000012000000443000001000100003900000040020000390000001d03000041004e000a0000040f000000000120004c0000004d0000c13d0000000001000031000000030110008c0000004d0000a13d0000000101000367000000000101043b0000001601100197000000170110009c0000004d0000c13d0000000101000039000000000101041a0000000202000039000000000202041a000000400300043d00000040043000390000001805200197000000000600041a0000000000540435000000180110019700000020043000390000000000140435000000a0012002700000001901100197000000600430003900000000001404350000001a012001980000001b010000410000000001006019000000b8022002700000001c02200197000000000121019f0000008002300039000000000012043500000018016001970000000000130435000000400100043d0000000002130049000000a0022000390000000003000019004e000a0000040f004e00140000040f0000004e000004320000004f0001042e000000500001043000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000ffffffffffffffff000000000000000000000000000000000000000000000000000000008903573000000000000000000000000000000000000000000000000000000000000000000000000000000000ffffffffffffffffffffffffffffffffffffffff0000000000000000000000000000000000000000000000000000000000ffffff0000000000008000000000000000000000000000000000000000000000000000ffffffffffffffffffffffffffffffffffffffffffffffffffffffffff80000000000000000000000000000000000000000000000000000000000000007fffff00000002000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000").unwrap();
         let compressed = compress_bytecode(&example_code).unwrap();
-        let decompressed = decompress_bytecode(&compressed);
+        let decompressed = decompress_bytecode(&compressed).unwrap();
 
         assert_eq!(example_code, decompressed);
+        verify_compressed(&example_code, &compressed).unwrap();
     }
 
     #[test]
@@ -220,4 +368,167 @@ mod test {
 
         assert_eq!(expected_encoding, compress_bytecode(&example_code).unwrap());
     }
+
+    #[test]
+    fn decompressing_malformed_bytecode() {
+        assert_eq!(
+            decompress_bytecode(&[0]),
+            Err(FailedToDecompressBytecodeError::MissingDictionaryLength)
+        );
+        assert_eq!(
+            decompress_bytecode(&[0, 2, 1, 2, 3, 4, 5, 6, 7, 8]),
+            Err(FailedToDecompressBytecodeError::TruncatedDictionary(16, 8))
+        );
+        assert_eq!(
+            decompress_bytecode(&[0, 1, 1, 2, 3, 4, 5, 6, 7, 8, 0]),
+            Err(FailedToDecompressBytecodeError::OddEncodedDataLength(1))
+        );
+        assert_eq!(
+            decompress_bytecode(&[0, 1, 1, 2, 3, 4, 5, 6, 7, 8, 0, 0, 0, 1]),
+            Err(FailedToDecompressBytecodeError::ChunkIndexOutOfBounds(1, 1))
+        );
+    }
+
+    #[test]
+    fn verifying_compressed_bytecode() {
+        let code = [[1_u8; 8], [2; 8], [1; 8], [3; 8]].repeat(3).concat();
+        let compressed = compress_bytecode(&code).unwrap();
+        verify_compressed(&code, &compressed).unwrap();
+
+        let info = CompressedBytecodeInfo::from_original(code.clone()).unwrap();
+        info.verify(hash_bytecode(&code)).unwrap();
+        let err = info.verify(B256::ZERO).unwrap_err();
+        assert!(
+            matches!(err, InvalidCompressedBytecodeError::HashMismatch { .. }),
+            "{err:?}"
+        );
+
+        // Encoded data for a different bytecode.
+        let mut other_code = code.clone();
+        other_code[..8].copy_from_slice(&[2; 8]);
+        let err = verify_compressed(&other_code, &compressed).unwrap_err();
+        assert_eq!(err, InvalidCompressedBytecodeError::ChunkMismatch(0));
+
+        // Truncated encoded data.
+        let err = verify_compressed(&code, &compressed[..compressed.len() - 2]).unwrap_err();
+        assert_eq!(
+            err,
+            InvalidCompressedBytecodeError::EncodedDataLengthMismatch(22, 96)
+        );
+
+        // Invalid original bytecode.
+        let err = verify_compressed(&code[..64], &compressed).unwrap_err();
+        assert_eq!(
+            err,
+            InvalidCompressedBytecodeError::InvalidBytecode(
+                InvalidBytecodeError::BytecodeLengthInWordsIsEven
+            )
+        );
+    }
+
+    /// Generates valid bytecodes with a configurable number of distinct 8-byte chunks
+    /// to have both highly repetitive and mostly unique bytecodes.
+    fn bytecode_strategy() -> impl Strategy<Value = Vec<u8>> {
+        (0_usize..8, 1_u64..64).prop_flat_map(|(half_len_in_words, distinct_chunks)| {
+            let len_in_chunks = (half_len_in_words * 2 + 1) * 4;
+            prop::collection::vec(0..distinct_chunks, len_in_chunks).prop_map(|chunks| {
+                chunks
+                    .into_iter()
+                    .flat_map(|chunk| chunk.wrapping_mul(0x9e37_79b9_7f4a_7c15).to_be_bytes())
+                    .collect()
+            })
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn compression_roundtrip(code in bytecode_strategy()) {
+            let compressed = compress_bytecode(&code).unwrap();
+            prop_assert_eq!(decompress_bytecode(&compressed).unwrap(), code.clone());
+            verify_compressed(&code, &compressed).unwrap();
+            CompressedBytecodeInfo { original: code.clone(), compressed }
+                .verify(hash_bytecode(&code))
+                .unwrap();
+        }
+
+        #[test]
+        fn compression_is_used_only_if_profitable(code in bytecode_strategy()) {
+            let published = compress_bytecode_if_profitable(&code, &TEST_COSTS).unwrap();
+            let raw_gas = TEST_COSTS.publishing_gas(&code);
+            let published_gas = published
+                .as_deref()
+                .map_or(raw_gas, |compressed| TEST_COSTS.publishing_gas(compressed));
+            if let Some(compressed) = &published {
+                verify_compressed(&code, compressed).unwrap();
+            }
+            let compressed_gas = TEST_COSTS.publishing_gas(&compress_bytecode(&code).unwrap());
+            prop_assert_eq!(published_gas, compressed_gas.min(raw_gas));
+        }
+
+        #[test]
+        fn verification_rejects_corrupted_compression(
+            code in bytecode_strategy(),
+            corrupted_byte in any::<prop::sample::Index>(),
+            xor_mask in 1_u8..,
+        ) {
+            let mut compressed = compress_bytecode(&code).unwrap();
+            let corrupted_byte = corrupted_byte.index(compressed.len());
+            compressed[corrupted_byte] ^= xor_mask;
+
+            // Corrupting a byte may still produce a valid compression, e.g. if it modifies
+            // an unused dictionary chunk, so we check consistency with decompression.
+            let expands_to_original =
+                decompress_bytecode(&compressed).is_ok_and(|decompressed| decompressed == code);
+            prop_assert_eq!(verify_compressed(&code, &compressed).is_ok(), expands_to_original);
+        }
+    }
+
+    const TEST_COSTS: BytecodePublishingCosts = BytecodePublishingCosts {
+        gas_per_pubdata_byte: 17,
+        gas_per_zero_pubdata_byte: 4,
+        publish_bytecode_overhead: 100,
+    };
+
+    #[test]
+    fn publishing_gas_depends_on_zero_bytes() {
+        assert_eq!(TEST_COSTS.publishing_gas(&[]), 1_700);
+        assert_eq!(
+            TEST_COSTS.publishing_gas(&[1, 0, 0, 2]),
+            1_700 + 2 * 17 + 2 * 4
+        );
+    }
+
+    #[test]
+    fn bytecode_is_compressed_only_if_profitable() {
+        // 12 chunks, 3 of which are distinct: 50 bytes with mostly zero indices vs 96 bytes.
+        let repetitive_code = [[1_u8; 8], [2; 8], [1; 8], [3; 8]].repeat(3).concat();
+        let compressed = compress_bytecode_if_profitable(&repetitive_code, &TEST_COSTS)
+            .unwrap()
+            .expect("compression should pay off");
+        assert_eq!(compressed, compress_bytecode(&repetitive_code).unwrap());
+
+        // 12 distinct chunks: 2 + 12 * 8 + 12 * 2 = 122 bytes vs 96 bytes.
+        let unique_code: Vec<_> = (1_u64..=12).flat_map(u64::to_be_bytes).collect();
+        let compressed = compress_bytecode_if_profitable(&unique_code, &TEST_COSTS).unwrap();
+        assert_eq!(compressed, None);
+        let info =
+            CompressedBytecodeInfo::from_original_if_profitable(unique_code, &TEST_COSTS).unwrap();
+        assert_eq!(info, None);
+
+        // 8 distinct chunks of mostly zero bytes: 2 + 8 * 8 + 12 * 2 = 90 bytes vs 96 bytes,
+        // but 607 gas vs 540 gas excluding the overhead, since the raw chunks cost
+        // 7 * 4 + 17 = 45 gas each, and most of the indices cost 4 + 17 = 21 gas.
+        let code: Vec<_> = (1_u64..=8)
+            .chain(1..=4)
+            .flat_map(u64::to_be_bytes)
+            .collect();
+        let compressed_len = compress_bytecode(&code).unwrap().len();
+        assert_eq!(compressed_len, 90);
+        assert_eq!(TEST_COSTS.publishing_gas(&code), 1_700 + 540);
+        let compressed = compress_bytecode_if_profitable(&code, &TEST_COSTS).unwrap();
+        assert_eq!(compressed, None);
+
+        let err = compress_bytecode_if_profitable(&[0; 64], &TEST_COSTS).unwrap_err();
+        assert_eq!(err, InvalidBytecodeError::BytecodeLengthInWordsIsEven);
+    }
 }
@@ -1,61 +1,477 @@
-use reqwest::{header::HeaderMap, Client, Error, Method, Response};
-use tokio::time::{sleep, Duration};
+use std::{fmt, sync::Arc};
+
+use rand::Rng;
+use reqwest::{
+    header::{HeaderMap, RETRY_AFTER},
+    Client, Error, Method, Response, StatusCode,
+};
+use tokio::time::{sleep, Duration, Instant};
+use vetric::{
+    Buckets, Counter, EncodeLabelSet, EncodeLabelValue, Family, Histogram, Metrics, Unit,
+};
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    EncodeLabelValue,
+    EncodeLabelSet
+)]
+#[metrics(label = "outcome", rename_all = "snake_case")]
+enum AttemptOutcome {
+    Success,
+    RetryableStatus,
+    NonRetryableStatus,
+    Error,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    EncodeLabelValue,
+    EncodeLabelSet
+)]
+#[metrics(label = "outcome", rename_all = "snake_case")]
+enum RequestOutcome {
+    Success,
+    NonRetryableStatus,
+    NonRetryableError,
+    RetriesExhausted,
+}
+
+const RETRY_BUCKETS: Buckets = Buckets::linear(0.0..=10.0, 1.0);
+
+#[derive(Debug, Metrics)]
+#[metrics(prefix = "http_with_retries")]
+struct HttpRetryMetrics {
+    /// Number of HTTP request attempts grouped by their outcome.
+    attempts: Family<AttemptOutcome, Counter>,
+    /// Latency of a single HTTP request attempt.
+    #[metrics(buckets = Buckets::LATENCIES, unit = Unit::Seconds)]
+    attempt_latency: Histogram<Duration>,
+    /// Number of HTTP requests (including all retries) grouped by their outcome.
+    requests: Family<RequestOutcome, Counter>,
+    /// Number of retries made for an HTTP request.
+    #[metrics(buckets = RETRY_BUCKETS)]
+    retries: Histogram<usize>,
+}
+
+#[vetric::register]
+static METRICS: vetric::Global<HttpRetryMetrics> = vetric::Global::new();
 
 #[derive(Debug)]
 pub enum HttpError {
     ReqwestError(Error),
     RetryExhausted(String),
+    /// The server responded with a status that should not be retried according to the policy.
+    NonRetryableStatus(Response),
 }
 
-/// Method to send HTTP request with fixed number of retires with exponential back-offs.
-pub async fn send_request_with_retries(
-    url: &str,
+/// Returns `true` for statuses that indicate a transient failure: request timeout (408),
+/// too many requests (429) and server errors (5xx).
+pub fn is_transient_status(status: StatusCode) -> bool {
+    status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status.is_server_error()
+}
+
+type StatusPredicate = Arc<dyn Fn(StatusCode) -> bool + Send + Sync>;
+
+/// Policy for retrying HTTP requests with exponential back-offs.
+///
+/// The policy holds an HTTP client, which is reused across attempts and requests;
+/// clones of the policy share the client.
+#[derive(Clone)]
+pub struct RetryPolicy {
+    client: Client,
     max_retries: usize,
-    method: Method,
-    headers: Option<HeaderMap>,
-    body: Option<Vec<u8>>,
-) -> Result<Response, HttpError> {
-    let mut retries = 0usize;
-    let mut delay = Duration::from_secs(1);
-    loop {
-        let result = send_request(url, method.clone(), headers.clone(), body.clone()).await;
-        match result {
-            Ok(response) if response.status().is_success() => return Ok(response),
-            Ok(response) => {
-                tracing::error!("Received non OK http response {:?}", response.status())
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: f64,
+    is_retryable_status: StatusPredicate,
+    respect_retry_after: bool,
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("RetryPolicy")
+            .field("max_retries", &self.max_retries)
+            .field("base_delay", &self.base_delay)
+            .field("max_delay", &self.max_delay)
+            .field("jitter", &self.jitter)
+            .field("respect_retry_after", &self.respect_retry_after)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a policy with the specified number of retries and the default parameters:
+    ///
+    /// - The delay starts from 1s, doubles after each retry and is capped at 1 minute
+    /// - Up to 10% of the delay is randomly subtracted
+    /// - Only transient statuses (see [`is_transient_status()`]) are retried
+    /// - `Retry-After` header in responses is respected
+    pub fn new(max_retries: usize) -> Self {
+        Self {
+            client: Client::new(),
+            max_retries,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            jitter: 0.1,
+            is_retryable_status: Arc::new(is_transient_status),
+            respect_retry_after: true,
+        }
+    }
+
+    /// Sets the client used to send requests, e.g. to configure timeouts.
+    #[must_use]
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Sets the delay before the first retry.
+    #[must_use]
+    pub fn with_base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Sets the maximum delay between retries. Also caps delays requested via `Retry-After`.
+    #[must_use]
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Sets the maximum share of the delay randomly subtracted from it, so that clients
+    /// don't retry in lockstep.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `jitter` is not in the `0.0..=1.0` range.
+    #[must_use]
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&jitter),
+            "jitter must be in 0.0..=1.0 range, got {jitter}"
+        );
+        self.jitter = jitter;
+        self
+    }
+
+    /// Sets the predicate deciding whether an unsuccessful response status should be retried.
+    #[must_use]
+    pub fn with_retryable_status(
+        mut self,
+        predicate: impl Fn(StatusCode) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.is_retryable_status = Arc::new(predicate);
+        self
+    }
+
+    /// Sets whether the delay requested by the server via `Retry-After` header (in seconds)
+    /// should be used instead of the exponential back-off.
+    #[must_use]
+    pub fn with_retry_after(mut self, respect_retry_after: bool) -> Self {
+        self.respect_retry_after = respect_retry_after;
+        self
+    }
+
+    /// Computes the delay before the retry with the specified 0-based index.
+    fn delay(&self, retry: usize, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after.min(self.max_delay);
+        }
+
+        let multiplier = 1_u32.checked_shl(retry as u32).unwrap_or(u32::MAX);
+        let delay = self
+            .base_delay
+            .checked_mul(multiplier)
+            .unwrap_or(Duration::MAX)
+            .min(self.max_delay);
+        if self.jitter > 0.0 {
+            let factor = 1.0 - rand::thread_rng().gen_range(0.0..=self.jitter);
+            delay.mul_f64(factor)
+        } else {
+            delay
+        }
+    }
+
+    fn retry_after(&self, response: &Response) -> Option<Duration> {
+        if !self.respect_retry_after {
+            return None;
+        }
+        let header = response.headers().get(RETRY_AFTER)?;
+        let seconds = header.to_str().ok()?.trim().parse().ok()?;
+        Some(Duration::from_secs(seconds))
+    }
+
+    /// Sends an HTTP request retrying it according to the policy.
+    pub async fn send(
+        &self,
+        url: &str,
+        method: Method,
+        headers: Option<HeaderMap>,
+        body: Option<Vec<u8>>,
+    ) -> Result<Response, HttpError> {
+        let mut retries = 0;
+        loop {
+            let started_at = Instant::now();
+            let result = self
+                .send_request(url, method.clone(), headers.clone(), body.clone())
+                .await;
+            METRICS.attempt_latency.observe(started_at.elapsed());
+
+            let retry_after = match result {
+                Ok(response) if response.status().is_success() => {
+                    METRICS.attempts[&AttemptOutcome::Success].inc();
+                    self.report_request(RequestOutcome::Success, retries);
+                    return Ok(response);
+                }
+                Ok(response) if !(self.is_retryable_status)(response.status()) => {
+                    tracing::error!(
+                        "Received non-retryable http response {:?}",
+                        response.status()
+                    );
+                    METRICS.attempts[&AttemptOutcome::NonRetryableStatus].inc();
+                    self.report_request(RequestOutcome::NonRetryableStatus, retries);
+                    return Err(HttpError::NonRetryableStatus(response));
+                }
+                Ok(response) => {
+                    tracing::error!("Received non OK http response {:?}", response.status());
+                    METRICS.attempts[&AttemptOutcome::RetryableStatus].inc();
+                    self.retry_after(&response)
+                }
+                Err(err) if err.is_builder() => {
+                    METRICS.attempts[&AttemptOutcome::Error].inc();
+                    self.report_request(RequestOutcome::NonRetryableError, retries);
+                    return Err(HttpError::ReqwestError(err));
+                }
+                Err(err) => {
+                    tracing::error!("Error while sending http request {:?}", err);
+                    METRICS.attempts[&AttemptOutcome::Error].inc();
+                    None
+                }
+            };
+
+            if retries >= self.max_retries {
+                self.report_request(RequestOutcome::RetriesExhausted, retries);
+                return Err(HttpError::RetryExhausted(format!(
+                    "All {} http retires failed",
+                    self.max_retries
+                )));
             }
-            Err(err) => tracing::error!("Error while sending http request {:?}", err),
+            sleep(self.delay(retries, retry_after)).await;
+            retries += 1;
         }
-        if retries >= max_retries {
-            return Err(HttpError::RetryExhausted(format!(
-                "All {} http retires failed",
-                max_retries
-            )));
+    }
+
+    fn report_request(&self, outcome: RequestOutcome, retries: usize) {
+        METRICS.requests[&outcome].inc();
+        METRICS.retries.observe(retries);
+    }
+
+    async fn send_request(
+        &self,
+        url: &str,
+        method: Method,
+        headers: Option<HeaderMap>,
+        body: Option<Vec<u8>>,
+    ) -> Result<Response, Error> {
+        let mut request = self.client.request(method, url);
+
+        if let Some(headers) = headers {
+            request = request.headers(headers);
+        }
+
+        if let Some(body) = body {
+            request = request.body(body);
         }
-        retries += 1;
-        sleep(delay).await;
-        delay = delay.checked_mul(2).unwrap_or(Duration::MAX);
+
+        let request = request.build()?;
+        let response = self.client.execute(request).await?;
+        Ok(response)
     }
 }
 
-async fn send_request(
+/// Method to send HTTP request with fixed number of retires with exponential back-offs.
+/// Uses [`RetryPolicy::new()`] with the specified number of retries; create a [`RetryPolicy`]
+/// explicitly to customize retries or to reuse the HTTP client across requests.
+pub async fn send_request_with_retries(
     url: &str,
+    max_retries: usize,
     method: Method,
     headers: Option<HeaderMap>,
     body: Option<Vec<u8>>,
-) -> Result<Response, Error> {
-    let client = Client::new();
-    let mut request = client.request(method, url);
+) -> Result<Response, HttpError> {
+    RetryPolicy::new(max_retries)
+        .send(url, method, headers, body)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
 
-    if let Some(headers) = headers {
-        request = request.headers(headers);
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// Minimal stand-in for an HTTP server replying with the provided responses in order
+    /// (the last response is repeated once the others are exhausted). Returns the server URL
+    /// and a shared list of received request bodies.
+    async fn start_server(responses: Vec<&'static str>) -> (String, Arc<Mutex<Vec<Vec<u8>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let requests = Arc::<Mutex<Vec<_>>>::default();
+        let requests_for_server = requests.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut stream = BufReader::new(stream);
+                let mut content_len = 0;
+                loop {
+                    let mut header = String::new();
+                    stream.read_line(&mut header).await.unwrap();
+                    let header = header.trim_end();
+                    if header.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = header.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_len = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+                let mut body = vec![0_u8; content_len];
+                stream.read_exact(&mut body).await.unwrap();
+
+                let request_index = {
+                    let mut requests = requests_for_server.lock().unwrap();
+                    requests.push(body);
+                    requests.len() - 1
+                };
+                let status_and_headers = responses[request_index.min(responses.len() - 1)];
+                let response = format!(
+                    "HTTP/1.1 {status_and_headers}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (url, requests)
     }
 
-    if let Some(body) = body {
-        request = request.body(body);
+    fn fast_policy(max_retries: usize) -> RetryPolicy {
+        RetryPolicy::new(max_retries)
+            .with_base_delay(Duration::from_millis(1))
+            .with_max_delay(Duration::from_millis(10))
     }
 
-    let request = request.build()?;
-    let response = client.execute(request).await?;
-    Ok(response)
+    #[tokio::test]
+    async fn transient_errors_are_retried() {
+        let responses = vec!["503 Service Unavailable", "429 Too Many Requests", "200 OK"];
+        let (url, requests) = start_server(responses).await;
+
+        let response = fast_policy(5)
+            .send(&url, Method::POST, None, Some(b"test".to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let requests = requests.lock().unwrap();
+        assert_eq!(*requests, [b"test"; 3]);
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_not_retried() {
+        let (url, requests) = start_server(vec!["404 Not Found", "200 OK"]).await;
+
+        let err = fast_policy(5)
+            .send(&url, Method::GET, None, None)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, HttpError::NonRetryableStatus(response) if response.status() == StatusCode::NOT_FOUND),
+            "{err:?}"
+        );
+        assert_eq!(requests.lock().unwrap().len(), 1);
+
+        // With a custom predicate, the request is retried.
+        let (url, requests) = start_server(vec!["404 Not Found", "200 OK"]).await;
+        let response = fast_policy(5)
+            .with_retryable_status(|status| status == StatusCode::NOT_FOUND)
+            .send(&url, Method::GET, None, None)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn retries_can_be_exhausted() {
+        let (url, requests) = start_server(vec!["500 Internal Server Error"]).await;
+
+        let err = fast_policy(2)
+            .send(&url, Method::GET, None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, HttpError::RetryExhausted(_)), "{err:?}");
+        assert_eq!(requests.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn retry_after_header_is_respected() {
+        let responses = vec!["503 Service Unavailable\r\nretry-after: 0", "200 OK"];
+        let (url, _) = start_server(responses).await;
+        // The exponential back-off would take an hour.
+        let policy = RetryPolicy::new(1)
+            .with_base_delay(Duration::from_secs(3_600))
+            .with_max_delay(Duration::from_secs(3_600));
+
+        let response = tokio::time::timeout(
+            Duration::from_secs(10),
+            policy.send(&url, Method::GET, None, None),
+        )
+        .await
+        .expect("Retry-After was not respected")
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn computing_delays() {
+        let policy = RetryPolicy::new(10)
+            .with_base_delay(Duration::from_secs(1))
+            .with_max_delay(Duration::from_secs(10))
+            .with_jitter(0.0);
+        let delays: Vec<_> = (0..6).map(|retry| policy.delay(retry, None)).collect();
+        assert_eq!(delays, [1, 2, 4, 8, 10, 10].map(Duration::from_secs));
+        assert_eq!(policy.delay(100, None), Duration::from_secs(10));
+        assert_eq!(
+            policy.delay(0, Some(Duration::from_secs(5))),
+            Duration::from_secs(5)
+        );
+        assert_eq!(
+            policy.delay(0, Some(Duration::from_secs(3_600))),
+            Duration::from_secs(10)
+        );
+
+        let policy = policy.with_jitter(0.5);
+        for (retry, &max_delay) in delays.iter().enumerate() {
+            let delay = policy.delay(retry, None);
+            assert!(delay <= max_delay && delay >= max_delay / 2, "{delay:?}");
+        }
+    }
 }
@@ -4,12 +4,32 @@
 
 use std::{backtrace::Backtrace, borrow::Cow, panic::PanicInfo};
 
+use opentelemetry::{
+    sdk::{
+        propagation::TraceContextPropagator,
+        trace::{self as sdk_trace, Sampler, TracerProvider},
+        Resource,
+    },
+    trace::TracerProvider as _,
+    KeyValue,
+};
+use opentelemetry_otlp::{SpanExporterBuilder, WithExportConfig};
+use opentelemetry_semantic_conventions::resource::SERVICE_NAME;
 /// Temporary re-export of `sentry::capture_message` aiming to simplify the transition from
 /// `vlog` to using crates directly.
 pub use sentry::capture_message;
 pub use sentry::Level as AlertLevel;
 use sentry::{types::Dsn, ClientInitGuard};
-use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt};
+use tokio::runtime::RuntimeFlavor;
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::{
+    fmt, layer::SubscriberExt, registry::LookupSpan, util::SubscriberInitExt, EnvFilter,
+};
+
+pub use crate::log_filter::{LogFilterError, LogFilterHandle, LogFilterServer};
+
+mod log_filter;
 
 /// Specifies the format of the logs in stdout.
 #[derive(Debug, Clone, Copy, Default)]
@@ -19,21 +39,150 @@ pub enum LogFormat {
     Json,
 }
 
+/// Configuration of the OpenTelemetry span exporter using the OTLP protocol over HTTP.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenTelemetryOptions {
+    otlp_endpoint: String,
+    service_name: String,
+    sampling_ratio: f64,
+    resource_attributes: Vec<(String, String)>,
+}
+
+impl OpenTelemetryOptions {
+    /// Creates options exporting spans to the specified OTLP HTTP collector endpoint
+    /// (e.g., `http://127.0.0.1:4318/v1/traces`). `service_name` is reported
+    /// as the `service.name` resource attribute and distinguishes processes in the collector.
+    pub fn new(otlp_endpoint: impl Into<String>, service_name: impl Into<String>) -> Self {
+        Self {
+            otlp_endpoint: otlp_endpoint.into(),
+            service_name: service_name.into(),
+            sampling_ratio: 1.0,
+            resource_attributes: Vec::new(),
+        }
+    }
+
+    /// Sets the share of traces to export, from 0.0 (none) to 1.0 (all, the default).
+    /// The sampling decision is made for root spans; child spans follow the decision
+    /// of their parent.
+    pub fn with_sampling_ratio(mut self, sampling_ratio: f64) -> Self {
+        self.sampling_ratio = sampling_ratio;
+        self
+    }
+
+    /// Adds a resource attribute attached to all exported spans, such as the network name
+    /// or the component running in the process.
+    pub fn with_resource_attribute(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.resource_attributes.push((key.into(), value.into()));
+        self
+    }
+
+    /// Creates a tracer provider with a batch span processor running on the Tokio runtime.
+    fn tracer_provider(&self) -> Result<TracerProvider, opentelemetry::trace::TraceError> {
+        let exporter = opentelemetry_otlp::new_exporter()
+            .http()
+            .with_endpoint(&self.otlp_endpoint);
+        let exporter = SpanExporterBuilder::from(exporter).build_span_exporter()?;
+
+        let resource_attributes = self
+            .resource_attributes
+            .iter()
+            .map(|(key, value)| KeyValue::new(key.clone(), value.clone()))
+            .chain([KeyValue::new(SERVICE_NAME, self.service_name.clone())]);
+        let sampler =
+            Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(self.sampling_ratio)));
+        let config = sdk_trace::config()
+            .with_resource(Resource::new(resource_attributes))
+            .with_sampler(sampler);
+
+        Ok(TracerProvider::builder()
+            .with_batch_exporter(exporter, opentelemetry::runtime::Tokio)
+            .with_config(config)
+            .build())
+    }
+}
+
+fn opentelemetry_layer<S>(
+    tracer_provider: Option<&TracerProvider>,
+) -> Option<OpenTelemetryLayer<S, sdk_trace::Tracer>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let tracer = tracer_provider?.tracer("vlog");
+    Some(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
 /// Builder for the observability subsystem.
-/// Currently capable of configuring logging output and sentry integration.
+/// Currently capable of configuring logging output, sentry integration
+/// and OpenTelemetry span export.
 #[derive(Debug, Default)]
 pub struct ObservabilityBuilder {
     log_format: LogFormat,
     sentry_url: Option<Dsn>,
     sentry_environment: Option<String>,
+    opentelemetry_options: Option<OpenTelemetryOptions>,
 }
 
 /// Guard for the observability subsystem.
 /// Releases configured integrations upon being dropped.
 pub struct ObservabilityGuard {
+    log_filter: LogFilterHandle,
+    tracer_provider: Option<TracerProvider>,
     _sentry_guard: Option<ClientInitGuard>,
 }
 
+/// Exports spans buffered by the batch processor. Blocks until the export task running
+/// on the Tokio runtime is done, so it must not be called on a runtime thread.
+fn flush_spans(tracer_provider: &TracerProvider) {
+    for result in tracer_provider.force_flush() {
+        if let Err(err) = result {
+            tracing::warn!("Failed flushing OpenTelemetry spans: {err}");
+        }
+    }
+}
+
+impl Drop for ObservabilityGuard {
+    fn drop(&mut self) {
+        let Some(tracer_provider) = self.tracer_provider.take() else {
+            return;
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(|| flush_spans(&tracer_provider));
+            }
+            Ok(_) => {
+                // Blocking the only runtime thread would deadlock with the export task.
+                tracing::warn!(
+                    "Cannot flush OpenTelemetry spans on a current-thread runtime; \
+                     call `ObservabilityGuard::shutdown()` before dropping the guard"
+                );
+            }
+            Err(_) => flush_spans(&tracer_provider),
+        }
+    }
+}
+
+impl ObservabilityGuard {
+    /// Returns a handle allowing to change log filtering directives at runtime.
+    pub fn log_filter(&self) -> LogFilterHandle {
+        self.log_filter.clone()
+    }
+
+    /// Exports spans buffered by the OpenTelemetry batch processor and releases
+    /// the integrations. Unlike dropping the guard, works on any Tokio runtime,
+    /// so it's the preferred way to stop the subsystem at the end of `main()`.
+    pub async fn shutdown(mut self) {
+        if let Some(tracer_provider) = self.tracer_provider.take() {
+            tokio::task::spawn_blocking(move || flush_spans(&tracer_provider))
+                .await
+                .expect("flushing OpenTelemetry spans panicked");
+        }
+    }
+}
+
 impl std::fmt::Debug for ObservabilityGuard {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("ObservabilityGuard").finish()
@@ -71,20 +220,47 @@ impl ObservabilityBuilder {
         self
     }
 
-    /// Initializes the observability subsystem.
+    /// Enables export of spans to an OpenTelemetry collector.
+    /// Spans are filtered by the same `RUST_LOG` directives as logs.
+    pub fn with_opentelemetry(mut self, options: OpenTelemetryOptions) -> Self {
+        self.opentelemetry_options = Some(options);
+        self
+    }
+
+    /// Initializes the observability subsystem. Log filtering directives are initialized
+    /// from `RUST_LOG` and can be changed later using [`ObservabilityGuard::log_filter()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the OpenTelemetry exporter cannot be initialized. If OpenTelemetry export
+    /// is enabled, this method must be called within a Tokio runtime.
     pub fn build(self) -> ObservabilityGuard {
-        // Initialize logs.
+        let tracer_provider = self.opentelemetry_options.as_ref().map(|options| {
+            options
+                .tracer_provider()
+                .expect("failed initializing OpenTelemetry exporter")
+        });
+        if tracer_provider.is_some() {
+            // Allows to propagate trace context across processes using W3C headers.
+            opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        }
+
+        // Initialize logs. The filter is reloadable, so that verbosity can be changed
+        // without restarting the process.
+        let (filter_layer, log_filter) = LogFilterHandle::new(EnvFilter::from_default_env());
         match self.log_format {
             LogFormat::Plain => {
                 tracing_subscriber::registry()
-                    .with(tracing_subscriber::EnvFilter::from_default_env())
+                    .with(filter_layer)
+                    .with(opentelemetry_layer(tracer_provider.as_ref()))
                     .with(fmt::Layer::default())
                     .init();
             }
             LogFormat::Json => {
                 let timer = tracing_subscriber::fmt::time::UtcTime::rfc_3339();
                 tracing_subscriber::registry()
-                    .with(tracing_subscriber::EnvFilter::from_default_env())
+                    .with(filter_layer)
+                    .with(opentelemetry_layer(tracer_provider.as_ref()))
                     .with(
                         fmt::Layer::default()
                             .with_file(true)
@@ -122,6 +298,8 @@ impl ObservabilityBuilder {
         };
 
         ObservabilityGuard {
+            log_filter,
+            tracer_provider,
             _sentry_guard: sentry_guard,
         }
     }
@@ -159,3 +337,151 @@ fn json_panic_handler(panic_info: &PanicInfo) {
         })
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+        sync::mpsc,
+    };
+
+    use super::*;
+
+    /// Minimal stand-in for an OTLP HTTP collector. Returns the collector endpoint and a receiver
+    /// of request paths and bodies.
+    async fn start_collector() -> (String, mpsc::UnboundedReceiver<(String, Vec<u8>)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/v1/traces", listener.local_addr().unwrap());
+        let (requests_sender, requests) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut stream = BufReader::new(stream);
+                let mut request_line = String::new();
+                stream.read_line(&mut request_line).await.unwrap();
+                let path = request_line.split(' ').nth(1).unwrap().to_owned();
+
+                let mut content_len = 0;
+                loop {
+                    let mut header = String::new();
+                    stream.read_line(&mut header).await.unwrap();
+                    let header = header.trim_end();
+                    if header.is_empty() {
+                        break;
+                    }
+                    let (name, value) = header.split_once(':').unwrap();
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_len = value.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0_u8; content_len];
+                stream.read_exact(&mut body).await.unwrap();
+                stream
+                    .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                    .await
+                    .unwrap();
+                requests_sender.send((path, body)).ok();
+            }
+        });
+        (endpoint, requests)
+    }
+
+    fn test_guard(tracer_provider: TracerProvider) -> ObservabilityGuard {
+        let (_filter_layer, log_filter) = LogFilterHandle::new(EnvFilter::new("info"));
+        ObservabilityGuard {
+            log_filter,
+            tracer_provider: Some(tracer_provider),
+            _sentry_guard: None,
+        }
+    }
+
+    async fn export_test_span(options: OpenTelemetryOptions) {
+        let tracer_provider = options.tracer_provider().unwrap();
+        let subscriber =
+            tracing_subscriber::registry().with(opentelemetry_layer(Some(&tracer_provider)));
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("test_span", batch = 1);
+            span.in_scope(|| tracing::info_span!("child_span").in_scope(|| ()));
+        });
+        test_guard(tracer_provider).shutdown().await;
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn exporting_spans_to_collector() {
+        let (endpoint, mut requests) = start_collector().await;
+        let options = OpenTelemetryOptions::new(endpoint, "test-service")
+            .with_resource_attribute("network", "localhost")
+            .with_resource_attribute("component", "state_keeper");
+        export_test_span(options).await;
+
+        // Bodies are protobuf messages, in which strings are included verbatim. Spans may be
+        // split among several requests, so requests are received until all strings are seen.
+        let missing = vec![
+            "test-service",
+            "localhost",
+            "state_keeper",
+            "test_span",
+            "child_span",
+        ];
+        assert_exported(&mut requests, missing).await;
+    }
+
+    async fn assert_exported(
+        requests: &mut mpsc::UnboundedReceiver<(String, Vec<u8>)>,
+        mut missing: Vec<&str>,
+    ) {
+        let receiving = async {
+            while !missing.is_empty() {
+                let (path, body) = requests.recv().await.unwrap();
+                assert_eq!(path, "/v1/traces");
+                missing.retain(|expected| !contains(&body, expected.as_bytes()));
+            }
+        };
+        let received = tokio::time::timeout(Duration::from_secs(10), receiving).await;
+        assert!(received.is_ok(), "not exported: {missing:?}");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn dropping_guard_exports_spans_on_multi_thread_runtime() {
+        let (endpoint, mut requests) = start_collector().await;
+        let options = OpenTelemetryOptions::new(endpoint, "test-service");
+        let tracer_provider = options.tracer_provider().unwrap();
+        let subscriber =
+            tracing_subscriber::registry().with(opentelemetry_layer(Some(&tracer_provider)));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("dropped_guard_span").in_scope(|| ());
+        });
+        drop(test_guard(tracer_provider));
+
+        assert_exported(&mut requests, vec!["dropped_guard_span"]).await;
+    }
+
+    #[tokio::test]
+    async fn dropping_guard_does_not_block_current_thread_runtime() {
+        let (endpoint, _requests) = start_collector().await;
+        let options = OpenTelemetryOptions::new(endpoint, "test-service");
+        let tracer_provider = options.tracer_provider().unwrap();
+        // Flushing here would deadlock with the export task spawned on the same thread.
+        drop(test_guard(tracer_provider));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn spans_are_not_exported_with_zero_sampling_ratio() {
+        let (endpoint, mut requests) = start_collector().await;
+        let options = OpenTelemetryOptions::new(endpoint, "test-service").with_sampling_ratio(0.0);
+        export_test_span(options).await;
+
+        let request = tokio::time::timeout(Duration::from_millis(500), requests.recv()).await;
+        assert!(request.is_err(), "{request:?}");
+    }
+}
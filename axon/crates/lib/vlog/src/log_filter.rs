@@ -0,0 +1,352 @@
+//! Runtime-reloadable log filtering and an admin web server exposing it.
+
+use std::{
+    fmt,
+    future::{self, Future},
+    net::SocketAddr,
+    pin::Pin,
+    str,
+};
+
+use http_body_util::{BodyExt, Full};
+use hyper::{
+    body::{Bytes, Incoming},
+    header,
+    server::conn::http1,
+    service::service_fn,
+    Method, Request, Response, StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use tokio::net::TcpListener;
+use tracing_subscriber::{filter::ParseError, reload, EnvFilter, Registry};
+
+type FullBody = Full<Bytes>;
+
+/// Errors that can occur when changing log filtering directives.
+#[derive(Debug, thiserror::Error)]
+pub enum LogFilterError {
+    /// Provided directives cannot be parsed.
+    #[error("invalid log filtering directives: {0}")]
+    Parse(#[from] ParseError),
+    /// The subscriber using the filter is dropped.
+    #[error("failed reloading log filter: {0}")]
+    Reload(#[from] reload::Error),
+}
+
+/// Handle allowing to change log filtering directives (e.g., `info,merkle_tree=trace`)
+/// of the installed subscriber at runtime. Directives use the same syntax as `RUST_LOG`.
+///
+/// The handle is cheap to clone.
+#[derive(Clone)]
+pub struct LogFilterHandle {
+    inner: reload::Handle<EnvFilter, Registry>,
+    initial_directives: String,
+}
+
+impl fmt::Debug for LogFilterHandle {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("LogFilterHandle")
+            .field("initial_directives", &self.initial_directives)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LogFilterHandle {
+    /// Wraps the provided filter into a reloadable layer, which should be the first layer
+    /// on top of the [`Registry`].
+    pub(crate) fn new(filter: EnvFilter) -> (reload::Layer<EnvFilter, Registry>, Self) {
+        let initial_directives = filter.to_string();
+        let (layer, inner) = reload::Layer::new(filter);
+        let this = Self {
+            inner,
+            initial_directives,
+        };
+        (layer, this)
+    }
+
+    /// Returns currently active directives. Directives are normalized, so their order may differ
+    /// from the one they were set in.
+    pub fn directives(&self) -> Result<String, LogFilterError> {
+        Ok(self.inner.with_current(ToString::to_string)?)
+    }
+
+    /// Replaces filtering directives. If the directives cannot be parsed, the current filter
+    /// is left intact.
+    pub fn set_directives(&self, directives: &str) -> Result<(), LogFilterError> {
+        let filter = EnvFilter::builder().parse(directives)?;
+        self.inner.reload(filter)?;
+        tracing::info!("Changed log filtering directives to `{directives}`");
+        Ok(())
+    }
+
+    /// Restores directives the subscriber was initialized with.
+    pub fn reset(&self) -> Result<(), LogFilterError> {
+        self.set_directives(&self.initial_directives)
+    }
+}
+
+/// Admin web server allowing to inspect and change log filtering directives via HTTP.
+/// Intended to run alongside the metrics exporter on a separate, non-public port.
+pub struct LogFilterServer<'a> {
+    handle: LogFilterHandle,
+    shutdown_future: Pin<Box<dyn Future<Output = ()> + Send + 'a>>,
+}
+
+impl fmt::Debug for LogFilterServer<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("LogFilterServer")
+            .field("handle", &self.handle)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> LogFilterServer<'a> {
+    /// Path served by the server.
+    pub const PATH: &'static str = "/log_filter";
+
+    /// Creates a server changing directives using the provided handle.
+    pub fn new(handle: LogFilterHandle) -> Self {
+        Self {
+            handle,
+            shutdown_future: Box::pin(future::pending()),
+        }
+    }
+
+    /// Configures graceful shutdown for the server.
+    #[must_use]
+    pub fn with_graceful_shutdown<F>(mut self, shutdown: F) -> Self
+    where
+        F: Future<Output = ()> + Send + 'a,
+    {
+        self.shutdown_future = Box::pin(shutdown);
+        self
+    }
+
+    /// Starts the server on the specified address. This future resolves when the server is shut
+    /// down.
+    ///
+    /// The server will expose the following endpoints:
+    ///
+    /// - `GET /log_filter`: returns currently active directives
+    /// - `PUT /log_filter`: replaces directives with the ones in the UTF-8 request body,
+    ///   e.g. `info,merkle_tree=trace`
+    /// - `DELETE /log_filter`: restores directives the subscriber was initialized with
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if binding to the specified address fails.
+    pub async fn start(self, bind_address: SocketAddr) -> std::io::Result<()> {
+        let listener = TcpListener::bind(bind_address).await?;
+        tracing::info!(
+            "Starting log filter admin server on {}",
+            listener.local_addr()?
+        );
+        self.run(listener).await;
+        Ok(())
+    }
+
+    async fn run(self, listener: TcpListener) {
+        let mut shutdown = self.shutdown_future;
+        loop {
+            let socket = tokio::select! {
+                result = listener.accept() => match result {
+                    Ok((socket, _)) => socket,
+                    Err(err) => {
+                        tracing::warn!(%err, "Failed accepting connection to log filter admin server");
+                        continue;
+                    }
+                },
+                () = &mut shutdown => {
+                    tracing::info!("Stop signal received, log filter admin server is shutting down");
+                    break;
+                }
+            };
+
+            let handle = self.handle.clone();
+            tokio::spawn(async move {
+                let socket = TokioIo::new(socket);
+                let service = service_fn(move |request| {
+                    let handle = handle.clone();
+                    async move { Ok::<_, hyper::Error>(handle_request(&handle, request).await) }
+                });
+
+                if let Err(err) = http1::Builder::new()
+                    .serve_connection(socket, service)
+                    .await
+                {
+                    tracing::error!("Error serving connection: {err:?}");
+                }
+            });
+        }
+    }
+}
+
+async fn handle_request(
+    handle: &LogFilterHandle,
+    request: Request<Incoming>,
+) -> Response<FullBody> {
+    if request.uri().path() != LogFilterServer::PATH {
+        return text_response(StatusCode::NOT_FOUND, "Not found".to_owned());
+    }
+
+    let result = match *request.method() {
+        Method::GET => handle.directives(),
+        Method::PUT => {
+            let body = match request.into_body().collect().await {
+                Ok(body) => body.to_bytes(),
+                Err(err) => return text_response(StatusCode::BAD_REQUEST, err.to_string()),
+            };
+            let Ok(directives) = str::from_utf8(&body) else {
+                let message = "Directives must be UTF-8".to_owned();
+                return text_response(StatusCode::BAD_REQUEST, message);
+            };
+            handle
+                .set_directives(directives.trim())
+                .and_then(|()| handle.directives())
+        }
+        Method::DELETE => handle.reset().and_then(|()| handle.directives()),
+        _ => {
+            let message = "Only GET, PUT and DELETE are supported".to_owned();
+            return text_response(StatusCode::METHOD_NOT_ALLOWED, message);
+        }
+    };
+
+    match result {
+        Ok(directives) => text_response(StatusCode::OK, directives),
+        Err(err @ LogFilterError::Parse(_)) => {
+            text_response(StatusCode::BAD_REQUEST, err.to_string())
+        }
+        Err(err) => text_response(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
+
+fn text_response(status: StatusCode, body: String) -> Response<FullBody> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(FullBody::from(body))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::Ipv4Addr,
+        sync::{Arc, Mutex},
+    };
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+        sync::oneshot,
+    };
+    use tracing_subscriber::{layer::SubscriberExt, Layer};
+
+    use super::*;
+
+    /// Layer recording names of targets for all events passing through the filter.
+    #[derive(Debug, Clone, Default)]
+    struct RecordingLayer(Arc<Mutex<Vec<String>>>);
+
+    impl<S: tracing::Subscriber> Layer<S> for RecordingLayer {
+        fn on_event(
+            &self,
+            event: &tracing::Event<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let target = event.metadata().target().to_owned();
+            self.0.lock().unwrap().push(target);
+        }
+    }
+
+    impl RecordingLayer {
+        fn take(&self) -> Vec<String> {
+            std::mem::take(&mut self.0.lock().unwrap())
+        }
+    }
+
+    fn emit_events() {
+        tracing::info!(target: "state_keeper", "info");
+        tracing::debug!(target: "state_keeper", "debug");
+        tracing::trace!(target: "merkle_tree", "trace");
+    }
+
+    #[test]
+    fn changing_directives_at_runtime() {
+        let (filter_layer, handle) = LogFilterHandle::new(EnvFilter::new("info"));
+        let recording_layer = RecordingLayer::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(filter_layer)
+            .with(recording_layer.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            emit_events();
+            assert_eq!(recording_layer.take(), ["state_keeper"]);
+
+            handle.set_directives("warn,merkle_tree=trace").unwrap();
+            assert_eq!(handle.directives().unwrap(), "merkle_tree=trace,warn");
+            emit_events();
+            assert_eq!(recording_layer.take(), ["merkle_tree"]);
+
+            let err = handle.set_directives("merkle_tree=loud").unwrap_err();
+            assert!(matches!(err, LogFilterError::Parse(_)), "{err:?}");
+            assert_eq!(handle.directives().unwrap(), "merkle_tree=trace,warn");
+
+            handle.reset().unwrap();
+            assert_eq!(handle.directives().unwrap(), "info");
+            // The change of directives is logged itself.
+            assert_eq!(recording_layer.take(), ["vlog::log_filter"]);
+            emit_events();
+            assert_eq!(recording_layer.take(), ["state_keeper"]);
+        });
+    }
+
+    async fn send_request(address: SocketAddr, method: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let request = format!(
+            "{method} {} HTTP/1.1\r\nhost: localhost\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+            LogFilterServer::PATH,
+            body.len()
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        let status = response.split(' ').nth(1).unwrap().parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (status, body.to_owned())
+    }
+
+    #[tokio::test]
+    async fn changing_directives_via_admin_server() {
+        let (_filter_layer, handle) = LogFilterHandle::new(EnvFilter::new("info"));
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
+        let server = LogFilterServer::new(handle.clone()).with_graceful_shutdown(async move {
+            shutdown_receiver.await.ok();
+        });
+        let server_task = tokio::spawn(server.run(listener));
+
+        let response = send_request(address, "GET", "").await;
+        assert_eq!(response, (200, "info".to_owned()));
+        let response = send_request(address, "PUT", "info,merkle_tree=trace\n").await;
+        assert_eq!(response, (200, "merkle_tree=trace,info".to_owned()));
+        assert_eq!(handle.directives().unwrap(), "merkle_tree=trace,info");
+
+        let (status, body) = send_request(address, "PUT", "merkle_tree=loud").await;
+        assert_eq!(status, 400);
+        assert!(body.contains("invalid log filtering directives"), "{body}");
+        assert_eq!(handle.directives().unwrap(), "merkle_tree=trace,info");
+
+        let response = send_request(address, "DELETE", "").await;
+        assert_eq!(response, (200, "info".to_owned()));
+        let (status, _) = send_request(address, "POST", "").await;
+        assert_eq!(status, 405);
+
+        shutdown_sender.send(()).unwrap();
+        server_task.await.unwrap();
+    }
+}